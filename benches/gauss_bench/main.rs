@@ -1,7 +1,7 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use image::io::Reader as ImageReader;
 use image::GenericImageView;
-use libblur::{EdgeMode, FastBlurChannels, ThreadingPolicy};
+use libblur::{AlphaMode, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy};
 use opencv::core::{
     find_file, mean, split, Mat, MatTraitConst, MatTraitConstManual, Size, Vector, BORDER_DEFAULT,
 };
@@ -47,18 +47,27 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             let mut dst_bytes: Vec<u8> = Vec::with_capacity(dimensions.1 as usize * stride);
             dst_bytes.resize(dimensions.1 as usize * stride, 0);
             libblur::gaussian_blur(
-                &src_bytes,
-                stride as u32,
-                &mut dst_bytes,
-                stride as u32,
-                dimensions.0,
-                dimensions.1,
+                &BlurImage::borrow_with_stride(
+                    &src_bytes,
+                    stride as u32,
+                    dimensions.0,
+                    dimensions.1,
+                    FastBlurChannels::Channels4,
+                ),
+                &mut BlurImageMut::borrow_with_stride(
+                    &mut dst_bytes,
+                    stride as u32,
+                    dimensions.0,
+                    dimensions.1,
+                    FastBlurChannels::Channels4,
+                ),
                 77 * 2 + 1,
                 (77f32 * 2f32 + 1f32) / 6f32,
-                FastBlurChannels::Channels4,
                 EdgeMode::KernelClip,
+                AlphaMode::Independent,
                 ThreadingPolicy::Adaptive,
-            );
+            )
+            .unwrap();
         })
     });
     c.bench_function("RGBA gauss blur edge clamp", |b| {
@@ -66,18 +75,27 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             let mut dst_bytes: Vec<u8> = Vec::with_capacity(dimensions.1 as usize * stride);
             dst_bytes.resize(dimensions.1 as usize * stride, 0);
             libblur::gaussian_blur(
-                &src_bytes,
-                stride as u32,
-                &mut dst_bytes,
-                stride as u32,
-                dimensions.0,
-                dimensions.1,
+                &BlurImage::borrow_with_stride(
+                    &src_bytes,
+                    stride as u32,
+                    dimensions.0,
+                    dimensions.1,
+                    FastBlurChannels::Channels4,
+                ),
+                &mut BlurImageMut::borrow_with_stride(
+                    &mut dst_bytes,
+                    stride as u32,
+                    dimensions.0,
+                    dimensions.1,
+                    FastBlurChannels::Channels4,
+                ),
                 77 * 2 + 1,
                 (77f32 * 2f32 + 1f32) / 6f32,
-                FastBlurChannels::Channels4,
                 EdgeMode::Clamp,
+                AlphaMode::Independent,
                 ThreadingPolicy::Adaptive,
-            );
+            )
+            .unwrap();
         })
     });
 
@@ -118,18 +136,27 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 let mut dst_bytes: Vec<u8> = Vec::with_capacity(dimensions.1 as usize * stride);
                 dst_bytes.resize(dimensions.1 as usize * stride, 0);
                 libblur::gaussian_blur(
-                    &src_bytes,
-                    stride as u32,
-                    &mut dst_bytes,
-                    stride as u32,
-                    dimensions.0,
-                    dimensions.1,
+                    &BlurImage::borrow_with_stride(
+                        &src_bytes,
+                        stride as u32,
+                        dimensions.0,
+                        dimensions.1,
+                        FastBlurChannels::Channels3,
+                    ),
+                    &mut BlurImageMut::borrow_with_stride(
+                        &mut dst_bytes,
+                        stride as u32,
+                        dimensions.0,
+                        dimensions.1,
+                        FastBlurChannels::Channels3,
+                    ),
                     77 * 2 + 1,
                     (77f32 * 2f32 + 1f32) / 6f32,
-                    FastBlurChannels::Channels3,
                     EdgeMode::Clamp,
+                    AlphaMode::Independent,
                     ThreadingPolicy::Adaptive,
-                );
+                )
+                .unwrap();
             })
         });
 
@@ -182,18 +209,27 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 let mut dst_plane_1 = vec![0u8; width * height];
                 let stride = width;
                 libblur::gaussian_blur(
-                    &plane_1,
-                    stride as u32,
-                    &mut dst_plane_1,
-                    stride as u32,
-                    dimensions.0,
-                    dimensions.1,
+                    &BlurImage::borrow_with_stride(
+                        &plane_1,
+                        stride as u32,
+                        dimensions.0,
+                        dimensions.1,
+                        FastBlurChannels::Plane,
+                    ),
+                    &mut BlurImageMut::borrow_with_stride(
+                        &mut dst_plane_1,
+                        stride as u32,
+                        dimensions.0,
+                        dimensions.1,
+                        FastBlurChannels::Plane,
+                    ),
                     77 * 2 + 1,
                     (77f32 * 2f32 + 1f32) / 6f32,
-                    FastBlurChannels::Plane,
                     EdgeMode::Clamp,
+                    AlphaMode::Independent,
                     ThreadingPolicy::Adaptive,
-                );
+                )
+                .unwrap();
             })
         });
 
@@ -2,7 +2,7 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use image::io::Reader as ImageReader;
 use image::GenericImageView;
 
-use libblur::{FastBlurChannels, ThreadingPolicy};
+use libblur::{AlphaMode, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let img = ImageReader::open("assets/test_image_4.png")
@@ -24,14 +24,18 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 );
             }
             libblur::stack_blur(
-                &mut dst_bytes,
-                stride as u32,
-                dimensions.0,
-                dimensions.1,
+                &mut BlurImageMut::borrow(
+                    &mut dst_bytes,
+                    dimensions.0,
+                    dimensions.1,
+                    FastBlurChannels::Channels4,
+                ),
                 77,
-                FastBlurChannels::Channels4,
+                EdgeMode::Clamp,
+                AlphaMode::Independent,
                 ThreadingPolicy::Single,
-            );
+            )
+            .unwrap();
         })
     });
 }
@@ -0,0 +1,406 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{BlurError, BlurImage, BlurImageMut, FastBlurChannels};
+use half::f16;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares how alpha channel takes part in blurring of images that have one,
+/// alpha position is taken from [AlphaPosition](crate::AlphaPosition) of the image
+pub enum AlphaMode {
+    /// Every channel including alpha is blurred independently
+    #[default]
+    Independent,
+    /// Color channels are multiplied by alpha before blurring and divided by blurred alpha afterwards.
+    /// This avoids dark or colored halos around transparent areas.
+    /// Alpha is expected to be full range of the storage: `255` for `u8`, `65535` for `u16` and `1.` for `f32` and `f16`.
+    /// Ignored for images without alpha
+    Premultiply,
+    /// Only color channels are blurred, alpha is copied from the source as is.
+    /// Keeps an alpha mask sharp, e.g. for a blurred background behind a cut-out.
+    /// Ignored for images without alpha
+    Preserve,
+}
+
+pub(crate) trait AlphaPremultiply: Copy {
+    fn premultiply(self, alpha: Self) -> Self;
+    fn unpremultiply(self, alpha: Self) -> Self;
+}
+
+impl AlphaPremultiply for u8 {
+    #[inline(always)]
+    fn premultiply(self, alpha: Self) -> Self {
+        ((self as u32 * alpha as u32 + 127) / 255) as u8
+    }
+
+    #[inline(always)]
+    fn unpremultiply(self, alpha: Self) -> Self {
+        if alpha == 0 {
+            return 0;
+        }
+        ((self as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8
+    }
+}
+
+impl AlphaPremultiply for u16 {
+    #[inline(always)]
+    fn premultiply(self, alpha: Self) -> Self {
+        ((self as u32 * alpha as u32 + 32767) / 65535) as u16
+    }
+
+    #[inline(always)]
+    fn unpremultiply(self, alpha: Self) -> Self {
+        if alpha == 0 {
+            return 0;
+        }
+        ((self as u64 * 65535 + alpha as u64 / 2) / alpha as u64).min(65535) as u16
+    }
+}
+
+impl AlphaPremultiply for f32 {
+    #[inline(always)]
+    fn premultiply(self, alpha: Self) -> Self {
+        self * alpha
+    }
+
+    #[inline(always)]
+    fn unpremultiply(self, alpha: Self) -> Self {
+        if alpha == 0f32 {
+            return 0f32;
+        }
+        self / alpha
+    }
+}
+
+impl AlphaPremultiply for f16 {
+    #[inline(always)]
+    fn premultiply(self, alpha: Self) -> Self {
+        f16::from_f32(self.to_f32().premultiply(alpha.to_f32()))
+    }
+
+    #[inline(always)]
+    fn unpremultiply(self, alpha: Self) -> Self {
+        f16::from_f32(self.to_f32().unpremultiply(alpha.to_f32()))
+    }
+}
+
+fn premultiply_row<T: AlphaPremultiply>(
+    src: &[T],
+    dst: &mut [T],
+    channels: usize,
+    alpha_index: usize,
+) {
+    for (src, dst) in src
+        .chunks_exact(channels)
+        .zip(dst.chunks_exact_mut(channels))
+    {
+        let alpha = src[alpha_index];
+        for (c, (&src, dst)) in src.iter().zip(dst.iter_mut()).enumerate() {
+            *dst = if c == alpha_index {
+                alpha
+            } else {
+                src.premultiply(alpha)
+            };
+        }
+    }
+}
+
+fn premultiply_image<T: AlphaPremultiply>(image: &mut BlurImageMut<T>, alpha_index: usize) {
+    let row_length = image.row_length();
+    let channels = image.channels.get_channels();
+    for row in image
+        .data
+        .chunks_mut(image.stride as usize)
+        .take(image.height as usize)
+    {
+        for px in row[..row_length].chunks_exact_mut(channels) {
+            let alpha = px[alpha_index];
+            for (c, v) in px.iter_mut().enumerate() {
+                if c != alpha_index {
+                    *v = v.premultiply(alpha);
+                }
+            }
+        }
+    }
+}
+
+fn unpremultiply_image<T: AlphaPremultiply>(image: &mut BlurImageMut<T>, alpha_index: usize) {
+    let row_length = image.row_length();
+    let channels = image.channels.get_channels();
+    for row in image
+        .data
+        .chunks_mut(image.stride as usize)
+        .take(image.height as usize)
+    {
+        for px in row[..row_length].chunks_exact_mut(channels) {
+            let alpha = px[alpha_index];
+            for (c, v) in px.iter_mut().enumerate() {
+                if c != alpha_index {
+                    *v = v.unpremultiply(alpha);
+                }
+            }
+        }
+    }
+}
+
+/// Writes alpha from `src` laid out as `src_channels` per pixel with alpha at `src_alpha_index`
+/// into alpha channel of `dst`
+fn copy_alpha<T: Copy>(
+    src: &[T],
+    src_stride: usize,
+    src_channels: usize,
+    src_alpha_index: usize,
+    dst: &mut BlurImageMut<T>,
+    alpha_index: usize,
+) {
+    let width = dst.width as usize;
+    let channels = dst.channels.get_channels();
+    for (src_row, dst_row) in src.chunks(src_stride).zip(
+        dst.data
+            .chunks_mut(dst.stride as usize)
+            .take(dst.height as usize),
+    ) {
+        for (src, dst) in src_row[..width * src_channels]
+            .chunks_exact(src_channels)
+            .zip(dst_row[..width * channels].chunks_exact_mut(channels))
+        {
+            dst[alpha_index] = src[src_alpha_index];
+        }
+    }
+}
+
+/// Color channels of an image with alpha as the last channel, tightly packed without alpha
+fn strip_alpha<T: Copy>(data: &[T], stride: usize, row_length: usize, channels: usize) -> Vec<T> {
+    data.chunks(stride)
+        .flat_map(|row| {
+            row[..row_length]
+                .chunks_exact(channels)
+                .flat_map(|px| px[..channels - 1].iter().copied())
+        })
+        .collect()
+}
+
+/// Writes color channels made by [strip_alpha] back into `dst`, alpha of `dst` is left untouched
+fn restore_color<T: Copy>(color: &[T], dst: &mut BlurImageMut<T>) {
+    let row_length = dst.row_length();
+    let channels = dst.channels.get_channels();
+    for (src_row, dst_row) in color.chunks_exact(dst.width as usize * (channels - 1)).zip(
+        dst.data
+            .chunks_mut(dst.stride as usize)
+            .take(dst.height as usize),
+    ) {
+        for (src, dst) in src_row
+            .chunks_exact(channels - 1)
+            .zip(dst_row[..row_length].chunks_exact_mut(channels))
+        {
+            dst[..channels - 1].copy_from_slice(src);
+        }
+    }
+}
+
+/// Layout of the image made by [strip_alpha]
+fn color_channels(channels: usize) -> FastBlurChannels {
+    if channels == 4 {
+        FastBlurChannels::Channels3
+    } else {
+        FastBlurChannels::Plane
+    }
+}
+
+/// Runs in-place `filter` over the image premultiplied by alpha when [AlphaMode::Premultiply] is requested
+/// for an image with alpha, for [AlphaMode::Preserve] filters only color channels when alpha is the last one
+/// and otherwise restores original alpha after `filter`,
+/// otherwise runs `filter` directly
+pub(crate) fn filter_alpha_mode<T: AlphaPremultiply>(
+    image: &mut BlurImageMut<T>,
+    alpha_mode: AlphaMode,
+    filter: impl FnOnce(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+) -> Result<(), BlurError> {
+    let alpha_index = match image.channels.alpha_index(image.alpha_position) {
+        Some(alpha_index) if alpha_mode != AlphaMode::Independent => alpha_index,
+        _ => return filter(image),
+    };
+    image.check_layout()?;
+    let channels = image.channels.get_channels();
+    // Alpha is left out of filtering only when color channels keep their indices,
+    // so per-channel settings as EdgeMode::Constant color still apply to the same channels
+    if alpha_mode == AlphaMode::Preserve && alpha_index == channels - 1 {
+        let mut color = strip_alpha(
+            image.data,
+            image.stride as usize,
+            image.row_length(),
+            channels,
+        );
+        filter(&mut BlurImageMut::borrow(
+            &mut color,
+            image.width,
+            image.height,
+            color_channels(channels),
+        ))?;
+        restore_color(&color, image);
+        return Ok(());
+    }
+    if alpha_mode == AlphaMode::Preserve {
+        let alpha = image
+            .data
+            .chunks(image.stride as usize)
+            .take(image.height as usize)
+            .flat_map(|row| {
+                row[..image.row_length()]
+                    .chunks_exact(channels)
+                    .map(|px| px[alpha_index])
+            })
+            .collect::<Vec<T>>();
+        filter(image)?;
+        copy_alpha(&alpha, image.width as usize, 1, 0, image, alpha_index);
+        return Ok(());
+    }
+    premultiply_image(image, alpha_index);
+    let result = filter(image);
+    unpremultiply_image(image, alpha_index);
+    result
+}
+
+/// Runs `filter` from a premultiplied copy of `src` into `dst` when [AlphaMode::Premultiply] is requested
+/// for an image with alpha, for [AlphaMode::Preserve] filters only color channels when alpha is the last one
+/// and copies alpha of `src` into `dst` after `filter`,
+/// otherwise runs `filter` directly
+pub(crate) fn filter_alpha_mode_into<T: AlphaPremultiply + Default>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    alpha_mode: AlphaMode,
+    filter: impl FnOnce(&BlurImage<T>, &mut BlurImageMut<T>) -> Result<(), BlurError>,
+) -> Result<(), BlurError> {
+    let alpha_index = match src.channels.alpha_index(src.alpha_position) {
+        Some(alpha_index) if alpha_mode != AlphaMode::Independent => alpha_index,
+        _ => return filter(src, dst),
+    };
+    src.check_layout()?;
+    let channels = src.channels.get_channels();
+    if alpha_mode == AlphaMode::Preserve && alpha_index == channels - 1 {
+        let color = strip_alpha(src.data, src.stride as usize, src.row_length(), channels);
+        let mut filtered = vec![T::default(); color.len()];
+        filter(
+            &BlurImage::borrow(&color, src.width, src.height, color_channels(channels)),
+            &mut BlurImageMut::borrow(
+                &mut filtered,
+                dst.width,
+                dst.height,
+                color_channels(channels),
+            ),
+        )?;
+        restore_color(&filtered, dst);
+        copy_alpha(
+            src.data,
+            src.stride as usize,
+            channels,
+            alpha_index,
+            dst,
+            alpha_index,
+        );
+        return Ok(());
+    }
+    if alpha_mode == AlphaMode::Preserve {
+        filter(src, dst)?;
+        copy_alpha(
+            src.data,
+            src.stride as usize,
+            channels,
+            alpha_index,
+            dst,
+            alpha_index,
+        );
+        return Ok(());
+    }
+    let row_length = src.row_length();
+    let mut premultiplied = vec![T::default(); row_length * src.height as usize];
+    for (src_row, dst_row) in src
+        .data
+        .chunks(src.stride as usize)
+        .zip(premultiplied.chunks_exact_mut(row_length))
+    {
+        premultiply_row(&src_row[..row_length], dst_row, channels, alpha_index);
+    }
+    filter(
+        &BlurImage::borrow(&premultiplied, src.width, src.height, src.channels)
+            .with_alpha_position(src.alpha_position),
+        dst,
+    )?;
+    unpremultiply_image(dst, alpha_index);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AlphaPosition;
+
+    #[test]
+    fn preserve_filters_only_color_channels() {
+        let src = [10u8, 20, 30, 40, 50, 60, 70, 80];
+        for (alpha_position, filtered_channels) in [
+            (AlphaPosition::Last, FastBlurChannels::Channels3),
+            (AlphaPosition::First, FastBlurChannels::Channels4),
+        ] {
+            let mut image = src;
+            filter_alpha_mode(
+                &mut BlurImageMut::borrow(&mut image, 2, 1, FastBlurChannels::Channels4)
+                    .with_alpha_position(alpha_position),
+                AlphaMode::Preserve,
+                |image| {
+                    assert_eq!(image.channels, filtered_channels);
+                    image.data.iter_mut().for_each(|v| *v += 1);
+                    Ok(())
+                },
+            )
+            .unwrap();
+            let expected = match alpha_position {
+                AlphaPosition::Last => [11, 21, 31, 40, 51, 61, 71, 80],
+                AlphaPosition::First => [10, 21, 31, 41, 50, 61, 71, 81],
+            };
+            assert_eq!(image, expected, "{alpha_position:?}");
+
+            let mut dst = [0u8; 8];
+            filter_alpha_mode_into(
+                &BlurImage::borrow(&src, 2, 1, FastBlurChannels::Channels4)
+                    .with_alpha_position(alpha_position),
+                &mut BlurImageMut::borrow(&mut dst, 2, 1, FastBlurChannels::Channels4)
+                    .with_alpha_position(alpha_position),
+                AlphaMode::Preserve,
+                |src, dst| {
+                    assert_eq!(src.channels, filtered_channels);
+                    for (dst, src) in dst.data.iter_mut().zip(src.data.iter()) {
+                        *dst = src + 1;
+                    }
+                    Ok(())
+                },
+            )
+            .unwrap();
+            assert_eq!(dst, expected, "{alpha_position:?}");
+        }
+    }
+}
@@ -0,0 +1,592 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::{filter_alpha_mode_into, AlphaPremultiply};
+use crate::edge_padding::pad_image;
+use crate::gaussian::get_gaussian_kernel_1d;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+
+const RANGE_TABLE_SIZE: usize = 4096;
+/// Range weights beyond this count of sigmas are treated as zero
+const RANGE_TABLE_EXTENT: f32 = 6.;
+
+/// Gaussian of the range distance sampled into a table
+struct RangeWeights {
+    table: Vec<f32>,
+    scale: f32,
+}
+
+impl RangeWeights {
+    fn new(range_sigma: f32) -> RangeWeights {
+        let scale = (RANGE_TABLE_SIZE - 1) as f32 / (RANGE_TABLE_EXTENT * range_sigma);
+        let table = (0..RANGE_TABLE_SIZE)
+            .map(|i| {
+                let distance = i as f32 / scale / range_sigma;
+                f32::exp(-0.5 * distance * distance)
+            })
+            .collect();
+        RangeWeights { table, scale }
+    }
+
+    #[inline(always)]
+    fn weight(&self, distance: f32) -> f32 {
+        let index = (distance * self.scale + 0.5) as usize;
+        if index < RANGE_TABLE_SIZE {
+            unsafe { *self.table.get_unchecked(index) }
+        } else {
+            0.
+        }
+    }
+}
+
+/// One dimensional bilateral kernel over lines of a padded image.
+///
+/// Every line holds `length + 2 * radius` samples `step` apart, output sample `i`
+/// is centered at padded sample `i + radius`. With `clip` taps outside of `radius..radius + length` are skipped.
+struct BilateralPass<'a> {
+    kernel: &'a [f32],
+    range: &'a RangeWeights,
+    radius: usize,
+    length: usize,
+    clip: bool,
+}
+
+impl BilateralPass<'_> {
+    #[inline(always)]
+    fn filter<T: Copy + AsPrimitive<f32>, const CHANNELS: usize>(
+        &self,
+        line: &[T],
+        step: usize,
+        i: usize,
+    ) -> [f32; CHANNELS] {
+        let diameter = self.radius * 2 + 1;
+        let (first, last) = if self.clip {
+            (
+                self.radius.saturating_sub(i),
+                std::cmp::min(diameter - 1, self.length - 1 + self.radius - i),
+            )
+        } else {
+            (0, diameter - 1)
+        };
+        let center_offset = (i + self.radius) * step;
+        let mut center = [0f32; CHANNELS];
+        for (c, dst) in center.iter_mut().enumerate() {
+            *dst = line[center_offset + c].as_();
+        }
+        let mut sums = [0f32; CHANNELS];
+        let mut weights = 0f32;
+        for (k, &spatial) in self.kernel.iter().enumerate().take(last + 1).skip(first) {
+            let offset = (i + k) * step;
+            let mut sample = [0f32; CHANNELS];
+            let mut distance = 0f32;
+            for c in 0..CHANNELS {
+                sample[c] = line[offset + c].as_();
+                distance += (sample[c] - center[c]).abs();
+            }
+            let weight = spatial * self.range.weight(distance);
+            for c in 0..CHANNELS {
+                sums[c] += sample[c] * weight;
+            }
+            weights += weight;
+        }
+        let norm = 1. / weights;
+        sums.map(|v| v * norm)
+    }
+}
+
+/// Runs `worker` over lines `0..count` split between threads
+fn bilateral_lines<F: Fn(usize, usize) + Send + Sync>(
+    count: usize,
+    thread_count: usize,
+    threading_policy: ThreadingPolicy,
+    worker: F,
+) {
+    if thread_count == 1 {
+        worker(0, count);
+        return;
+    }
+    let pool = threading_policy.get_pool(thread_count);
+    let worker = &worker;
+    pool.scope(|scope| {
+        let segment_size = count / thread_count;
+        for i in 0..thread_count {
+            let start = i * segment_size;
+            let mut end = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end = count;
+            }
+            scope.spawn(move |_| worker(start, end));
+        }
+    });
+}
+
+fn bilateral_filter_impl<
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32>,
+    const CHANNELS: usize,
+>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel_size: u32,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let radius = kernel_size as usize / 2;
+    let kernel = get_gaussian_kernel_1d(kernel_size, spatial_sigma);
+    let range = RangeWeights::new(range_sigma);
+    let clip = edge_mode == EdgeMode::KernelClip;
+    let padded = pad_image(src, radius, edge_mode);
+    let padded_stride = (width + radius * 2) * CHANNELS;
+    let padded_height = height + radius * 2;
+    let thread_count = threading_policy.get_threads_count(src.width, padded_height as u32);
+
+    // Horizontal pass keeps padded rows, so vertical pass reads them as its edge
+    let row_length = width * CHANNELS;
+    let mut horizontal = vec![0f32; row_length * padded_height];
+    let horizontal_pass = BilateralPass {
+        kernel: &kernel,
+        range: &range,
+        radius,
+        length: width,
+        clip,
+    };
+    let unsafe_horizontal = UnsafeSlice::new(&mut horizontal);
+    bilateral_lines(
+        padded_height,
+        thread_count,
+        threading_policy,
+        |start_y, end_y| {
+            for y in start_y..end_y {
+                let line = &padded[y * padded_stride..(y + 1) * padded_stride];
+                for x in 0..width {
+                    let v = horizontal_pass.filter::<T, CHANNELS>(line, CHANNELS, x);
+                    let offset = y * row_length + x * CHANNELS;
+                    for (c, v) in v.into_iter().enumerate() {
+                        unsafe {
+                            unsafe_horizontal.write(offset + c, v);
+                        }
+                    }
+                }
+            }
+        },
+    );
+
+    let vertical_pass = BilateralPass {
+        kernel: &kernel,
+        range: &range,
+        radius,
+        length: height,
+        clip,
+    };
+    let horizontal = &horizontal;
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+    bilateral_lines(height, thread_count, threading_policy, |start_y, end_y| {
+        for y in start_y..end_y {
+            for x in 0..width {
+                let line = &horizontal[x * CHANNELS..];
+                let v = vertical_pass.filter::<f32, CHANNELS>(line, row_length, y);
+                let offset = y * dst_stride + x * CHANNELS;
+                for (c, v) in v.into_iter().enumerate() {
+                    unsafe {
+                        unsafe_dst.write(offset + c, v.to_());
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn bilateral_filter_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel_size: u32,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    if kernel_size.is_multiple_of(2) {
+        return Err(BlurError::KernelSizeNotOdd(kernel_size));
+    }
+    for sigma in [spatial_sigma, range_sigma] {
+        if sigma <= 0f32 || !sigma.is_finite() {
+            return Err(BlurError::NegativeOrZeroSigma);
+        }
+    }
+    let _dispatcher = match src.channels {
+        FastBlurChannels::Plane => bilateral_filter_impl::<T, 1>,
+        FastBlurChannels::Channels2 => bilateral_filter_impl::<T, 2>,
+        FastBlurChannels::Channels3 => bilateral_filter_impl::<T, 3>,
+        FastBlurChannels::Channels4 => bilateral_filter_impl::<T, 4>,
+    };
+    _dispatcher(
+        src,
+        dst,
+        kernel_size,
+        spatial_sigma,
+        range_sigma,
+        edge_mode,
+        threading_policy,
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn bilateral_filter_alpha<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel_size: u32,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32> + AlphaPremultiply,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        bilateral_filter_dispatch(
+            src,
+            dst,
+            kernel_size,
+            spatial_sigma,
+            range_sigma,
+            edge_mode,
+            threading_policy,
+        )
+    })
+}
+
+/// Performs bilateral filter on the image.
+///
+/// Edge preserving smoothing, neighbours are weighted by the distance to the pixel
+/// and by the difference of their colors, so edges stronger than `range_sigma` stay sharp.
+/// Uses separable approximation: horizontal bilateral pass followed by the vertical one.
+/// Color difference is the sum of absolute differences of all channels.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of the spatial kernel. Must be odd.
+/// * `spatial_sigma` - Sigma of the spatial gaussian, in pixels
+/// * `range_sigma` - Sigma of the range gaussian, in the range of the image storage, e.g. `25.` is a moderate value for `u8`
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or any of sigmas is not positive
+#[allow(clippy::too_many_arguments)]
+pub fn bilateral_filter(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    kernel_size: u32,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    bilateral_filter_alpha(
+        src,
+        dst,
+        kernel_size,
+        spatial_sigma,
+        range_sigma,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs bilateral filter on the 16-bit image.
+///
+/// Edge preserving smoothing, neighbours are weighted by the distance to the pixel
+/// and by the difference of their colors, so edges stronger than `range_sigma` stay sharp.
+/// Uses separable approximation: horizontal bilateral pass followed by the vertical one.
+/// Color difference is the sum of absolute differences of all channels.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of the spatial kernel. Must be odd.
+/// * `spatial_sigma` - Sigma of the spatial gaussian, in pixels
+/// * `range_sigma` - Sigma of the range gaussian, in the range of the image storage, e.g. `6500.` is a moderate value for 16-bit
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or any of sigmas is not positive
+#[allow(clippy::too_many_arguments)]
+pub fn bilateral_filter_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    kernel_size: u32,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    bilateral_filter_alpha(
+        src,
+        dst,
+        kernel_size,
+        spatial_sigma,
+        range_sigma,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs bilateral filter on the image in f32.
+///
+/// Edge preserving smoothing, neighbours are weighted by the distance to the pixel
+/// and by the difference of their colors, so edges stronger than `range_sigma` stay sharp.
+/// Uses separable approximation: horizontal bilateral pass followed by the vertical one.
+/// Color difference is the sum of absolute differences of all channels.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of the spatial kernel. Must be odd.
+/// * `spatial_sigma` - Sigma of the spatial gaussian, in pixels
+/// * `range_sigma` - Sigma of the range gaussian, in the range of the image storage, e.g. `0.1` is a moderate value for images in `0..1`
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or any of sigmas is not positive
+#[allow(clippy::too_many_arguments)]
+pub fn bilateral_filter_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    kernel_size: u32,
+    spatial_sigma: f32,
+    range_sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    bilateral_filter_alpha(
+        src,
+        dst,
+        kernel_size,
+        spatial_sigma,
+        range_sigma,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+
+    /// Horizontal then vertical bilateral pass written straight from the definition
+    fn reference_bilateral(
+        image: &ReferenceImage,
+        kernel_size: usize,
+        spatial_sigma: f32,
+        range_sigma: f32,
+        edge_mode: EdgeMode,
+    ) -> Vec<f32> {
+        let radius = kernel_size as i64 / 2;
+        let (width, height, channels) = (image.width, image.height, image.channels);
+        let filter = |center: &[f32], taps: &mut dyn Iterator<Item = (i64, Option<Vec<f32>>)>| {
+            let mut sums = vec![0f64; channels];
+            let mut weights = 0f64;
+            for (k, tap) in taps {
+                let Some(tap) = tap else { continue };
+                let distance: f32 = tap.iter().zip(center).map(|(a, b)| (a - b).abs()).sum();
+                let range = if distance < 6. * range_sigma {
+                    (-0.5 * (distance / range_sigma).powi(2)).exp()
+                } else {
+                    0.
+                };
+                let weight = (-0.5 * (k as f32 / spatial_sigma).powi(2)).exp() * range;
+                for (sum, &v) in sums.iter_mut().zip(&tap) {
+                    *sum += (v * weight) as f64;
+                }
+                weights += weight as f64;
+            }
+            sums.into_iter()
+                .map(|v| (v / weights) as f32)
+                .collect::<Vec<f32>>()
+        };
+        let pixel = |x: i64, y: i64| -> Option<Vec<f32>> {
+            (0..channels)
+                .map(|c| image.sample(x, y, c, edge_mode))
+                .collect()
+        };
+        // Rows outside of the image are filtered as well, vertical pass reads them as its edge
+        let horizontal = (-radius..height as i64 + radius)
+            .flat_map(|y| (0..width as i64).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let center = pixel(x, y)?;
+                Some(filter(
+                    &center,
+                    &mut (-radius..=radius).map(|k| (k, pixel(x + k, y))),
+                ))
+            })
+            .collect::<Vec<Option<Vec<f32>>>>();
+        let mut dst = Vec::with_capacity(image.data.len());
+        for y in 0..height as i64 {
+            for x in 0..width {
+                let row = |y: i64| (y + radius) as usize * width + x;
+                let center = horizontal[row(y)].clone().unwrap();
+                dst.extend(filter(
+                    &center,
+                    &mut (-radius..=radius).map(|k| (k, horizontal[row(y + k)].clone())),
+                ));
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn bilateral_filter_f32_matches_reference() {
+        let (width, height) = (17usize, 12usize);
+        let src = noise(width * height * 3, 256, 11)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 3,
+        };
+        for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.])
+            .into_iter()
+            .chain([EdgeMode::KernelClip])
+        {
+            let mut dst = vec![0f32; src.len()];
+            bilateral_filter_f32(
+                &BlurImage::borrow(
+                    &src,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                &mut BlurImageMut::borrow(
+                    &mut dst,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                7,
+                1.5,
+                0.3,
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Fixed(2),
+            )
+            .unwrap();
+            let expected = reference_bilateral(&image, 7, 1.5, 0.3, edge_mode);
+            assert_close(&dst, &expected, 2e-3, &format!("{edge_mode:?}"));
+        }
+    }
+
+    #[test]
+    fn bilateral_filter_keeps_constant_image() {
+        let (width, height) = (15u32, 22u32);
+        let pixel = [200u8, 17, 96, 255];
+        let src = pixel.repeat(width as usize * height as usize);
+        for edge_mode in edge_modes(pixel.map(|v| v as f32)) {
+            let mut dst = vec![0u8; src.len()];
+            bilateral_filter(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels4),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels4),
+                9,
+                2.,
+                25.,
+                edge_mode,
+                AlphaMode::Premultiply,
+                ThreadingPolicy::Fixed(3),
+            )
+            .unwrap();
+            assert_eq!(dst, src, "{edge_mode:?}");
+        }
+    }
+
+    #[test]
+    fn bilateral_filter_u16_keeps_constant_image() {
+        let (width, height) = (13u32, 8u32);
+        let src = vec![51234u16; (width * height) as usize];
+        for edge_mode in edge_modes([51234.; 4]) {
+            let mut dst = vec![0u16; src.len()];
+            bilateral_filter_u16(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Plane),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Plane),
+                7,
+                1.5,
+                6500.,
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            assert_eq!(dst, src, "{edge_mode:?}");
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::EdgeMode;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Pair of expected and received sizes used to report a mismatch
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct MismatchedSize {
+    pub expected: usize,
+    pub received: usize,
+}
+
+/// Errors that may be returned by any filter of the library
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlurError {
+    /// Image width or height is zero
+    ZeroBaseSize,
+    /// Buffer is shorter than `stride * (height - 1) + width * channels`
+    MinimumSliceSizeMismatch(MismatchedSize),
+    /// Stride is less than `width * channels`
+    MinimumStrideSizeMismatch(MismatchedSize),
+    /// Source and destination images have different width, height or channels layout
+    ImagesMustMatch,
+    /// Kernel size must be odd, even kernels with unbalanced center is not accepted
+    KernelSizeNotOdd(u32),
+    /// Sigma of gaussian kernel must be positive and finite
+    NegativeOrZeroSigma,
+    /// Sigma is out of range supported by the filter
+    SigmaOutOfRange { sigma: f32, min: f32, max: f32 },
+    /// Radius is out of range supported by the filter
+    RadiusOutOfRange { radius: u32, min: u32, max: u32 },
+    /// Edge mode is not supported by the filter
+    EdgeModeNotSupported(EdgeMode),
+    /// Channels count is not one of 1, 2, 3 or 4
+    UnsupportedChannels(usize),
+    /// Value doesn't correspond to any edge mode
+    UnknownEdgeMode(usize),
+    /// Strength of the effect must be finite
+    InvalidStrength,
+    /// Polygonal aperture must have at least 3 blades
+    InvalidAperture,
+    /// Radius map contains negative or not finite radius
+    InvalidRadiusMap,
+    /// Focus band must be finite and have non-negative width and falloff
+    InvalidFocusBand,
+    /// Kernel weights count doesn't match kernel width and height
+    KernelShapeMismatch(MismatchedSize),
+    /// Kernel anchor is outside of the kernel
+    InvalidAnchor,
+    /// [crate::ThreadingPolicy::Fixed] requested zero threads
+    ZeroThreadsCount,
+}
+
+impl Display for BlurError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlurError::ZeroBaseSize => f.write_str("Image width and height must not be zero"),
+            BlurError::MinimumSliceSizeMismatch(size) => f.write_fmt(format_args!(
+                "Buffer must have at least {} elements, but it has {}",
+                size.expected, size.received
+            )),
+            BlurError::MinimumStrideSizeMismatch(size) => f.write_fmt(format_args!(
+                "Stride must be at least {}, but it is {}",
+                size.expected, size.received
+            )),
+            BlurError::ImagesMustMatch => {
+                f.write_str("Source and destination images must have the same size and layout")
+            }
+            BlurError::KernelSizeNotOdd(kernel_size) => f.write_fmt(format_args!(
+                "Kernel size must be odd, but it is {kernel_size}"
+            )),
+            BlurError::NegativeOrZeroSigma => f.write_str("Sigma must be positive"),
+            BlurError::SigmaOutOfRange { sigma, min, max } => f.write_fmt(format_args!(
+                "Sigma {sigma} is out of supported range {min}..={max}"
+            )),
+            BlurError::RadiusOutOfRange { radius, min, max } => f.write_fmt(format_args!(
+                "Radius {radius} is out of supported range {min}..={max}"
+            )),
+            BlurError::EdgeModeNotSupported(edge_mode) => {
+                f.write_fmt(format_args!("Edge mode {edge_mode:?} is not supported"))
+            }
+            BlurError::UnsupportedChannels(channels) => {
+                f.write_fmt(format_args!("Channels count {channels} is not supported"))
+            }
+            BlurError::UnknownEdgeMode(value) => {
+                f.write_fmt(format_args!("Unknown edge mode for value: {value}"))
+            }
+            BlurError::InvalidStrength => f.write_str("Strength must be finite"),
+            BlurError::InvalidAperture => {
+                f.write_str("Polygonal aperture must have at least 3 blades")
+            }
+            BlurError::InvalidRadiusMap => {
+                f.write_str("Radius map must contain only finite non-negative radii")
+            }
+            BlurError::InvalidFocusBand => {
+                f.write_str("Focus band must be finite and have non-negative width and falloff")
+            }
+            BlurError::KernelShapeMismatch(size) => f.write_fmt(format_args!(
+                "Kernel must have {} weights, but it has {}",
+                size.expected, size.received
+            )),
+            BlurError::InvalidAnchor => f.write_str("Anchor must lie inside the kernel"),
+            BlurError::ZeroThreadsCount => f.write_str("Fixed threads count must not be zero"),
+        }
+    }
+}
+
+impl Error for BlurError {}
+
+#[inline]
+pub(crate) fn check_radius(radius: u32, min: u32, max: u32) -> Result<(), BlurError> {
+    if radius < min || radius > max {
+        return Err(BlurError::RadiusOutOfRange { radius, min, max });
+    }
+    Ok(())
+}
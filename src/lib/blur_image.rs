@@ -0,0 +1,283 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::{AlphaPosition, FastBlurChannels};
+use crate::{BlurError, MismatchedSize};
+
+/// Immutable view over an image
+///
+/// Carries everything that describes the layout of a buffer so that filters never take
+/// loosely ordered `stride`, `width` and `height` arguments.
+#[derive(Debug, Copy, Clone)]
+pub struct BlurImage<'a, T> {
+    /// Image buffer
+    pub data: &'a [T],
+    /// Row length in elements, not in bytes. Usually it is `width * channels`
+    pub stride: u32,
+    /// Width of the image
+    pub width: u32,
+    /// Height of the image
+    pub height: u32,
+    /// Channels layout of the image
+    pub channels: FastBlurChannels,
+    /// Position of alpha for layouts that have it
+    pub alpha_position: AlphaPosition,
+}
+
+/// Mutable view over an image
+///
+/// Used as a destination for filters and as an image to process for in-place filters.
+#[derive(Debug)]
+pub struct BlurImageMut<'a, T> {
+    /// Image buffer
+    pub data: &'a mut [T],
+    /// Row length in elements, not in bytes. Usually it is `width * channels`
+    pub stride: u32,
+    /// Width of the image
+    pub width: u32,
+    /// Height of the image
+    pub height: u32,
+    /// Channels layout of the image
+    pub channels: FastBlurChannels,
+    /// Position of alpha for layouts that have it
+    pub alpha_position: AlphaPosition,
+}
+
+impl<'a, T> BlurImage<'a, T> {
+    /// Borrows tightly packed image where stride is `width * channels`
+    pub fn borrow(data: &'a [T], width: u32, height: u32, channels: FastBlurChannels) -> Self {
+        BlurImage {
+            data,
+            stride: packed_stride(width, channels),
+            width,
+            height,
+            channels,
+            alpha_position: AlphaPosition::Last,
+        }
+    }
+
+    /// Borrows image which rows are padded to `stride` elements
+    pub fn borrow_with_stride(
+        data: &'a [T],
+        stride: u32,
+        width: u32,
+        height: u32,
+        channels: FastBlurChannels,
+    ) -> Self {
+        BlurImage {
+            data,
+            stride,
+            width,
+            height,
+            channels,
+            alpha_position: AlphaPosition::Last,
+        }
+    }
+
+    /// Declares position of alpha, by default alpha is the last channel
+    pub fn with_alpha_position(mut self, alpha_position: AlphaPosition) -> Self {
+        self.alpha_position = alpha_position;
+        self
+    }
+
+    /// Length of one row of pixels in elements, without padding
+    #[inline]
+    pub fn row_length(&self) -> usize {
+        self.width as usize * self.channels.get_channels()
+    }
+
+    /// Checks that size is not zero, stride covers the row and buffer covers every row,
+    /// the last row may be cropped to `width * channels`
+    pub fn check_layout(&self) -> Result<(), BlurError> {
+        check_layout(
+            self.data.len(),
+            self.stride,
+            self.width,
+            self.height,
+            self.channels,
+        )
+    }
+
+    /// Checks that destination has the same width, height, channels layout and alpha position
+    pub fn size_matches(&self, other: &BlurImageMut<'_, T>) -> Result<(), BlurError> {
+        if self.width != other.width
+            || self.height != other.height
+            || self.channels != other.channels
+            || self.alpha_position != other.alpha_position
+        {
+            return Err(BlurError::ImagesMustMatch);
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> BlurImageMut<'a, T> {
+    /// Borrows tightly packed image where stride is `width * channels`
+    pub fn borrow(data: &'a mut [T], width: u32, height: u32, channels: FastBlurChannels) -> Self {
+        BlurImageMut {
+            data,
+            stride: packed_stride(width, channels),
+            width,
+            height,
+            channels,
+            alpha_position: AlphaPosition::Last,
+        }
+    }
+
+    /// Borrows image which rows are padded to `stride` elements
+    pub fn borrow_with_stride(
+        data: &'a mut [T],
+        stride: u32,
+        width: u32,
+        height: u32,
+        channels: FastBlurChannels,
+    ) -> Self {
+        BlurImageMut {
+            data,
+            stride,
+            width,
+            height,
+            channels,
+            alpha_position: AlphaPosition::Last,
+        }
+    }
+
+    /// Declares position of alpha, by default alpha is the last channel
+    pub fn with_alpha_position(mut self, alpha_position: AlphaPosition) -> Self {
+        self.alpha_position = alpha_position;
+        self
+    }
+
+    /// Length of one row of pixels in elements, without padding
+    #[inline]
+    pub fn row_length(&self) -> usize {
+        self.width as usize * self.channels.get_channels()
+    }
+
+    /// Checks that size is not zero, stride covers the row and buffer covers every row,
+    /// the last row may be cropped to `width * channels`
+    pub fn check_layout(&self) -> Result<(), BlurError> {
+        check_layout(
+            self.data.len(),
+            self.stride,
+            self.width,
+            self.height,
+            self.channels,
+        )
+    }
+
+    /// Copies pixels of the same sized `src` into this image, strides may differ
+    pub(crate) fn copy_from(&mut self, src: &BlurImage<'_, T>)
+    where
+        T: Copy,
+    {
+        let row_length = self.row_length();
+        for (dst_row, src_row) in self
+            .data
+            .chunks_mut(self.stride as usize)
+            .zip(src.data.chunks(src.stride as usize))
+            .take(self.height as usize)
+        {
+            dst_row[..row_length].copy_from_slice(&src_row[..row_length]);
+        }
+    }
+
+    /// Reborrows this image as immutable view
+    pub fn to_immutable_ref(&self) -> BlurImage<'_, T> {
+        BlurImage {
+            data: self.data,
+            stride: self.stride,
+            width: self.width,
+            height: self.height,
+            channels: self.channels,
+            alpha_position: self.alpha_position,
+        }
+    }
+}
+
+/// Stride of tightly packed rows, zero when `width * channels` does not fit into `u32`
+/// so [check_layout] rejects the view instead of working with a wrapped stride
+fn packed_stride(width: u32, channels: FastBlurChannels) -> u32 {
+    width
+        .checked_mul(channels.get_channels() as u32)
+        .unwrap_or(0)
+}
+
+fn check_layout(
+    data_length: usize,
+    stride: u32,
+    width: u32,
+    height: u32,
+    channels: FastBlurChannels,
+) -> Result<(), BlurError> {
+    if width == 0 || height == 0 {
+        return Err(BlurError::ZeroBaseSize);
+    }
+    let row_length = width as usize * channels.get_channels();
+    if (stride as usize) < row_length {
+        return Err(BlurError::MinimumStrideSizeMismatch(MismatchedSize {
+            expected: row_length,
+            received: stride as usize,
+        }));
+    }
+    let required_length = stride as usize * (height as usize - 1) + row_length;
+    if data_length < required_length {
+        return Err(BlurError::MinimumSliceSizeMismatch(MismatchedSize {
+            expected: required_length,
+            received: data_length,
+        }));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrow_rejects_overflowing_stride() {
+        let data = [0u8; 4];
+        let image = BlurImage::borrow(&data, u32::MAX, 1, FastBlurChannels::Channels4);
+        assert!(matches!(
+            image.check_layout(),
+            Err(BlurError::MinimumStrideSizeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn check_layout_accepts_cropped_last_row() {
+        let data = [0u8; 10 * 2 + 3 * 3];
+        let image = BlurImage::borrow_with_stride(&data, 10, 3, 3, FastBlurChannels::Channels3);
+        assert!(image.check_layout().is_ok());
+        let short =
+            BlurImage::borrow_with_stride(&data[..28], 10, 3, 3, FastBlurChannels::Channels3);
+        assert!(matches!(
+            short.check_layout(),
+            Err(BlurError::MinimumSliceSizeMismatch(_))
+        ));
+    }
+}
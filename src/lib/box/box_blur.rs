@@ -25,28 +25,35 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use colorutils_rs::linear_to_planar::linear_to_plane;
-use colorutils_rs::planar_to_linear::plane_to_linear;
-use colorutils_rs::{
-    linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
-};
+use crate::linear_transform::{from_linear_transformer, to_linear_transformer};
+use crate::threading_policy::BlurThreadPool;
+use colorutils_rs::TransferFunction;
 use num_traits::cast::FromPrimitive;
 use num_traits::AsPrimitive;
-use rayon::ThreadPool;
 
+use crate::alpha_mode::filter_alpha_mode_into;
+use crate::blur_error::check_radius;
 use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::reflect_index;
+use crate::edge_padding::filter_constant_padded_into;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::r#box::box_blur_neon::*;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::r#box::box_blur_sse::*;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::ThreadingPolicy;
+use crate::{
+    clamp_edge, reflect_101, AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode,
+    ThreadingPolicy,
+};
 
-fn box_blur_horizontal_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
+#[allow(clippy::too_many_arguments)]
+fn box_blur_horizontal_pass_impl<
+    T,
+    J,
+    const CHANNELS_CONFIGURATION: usize,
+    const EDGE_MODE: usize,
+>(
     src: &[T],
     src_stride: u32,
     unsafe_dst: &UnsafeSlice<T>,
@@ -72,33 +79,21 @@ fn box_blur_horizontal_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
         + AsPrimitive<f32>,
     f32: ToStorage<T>,
 {
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let kernel_size = radius * 2 + 1;
-    let edge_count = J::from_u32((kernel_size / 2) + 1).unwrap();
     let half_kernel = kernel_size / 2;
 
-    let weight = 1f32 / (radius * 2) as f32;
+    let weight = 1f32 / kernel_size as f32;
 
     for y in start_y..end_y {
-        let mut weight0;
+        let mut weight0 = J::from_u32(0u32).unwrap();
         let mut weight1 = J::from_u32(0u32).unwrap();
         let mut weight2 = J::from_u32(0u32).unwrap();
         let mut weight3 = J::from_u32(0u32).unwrap();
         let y_src_shift = (y * src_stride) as usize;
         let y_dst_shift = (y * dst_stride) as usize;
-        // replicate edge
-        weight0 = (unsafe { *src.get_unchecked(y_src_shift) }.as_()) * edge_count;
-        if CHANNELS_CONFIGURATION > 1 {
-            weight1 = (unsafe { *src.get_unchecked(y_src_shift + 1) }.as_()) * edge_count;
-        }
-        if CHANNELS_CONFIGURATION > 2 {
-            weight2 = (unsafe { *src.get_unchecked(y_src_shift + 2) }.as_()) * edge_count;
-        }
-        if CHANNELS_CONFIGURATION == 4 {
-            weight3 = (unsafe { *src.get_unchecked(y_src_shift + 3) }.as_()) * edge_count;
-        }
-
-        for x in 1..std::cmp::min(half_kernel, width) {
-            let px = x as usize * CHANNELS_CONFIGURATION;
+        for x in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let px = clamp_edge!(edge_mode, x, 0i64, width as i64 - 1) * CHANNELS_CONFIGURATION;
             weight0 += unsafe { *src.get_unchecked(y_src_shift + px) }.as_();
             if CHANNELS_CONFIGURATION > 1 {
                 weight1 += unsafe { *src.get_unchecked(y_src_shift + px + 1) }.as_();
@@ -112,9 +107,18 @@ fn box_blur_horizontal_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
         }
 
         for x in 0..width {
-            let next = std::cmp::min(x + half_kernel, width - 1) as usize * CHANNELS_CONFIGURATION;
-            let previous =
-                std::cmp::max(x as i64 - half_kernel as i64, 0) as usize * CHANNELS_CONFIGURATION;
+            let next = clamp_edge!(
+                edge_mode,
+                x as i64 + half_kernel as i64,
+                0i64,
+                width as i64 - 1
+            ) * CHANNELS_CONFIGURATION;
+            let previous = clamp_edge!(
+                edge_mode,
+                x as i64 - half_kernel as i64 - 1,
+                0i64,
+                width as i64 - 1
+            ) * CHANNELS_CONFIGURATION;
             let px = x as usize * CHANNELS_CONFIGURATION;
             // Prune previous and add next and compute mean
 
@@ -141,7 +145,7 @@ fn box_blur_horizontal_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
 
             let write_offset = y_dst_shift + px;
             unsafe {
-                unsafe_dst.write(write_offset + 0, (weight0.as_() * weight).to_());
+                unsafe_dst.write(write_offset, (weight0.as_() * weight).to_());
                 if CHANNELS_CONFIGURATION > 1 {
                     unsafe_dst.write(write_offset + 1, (weight1.as_() * weight).to_());
                 }
@@ -156,10 +160,8 @@ fn box_blur_horizontal_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
     }
 }
 
-fn box_blur_horizontal_pass<
-    T: FromPrimitive + Default + Send + Sync,
-    const CHANNEL_CONFIGURATION: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn box_blur_horizontal_pass<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
@@ -167,10 +169,14 @@ fn box_blur_horizontal_pass<
     width: u32,
     height: u32,
     radius: u32,
-    pool: &ThreadPool,
+    pool: &BlurThreadPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
         + std::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
@@ -179,6 +185,7 @@ fn box_blur_horizontal_pass<
         + AsPrimitive<f64>,
     f32: ToStorage<T>,
 {
+    #[allow(clippy::type_complexity)]
     let mut _dispatcher_horizontal: fn(
         src: &[T],
         src_stride: u32,
@@ -188,29 +195,28 @@ fn box_blur_horizontal_pass<
         radius: u32,
         start_y: u32,
         end_y: u32,
-    ) = box_blur_horizontal_pass_impl::<T, u32, CHANNEL_CONFIGURATION>;
+    ) = box_blur_horizontal_pass_impl::<T, u32, CHANNEL_CONFIGURATION, EDGE_MODE>;
     if std::any::type_name::<T>() == "f32"
         || std::any::type_name::<T>() == "f16"
         || std::any::type_name::<T>() == "half::f16"
         || std::any::type_name::<T>() == "half::binary16::f16"
     {
-        _dispatcher_horizontal = box_blur_horizontal_pass_impl::<T, f32, CHANNEL_CONFIGURATION>;
+        _dispatcher_horizontal =
+            box_blur_horizontal_pass_impl::<T, f32, CHANNEL_CONFIGURATION, EDGE_MODE>;
     }
     if CHANNEL_CONFIGURATION >= 3 {
         #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
         {
             if std::any::type_name::<T>() == "u8" {
-                _dispatcher_horizontal = box_blur_horizontal_pass_neon::<T, CHANNEL_CONFIGURATION>;
+                _dispatcher_horizontal =
+                    box_blur_horizontal_pass_neon::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
             }
         }
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            if std::any::type_name::<T>() == "u8" {
+            if is_x86_feature_detected!("sse4.1") && std::any::type_name::<T>() == "u8" {
                 _dispatcher_horizontal =
-                    box_blur_horizontal_pass_sse::<T, { CHANNEL_CONFIGURATION }>;
+                    box_blur_horizontal_pass_sse::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
             }
         }
     }
@@ -240,7 +246,8 @@ fn box_blur_horizontal_pass<
     });
 }
 
-fn box_blur_vertical_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
+#[allow(clippy::too_many_arguments)]
+fn box_blur_vertical_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     unsafe_dst: &UnsafeSlice<T>,
@@ -267,33 +274,21 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
         + AsPrimitive<f32>,
     f32: ToStorage<T>,
 {
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let kernel_size = radius * 2 + 1;
-
-    let edge_count = J::from_u32((kernel_size / 2) + 1).unwrap();
     let half_kernel = kernel_size / 2;
 
-    let weight = 1f32 / (radius * 2) as f32;
+    let weight = 1f32 / kernel_size as f32;
 
     for x in start_x..end_x {
-        let mut weight0;
+        let mut weight0 = J::from_u32(0u32).unwrap();
         let mut weight1 = J::from_u32(0u32).unwrap();
         let mut weight2 = J::from_u32(0u32).unwrap();
         let mut weight3 = J::from_u32(0u32).unwrap();
-        // replicate edge
         let px = x as usize * CHANNELS_CONFIGURATION;
-        weight0 = (unsafe { *src.get_unchecked(px) }.as_()) * edge_count;
-        if CHANNELS_CONFIGURATION > 1 {
-            weight1 = (unsafe { *src.get_unchecked(px + 1) }.as_()) * edge_count;
-        }
-        if CHANNELS_CONFIGURATION > 2 {
-            weight2 = (unsafe { *src.get_unchecked(px + 2) }.as_()) * edge_count;
-        }
-        if CHANNELS_CONFIGURATION == 4 {
-            weight3 = (unsafe { *src.get_unchecked(px + 3) }.as_()) * edge_count;
-        }
-
-        for y in 1..std::cmp::min(half_kernel, height) {
-            let y_src_shift = y as usize * src_stride as usize;
+        for y in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let y_src_shift =
+                clamp_edge!(edge_mode, y, 0i64, height as i64 - 1) * src_stride as usize;
             weight0 += unsafe { *src.get_unchecked(y_src_shift + px) }.as_();
             if CHANNELS_CONFIGURATION > 1 {
                 weight1 += unsafe { *src.get_unchecked(y_src_shift + px + 1) }.as_();
@@ -307,9 +302,18 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
         }
 
         for y in 0..height {
-            let next = std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
-            let previous =
-                std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+            let next = clamp_edge!(
+                edge_mode,
+                y as i64 + half_kernel as i64,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
+            let previous = clamp_edge!(
+                edge_mode,
+                y as i64 - half_kernel as i64 - 1,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
             let y_dst_shift = dst_stride as usize * y as usize;
             // Prune previous and add next and compute mean
 
@@ -336,7 +340,7 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
 
             let write_offset = y_dst_shift + px;
             unsafe {
-                unsafe_dst.write(write_offset + 0, (weight0.as_() * weight).to_());
+                unsafe_dst.write(write_offset, (weight0.as_() * weight).to_());
                 if CHANNELS_CONFIGURATION > 1 {
                     unsafe_dst.write(write_offset + 1, (weight1.as_() * weight).to_());
                 }
@@ -351,10 +355,8 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNELS_CONFIGURATION: usize>(
     }
 }
 
-fn box_blur_vertical_pass<
-    T: FromPrimitive + Default + Sync + Send + Copy,
-    const CHANNEL_CONFIGURATION: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn box_blur_vertical_pass<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
@@ -362,10 +364,15 @@ fn box_blur_vertical_pass<
     width: u32,
     height: u32,
     radius: u32,
-    pool: &ThreadPool,
+    pool: &BlurThreadPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + Copy
+        + std::ops::AddAssign
         + std::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
@@ -374,6 +381,7 @@ fn box_blur_vertical_pass<
         + AsPrimitive<f64>,
     f32: ToStorage<T>,
 {
+    #[allow(clippy::type_complexity)]
     let mut _dispatcher_vertical: fn(
         src: &[T],
         src_stride: u32,
@@ -384,28 +392,28 @@ fn box_blur_vertical_pass<
         radius: u32,
         start_x: u32,
         end_x: u32,
-    ) = box_blur_vertical_pass_impl::<T, u32, CHANNEL_CONFIGURATION>;
+    ) = box_blur_vertical_pass_impl::<T, u32, CHANNEL_CONFIGURATION, EDGE_MODE>;
     if std::any::type_name::<T>() == "f32"
         || std::any::type_name::<T>() == "f16"
         || std::any::type_name::<T>() == "half::f16"
         || std::any::type_name::<T>() == "half::binary16::f16"
     {
-        _dispatcher_vertical = box_blur_vertical_pass_impl::<T, f32, CHANNEL_CONFIGURATION>;
+        _dispatcher_vertical =
+            box_blur_vertical_pass_impl::<T, f32, CHANNEL_CONFIGURATION, EDGE_MODE>;
     }
     if CHANNEL_CONFIGURATION >= 3 {
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            if std::any::type_name::<T>() == "u8" {
-                _dispatcher_vertical = box_blur_vertical_pass_sse::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("sse4.1") && std::any::type_name::<T>() == "u8" {
+                _dispatcher_vertical =
+                    box_blur_vertical_pass_sse::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
             }
         }
         #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
         {
             if std::any::type_name::<T>() == "u8" {
-                _dispatcher_vertical = box_blur_vertical_pass_neon::<T, CHANNEL_CONFIGURATION>;
+                _dispatcher_vertical =
+                    box_blur_vertical_pass_neon::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
             }
         }
     }
@@ -437,10 +445,8 @@ fn box_blur_vertical_pass<
     });
 }
 
-fn box_blur_impl<
-    T: FromPrimitive + Default + Sync + Send + Copy,
-    const CHANNEL_CONFIGURATION: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn box_blur_impl<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
@@ -448,10 +454,15 @@ fn box_blur_impl<
     width: u32,
     height: u32,
     radius: u32,
-    pool: &ThreadPool,
+    pool: &BlurThreadPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + Copy
+        + std::ops::AddAssign
         + std::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
@@ -462,7 +473,7 @@ fn box_blur_impl<
 {
     let mut transient: Vec<T> =
         vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
-    box_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION>(
+    box_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
         src,
         src_stride,
         &mut transient,
@@ -473,9 +484,9 @@ fn box_blur_impl<
         pool,
         thread_count,
     );
-    box_blur_vertical_pass::<T, CHANNEL_CONFIGURATION>(
+    box_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
         &transient,
-        src_stride,
+        dst_stride,
         dst,
         dst_stride,
         width,
@@ -486,6 +497,100 @@ fn box_blur_impl<
     );
 }
 
+fn box_blur_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+    f32: ToStorage<T> + AsPrimitive<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    check_radius(radius, 1, u32::MAX / 2)?;
+    let thread_count = threading_policy.get_threads_count(src.width, src.height) as u32;
+    let pool = threading_policy.get_pool(thread_count as usize);
+    // Past a single pixel of color clamping keeps repeating it
+    filter_constant_padded_into(src, dst, 1, edge_mode, |src, dst, edge_mode| {
+        let _dispatcher = match edge_mode {
+            EdgeMode::Clamp => match src.channels {
+                FastBlurChannels::Plane => box_blur_impl::<T, 1, { EdgeMode::Clamp.as_const() }>,
+                FastBlurChannels::Channels2 => {
+                    box_blur_impl::<T, 2, { EdgeMode::Clamp.as_const() }>
+                }
+                FastBlurChannels::Channels3 => {
+                    box_blur_impl::<T, 3, { EdgeMode::Clamp.as_const() }>
+                }
+                FastBlurChannels::Channels4 => {
+                    box_blur_impl::<T, 4, { EdgeMode::Clamp.as_const() }>
+                }
+            },
+            EdgeMode::KernelClip | EdgeMode::Constant(_) => {
+                return Err(BlurError::EdgeModeNotSupported(edge_mode));
+            }
+            EdgeMode::Wrap => match src.channels {
+                FastBlurChannels::Plane => box_blur_impl::<T, 1, { EdgeMode::Wrap.as_const() }>,
+                FastBlurChannels::Channels2 => box_blur_impl::<T, 2, { EdgeMode::Wrap.as_const() }>,
+                FastBlurChannels::Channels3 => box_blur_impl::<T, 3, { EdgeMode::Wrap.as_const() }>,
+                FastBlurChannels::Channels4 => box_blur_impl::<T, 4, { EdgeMode::Wrap.as_const() }>,
+            },
+            EdgeMode::Reflect => match src.channels {
+                FastBlurChannels::Plane => box_blur_impl::<T, 1, { EdgeMode::Reflect.as_const() }>,
+                FastBlurChannels::Channels2 => {
+                    box_blur_impl::<T, 2, { EdgeMode::Reflect.as_const() }>
+                }
+                FastBlurChannels::Channels3 => {
+                    box_blur_impl::<T, 3, { EdgeMode::Reflect.as_const() }>
+                }
+                FastBlurChannels::Channels4 => {
+                    box_blur_impl::<T, 4, { EdgeMode::Reflect.as_const() }>
+                }
+            },
+            EdgeMode::Reflect101 => match src.channels {
+                FastBlurChannels::Plane => {
+                    box_blur_impl::<T, 1, { EdgeMode::Reflect101.as_const() }>
+                }
+                FastBlurChannels::Channels2 => {
+                    box_blur_impl::<T, 2, { EdgeMode::Reflect101.as_const() }>
+                }
+                FastBlurChannels::Channels3 => {
+                    box_blur_impl::<T, 3, { EdgeMode::Reflect101.as_const() }>
+                }
+                FastBlurChannels::Channels4 => {
+                    box_blur_impl::<T, 4, { EdgeMode::Reflect101.as_const() }>
+                }
+            },
+        };
+        _dispatcher(
+            src.data,
+            src.stride,
+            dst.data,
+            dst.stride,
+            src.width,
+            src.height,
+            radius,
+            &pool,
+            thread_count,
+        );
+        Ok(())
+    })
+}
+
 /// Performs box blur on the image.
 ///
 /// Convergence of this function is very high so strong effect applies very fast
@@ -494,71 +599,26 @@ fn box_blur_impl<
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn box_blur(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    match channels {
-        FastBlurChannels::Plane => {
-            box_blur_impl::<u8, 1>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            box_blur_impl::<u8, 3>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            box_blur_impl::<u8, 4>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        box_blur_dispatch::<u8>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs box blur on the image.
@@ -569,70 +629,26 @@ pub fn box_blur(
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn box_blur_u16(
-    src: &[u16],
-    dst: &mut [u16],
-    width: u32,
-    height: u32,
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let stride = width * channels.get_channels() as u32;
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    match channels {
-        FastBlurChannels::Plane => {
-            box_blur_impl::<u16, 1>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            box_blur_impl::<u16, 3>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            box_blur_impl::<u16, 4>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        box_blur_dispatch::<u16>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs box blur on the image.
@@ -643,69 +659,26 @@ pub fn box_blur_u16(
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn box_blur_f32(
-    src: &[f32],
-    dst: &mut [f32],
-    width: u32,
-    height: u32,
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let stride = width * channels.get_channels() as u32;
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    match channels {
-        FastBlurChannels::Plane => {
-            box_blur_impl::<f32, 1>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            box_blur_impl::<f32, 3>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            box_blur_impl::<f32, 4>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                &pool,
-                thread_count,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        box_blur_dispatch::<f32>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs box blur on the image in linear colorspace
@@ -716,48 +689,42 @@ pub fn box_blur_f32(
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
 /// * `threading_policy` - Threads usage policy
 /// * `transfer_function` - Transfer function in linear colorspace
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn box_blur_in_linear(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
     transfer_function: TransferFunction,
-) {
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
     let mut linear_data: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
     let mut linear_data_2: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
 
-    let forward_transformer = match channels {
-        FastBlurChannels::Plane => plane_to_linear,
-        FastBlurChannels::Channels3 => rgb_to_linear,
-        FastBlurChannels::Channels4 => rgba_to_linear,
-    };
+    let forward_transformer = to_linear_transformer(channels, src.alpha_position);
 
-    let inverse_transformer = match channels {
-        FastBlurChannels::Plane => linear_to_plane,
-        FastBlurChannels::Channels3 => linear_to_rgb,
-        FastBlurChannels::Channels4 => linear_to_rgba,
-    };
+    let inverse_transformer = from_linear_transformer(channels, src.alpha_position);
 
     forward_transformer(
-        &src,
-        src_stride,
+        src.data,
+        src.stride,
         &mut linear_data,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
@@ -766,30 +733,28 @@ pub fn box_blur_in_linear(
     );
 
     box_blur_f32(
-        &linear_data,
-        &mut linear_data_2,
-        width,
-        height,
+        &BlurImage::borrow(&linear_data, width, height, channels),
+        &mut BlurImageMut::borrow(&mut linear_data_2, width, height, channels),
         radius,
-        channels,
+        edge_mode.to_linear(channels, src.alpha_position, transfer_function),
+        AlphaMode::Independent,
         threading_policy,
-    );
+    )?;
 
     inverse_transformer(
         &linear_data_2,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
-        dst,
-        dst_stride,
+        dst.data,
+        dst.stride,
         width,
         height,
         transfer_function,
     );
+    Ok(())
 }
 
-fn tent_blur_impl<
-    T: FromPrimitive + Default + Sync + Send + Copy,
-    const CHANNEL_CONFIGURATION: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn tent_blur_impl<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
@@ -799,7 +764,12 @@ fn tent_blur_impl<
     radius: u32,
     threading_policy: ThreadingPolicy,
 ) where
-    T: std::ops::AddAssign
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + Copy
+        + std::ops::AddAssign
         + std::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
@@ -809,13 +779,10 @@ fn tent_blur_impl<
     f32: ToStorage<T>,
 {
     let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.get_pool(thread_count as usize);
     let mut transient: Vec<T> =
         vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
+    box_blur_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
         src,
         src_stride,
         &mut transient,
@@ -826,9 +793,9 @@ fn tent_blur_impl<
         &pool,
         thread_count,
     );
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
+    box_blur_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
         &transient,
-        src_stride,
+        dst_stride,
         dst,
         dst_stride,
         width,
@@ -839,6 +806,103 @@ fn tent_blur_impl<
     );
 }
 
+fn tent_blur_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+    f32: ToStorage<T> + AsPrimitive<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    check_radius(radius, 1, u32::MAX / 2)?;
+    // Intermediate pass is exact within `radius` beyond the color border and constant past it
+    filter_constant_padded_into(src, dst, radius + 1, edge_mode, |src, dst, edge_mode| {
+        let _dispatcher = match edge_mode {
+            EdgeMode::Clamp => match src.channels {
+                FastBlurChannels::Plane => tent_blur_impl::<T, 1, { EdgeMode::Clamp.as_const() }>,
+                FastBlurChannels::Channels2 => {
+                    tent_blur_impl::<T, 2, { EdgeMode::Clamp.as_const() }>
+                }
+                FastBlurChannels::Channels3 => {
+                    tent_blur_impl::<T, 3, { EdgeMode::Clamp.as_const() }>
+                }
+                FastBlurChannels::Channels4 => {
+                    tent_blur_impl::<T, 4, { EdgeMode::Clamp.as_const() }>
+                }
+            },
+            EdgeMode::KernelClip | EdgeMode::Constant(_) => {
+                return Err(BlurError::EdgeModeNotSupported(edge_mode));
+            }
+            EdgeMode::Wrap => match src.channels {
+                FastBlurChannels::Plane => tent_blur_impl::<T, 1, { EdgeMode::Wrap.as_const() }>,
+                FastBlurChannels::Channels2 => {
+                    tent_blur_impl::<T, 2, { EdgeMode::Wrap.as_const() }>
+                }
+                FastBlurChannels::Channels3 => {
+                    tent_blur_impl::<T, 3, { EdgeMode::Wrap.as_const() }>
+                }
+                FastBlurChannels::Channels4 => {
+                    tent_blur_impl::<T, 4, { EdgeMode::Wrap.as_const() }>
+                }
+            },
+            EdgeMode::Reflect => match src.channels {
+                FastBlurChannels::Plane => tent_blur_impl::<T, 1, { EdgeMode::Reflect.as_const() }>,
+                FastBlurChannels::Channels2 => {
+                    tent_blur_impl::<T, 2, { EdgeMode::Reflect.as_const() }>
+                }
+                FastBlurChannels::Channels3 => {
+                    tent_blur_impl::<T, 3, { EdgeMode::Reflect.as_const() }>
+                }
+                FastBlurChannels::Channels4 => {
+                    tent_blur_impl::<T, 4, { EdgeMode::Reflect.as_const() }>
+                }
+            },
+            EdgeMode::Reflect101 => match src.channels {
+                FastBlurChannels::Plane => {
+                    tent_blur_impl::<T, 1, { EdgeMode::Reflect101.as_const() }>
+                }
+                FastBlurChannels::Channels2 => {
+                    tent_blur_impl::<T, 2, { EdgeMode::Reflect101.as_const() }>
+                }
+                FastBlurChannels::Channels3 => {
+                    tent_blur_impl::<T, 3, { EdgeMode::Reflect101.as_const() }>
+                }
+                FastBlurChannels::Channels4 => {
+                    tent_blur_impl::<T, 4, { EdgeMode::Reflect101.as_const() }>
+                }
+            },
+        };
+        _dispatcher(
+            src.data,
+            src.stride,
+            dst.data,
+            dst.stride,
+            src.width,
+            src.height,
+            radius,
+            threading_policy,
+        );
+        Ok(())
+    })
+}
+
 /// Performs tent blur on the image.
 ///
 /// Tent blur just makes a two passes box blur on the image since two times box it is almost equal to tent filter.
@@ -850,63 +914,26 @@ fn tent_blur_impl<
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn tent_blur(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    match channels {
-        FastBlurChannels::Plane => {
-            tent_blur_impl::<u8, 1>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            tent_blur_impl::<u8, 3>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            tent_blur_impl::<u8, 4>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        tent_blur_dispatch::<u8>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs tent blur on the image.
@@ -920,62 +947,26 @@ pub fn tent_blur(
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn tent_blur_u16(
-    src: &[u16],
-    dst: &mut [u16],
-    width: u32,
-    height: u32,
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let stride = width * channels.get_channels() as u32;
-    match channels {
-        FastBlurChannels::Plane => {
-            tent_blur_impl::<u16, 1>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            tent_blur_impl::<u16, 3>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            tent_blur_impl::<u16, 4>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        tent_blur_dispatch::<u16>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs tent blur on the image.
@@ -983,65 +974,32 @@ pub fn tent_blur_u16(
 /// Tent blur just makes a two passes box blur on the image since two times box it is almost equal to tent filter.
 /// https://en.wikipedia.org/wiki/Central_limit_theorem
 ///
+/// Convergence of this function is very high so strong effect applies very fast
+///
 /// O(1) complexity.
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn tent_blur_f32(
-    src: &[f32],
-    dst: &mut [f32],
-    width: u32,
-    height: u32,
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let stride = width * channels.get_channels() as u32;
-    match channels {
-        FastBlurChannels::Plane => {
-            tent_blur_impl::<f32, 1>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            tent_blur_impl::<f32, 3>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            tent_blur_impl::<f32, 4>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        tent_blur_dispatch::<f32>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs tent blur on the image in linear colorspace
@@ -1055,48 +1013,42 @@ pub fn tent_blur_f32(
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
 /// * `threading_policy` - Threads usage policy
 /// * `transfer_function` - Transfer function in linear colorspace
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn tent_blur_in_linear(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
     transfer_function: TransferFunction,
-) {
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
     let mut linear_data: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
     let mut linear_data_2: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
 
-    let forward_transformer = match channels {
-        FastBlurChannels::Plane => plane_to_linear,
-        FastBlurChannels::Channels3 => rgb_to_linear,
-        FastBlurChannels::Channels4 => rgba_to_linear,
-    };
+    let forward_transformer = to_linear_transformer(channels, src.alpha_position);
 
-    let inverse_transformer = match channels {
-        FastBlurChannels::Plane => linear_to_plane,
-        FastBlurChannels::Channels3 => linear_to_rgb,
-        FastBlurChannels::Channels4 => linear_to_rgba,
-    };
+    let inverse_transformer = from_linear_transformer(channels, src.alpha_position);
 
     forward_transformer(
-        &src,
-        src_stride,
+        src.data,
+        src.stride,
         &mut linear_data,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
@@ -1105,30 +1057,28 @@ pub fn tent_blur_in_linear(
     );
 
     tent_blur_f32(
-        &linear_data,
-        &mut linear_data_2,
-        width,
-        height,
+        &BlurImage::borrow(&linear_data, width, height, channels),
+        &mut BlurImageMut::borrow(&mut linear_data_2, width, height, channels),
         radius,
-        channels,
+        edge_mode.to_linear(channels, src.alpha_position, transfer_function),
+        AlphaMode::Independent,
         threading_policy,
-    );
+    )?;
 
     inverse_transformer(
         &linear_data_2,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
-        dst,
-        dst_stride,
+        dst.data,
+        dst.stride,
         width,
         height,
         transfer_function,
     );
+    Ok(())
 }
 
-fn gaussian_box_blur_impl<
-    T: FromPrimitive + Default + Sync + Send + Copy,
-    const CHANNEL_CONFIGURATION: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn gaussian_box_blur_impl<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
@@ -1138,7 +1088,12 @@ fn gaussian_box_blur_impl<
     radius: u32,
     threading_policy: ThreadingPolicy,
 ) where
-    T: std::ops::AddAssign
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + Copy
+        + std::ops::AddAssign
         + std::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
@@ -1148,16 +1103,13 @@ fn gaussian_box_blur_impl<
     f32: ToStorage<T>,
 {
     let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.get_pool(thread_count as usize);
     let mut transient: Vec<T> =
         vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
     let mut transient2: Vec<T> =
         vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
-        &src,
+    box_blur_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
+        src,
         src_stride,
         &mut transient,
         dst_stride,
@@ -1167,9 +1119,9 @@ fn gaussian_box_blur_impl<
         &pool,
         thread_count,
     );
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
+    box_blur_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
         &transient,
-        src_stride,
+        dst_stride,
         &mut transient2,
         dst_stride,
         width,
@@ -1178,9 +1130,9 @@ fn gaussian_box_blur_impl<
         &pool,
         thread_count,
     );
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
+    box_blur_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
         &transient2,
-        src_stride,
+        dst_stride,
         dst,
         dst_stride,
         width,
@@ -1191,6 +1143,115 @@ fn gaussian_box_blur_impl<
     );
 }
 
+fn gaussian_box_blur_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+    f32: ToStorage<T> + AsPrimitive<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    check_radius(radius, 1, u32::MAX / 2)?;
+    // Intermediate passes are exact within `radius` beyond the color border and constant past it
+    filter_constant_padded_into(
+        src,
+        dst,
+        radius * 2 + 1,
+        edge_mode,
+        |src, dst, edge_mode| {
+            let _dispatcher = match edge_mode {
+                EdgeMode::Clamp => match src.channels {
+                    FastBlurChannels::Plane => {
+                        gaussian_box_blur_impl::<T, 1, { EdgeMode::Clamp.as_const() }>
+                    }
+                    FastBlurChannels::Channels2 => {
+                        gaussian_box_blur_impl::<T, 2, { EdgeMode::Clamp.as_const() }>
+                    }
+                    FastBlurChannels::Channels3 => {
+                        gaussian_box_blur_impl::<T, 3, { EdgeMode::Clamp.as_const() }>
+                    }
+                    FastBlurChannels::Channels4 => {
+                        gaussian_box_blur_impl::<T, 4, { EdgeMode::Clamp.as_const() }>
+                    }
+                },
+                EdgeMode::KernelClip | EdgeMode::Constant(_) => {
+                    return Err(BlurError::EdgeModeNotSupported(edge_mode));
+                }
+                EdgeMode::Wrap => match src.channels {
+                    FastBlurChannels::Plane => {
+                        gaussian_box_blur_impl::<T, 1, { EdgeMode::Wrap.as_const() }>
+                    }
+                    FastBlurChannels::Channels2 => {
+                        gaussian_box_blur_impl::<T, 2, { EdgeMode::Wrap.as_const() }>
+                    }
+                    FastBlurChannels::Channels3 => {
+                        gaussian_box_blur_impl::<T, 3, { EdgeMode::Wrap.as_const() }>
+                    }
+                    FastBlurChannels::Channels4 => {
+                        gaussian_box_blur_impl::<T, 4, { EdgeMode::Wrap.as_const() }>
+                    }
+                },
+                EdgeMode::Reflect => match src.channels {
+                    FastBlurChannels::Plane => {
+                        gaussian_box_blur_impl::<T, 1, { EdgeMode::Reflect.as_const() }>
+                    }
+                    FastBlurChannels::Channels2 => {
+                        gaussian_box_blur_impl::<T, 2, { EdgeMode::Reflect.as_const() }>
+                    }
+                    FastBlurChannels::Channels3 => {
+                        gaussian_box_blur_impl::<T, 3, { EdgeMode::Reflect.as_const() }>
+                    }
+                    FastBlurChannels::Channels4 => {
+                        gaussian_box_blur_impl::<T, 4, { EdgeMode::Reflect.as_const() }>
+                    }
+                },
+                EdgeMode::Reflect101 => match src.channels {
+                    FastBlurChannels::Plane => {
+                        gaussian_box_blur_impl::<T, 1, { EdgeMode::Reflect101.as_const() }>
+                    }
+                    FastBlurChannels::Channels2 => {
+                        gaussian_box_blur_impl::<T, 2, { EdgeMode::Reflect101.as_const() }>
+                    }
+                    FastBlurChannels::Channels3 => {
+                        gaussian_box_blur_impl::<T, 3, { EdgeMode::Reflect101.as_const() }>
+                    }
+                    FastBlurChannels::Channels4 => {
+                        gaussian_box_blur_impl::<T, 4, { EdgeMode::Reflect101.as_const() }>
+                    }
+                },
+            };
+            _dispatcher(
+                src.data,
+                src.stride,
+                dst.data,
+                dst.stride,
+                src.width,
+                src.height,
+                radius,
+                threading_policy,
+            );
+            Ok(())
+        },
+    )
+}
+
 /// Performs gaussian box blur approximation on the image.
 ///
 /// This method launches three times box blur on the image since 2 passes box filter it is a tent filter and 3 passes of box blur it is almost gaussian filter.
@@ -1203,63 +1264,26 @@ fn gaussian_box_blur_impl<
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn gaussian_box_blur(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    match channels {
-        FastBlurChannels::Plane => {
-            gaussian_box_blur_impl::<u8, 1>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            gaussian_box_blur_impl::<u8, 3>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            gaussian_box_blur_impl::<u8, 4>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        gaussian_box_blur_dispatch::<u8>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs gaussian box blur approximation on the image.
@@ -1274,62 +1298,26 @@ pub fn gaussian_box_blur(
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn gaussian_box_blur_u16(
-    src: &[u16],
-    dst: &mut [u16],
-    width: u32,
-    height: u32,
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let stride = width * channels.get_channels() as u32;
-    match channels {
-        FastBlurChannels::Plane => {
-            gaussian_box_blur_impl::<u16, 1>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            gaussian_box_blur_impl::<u16, 3>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            gaussian_box_blur_impl::<u16, 4>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        gaussian_box_blur_dispatch::<u16>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
 /// Performs gaussian box blur approximation on the image.
@@ -1344,64 +1332,29 @@ pub fn gaussian_box_blur_u16(
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn gaussian_box_blur_f32(
-    src: &[f32],
-    dst: &mut [f32],
-    width: u32,
-    height: u32,
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let stride = width * channels.get_channels() as u32;
-    match channels {
-        FastBlurChannels::Plane => {
-            gaussian_box_blur_impl::<f32, 1>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            gaussian_box_blur_impl::<f32, 3>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            gaussian_box_blur_impl::<f32, 4>(
-                src,
-                stride,
-                dst,
-                stride,
-                width,
-                height,
-                radius,
-                threading_policy,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        gaussian_box_blur_dispatch::<f32>(src, dst, radius, edge_mode, threading_policy)
+    })
 }
 
-/// Performs gaussian box blur approximation on the image.
+/// Performs gaussian box blur approximation on the image in linear colorspace
 ///
 /// This method launches three times box blur on the image since 2 passes box filter it is a tent filter and 3 passes of box blur it is almost gaussian filter.
 /// https://en.wikipedia.org/wiki/Central_limit_theorem
@@ -1412,48 +1365,42 @@ pub fn gaussian_box_blur_f32(
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - almost any radius is supported, must be at least 1
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
 /// * `threading_policy` - Threads usage policy
 /// * `transfer_function` - Transfer function in linear colorspace
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is zero or edge mode is not supported
 pub fn gaussian_box_blur_in_linear(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
     transfer_function: TransferFunction,
-) {
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
     let mut linear_data: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
     let mut linear_data_2: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
 
-    let forward_transformer = match channels {
-        FastBlurChannels::Plane => plane_to_linear,
-        FastBlurChannels::Channels3 => rgb_to_linear,
-        FastBlurChannels::Channels4 => rgba_to_linear,
-    };
+    let forward_transformer = to_linear_transformer(channels, src.alpha_position);
 
-    let inverse_transformer = match channels {
-        FastBlurChannels::Plane => linear_to_plane,
-        FastBlurChannels::Channels3 => linear_to_rgb,
-        FastBlurChannels::Channels4 => linear_to_rgba,
-    };
+    let inverse_transformer = from_linear_transformer(channels, src.alpha_position);
 
     forward_transformer(
-        &src,
-        src_stride,
+        src.data,
+        src.stride,
         &mut linear_data,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
@@ -1462,22 +1409,349 @@ pub fn gaussian_box_blur_in_linear(
     );
 
     gaussian_box_blur_f32(
-        &linear_data,
-        &mut linear_data_2,
-        width,
-        height,
+        &BlurImage::borrow(&linear_data, width, height, channels),
+        &mut BlurImageMut::borrow(&mut linear_data_2, width, height, channels),
         radius,
-        channels,
+        edge_mode.to_linear(channels, src.alpha_position, transfer_function),
+        AlphaMode::Independent,
         threading_policy,
-    );
+    )?;
 
     inverse_transformer(
         &linear_data_2,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
-        dst,
-        dst_stride,
+        dst.data,
+        dst.stride,
         width,
         height,
         transfer_function,
     );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+
+    /// Repeated box passes, [EdgeMode::Constant] extends the image once and then every pass clamps
+    fn reference_box_passes(
+        src: &[f32],
+        width: usize,
+        height: usize,
+        channels: usize,
+        radius: usize,
+        passes: usize,
+        edge_mode: EdgeMode,
+    ) -> Vec<f32> {
+        if let EdgeMode::Constant(_) = edge_mode {
+            let padding = radius * passes;
+            let image = ReferenceImage {
+                data: src,
+                width,
+                height,
+                channels,
+            };
+            let (padded_width, padded_height) = (width + padding * 2, height + padding * 2);
+            let filtered = reference_box_passes(
+                &image.padded(padding, edge_mode),
+                padded_width,
+                padded_height,
+                channels,
+                radius,
+                passes,
+                EdgeMode::Clamp,
+            );
+            return ReferenceImage {
+                data: &filtered,
+                width: padded_width,
+                height: padded_height,
+                channels,
+            }
+            .unpadded(padding);
+        }
+        let kernel_size = 2 * radius + 1;
+        let kernel = vec![1. / kernel_size as f32; kernel_size];
+        let mut data = src.to_vec();
+        for _ in 0..passes {
+            let image = ReferenceImage {
+                data: &data,
+                width,
+                height,
+                channels,
+            };
+            let horizontal = image.filter_2d(&kernel, kernel_size, (radius, 0), edge_mode);
+            let image = ReferenceImage {
+                data: &horizontal,
+                ..image
+            };
+            data = image.filter_2d(&kernel, 1, (0, radius), edge_mode);
+        }
+        data
+    }
+
+    #[test]
+    fn box_filters_f32_match_reference() {
+        let (width, height, radius) = (17usize, 12usize, 3usize);
+        let src = noise(width * height * 3, 256, 23)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        #[allow(clippy::type_complexity)]
+        let filters: [(
+            &str,
+            usize,
+            fn(&BlurImage<f32>, &mut BlurImageMut<f32>, EdgeMode) -> Result<(), BlurError>,
+        ); 3] = [
+            ("box_blur_f32", 1, |src, dst, edge_mode| {
+                box_blur_f32(
+                    src,
+                    dst,
+                    3,
+                    edge_mode,
+                    AlphaMode::Independent,
+                    ThreadingPolicy::Fixed(2),
+                )
+            }),
+            ("tent_blur_f32", 2, |src, dst, edge_mode| {
+                tent_blur_f32(
+                    src,
+                    dst,
+                    3,
+                    edge_mode,
+                    AlphaMode::Independent,
+                    ThreadingPolicy::Fixed(2),
+                )
+            }),
+            ("gaussian_box_blur_f32", 3, |src, dst, edge_mode| {
+                gaussian_box_blur_f32(
+                    src,
+                    dst,
+                    3,
+                    edge_mode,
+                    AlphaMode::Independent,
+                    ThreadingPolicy::Fixed(2),
+                )
+            }),
+        ];
+        for (name, passes, filter) in filters {
+            for edge_mode in edge_modes([0.9, 0.1, 0.5, 1.]) {
+                let mut dst = vec![0f32; src.len()];
+                filter(
+                    &BlurImage::borrow(
+                        &src,
+                        width as u32,
+                        height as u32,
+                        FastBlurChannels::Channels3,
+                    ),
+                    &mut BlurImageMut::borrow(
+                        &mut dst,
+                        width as u32,
+                        height as u32,
+                        FastBlurChannels::Channels3,
+                    ),
+                    edge_mode,
+                )
+                .unwrap();
+                let expected =
+                    reference_box_passes(&src, width, height, 3, radius, passes, edge_mode);
+                assert_close(&dst, &expected, 1e-4, &format!("{name} {edge_mode:?}"));
+            }
+        }
+    }
+
+    #[test]
+    fn tent_and_gaussian_box_blur_match_reference() {
+        let (width, height, radius) = (23usize, 19usize, 4usize);
+        let src = noise(width * height * 4, 256, 8)
+            .into_iter()
+            .map(|v| v as u8)
+            .collect::<Vec<u8>>();
+        let src_f32 = src.iter().map(|&v| v as f32).collect::<Vec<f32>>();
+        for edge_mode in edge_modes([250., 3., 128., 255.]) {
+            for passes in 1..=3 {
+                let mut dst = vec![0u8; src.len()];
+                let src_image = BlurImage::borrow(
+                    &src,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels4,
+                );
+                let mut dst_image = BlurImageMut::borrow(
+                    &mut dst,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels4,
+                );
+                match passes {
+                    1 => box_blur(
+                        &src_image,
+                        &mut dst_image,
+                        radius as u32,
+                        edge_mode,
+                        AlphaMode::Independent,
+                        ThreadingPolicy::Single,
+                    ),
+                    2 => tent_blur(
+                        &src_image,
+                        &mut dst_image,
+                        radius as u32,
+                        edge_mode,
+                        AlphaMode::Independent,
+                        ThreadingPolicy::Single,
+                    ),
+                    _ => gaussian_box_blur(
+                        &src_image,
+                        &mut dst_image,
+                        radius as u32,
+                        edge_mode,
+                        AlphaMode::Independent,
+                        ThreadingPolicy::Single,
+                    ),
+                }
+                .unwrap();
+                let expected =
+                    reference_box_passes(&src_f32, width, height, 4, radius, passes, edge_mode);
+                let dst = dst.iter().map(|&v| v as f32).collect::<Vec<f32>>();
+                // Every pass stores its result rounded to u8
+                assert_close(
+                    &dst,
+                    &expected,
+                    passes as f32,
+                    &format!("{passes} passes {edge_mode:?}"),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn box_filters_reject_kernel_clip() {
+        let src = vec![0u8; 8 * 8];
+        let mut dst = vec![0u8; 8 * 8];
+        let src = BlurImage::borrow(&src, 8, 8, FastBlurChannels::Plane);
+        let mut dst = BlurImageMut::borrow(&mut dst, 8, 8, FastBlurChannels::Plane);
+        let edge_mode = EdgeMode::KernelClip;
+        let policy = ThreadingPolicy::Single;
+        for result in [
+            box_blur(&src, &mut dst, 2, edge_mode, AlphaMode::Independent, policy),
+            tent_blur(&src, &mut dst, 2, edge_mode, AlphaMode::Independent, policy),
+            gaussian_box_blur(&src, &mut dst, 2, edge_mode, AlphaMode::Independent, policy),
+        ] {
+            assert!(matches!(
+                result,
+                Err(BlurError::EdgeModeNotSupported(EdgeMode::KernelClip))
+            ));
+        }
+    }
+
+    #[test]
+    fn tent_and_gaussian_box_blur_alpha_modes() {
+        let (width, height, radius) = (15usize, 11usize, 2usize);
+        let mut src = noise(width * height * 4, 256, 3)
+            .into_iter()
+            .map(|v| v as u8)
+            .collect::<Vec<u8>>();
+        // Opaque and fully transparent pixels premultiply exactly
+        for (i, px) in src.chunks_exact_mut(4).enumerate() {
+            px[3] = if i % 3 == 0 { 0 } else { 255 };
+        }
+        let channels = FastBlurChannels::Channels4;
+        for passes in [2usize, 3] {
+            let blur = |src: &BlurImage<u8>, dst: &mut BlurImageMut<u8>, alpha_mode| {
+                if passes == 2 {
+                    tent_blur(
+                        src,
+                        dst,
+                        radius as u32,
+                        EdgeMode::Reflect,
+                        alpha_mode,
+                        ThreadingPolicy::Single,
+                    )
+                } else {
+                    gaussian_box_blur(
+                        src,
+                        dst,
+                        radius as u32,
+                        EdgeMode::Reflect,
+                        alpha_mode,
+                        ThreadingPolicy::Single,
+                    )
+                }
+            };
+            let src_image = BlurImage::borrow(&src, width as u32, height as u32, channels);
+
+            let mut preserved = vec![0u8; src.len()];
+            blur(
+                &src_image,
+                &mut BlurImageMut::borrow(&mut preserved, width as u32, height as u32, channels),
+                AlphaMode::Preserve,
+            )
+            .unwrap();
+            let mut independent = vec![0u8; src.len()];
+            blur(
+                &src_image,
+                &mut BlurImageMut::borrow(&mut independent, width as u32, height as u32, channels),
+                AlphaMode::Independent,
+            )
+            .unwrap();
+            for ((preserved, independent), src) in preserved
+                .chunks_exact(4)
+                .zip(independent.chunks_exact(4))
+                .zip(src.chunks_exact(4))
+            {
+                assert_eq!(preserved[..3], independent[..3], "{passes} passes");
+                assert_eq!(preserved[3], src[3], "{passes} passes");
+            }
+
+            let mut premultiplied = vec![0u8; src.len()];
+            blur(
+                &src_image,
+                &mut BlurImageMut::borrow(
+                    &mut premultiplied,
+                    width as u32,
+                    height as u32,
+                    channels,
+                ),
+                AlphaMode::Premultiply,
+            )
+            .unwrap();
+            let premultiplied_src = src
+                .chunks_exact(4)
+                .flat_map(|px| {
+                    let alpha = px[3] as f32 / 255.;
+                    [
+                        px[0] as f32 * alpha,
+                        px[1] as f32 * alpha,
+                        px[2] as f32 * alpha,
+                        px[3] as f32,
+                    ]
+                })
+                .collect::<Vec<f32>>();
+            let expected = reference_box_passes(
+                &premultiplied_src,
+                width,
+                height,
+                4,
+                radius,
+                passes,
+                EdgeMode::Reflect,
+            );
+            for (actual, expected) in premultiplied.chunks_exact(4).zip(expected.chunks_exact(4)) {
+                let alpha = expected[3] / 255.;
+                for c in 0..4 {
+                    let expected = if c == 3 || alpha == 0. {
+                        expected[c]
+                    } else {
+                        expected[c] / alpha
+                    };
+                    // Rounding of intermediate passes is amplified by unpremultiplication of faint pixels
+                    let tolerance = passes as f32 / alpha.max(1. / 255.) + 1.;
+                    assert!(
+                        (actual[c] as f32 - expected).abs() <= tolerance,
+                        "{passes} passes: {actual:?}, expected {expected}"
+                    );
+                }
+            }
+        }
+    }
 }
@@ -27,11 +27,17 @@
 
 use std::arch::aarch64::*;
 
-use crate::neon::{load_u8_u16, load_u8_u32_fast, store_u8_u32, vmulq_u32_f32};
+use crate::edge_mode::reflect_index;
+use crate::neon::{load_u8_u16, store_u8_u32, vmulq_u32_f32};
 use crate::unsafe_slice::UnsafeSlice;
+use crate::{clamp_edge, reflect_101, EdgeMode};
 
 #[allow(dead_code)]
-pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usize>(
+pub(crate) fn box_blur_horizontal_pass_neon<
+    T,
+    const CHANNEL_CONFIGURATION: usize,
+    const EDGE_MODE: usize,
+>(
     undefined_src: &[T],
     src_stride: u32,
     undefined_dst: &UnsafeSlice<T>,
@@ -46,11 +52,10 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
     let eraser_store: [u32; 4] = [1u32, 1u32, 1u32, 0u32];
     let eraser: uint32x4_t = unsafe { vld1q_u32(eraser_store.as_ptr()) };
 
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let kernel_size = radius * 2 + 1;
-    let edge_count = (kernel_size / 2) + 1;
-    let v_edge_count = unsafe { vdupq_n_u32(edge_count) };
 
-    let v_weight = unsafe { vdupq_n_f32(1f32 / (radius * 2) as f32) };
+    let v_weight = unsafe { vdupq_n_f32(1f32 / kernel_size as f32) };
 
     let half_kernel = kernel_size / 2;
 
@@ -60,30 +65,13 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
         let y_src_shift = y as usize * src_stride as usize;
         let y_dst_shift = y as usize * dst_stride as usize;
 
-        let mut store_0: uint32x4_t;
-        let mut store_1: uint32x4_t;
-        let mut store_2: uint32x4_t;
-        let mut store_3: uint32x4_t;
-        {
-            let s_ptr_0 = unsafe { src.as_ptr().add(y_src_shift) };
-            let edge_colors_0 = unsafe { load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr_0) };
-            store_0 = unsafe { vmulq_u32(edge_colors_0, v_edge_count) };
-
-            let s_ptr_1 = unsafe { src.as_ptr().add(y_src_shift + src_stride as usize) };
-            let edge_colors_1 = unsafe { load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr_1) };
-            store_1 = unsafe { vmulq_u32(edge_colors_1, v_edge_count) };
-
-            let s_ptr_2 = unsafe { src.as_ptr().add(y_src_shift + src_stride as usize * 2) };
-            let edge_colors_2 = unsafe { load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr_2) };
-            store_2 = unsafe { vmulq_u32(edge_colors_2, v_edge_count) };
-
-            let s_ptr_3 = unsafe { src.as_ptr().add(y_src_shift + src_stride as usize * 3) };
-            let edge_colors_3 = unsafe { load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr_3) };
-            store_3 = unsafe { vmulq_u32(edge_colors_3, v_edge_count) };
-        }
+        let mut store_0: uint32x4_t = unsafe { vdupq_n_u32(0) };
+        let mut store_1: uint32x4_t = unsafe { vdupq_n_u32(0) };
+        let mut store_2: uint32x4_t = unsafe { vdupq_n_u32(0) };
+        let mut store_3: uint32x4_t = unsafe { vdupq_n_u32(0) };
 
-        for x in 1..std::cmp::min(half_kernel, width) {
-            let px = x as usize * CHANNEL_CONFIGURATION;
+        for x in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let px = clamp_edge!(edge_mode, x, 0i64, width as i64 - 1) * CHANNEL_CONFIGURATION;
 
             let s_ptr_0 = unsafe { src.as_ptr().add(y_src_shift + px) };
             let edge_colors_0 = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr_0) };
@@ -107,7 +95,12 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
 
             // subtract previous
             {
-                let previous_x = std::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
+                let previous_x = clamp_edge!(
+                    edge_mode,
+                    x as i64 - half_kernel as i64 - 1,
+                    0i64,
+                    width as i64 - 1
+                );
                 let previous = previous_x * CHANNEL_CONFIGURATION;
 
                 let s_ptr_0 = unsafe { src.as_ptr().add(y_src_shift + previous) };
@@ -138,7 +131,12 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
 
             // add next
             {
-                let next_x = std::cmp::min(x + half_kernel, width - 1) as usize;
+                let next_x = clamp_edge!(
+                    edge_mode,
+                    x as i64 + half_kernel as i64,
+                    0i64,
+                    width as i64 - 1
+                );
 
                 let next = next_x * CHANNEL_CONFIGURATION;
 
@@ -250,15 +248,10 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
         let y_src_shift = y as usize * src_stride as usize;
         let y_dst_shift = y as usize * dst_stride as usize;
 
-        let mut store: uint32x4_t;
-        {
-            let s_ptr = unsafe { src.as_ptr().add(y_src_shift) };
-            let edge_colors = unsafe { load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr) };
-            store = unsafe { vmulq_u32(edge_colors, v_edge_count) };
-        }
+        let mut store: uint32x4_t = unsafe { vdupq_n_u32(0) };
 
-        for x in 1..std::cmp::min(half_kernel, width) {
-            let px = x as usize * CHANNEL_CONFIGURATION;
+        for x in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let px = clamp_edge!(edge_mode, x, 0i64, width as i64 - 1) * CHANNEL_CONFIGURATION;
             let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
             let edge_colors = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
             store = unsafe { vaddw_u16(store, edge_colors) };
@@ -269,7 +262,12 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
 
             // subtract previous
             {
-                let previous_x = std::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
+                let previous_x = clamp_edge!(
+                    edge_mode,
+                    x as i64 - half_kernel as i64 - 1,
+                    0i64,
+                    width as i64 - 1
+                );
                 let previous = previous_x * CHANNEL_CONFIGURATION;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + previous) };
                 let edge_colors = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
@@ -278,7 +276,12 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
 
             // add next
             {
-                let next_x = std::cmp::min(x + half_kernel, width - 1) as usize;
+                let next_x = clamp_edge!(
+                    edge_mode,
+                    x as i64 + half_kernel as i64,
+                    0i64,
+                    width as i64 - 1
+                );
 
                 let next = next_x * CHANNEL_CONFIGURATION;
 
@@ -304,7 +307,11 @@ pub(crate) fn box_blur_horizontal_pass_neon<T, const CHANNEL_CONFIGURATION: usiz
     }
 }
 
-pub(crate) fn box_blur_vertical_pass_neon<T, const CHANNEL_CONFIGURATION: usize>(
+pub(crate) fn box_blur_vertical_pass_neon<
+    T,
+    const CHANNEL_CONFIGURATION: usize,
+    const EDGE_MODE: usize,
+>(
     undefined_src: &[T],
     src_stride: u32,
     undefined_unsafe_dst: &UnsafeSlice<T>,
@@ -320,33 +327,24 @@ pub(crate) fn box_blur_vertical_pass_neon<T, const CHANNEL_CONFIGURATION: usize>
     let eraser_store: [u32; 4] = [1u32, 1u32, 1u32, 0u32];
     let eraser: uint32x4_t = unsafe { vld1q_u32(eraser_store.as_ptr()) };
 
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let kernel_size = radius * 2 + 1;
-    let edge_count = (kernel_size / 2) + 1;
-    let v_edge_count = unsafe { vdupq_n_u32(edge_count) };
 
     let half_kernel = kernel_size / 2;
 
-    let v_weight = unsafe { vdupq_n_f32(1f32 / (radius * 2) as f32) };
+    let v_weight = unsafe { vdupq_n_f32(1f32 / kernel_size as f32) };
 
     let mut cx = start_x;
 
     for x in (start_x..end_x.saturating_sub(2)).step_by(2) {
         let px = x as usize * CHANNEL_CONFIGURATION;
 
-        let mut store_0: uint32x4_t;
-        let mut store_1: uint32x4_t;
-        {
-            let s_ptr = unsafe { src.as_ptr().add(px) };
-            let edge_colors_0 = unsafe { load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr) };
-            let edge_colors_1 = unsafe {
-                load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr.add(CHANNEL_CONFIGURATION))
-            };
-            store_0 = unsafe { vmulq_u32(edge_colors_0, v_edge_count) };
-            store_1 = unsafe { vmulq_u32(edge_colors_1, v_edge_count) };
-        }
+        let mut store_0: uint32x4_t = unsafe { vdupq_n_u32(0) };
+        let mut store_1: uint32x4_t = unsafe { vdupq_n_u32(0) };
 
-        for y in 1..std::cmp::min(half_kernel, height) {
-            let y_src_shift = y as usize * src_stride as usize;
+        for y in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let y_src_shift =
+                clamp_edge!(edge_mode, y, 0i64, height as i64 - 1) * src_stride as usize;
             let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
             let edge_colors_0 = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
             let edge_colors_1 =
@@ -357,9 +355,18 @@ pub(crate) fn box_blur_vertical_pass_neon<T, const CHANNEL_CONFIGURATION: usize>
 
         for y in 0..height {
             // preload edge pixels
-            let next = std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
-            let previous =
-                std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+            let next = clamp_edge!(
+                edge_mode,
+                y as i64 + half_kernel as i64,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
+            let previous = clamp_edge!(
+                edge_mode,
+                y as i64 - half_kernel as i64 - 1,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
             let y_dst_shift = dst_stride as usize * y as usize;
 
             // subtract previous
@@ -441,15 +448,11 @@ pub(crate) fn box_blur_vertical_pass_neon<T, const CHANNEL_CONFIGURATION: usize>
     for x in cx..end_x {
         let px = x as usize * CHANNEL_CONFIGURATION;
 
-        let mut store: uint32x4_t;
-        {
-            let s_ptr = unsafe { src.as_ptr().add(px) };
-            let edge_colors = unsafe { load_u8_u32_fast::<CHANNEL_CONFIGURATION>(s_ptr) };
-            store = unsafe { vmulq_u32(edge_colors, v_edge_count) };
-        }
+        let mut store: uint32x4_t = unsafe { vdupq_n_u32(0) };
 
-        for y in 1..std::cmp::min(half_kernel, height) {
-            let y_src_shift = y as usize * src_stride as usize;
+        for y in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let y_src_shift =
+                clamp_edge!(edge_mode, y, 0i64, height as i64 - 1) * src_stride as usize;
             let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
             let edge_colors = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
             store = unsafe { vaddw_u16(store, edge_colors) };
@@ -457,9 +460,18 @@ pub(crate) fn box_blur_vertical_pass_neon<T, const CHANNEL_CONFIGURATION: usize>
 
         for y in 0..height {
             // preload edge pixels
-            let next = std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
-            let previous =
-                std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+            let next = clamp_edge!(
+                edge_mode,
+                y as i64 + half_kernel as i64,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
+            let previous = clamp_edge!(
+                edge_mode,
+                y as i64 - half_kernel as i64 - 1,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
             let y_dst_shift = dst_stride as usize * y as usize;
 
             // subtract previous
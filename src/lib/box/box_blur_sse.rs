@@ -30,10 +30,63 @@ use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+use crate::edge_mode::reflect_index;
 use crate::sse::{_mm_mul_ps_epi32, load_u8_s32_fast, store_u8_u32};
 use crate::unsafe_slice::UnsafeSlice;
+use crate::{clamp_edge, reflect_101, EdgeMode};
 
-pub(crate) fn box_blur_horizontal_pass_sse<T, const CHANNELS: usize>(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn box_blur_horizontal_pass_sse<T, const CHANNELS: usize, const EDGE_MODE: usize>(
+    undefined_src: &[T],
+    src_stride: u32,
+    undefined_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    radius: u32,
+    start_y: u32,
+    end_y: u32,
+) {
+    unsafe {
+        box_blur_horizontal_pass_sse_def::<T, CHANNELS, EDGE_MODE>(
+            undefined_src,
+            src_stride,
+            undefined_unsafe_dst,
+            dst_stride,
+            width,
+            radius,
+            start_y,
+            end_y,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn box_blur_horizontal_pass_sse_def<T, const CHANNELS: usize, const EDGE_MODE: usize>(
+    undefined_src: &[T],
+    src_stride: u32,
+    undefined_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    radius: u32,
+    start_y: u32,
+    end_y: u32,
+) {
+    box_blur_horizontal_pass_sse_impl::<T, CHANNELS, EDGE_MODE>(
+        undefined_src,
+        src_stride,
+        undefined_unsafe_dst,
+        dst_stride,
+        width,
+        radius,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn box_blur_horizontal_pass_sse_impl<T, const CHANNELS: usize, const EDGE_MODE: usize>(
     undefined_src: &[T],
     src_stride: u32,
     undefined_unsafe_dst: &UnsafeSlice<T>,
@@ -52,11 +105,10 @@ pub(crate) fn box_blur_horizontal_pass_sse<T, const CHANNELS: usize>(
     };
     let eraser = unsafe { _mm_loadu_si128(eraser_store.as_ptr() as *const __m128i) };
 
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let kernel_size = radius * 2 + 1;
-    let edge_count = (kernel_size / 2) + 1;
-    let v_edge_count = unsafe { _mm_set1_epi32(edge_count as i32) };
 
-    let v_weight = unsafe { _mm_set1_ps(1f32 / (radius * 2) as f32) };
+    let v_weight = unsafe { _mm_set1_ps(1f32 / kernel_size as f32) };
 
     let half_kernel = kernel_size / 2;
 
@@ -64,15 +116,10 @@ pub(crate) fn box_blur_horizontal_pass_sse<T, const CHANNELS: usize>(
         let y_src_shift = y as usize * src_stride as usize;
         let y_dst_shift = y as usize * dst_stride as usize;
 
-        let mut store;
-        {
-            let s_ptr = unsafe { src.as_ptr().add(y_src_shift) };
-            let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
-            store = unsafe { _mm_mullo_epi32(edge_colors, v_edge_count) };
-        }
+        let mut store = unsafe { _mm_setzero_si128() };
 
-        for x in 1..std::cmp::min(half_kernel, width) {
-            let px = x as usize * CHANNELS;
+        for x in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let px = clamp_edge!(edge_mode, x, 0i64, width as i64 - 1) * CHANNELS;
             let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
             let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
             store = unsafe { _mm_add_epi32(store, edge_colors) };
@@ -83,7 +130,12 @@ pub(crate) fn box_blur_horizontal_pass_sse<T, const CHANNELS: usize>(
 
             // subtract previous
             {
-                let previous_x = std::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
+                let previous_x = clamp_edge!(
+                    edge_mode,
+                    x as i64 - half_kernel as i64 - 1,
+                    0i64,
+                    width as i64 - 1
+                );
                 let previous = previous_x * CHANNELS;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + previous) };
                 let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
@@ -92,7 +144,12 @@ pub(crate) fn box_blur_horizontal_pass_sse<T, const CHANNELS: usize>(
 
             // add next
             {
-                let next_x = std::cmp::min(x + half_kernel, width - 1) as usize;
+                let next_x = clamp_edge!(
+                    edge_mode,
+                    x as i64 + half_kernel as i64,
+                    0i64,
+                    width as i64 - 1
+                );
 
                 let next = next_x * CHANNELS;
 
@@ -118,7 +175,62 @@ pub(crate) fn box_blur_horizontal_pass_sse<T, const CHANNELS: usize>(
     }
 }
 
-pub(crate) fn box_blur_vertical_pass_sse<T, const CHANNELS: usize>(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn box_blur_vertical_pass_sse<T, const CHANNELS: usize, const EDGE_MODE: usize>(
+    undefined_src: &[T],
+    src_stride: u32,
+    undefined_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    start_x: u32,
+    end_x: u32,
+) {
+    unsafe {
+        box_blur_vertical_pass_sse_def::<T, CHANNELS, EDGE_MODE>(
+            undefined_src,
+            src_stride,
+            undefined_unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            radius,
+            start_x,
+            end_x,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn box_blur_vertical_pass_sse_def<T, const CHANNELS: usize, const EDGE_MODE: usize>(
+    undefined_src: &[T],
+    src_stride: u32,
+    undefined_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    start_x: u32,
+    end_x: u32,
+) {
+    box_blur_vertical_pass_sse_impl::<T, CHANNELS, EDGE_MODE>(
+        undefined_src,
+        src_stride,
+        undefined_unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        start_x,
+        end_x,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn box_blur_vertical_pass_sse_impl<T, const CHANNELS: usize, const EDGE_MODE: usize>(
     undefined_src: &[T],
     src_stride: u32,
     undefined_unsafe_dst: &UnsafeSlice<T>,
@@ -138,11 +250,10 @@ pub(crate) fn box_blur_vertical_pass_sse<T, const CHANNELS: usize>(
     };
     let eraser = unsafe { _mm_loadu_si128(eraser_store.as_ptr() as *const __m128i) };
 
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let kernel_size = radius * 2 + 1;
-    let edge_count = (kernel_size / 2) + 1;
-    let v_edge_count = unsafe { _mm_set1_epi32(edge_count as i32) };
 
-    let v_weight = unsafe { _mm_set1_ps(1f32 / (radius * 2) as f32) };
+    let v_weight = unsafe { _mm_set1_ps(1f32 / kernel_size as f32) };
 
     let half_kernel = kernel_size / 2;
 
@@ -151,19 +262,12 @@ pub(crate) fn box_blur_vertical_pass_sse<T, const CHANNELS: usize>(
     for x in (cx..end_x.saturating_sub(2)).step_by(2) {
         let px = x as usize * CHANNELS;
 
-        let mut store_0;
-        let mut store_1;
+        let mut store_0 = unsafe { _mm_setzero_si128() };
+        let mut store_1 = unsafe { _mm_setzero_si128() };
 
-        {
-            let s_ptr = unsafe { src.as_ptr().add(px) };
-            let edge_colors_0 = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
-            let edge_colors_1 = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr.add(CHANNELS)) };
-            store_0 = unsafe { _mm_mullo_epi32(edge_colors_0, v_edge_count) };
-            store_1 = unsafe { _mm_mullo_epi32(edge_colors_1, v_edge_count) };
-        }
-
-        for y in 1..std::cmp::min(half_kernel, height) {
-            let y_src_shift = y as usize * src_stride as usize;
+        for y in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let y_src_shift =
+                clamp_edge!(edge_mode, y, 0i64, height as i64 - 1) * src_stride as usize;
             let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
             let edge_colors_0 = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
             let edge_colors_1 = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr.add(CHANNELS)) };
@@ -173,9 +277,18 @@ pub(crate) fn box_blur_vertical_pass_sse<T, const CHANNELS: usize>(
 
         for y in 0..height {
             // preload edge pixels
-            let next = std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
-            let previous =
-                std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+            let next = clamp_edge!(
+                edge_mode,
+                y as i64 + half_kernel as i64,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
+            let previous = clamp_edge!(
+                edge_mode,
+                y as i64 - half_kernel as i64 - 1,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
             let y_dst_shift = dst_stride as usize * y as usize;
 
             // subtract previous
@@ -235,15 +348,11 @@ pub(crate) fn box_blur_vertical_pass_sse<T, const CHANNELS: usize>(
     for x in cx..end_x {
         let px = x as usize * CHANNELS;
 
-        let mut store;
-        {
-            let s_ptr = unsafe { src.as_ptr().add(px) };
-            let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
-            store = unsafe { _mm_mullo_epi32(edge_colors, v_edge_count) };
-        }
+        let mut store = unsafe { _mm_setzero_si128() };
 
-        for y in 1..std::cmp::min(half_kernel, height) {
-            let y_src_shift = y as usize * src_stride as usize;
+        for y in -(half_kernel as i64) - 1..(half_kernel as i64) {
+            let y_src_shift =
+                clamp_edge!(edge_mode, y, 0i64, height as i64 - 1) * src_stride as usize;
             let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
             let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
             store = unsafe { _mm_add_epi32(store, edge_colors) };
@@ -251,9 +360,18 @@ pub(crate) fn box_blur_vertical_pass_sse<T, const CHANNELS: usize>(
 
         for y in 0..height {
             // preload edge pixels
-            let next = std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
-            let previous =
-                std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+            let next = clamp_edge!(
+                edge_mode,
+                y as i64 + half_kernel as i64,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
+            let previous = clamp_edge!(
+                edge_mode,
+                y as i64 - half_kernel as i64 - 1,
+                0i64,
+                height as i64 - 1
+            ) * src_stride as usize;
             let y_dst_shift = dst_stride as usize * y as usize;
 
             // subtract previous
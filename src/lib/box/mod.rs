@@ -28,10 +28,7 @@
 mod box_blur;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 mod box_blur_neon;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 mod box_blur_sse;
 
 pub use box_blur::*;
@@ -25,18 +25,21 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::BlurError;
+
 #[repr(C)]
-#[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
 /// Declares channels count, generally channels order do not matter for blurring,
-/// except cases when transformation into linear colorspace is performed
-/// in case of linear transformation alpha plane expected to be last so if colorspace has 4 channels then it should be
-/// RGBA, BGRA etc
+/// except cases when alpha is treated separately, as in transformation into linear colorspace
+/// or alpha premultiplication, then alpha position is declared by [AlphaPosition] of the image
 pub enum FastBlurChannels {
     /// Single plane image
     Plane = 1,
+    /// Luma with alpha, alpha position is declared by [AlphaPosition]
+    Channels2 = 2,
     /// RGB, BGR etc
     Channels3 = 3,
-    /// RGBA, BGRA etc
+    /// RGBA, BGRA, ARGB etc, alpha position is declared by [AlphaPosition]
     Channels4 = 4,
 }
 
@@ -44,21 +47,46 @@ impl FastBlurChannels {
     pub fn get_channels(&self) -> usize {
         match self {
             FastBlurChannels::Plane => 1,
+            FastBlurChannels::Channels2 => 2,
             FastBlurChannels::Channels3 => 3,
             FastBlurChannels::Channels4 => 4,
         }
     }
 }
 
-impl From<usize> for FastBlurChannels {
-    fn from(value: usize) -> Self {
-        return match value {
-            1 => FastBlurChannels::Plane,
-            3 => FastBlurChannels::Channels3,
-            4 => FastBlurChannels::Channels4,
-            _ => {
-                panic!("Unknown value");
-            }
-        };
+impl TryFrom<usize> for FastBlurChannels {
+    type Error = BlurError;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(FastBlurChannels::Plane),
+            2 => Ok(FastBlurChannels::Channels2),
+            3 => Ok(FastBlurChannels::Channels3),
+            4 => Ok(FastBlurChannels::Channels4),
+            _ => Err(BlurError::UnsupportedChannels(value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares where alpha is stored in [FastBlurChannels::Channels2] and [FastBlurChannels::Channels4] images
+pub enum AlphaPosition {
+    /// Alpha is the last channel: luma + alpha, RGBA, BGRA etc
+    #[default]
+    Last,
+    /// Alpha is the first channel: alpha + luma, ARGB, ABGR etc
+    First,
+}
+
+impl FastBlurChannels {
+    /// Returns index of alpha inside a pixel, if the layout has alpha channel
+    pub(crate) fn alpha_index(&self, alpha_position: AlphaPosition) -> Option<usize> {
+        match self {
+            FastBlurChannels::Plane | FastBlurChannels::Channels3 => None,
+            FastBlurChannels::Channels2 | FastBlurChannels::Channels4 => match alpha_position {
+                AlphaPosition::Last => Some(self.get_channels() - 1),
+                AlphaPosition::First => Some(0),
+            },
+        }
     }
 }
@@ -25,49 +25,145 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::{AlphaPosition, BlurError, FastBlurChannels};
+use colorutils_rs::TransferFunction;
 use num_traits::{AsPrimitive, Euclid, FromPrimitive, Signed};
 
+/// Per-channel border color of [EdgeMode::Constant]
+///
+/// Colors are compared by their bit patterns, so [EdgeMode] stays `Eq` and `Ord`
+#[derive(Debug, Copy, Clone, Default)]
+pub struct EdgeColor(pub [f32; 4]);
+
+impl From<[f32; 4]> for EdgeColor {
+    fn from(value: [f32; 4]) -> Self {
+        EdgeColor(value)
+    }
+}
+
+impl PartialEq for EdgeColor {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.map(f32::to_bits) == other.0.map(f32::to_bits)
+    }
+}
+
+impl Eq for EdgeColor {}
+
+impl PartialOrd for EdgeColor {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EdgeColor {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| a.total_cmp(b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
 /// Declares an edge handling mode
 pub enum EdgeMode {
     /// If kernel goes out of bounds it will be clipped to an edge and edge pixel replicated across filter
     #[default]
-    Clamp = 0,
+    Clamp,
     /// If kernel goes out of bounds it will be clipped, this is a slightly faster than clamp, however have different visual effects at the edge.
     /// *Kernel clip is supported only for clear gaussian blur and not supported in any approximations!*
-    KernelClip = 1,
+    KernelClip,
     /// If filter goes out of bounds image will be replicated with rule `cdefgh|abcdefgh|abcdefg`
     /// Note that for gaussian blur *Wrap* is significantly slower when NEON or SSE is available than *KernelClip* and *Clamp*
-    Wrap = 2,
+    Wrap,
     /// If filter goes out of bounds image will be replicated with rule `fedcba|abcdefgh|hgfedcb`
     /// Note that for gaussian blur *Reflect* is significantly slower when NEON or SSE is available than *KernelClip* and *Clamp*
-    Reflect = 3,
+    Reflect,
     /// If filter goes out of bounds image will be replicated with rule `gfedcb|abcdefgh|gfedcba`
     /// Note that for gaussian blur *Reflect101* is significantly slower when NEON or SSE is available than *KernelClip* and *Clamp*
-    Reflect101 = 4,
+    Reflect101,
+    /// If filter goes out of bounds samples are taken as the given per-channel color `iiiiii|abcdefgh|iiiiiii`.
+    /// Values are in the range of the image storage, e.g. `EdgeColor([0., 0., 0., 0.])` is transparent black for any type,
+    /// and opaque white is `255.` for `u8`, `65535.` for `u16` and `1.` for `f32`.
+    /// Channels beyond image channels count are ignored.
+    /// Box, tent, gaussian box and fast gaussian approximations serve it from a copy of the image with the color border materialized
+    Constant(EdgeColor),
+}
+
+impl TryFrom<usize> for EdgeMode {
+    type Error = BlurError;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EdgeMode::Clamp),
+            1 => Ok(EdgeMode::KernelClip),
+            2 => Ok(EdgeMode::Wrap),
+            3 => Ok(EdgeMode::Reflect),
+            4 => Ok(EdgeMode::Reflect101),
+            _ => Err(BlurError::UnknownEdgeMode(value)),
+        }
+    }
 }
 
-impl From<usize> for EdgeMode {
-    fn from(value: usize) -> Self {
-        return match value {
+impl EdgeMode {
+    /// Maps unit edge modes into a const generic parameter, restored by [EdgeMode::from_const].
+    ///
+    /// [EdgeMode::Constant] is expected to be materialized by padding beforehand and then filtered as [EdgeMode::Clamp]
+    #[inline(always)]
+    pub(crate) const fn as_const(self) -> usize {
+        match self {
+            EdgeMode::Clamp | EdgeMode::Constant(_) => 0,
+            EdgeMode::KernelClip => 1,
+            EdgeMode::Wrap => 2,
+            EdgeMode::Reflect => 3,
+            EdgeMode::Reflect101 => 4,
+        }
+    }
+
+    /// Moves [EdgeMode::Constant] color of an `u8` image into linear `f32` range exactly as
+    /// the image itself is transformed: color channels are linearized, alpha is only normalized
+    pub(crate) fn to_linear(
+        self,
+        channels: FastBlurChannels,
+        alpha_position: AlphaPosition,
+        transfer_function: TransferFunction,
+    ) -> EdgeMode {
+        match self {
+            EdgeMode::Constant(EdgeColor(color)) => {
+                let linearize = transfer_function.get_linearize_function();
+                let mut linear = color.map(|v| linearize(v.clamp(0f32, 255f32) * (1f32 / 255f32)));
+                if let Some(alpha_index) = channels.alpha_index(alpha_position) {
+                    linear[alpha_index] = color[alpha_index].clamp(0f32, 255f32) * (1f32 / 255f32);
+                }
+                EdgeMode::Constant(EdgeColor(linear))
+            }
+            _ => self,
+        }
+    }
+
+    /// Restores edge mode from const generic parameter, value always produced by [EdgeMode::as_const]
+    #[inline(always)]
+    pub(crate) const fn from_const(value: usize) -> EdgeMode {
+        match value {
             0 => EdgeMode::Clamp,
             1 => EdgeMode::KernelClip,
             2 => EdgeMode::Wrap,
             3 => EdgeMode::Reflect,
-            4 => EdgeMode::Reflect101,
-            _ => {
-                panic!("Unknown edge mode for value: {}", value);
-            }
-        };
+            _ => EdgeMode::Reflect101,
+        }
     }
 }
 
+/// Mirrors index `i` into `0..=n` with edge repeated, rule `fedcba|abcdefgh|hgfedcb`
 #[inline(always)]
 pub(crate) fn reflect_index<
     T: Copy
         + 'static
         + PartialOrd
         + PartialEq
+        + std::ops::Add<Output = T>
         + std::ops::Sub<Output = T>
         + std::ops::Mul<Output = T>
         + Euclid
@@ -81,9 +177,15 @@ pub(crate) fn reflect_index<
 where
     i64: AsPrimitive<T>,
 {
-    let i = (i - n).rem_euclid(&(2i64.as_() * n));
-    let i = (i - n).abs();
-    return i.as_();
+    let one = T::from_i32(1).unwrap();
+    let count = n + one;
+    let period = 2i64.as_() * count;
+    let i = i.rem_euclid(&period);
+    if i < count {
+        i.as_()
+    } else {
+        (period - one - i).as_()
+    }
 }
 
 #[inline(always)]
@@ -119,43 +221,44 @@ where
             .max(T::from_i32(0i32).unwrap())
             .as_();
     }
-    return i.as_();
+    i.as_()
 }
 
+/// Mirrors index `$i` into `0..=$n` without edge repeat, rule `gfedcb|abcdefgh|gfedcba`
 #[macro_export]
 macro_rules! reflect_101 {
     ($i:expr, $n:expr) => {{
-        if $i < 0 {
-            let i = ($i - $n).rem_euclid(2i64 * $n as i64);
-            let i = (i - $n).abs();
-            (i + 1).min($n) as usize
-        } else if $i > $n {
-            let i = ($i - $n).rem_euclid(2i64 * $n as i64);
-            let i = (i - $n).abs();
-            (i - 1).max(0) as usize
+        if $n == 0 {
+            0usize
         } else {
-            $i as usize
+            let period = 2i64 * $n as i64;
+            let i = ($i as i64).rem_euclid(period);
+            if i <= $n as i64 {
+                i as usize
+            } else {
+                (period - i) as usize
+            }
         }
     }};
 }
 
-/**
+/*
     RRRRRR  OOOOO  U     U TTTTTTT IIIII NN   N EEEEEEE SSSSS
     R     R O     O U     U   T     I   I N N  N E       S
    RRRRRR  O     O U     U   T     I   I N  N N EEEEE    SSS
    R   R   O     O U     U   T     I   I N   NN E            S
    R    R   OOOOO   UUUUU    T    IIIII N    N EEEEEEE  SSSSS
-**/
+*/
 
 #[macro_export]
 macro_rules! clamp_edge {
     ($edge_mode:expr, $value:expr, $min:expr, $max:expr) => {{
         match $edge_mode {
-            EdgeMode::Clamp | EdgeMode::KernelClip => {
+            EdgeMode::Clamp | EdgeMode::KernelClip | EdgeMode::Constant(_) => {
                 (std::cmp::min(std::cmp::max($value, $min), $max) as u32) as usize
             }
             EdgeMode::Wrap => {
-                let cx = $value.rem_euclid($max);
+                let cx = $value.rem_euclid($max + 1);
                 cx as usize
             }
             EdgeMode::Reflect => {
@@ -169,3 +272,41 @@ macro_rules! clamp_edge {
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::reference_edge_index;
+
+    #[test]
+    fn clamp_edge_follows_documented_rules() {
+        for edge_mode in [
+            EdgeMode::Clamp,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+        ] {
+            for n in 1..6i64 {
+                for i in -20..20i64 {
+                    let index = clamp_edge!(edge_mode, i, 0i64, n - 1);
+                    assert_eq!(
+                        Some(index),
+                        reference_edge_index(edge_mode, i, n),
+                        "{edge_mode:?} i = {i}, n = {n}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constant_edge_mode_is_totally_ordered() {
+        let black = EdgeMode::Constant(EdgeColor([0., 0., 0., 255.]));
+        let white = EdgeMode::Constant(EdgeColor([255., 255., 255., 255.]));
+        assert_eq!(black, EdgeMode::Constant([0., 0., 0., 255.].into()));
+        assert!(black < white);
+        assert!(EdgeMode::Reflect101 < black);
+        let nan = EdgeMode::Constant(EdgeColor([f32::NAN; 4]));
+        assert_eq!(nan, nan);
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::edge_mode::{reflect_index, EdgeColor};
+use crate::{clamp_edge, reflect_101, BlurError, BlurImage, BlurImageMut, EdgeMode};
+use num_traits::AsPrimitive;
+
+/// Copies the image into a new tightly packed buffer extended by `padding` pixels on every side,
+/// samples outside the image are produced according to `edge_mode`.
+///
+/// Padded buffer has `width + padding * 2` columns and `height + padding * 2` rows.
+pub(crate) fn pad_image<T: Copy + Default + 'static>(
+    image: &BlurImage<T>,
+    padding: usize,
+    edge_mode: EdgeMode,
+) -> Vec<T>
+where
+    f32: AsPrimitive<T>,
+{
+    let channels = image.channels.get_channels();
+    let width = image.width as usize;
+    let height = image.height as usize;
+    let stride = image.stride as usize;
+
+    let padded_width = width + padding * 2;
+    let padded_height = height + padding * 2;
+    let padded_stride = padded_width * channels;
+    let mut padded = vec![T::default(); padded_stride * padded_height];
+
+    if let EdgeMode::Constant(EdgeColor(color)) = edge_mode {
+        let color: [T; 4] = color.map(|v| v.as_());
+        for dst in padded.chunks_exact_mut(channels) {
+            dst.copy_from_slice(&color[..channels]);
+        }
+        for (dst_row, src_row) in padded
+            .chunks_exact_mut(padded_stride)
+            .skip(padding)
+            .zip(image.data.chunks(stride))
+            .take(height)
+        {
+            dst_row[padding * channels..(padding + width) * channels]
+                .copy_from_slice(&src_row[..width * channels]);
+        }
+        return padded;
+    }
+
+    for (y, dst_row) in padded.chunks_exact_mut(padded_stride).enumerate() {
+        let src_y = clamp_edge!(
+            edge_mode,
+            y as i64 - padding as i64,
+            0i64,
+            height as i64 - 1
+        );
+        let src_row = &image.data[src_y * stride..src_y * stride + width * channels];
+        for (x, dst) in dst_row.chunks_exact_mut(channels).enumerate() {
+            let src_x = clamp_edge!(edge_mode, x as i64 - padding as i64, 0i64, width as i64 - 1);
+            dst.copy_from_slice(&src_row[src_x * channels..(src_x + 1) * channels]);
+        }
+    }
+    padded
+}
+
+/// Copies central area of an image made by [pad_image] into `dst`
+pub(crate) fn copy_unpadded<T: Copy>(padded: &[T], padding: usize, dst: &mut BlurImageMut<T>) {
+    let channels = dst.channels.get_channels();
+    let width = dst.width as usize;
+    let height = dst.height as usize;
+    let stride = dst.stride as usize;
+    let padded_stride = (width + padding * 2) * channels;
+    for (dst_row, src_row) in dst
+        .data
+        .chunks_mut(stride)
+        .take(height)
+        .zip(padded.chunks_exact(padded_stride).skip(padding))
+    {
+        dst_row[..width * channels]
+            .copy_from_slice(&src_row[padding * channels..(padding + width) * channels]);
+    }
+}
+
+/// Runs in-place `filter` over a copy of the image extended by `padding` pixels on every side
+/// using `edge_mode`, and writes the central area back.
+///
+/// In-place filters overwrite pixels that [EdgeMode::Wrap] and reflection modes still need
+/// to read near the opposite border, so they are served from an untouched extended copy instead.
+pub(crate) fn filter_edge_padded<T: Copy + Default + 'static>(
+    image: &mut BlurImageMut<T>,
+    padding: u32,
+    edge_mode: EdgeMode,
+    filter: impl FnOnce(&mut BlurImageMut<T>) -> Result<(), BlurError>,
+) -> Result<(), BlurError>
+where
+    f32: AsPrimitive<T>,
+{
+    let padding = padding as usize;
+    let mut padded = pad_image(&image.to_immutable_ref(), padding, edge_mode);
+    filter(
+        &mut BlurImageMut::borrow(
+            &mut padded,
+            image.width + padding as u32 * 2,
+            image.height + padding as u32 * 2,
+            image.channels,
+        )
+        .with_alpha_position(image.alpha_position),
+    )?;
+    copy_unpadded(&padded, padding, image);
+    Ok(())
+}
+
+/// Runs in-place `filter` with the edge mode it should use.
+///
+/// [EdgeMode::Constant] is materialized into a border of `padding` pixels and filtered as [EdgeMode::Clamp],
+/// so passes that only know how to index into the image can serve it, other modes are passed through.
+pub(crate) fn filter_constant_padded<T: Copy + Default + 'static>(
+    image: &mut BlurImageMut<T>,
+    padding: u32,
+    edge_mode: EdgeMode,
+    filter: impl FnOnce(&mut BlurImageMut<T>, EdgeMode) -> Result<(), BlurError>,
+) -> Result<(), BlurError>
+where
+    f32: AsPrimitive<T>,
+{
+    match edge_mode {
+        EdgeMode::Constant(_) => filter_edge_padded(image, padding, edge_mode, |padded| {
+            filter(padded, EdgeMode::Clamp)
+        }),
+        _ => filter(image, edge_mode),
+    }
+}
+
+/// Same as [filter_constant_padded], but runs out-of-place `filter` from `src` into `dst`
+pub(crate) fn filter_constant_padded_into<T: Copy + Default + 'static>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    padding: u32,
+    edge_mode: EdgeMode,
+    filter: impl FnOnce(&BlurImage<T>, &mut BlurImageMut<T>, EdgeMode) -> Result<(), BlurError>,
+) -> Result<(), BlurError>
+where
+    f32: AsPrimitive<T>,
+{
+    let EdgeMode::Constant(_) = edge_mode else {
+        return filter(src, dst, edge_mode);
+    };
+    let padded_width = src.width + padding * 2;
+    let padded_height = src.height + padding * 2;
+    let padded_src = pad_image(src, padding as usize, edge_mode);
+    let mut padded_dst = vec![T::default(); padded_src.len()];
+    filter(
+        &BlurImage::borrow(&padded_src, padded_width, padded_height, src.channels)
+            .with_alpha_position(src.alpha_position),
+        &mut BlurImageMut::borrow(&mut padded_dst, padded_width, padded_height, dst.channels)
+            .with_alpha_position(dst.alpha_position),
+        EdgeMode::Clamp,
+    )?;
+    copy_unpadded(&padded_dst, padding as usize, dst);
+    Ok(())
+}
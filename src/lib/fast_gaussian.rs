@@ -25,52 +25,43 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use colorutils_rs::linear_to_planar::linear_to_plane;
-use colorutils_rs::planar_to_linear::plane_to_linear;
-use colorutils_rs::{
-    linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
-};
+use crate::linear_transform::{from_linear_transformer, to_linear_transformer};
+use colorutils_rs::TransferFunction;
 use half::f16;
 use num_traits::cast::FromPrimitive;
 use num_traits::{AsPrimitive, Float};
 
+use crate::alpha_mode::filter_alpha_mode;
+use crate::blur_error::check_radius;
 use crate::channels_configuration::FastBlurChannels;
 use crate::edge_mode::reflect_index;
+use crate::edge_padding::filter_constant_padded;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::neon::{
     fast_gaussian_horizontal_pass_neon_f16, fast_gaussian_horizontal_pass_neon_f32,
     fast_gaussian_horizontal_pass_neon_u8, fast_gaussian_vertical_pass_neon_f16,
     fast_gaussian_vertical_pass_neon_f32, fast_gaussian_vertical_pass_neon_u8,
 };
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    all(target_feature = "sse4.1", target_feature = "f16c")
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::sse::{fast_gaussian_horizontal_pass_sse_f16, fast_gaussian_vertical_pass_sse_f16};
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::sse::{fast_gaussian_horizontal_pass_sse_f32, fast_gaussian_vertical_pass_sse_f32};
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::sse::{fast_gaussian_horizontal_pass_sse_u8, fast_gaussian_vertical_pass_sse_u8};
 use crate::threading_policy::ThreadingPolicy;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{clamp_edge, reflect_101, EdgeMode};
+use crate::{clamp_edge, reflect_101, AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode};
 
 const BASE_RADIUS_I64_CUTOFF: u32 = 180;
 
-/**
+/*
       RRRRRR  OOOOO  U     U TTTTTTT IIIII NN   N EEEEEEE SSSSS
       R     R O     O U     U   T     I   I N N  N E       S
      RRRRRR  O     O U     U   T     I   I N  N N EEEEE    SSS
      R   R   O     O U     U   T     I   I N   NN E            S
      R    R   OOOOO   UUUUU    T    IIIII N    N EEEEEEE  SSSSS
-**/
+*/
 
 macro_rules! update_differences_inside {
     ($dif_r:expr, $buffer_r:expr, $arr_index:expr, $d_arr_index:expr) => {{
@@ -123,6 +114,16 @@ macro_rules! impl_generic_call {
                     $threading_policy,
                 );
             }
+            FastBlurChannels::Channels2 => {
+                fast_gaussian_impl::<$store_type, 2, $edge_mode>(
+                    $bytes,
+                    $stride,
+                    $width,
+                    $height,
+                    $radius,
+                    $threading_policy,
+                );
+            }
             FastBlurChannels::Channels3 => {
                 fast_gaussian_impl::<$store_type, 3, $edge_mode>(
                     $bytes,
@@ -147,21 +148,6 @@ macro_rules! impl_generic_call {
     };
 }
 
-macro_rules! impl_generic_call_plane_count {
-    ($store_type:ty, $channels_type:expr, $edge_mode:expr,
-        $bytes:expr, $stride:expr, $width:expr, $height:expr,
-        $radius:expr, $threading_policy:expr) => {
-        fast_gaussian_impl::<$store_type, $channels_type, $edge_mode>(
-            $bytes,
-            $stride,
-            $width,
-            $height,
-            $radius,
-            $threading_policy,
-        );
-    };
-}
-
 macro_rules! impl_margin_call {
     ($store_type:ty, $channels_type:expr, $edge_mode:expr,
         $bytes:expr, $stride:expr, $width:expr, $height:expr,
@@ -171,7 +157,7 @@ macro_rules! impl_margin_call {
                 impl_generic_call!(
                     $store_type,
                     $channels_type,
-                    { EdgeMode::Clamp as usize },
+                    { EdgeMode::Clamp.as_const() },
                     $bytes,
                     $stride,
                     $width,
@@ -180,14 +166,14 @@ macro_rules! impl_margin_call {
                     $threading_policy
                 );
             }
-            EdgeMode::KernelClip => {
-                panic!("Kernel clip is supported only in gaussian")
+            EdgeMode::KernelClip | EdgeMode::Constant(_) => {
+                return Err(BlurError::EdgeModeNotSupported($edge_mode));
             }
             EdgeMode::Wrap => {
                 impl_generic_call!(
                     $store_type,
                     $channels_type,
-                    { EdgeMode::Wrap as usize },
+                    { EdgeMode::Wrap.as_const() },
                     $bytes,
                     $stride,
                     $width,
@@ -200,7 +186,7 @@ macro_rules! impl_margin_call {
                 impl_generic_call!(
                     $store_type,
                     $channels_type,
-                    { EdgeMode::Reflect as usize },
+                    { EdgeMode::Reflect.as_const() },
                     $bytes,
                     $stride,
                     $width,
@@ -213,71 +199,7 @@ macro_rules! impl_margin_call {
                 impl_generic_call!(
                     $store_type,
                     $channels_type,
-                    { EdgeMode::Reflect101 as usize },
-                    $bytes,
-                    $stride,
-                    $width,
-                    $height,
-                    $radius,
-                    $threading_policy
-                );
-            }
-        }
-    };
-}
-
-macro_rules! impl_margin_call_plane {
-    ($store_type:ty, $channels_type:expr, $edge_mode:expr,
-        $bytes:expr, $stride:expr, $width:expr, $height:expr,
-        $radius:expr, $threading_policy:expr) => {
-        match $edge_mode {
-            EdgeMode::Clamp => {
-                impl_generic_call_plane_count!(
-                    $store_type,
-                    $channels_type,
-                    { EdgeMode::Clamp as usize },
-                    $bytes,
-                    $stride,
-                    $width,
-                    $height,
-                    $radius,
-                    $threading_policy
-                );
-            }
-            EdgeMode::KernelClip => {
-                panic!("Kernel clip is supported only in gaussian")
-            }
-            EdgeMode::Wrap => {
-                impl_generic_call_plane_count!(
-                    $store_type,
-                    $channels_type,
-                    { EdgeMode::Wrap as usize },
-                    $bytes,
-                    $stride,
-                    $width,
-                    $height,
-                    $radius,
-                    $threading_policy
-                );
-            }
-            EdgeMode::Reflect => {
-                impl_generic_call_plane_count!(
-                    $store_type,
-                    $channels_type,
-                    { EdgeMode::Reflect as usize },
-                    $bytes,
-                    $stride,
-                    $width,
-                    $height,
-                    $radius,
-                    $threading_policy
-                );
-            }
-            EdgeMode::Reflect101 => {
-                impl_generic_call_plane_count!(
-                    $store_type,
-                    $channels_type,
-                    { EdgeMode::Reflect101 as usize },
+                    { EdgeMode::Reflect101.as_const() },
                     $bytes,
                     $stride,
                     $width,
@@ -363,7 +285,7 @@ fn fast_gaussian_vertical_pass<
     M: Copy + FromPrimitive + std::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
     i32: AsPrimitive<J>,
 {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let mut buffer_r: [J; 1024] = [0i32.as_(); 1024];
     let mut buffer_g: [J; 1024] = [0i32.as_(); 1024];
     let mut buffer_b: [J; 1024] = [0i32.as_(); 1024];
@@ -489,7 +411,7 @@ fn fast_gaussian_horizontal_pass<
     M: Copy + FromPrimitive + std::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
     i32: AsPrimitive<J>,
 {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let mut buffer_r: [J; 1024] = [0i32.as_(); 1024];
     let mut buffer_g: [J; 1024] = [0i32.as_(); 1024];
     let mut buffer_b: [J; 1024] = [0i32.as_(); 1024];
@@ -577,11 +499,7 @@ fn fast_gaussian_horizontal_pass<
     }
 }
 
-fn fast_gaussian_impl<
-    T: FromPrimitive + Default + Send + Sync,
-    const CHANNEL_CONFIGURATION: usize,
-    const EDGE_MODE: usize,
->(
+fn fast_gaussian_impl<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     bytes: &mut [T],
     stride: u32,
     width: u32,
@@ -589,7 +507,11 @@ fn fast_gaussian_impl<
     radius: u32,
     threading_policy: ThreadingPolicy,
 ) where
-    T: std::ops::AddAssign
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
         + std::ops::SubAssign
         + Copy
         + AsPrimitive<i32>
@@ -602,10 +524,7 @@ fn fast_gaussian_impl<
 {
     let unsafe_image = UnsafeSlice::new(bytes);
     let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.get_pool(thread_count as usize);
     let mut _dispatcher_vertical: fn(
         bytes: &UnsafeSlice<T>,
         stride: u32,
@@ -625,22 +544,28 @@ fn fast_gaussian_impl<
         } else {
             fast_gaussian_horizontal_pass::<T, i64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
         };
+    if std::any::type_name::<T>() == "f32"
+        || std::any::type_name::<T>() == "f16"
+        || std::any::type_name::<T>() == "half::f16"
+        || std::any::type_name::<T>() == "half::binary16::f16"
+    {
+        _dispatcher_vertical = if BASE_RADIUS_I64_CUTOFF > radius {
+            fast_gaussian_vertical_pass::<T, f32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
+        } else {
+            fast_gaussian_vertical_pass::<T, f64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
+        };
+        _dispatcher_horizontal = if BASE_RADIUS_I64_CUTOFF > radius {
+            fast_gaussian_horizontal_pass::<T, f32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
+        } else {
+            fast_gaussian_horizontal_pass::<T, f64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
+        };
+    }
     if CHANNEL_CONFIGURATION >= 3 {
         if std::any::type_name::<T>() == "f32"
             || std::any::type_name::<T>() == "f16"
             || std::any::type_name::<T>() == "half::f16"
             || std::any::type_name::<T>() == "half::binary16::f16"
         {
-            _dispatcher_vertical = if BASE_RADIUS_I64_CUTOFF > radius {
-                fast_gaussian_vertical_pass::<T, f32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
-            } else {
-                fast_gaussian_vertical_pass::<T, f64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
-            };
-            _dispatcher_horizontal = if BASE_RADIUS_I64_CUTOFF > radius {
-                fast_gaussian_horizontal_pass::<T, f32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
-            } else {
-                fast_gaussian_horizontal_pass::<T, f64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
-            };
             if std::any::type_name::<T>() == "f32" {
                 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
                 {
@@ -652,18 +577,20 @@ fn fast_gaussian_impl<
                         EDGE_MODE,
                     >;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher_vertical =
-                        fast_gaussian_vertical_pass_sse_f32::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
-                    _dispatcher_horizontal = fast_gaussian_horizontal_pass_sse_f32::<
-                        T,
-                        CHANNEL_CONFIGURATION,
-                        EDGE_MODE,
-                    >;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher_vertical = fast_gaussian_vertical_pass_sse_f32::<
+                            T,
+                            CHANNEL_CONFIGURATION,
+                            EDGE_MODE,
+                        >;
+                        _dispatcher_horizontal = fast_gaussian_horizontal_pass_sse_f32::<
+                            T,
+                            CHANNEL_CONFIGURATION,
+                            EDGE_MODE,
+                        >;
+                    }
                 }
             } else if std::any::type_name::<T>() == "f16"
                 || std::any::type_name::<T>() == "half::f16"
@@ -679,18 +606,20 @@ fn fast_gaussian_impl<
                         EDGE_MODE,
                     >;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    all(target_feature = "sse4.1", target_feature = "f16c")
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher_vertical =
-                        fast_gaussian_vertical_pass_sse_f16::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
-                    _dispatcher_horizontal = fast_gaussian_horizontal_pass_sse_f16::<
-                        T,
-                        CHANNEL_CONFIGURATION,
-                        EDGE_MODE,
-                    >;
+                    if is_x86_feature_detected!("sse4.1") && is_x86_feature_detected!("f16c") {
+                        _dispatcher_vertical = fast_gaussian_vertical_pass_sse_f16::<
+                            T,
+                            CHANNEL_CONFIGURATION,
+                            EDGE_MODE,
+                        >;
+                        _dispatcher_horizontal = fast_gaussian_horizontal_pass_sse_f16::<
+                            T,
+                            CHANNEL_CONFIGURATION,
+                            EDGE_MODE,
+                        >;
+                    }
                 }
             }
         }
@@ -708,18 +637,16 @@ fn fast_gaussian_impl<
                 }
             }
         }
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            if std::any::type_name::<T>() == "u8" {
-                if BASE_RADIUS_I64_CUTOFF > radius {
-                    _dispatcher_vertical =
-                        fast_gaussian_vertical_pass_sse_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
-                    _dispatcher_horizontal =
-                        fast_gaussian_horizontal_pass_sse_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
-                }
+            if is_x86_feature_detected!("sse4.1")
+                && std::any::type_name::<T>() == "u8"
+                && BASE_RADIUS_I64_CUTOFF > radius
+            {
+                _dispatcher_vertical =
+                    fast_gaussian_vertical_pass_sse_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
+                _dispatcher_horizontal =
+                    fast_gaussian_horizontal_pass_sse_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
             }
         }
     }
@@ -785,118 +712,236 @@ fn fast_gaussian_impl<
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - Radius more than 319 is not supported. To use larger radius convert image to f32 and use function for f32
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
-/// * `threading_policy` - Threads usage policy
+/// * `image` - Mutable image view that will be used as a source and destination
+/// * `radius` - Radius in range 1..=319. To use larger radius convert image to f32 and use function for f32
 /// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
 pub fn fast_gaussian(
-    bytes: &mut [u8],
-    stride: u32,
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 1, 319)?;
+    if edge_mode == EdgeMode::KernelClip {
+        return Err(BlurError::EdgeModeNotSupported(edge_mode));
+    }
+    filter_alpha_mode(image, alpha_mode, |image| {
+        filter_constant_padded(image, 1, edge_mode, |image, edge_mode| {
+            impl_margin_call!(
+                u8,
+                image.channels,
+                edge_mode,
+                image.data,
+                image.stride,
+                image.width,
+                image.height,
+                radius,
+                threading_policy
+            );
+            Ok(())
+        })
+    })
+}
+
+/// Performs gaussian approximation on the image from `src` into `dst`.
+///
+/// Same as [fast_gaussian], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius in range 1..=319. To use larger radius convert image to f32 and use function for f32
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
+pub fn fast_gaussian_into(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: u32,
     edge_mode: EdgeMode,
-) {
-    let radius = std::cmp::min(radius, 319);
-    impl_margin_call!(
-        u8,
-        channels,
-        edge_mode,
-        bytes,
-        stride,
-        width,
-        height,
-        radius,
-        threading_policy
-    );
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    fast_gaussian(dst, radius, edge_mode, alpha_mode, threading_policy)
 }
 
 /// Performs gaussian approximation on the image.
 ///
-/// Fast gaussian approximation for u16 image, limited to 319 radius, sometimes on the very bright images may start ringing on a very large radius.
+/// Fast gaussian approximation for u16 image, limited to 255 radius, sometimes on the very bright images may start ringing on a very large radius.
 /// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed.
 /// O(1) complexity.
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - Radius more than 255 is not supported. To use larger radius convert image to f32 and use function for f32
-/// * `channels` - Count of channels in the image
+/// * `image` - Mutable image view that will be used as a source and destination
+/// * `radius` - Radius in range 1..=255. To use larger radius convert image to f32 and use function for f32
 /// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
 pub fn fast_gaussian_u16(
-    bytes: &mut [u16],
-    stride: u32,
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<u16>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 1, 255)?;
+    if edge_mode == EdgeMode::KernelClip {
+        return Err(BlurError::EdgeModeNotSupported(edge_mode));
+    }
+    filter_alpha_mode(image, alpha_mode, |image| {
+        filter_constant_padded(image, 1, edge_mode, |image, edge_mode| {
+            impl_margin_call!(
+                u16,
+                image.channels,
+                edge_mode,
+                image.data,
+                image.stride,
+                image.width,
+                image.height,
+                radius,
+                threading_policy
+            );
+            Ok(())
+        })
+    })
+}
+
+/// Performs gaussian approximation on the image from `src` into `dst`.
+///
+/// Same as [fast_gaussian_u16], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius in range 1..=255. To use larger radius convert image to f32 and use function for f32
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
+pub fn fast_gaussian_u16_into(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    radius: u32,
     edge_mode: EdgeMode,
-) {
-    let radius = std::cmp::min(radius, 255);
-    impl_margin_call!(
-        u16,
-        channels,
-        edge_mode,
-        bytes,
-        stride,
-        width,
-        height,
-        radius,
-        threading_policy
-    );
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    fast_gaussian_u16(dst, radius, edge_mode, alpha_mode, threading_policy)
 }
 
 /// Performs gaussian approximation on the image.
 ///
-/// Fast gaussian approximation for f32 image. No limitations are expected.
+/// Fast gaussian approximation for f32 image. Radius is limited to 511.
 /// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed
 /// O(1) complexity.
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
-/// * `transfer_function` - Transfer function in linear colorspace
+/// * `image` - Mutable image view that will be used as a source and destination
+/// * `radius` - Radius in range 1..=511
 /// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
 pub fn fast_gaussian_f32(
-    bytes: &mut [f32],
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<f32>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 1, 511)?;
+    if edge_mode == EdgeMode::KernelClip {
+        return Err(BlurError::EdgeModeNotSupported(edge_mode));
+    }
+    filter_alpha_mode(image, alpha_mode, |image| {
+        filter_constant_padded(image, 1, edge_mode, |image, edge_mode| {
+            impl_margin_call!(
+                f32,
+                image.channels,
+                edge_mode,
+                image.data,
+                image.stride,
+                image.width,
+                image.height,
+                radius,
+                threading_policy
+            );
+            Ok(())
+        })
+    })
+}
+
+/// Performs gaussian approximation on the image from `src` into `dst`.
+///
+/// Same as [fast_gaussian_f32], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius in range 1..=511
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
+pub fn fast_gaussian_f32_into(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    radius: u32,
     edge_mode: EdgeMode,
-) {
-    impl_margin_call!(
-        f32,
-        channels,
-        edge_mode,
-        bytes,
-        width * channels.get_channels() as u32,
-        width,
-        height,
-        radius,
-        threading_policy
-    );
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    fast_gaussian_f32(dst, radius, edge_mode, alpha_mode, threading_policy)
 }
 
 /// Performs gaussian approximation on the image in linear colorspace
@@ -907,45 +952,39 @@ pub fn fast_gaussian_f32(
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - Almost any reasonable radius is supported
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `image` - Mutable image view that will be used as a source and destination
+/// * `radius` - Radius in range 1..=511
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
 /// * `threading_policy` - Threads usage policy
 /// * `transfer_function` - Transfer function in linear colorspace
-/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
 pub fn fast_gaussian_in_linear(
-    in_place: &mut [u8],
-    stride: u32,
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
     transfer_function: TransferFunction,
-    edge_mode: EdgeMode,
-) {
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 1, 511)?;
+    let stride = image.stride;
+    let width = image.width;
+    let height = image.height;
+    let channels = image.channels;
+
     let mut linear_data: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
 
-    let forward_transformer = match channels {
-        FastBlurChannels::Plane => plane_to_linear,
-        FastBlurChannels::Channels3 => rgb_to_linear,
-        FastBlurChannels::Channels4 => rgba_to_linear,
-    };
+    let forward_transformer = to_linear_transformer(channels, image.alpha_position);
 
-    let inverse_transformer = match channels {
-        FastBlurChannels::Plane => linear_to_plane,
-        FastBlurChannels::Channels3 => linear_to_rgb,
-        FastBlurChannels::Channels4 => linear_to_rgba,
-    };
+    let inverse_transformer = from_linear_transformer(channels, image.alpha_position);
 
     forward_transformer(
-        &in_place,
+        image.data,
         stride,
         &mut linear_data,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
@@ -954,143 +993,229 @@ pub fn fast_gaussian_in_linear(
         transfer_function,
     );
 
+    let mut linear_image = BlurImageMut::borrow(&mut linear_data, width, height, channels);
     fast_gaussian_f32(
-        &mut linear_data,
-        width,
-        height,
+        &mut linear_image,
         radius,
-        channels,
+        edge_mode.to_linear(channels, image.alpha_position, transfer_function),
+        AlphaMode::Independent,
         threading_policy,
-        edge_mode,
-    );
+    )?;
 
     inverse_transformer(
         &linear_data,
         width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
-        in_place,
+        image.data,
         stride,
         width,
         height,
         transfer_function,
     );
+    Ok(())
 }
 
-/// Performs gaussian approximation on the image.
+/// Performs gaussian approximation on the image in linear colorspace from `src` into `dst`.
 ///
-/// Fast gaussian approximation for f32 image. No limitations are expected.
-/// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed.
-/// O(1) complexity.
+/// Same as [fast_gaussian_in_linear], but source is left untouched and may have a different stride than destination.
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
-/// * `threading_policy` - Threads usage policy
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius in range 1..=511
 /// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `threading_policy` - Threads usage policy
+/// * `transfer_function` - Transfer function in linear colorspace
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
-pub fn fast_gaussian_f16(
-    bytes: &mut [f16],
-    width: u32,
-    height: u32,
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
+pub fn fast_gaussian_in_linear_into(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
-    threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
-) {
-    impl_margin_call!(
-        half::f16,
-        channels,
-        edge_mode,
-        unsafe { std::mem::transmute(bytes) },
-        width * channels.get_channels() as u32,
-        width,
-        height,
-        radius,
-        threading_policy
-    );
+    threading_policy: ThreadingPolicy,
+    transfer_function: TransferFunction,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    fast_gaussian_in_linear(dst, radius, edge_mode, threading_policy, transfer_function)
 }
 
-/// Performs gaussian approximation on the plane.
+/// Performs gaussian approximation on the image.
 ///
-/// Fast gaussian approximation for u8 plane, limited to 319 radius, sometimes on the very bright images may start ringing on a very large radius.
-/// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed
-/// Results better than in stack blur however this a little slower.
-/// This is a very fast approximation using i32 accumulator size with radius less that *BASE_RADIUS_I64_CUTOFF*,
-/// after it to avoid overflowing fallback to i64 accumulator will be used with some computational slowdown with factor ~2.
+/// Fast gaussian approximation for f16 image. Radius is limited to 511.
+/// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed.
 /// O(1) complexity.
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - Radius more than 319 is not supported. To use larger radius convert image to f32 and use function for f32
-/// * `threading_policy` - Threads usage policy
+/// * `image` - Mutable image view that will be used as a source and destination
+/// * `radius` - Radius in range 1..=511
 /// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
-pub fn fast_gaussian_plane(
-    bytes: &mut [u8],
-    stride: u32,
-    width: u32,
-    height: u32,
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
+pub fn fast_gaussian_f16(
+    image: &mut BlurImageMut<f16>,
     radius: u32,
-    threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
-) {
-    let radius = std::cmp::min(radius, 319);
-    impl_margin_call_plane!(
-        u8,
-        1,
-        edge_mode,
-        bytes,
-        stride,
-        width,
-        height,
-        radius,
-        threading_policy
-    );
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 1, 511)?;
+    filter_constant_padded(image, 1, edge_mode, |image, edge_mode| {
+        impl_margin_call!(
+            f16,
+            image.channels,
+            edge_mode,
+            image.data,
+            image.stride,
+            image.width,
+            image.height,
+            radius,
+            threading_policy
+        );
+        Ok(())
+    })
 }
 
-/// Performs gaussian approximation on the f32 plane.
+/// Performs gaussian approximation on the image from `src` into `dst`.
 ///
-/// Fast gaussian approximation for f32 plane. No limitations are expected.
-/// Approximation based on binomial filter. Algorithm is close to stack blur with better results and a little slower speed.
-/// O(1) complexity.
+/// Same as [fast_gaussian_f16], but source is left untouched and may have a different stride than destination.
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - almost any radius is supported
-/// * `channels` - Count of channels in the image
-/// * `transfer_function` - Transfer function in linear colorspace
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius in range 1..=511
 /// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
-pub fn fast_gaussian_plane_f32(
-    bytes: &mut [f32],
-    width: u32,
-    height: u32,
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range or edge mode is *Kernel clip*
+pub fn fast_gaussian_f16_into(
+    src: &BlurImage<f16>,
+    dst: &mut BlurImageMut<f16>,
     radius: u32,
-    threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
-) {
-    impl_margin_call_plane!(
-        f32,
-        1,
-        edge_mode,
-        bytes,
-        width,
-        width,
-        height,
-        radius,
-        threading_policy
-    );
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    fast_gaussian_f16(dst, radius, edge_mode, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge_mode::EdgeColor;
+    use crate::test_utils::{noise, ReferenceImage};
+
+    #[test]
+    fn fast_gaussian_constant_matches_clamp_over_padded_image() {
+        let (width, height, radius, padding) = (19usize, 14usize, 6u32, 12usize);
+        let color = [250., 3., 128., 255.];
+        let src = noise(width * height * 4, 256, 31)
+            .into_iter()
+            .map(|v| v as u8)
+            .collect::<Vec<u8>>();
+        let src_f32 = src.iter().map(|&v| v as f32).collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &src_f32,
+            width,
+            height,
+            channels: 4,
+        };
+        let (padded_width, padded_height) = (width + padding * 2, height + padding * 2);
+        let mut padded = image
+            .padded(padding, EdgeMode::Constant(EdgeColor(color)))
+            .into_iter()
+            .map(|v| v as u8)
+            .collect::<Vec<u8>>();
+        fast_gaussian(
+            &mut BlurImageMut::borrow(
+                &mut padded,
+                padded_width as u32,
+                padded_height as u32,
+                FastBlurChannels::Channels4,
+            ),
+            radius,
+            EdgeMode::Clamp,
+            AlphaMode::Independent,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let padded = padded.iter().map(|&v| v as f32).collect::<Vec<f32>>();
+        let expected = ReferenceImage {
+            data: &padded,
+            width: padded_width,
+            height: padded_height,
+            channels: 4,
+        }
+        .unpadded(padding);
+
+        let mut dst = src.clone();
+        fast_gaussian(
+            &mut BlurImageMut::borrow(
+                &mut dst,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Channels4,
+            ),
+            radius,
+            EdgeMode::Constant(EdgeColor(color)),
+            AlphaMode::Independent,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        let dst = dst.iter().map(|&v| v as f32).collect::<Vec<f32>>();
+        assert_eq!(dst, expected);
+    }
+
+    #[test]
+    fn fast_gaussian_rejects_kernel_clip() {
+        let (mut data_u8, mut data_f32, mut data_f16) =
+            (vec![0u8; 64], vec![0f32; 64], vec![f16::from_f32(0.); 64]);
+        let plane = FastBlurChannels::Plane;
+        let (edge_mode, policy) = (EdgeMode::KernelClip, ThreadingPolicy::Single);
+        for result in [
+            fast_gaussian(
+                &mut BlurImageMut::borrow(&mut data_u8, 8, 8, plane),
+                2,
+                edge_mode,
+                AlphaMode::Independent,
+                policy,
+            ),
+            fast_gaussian_f32(
+                &mut BlurImageMut::borrow(&mut data_f32, 8, 8, plane),
+                2,
+                edge_mode,
+                AlphaMode::Independent,
+                policy,
+            ),
+            fast_gaussian_f16(
+                &mut BlurImageMut::borrow(&mut data_f16, 8, 8, plane),
+                2,
+                edge_mode,
+                policy,
+            ),
+        ] {
+            assert!(matches!(
+                result,
+                Err(BlurError::EdgeModeNotSupported(EdgeMode::KernelClip))
+            ));
+        }
+    }
 }
@@ -25,8 +25,11 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{FastBlurChannels, ThreadingPolicy};
+use crate::alpha_mode::filter_alpha_mode;
+use crate::blur_error::check_radius;
+use crate::{AlphaMode, BlurError, BlurImage, BlurImageMut, FastBlurChannels, ThreadingPolicy};
 
+#[allow(clippy::module_inception)]
 mod fast_gaussian_superior {
     use num_traits::{AsPrimitive, FromPrimitive, ToPrimitive};
 
@@ -73,10 +76,7 @@ mod fast_gaussian_superior {
         }};
     }
 
-    fn fast_gaussian_vertical_pass<
-        T: FromPrimitive + ToPrimitive + Default + Into<i64> + Send + Sync,
-        const CHANNELS_COUNT: usize,
-    >(
+    fn fast_gaussian_vertical_pass<T, const CHANNELS_COUNT: usize>(
         bytes: &UnsafeSlice<T>,
         stride: u32,
         width: u32,
@@ -85,7 +85,16 @@ mod fast_gaussian_superior {
         start: u32,
         end: u32,
     ) where
-        T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+        T: FromPrimitive
+            + ToPrimitive
+            + Default
+            + Into<i64>
+            + Send
+            + Sync
+            + std::ops::AddAssign
+            + std::ops::SubAssign
+            + Copy
+            + 'static,
         f64: ToStorage<T> + AsPrimitive<T>,
     {
         let mut buffer_r: [i64; 2048] = [0; 2048];
@@ -261,10 +270,7 @@ mod fast_gaussian_superior {
         }
     }
 
-    fn fast_gaussian_horizontal_pass<
-        T: FromPrimitive + ToPrimitive + Default + Into<i64> + Send + Sync,
-        const CHANNELS_COUNT: usize,
-    >(
+    fn fast_gaussian_horizontal_pass<T, const CHANNELS_COUNT: usize>(
         bytes: &UnsafeSlice<T>,
         stride: u32,
         width: u32,
@@ -273,7 +279,16 @@ mod fast_gaussian_superior {
         start: u32,
         end: u32,
     ) where
-        T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+        T: FromPrimitive
+            + ToPrimitive
+            + Default
+            + Into<i64>
+            + Send
+            + Sync
+            + std::ops::AddAssign
+            + std::ops::SubAssign
+            + Copy
+            + 'static,
         f64: ToStorage<T> + AsPrimitive<T>,
     {
         let mut buffer_r: [i64; 2048] = [0; 2048];
@@ -448,10 +463,7 @@ mod fast_gaussian_superior {
         }
     }
 
-    pub(crate) fn fast_gaussian_impl<
-        T: FromPrimitive + ToPrimitive + Default + Into<i64> + Send + Sync,
-        const CHANNELS_COUNT: usize,
-    >(
+    pub(crate) fn fast_gaussian_impl<T, const CHANNELS_COUNT: usize>(
         bytes: &mut [T],
         stride: u32,
         width: u32,
@@ -459,15 +471,21 @@ mod fast_gaussian_superior {
         radius: u32,
         threading_policy: ThreadingPolicy,
     ) where
-        T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+        T: FromPrimitive
+            + ToPrimitive
+            + Default
+            + Into<i64>
+            + Send
+            + Sync
+            + std::ops::AddAssign
+            + std::ops::SubAssign
+            + Copy
+            + 'static,
         f64: ToStorage<T> + AsPrimitive<T>,
     {
         let unsafe_image = UnsafeSlice::new(bytes);
         let thread_count = threading_policy.get_threads_count(width, height) as u32;
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(thread_count as usize)
-            .build()
-            .unwrap();
+        let pool = threading_policy.get_pool(thread_count as usize);
         pool.scope(|scope| {
             let segment_size = width / thread_count;
 
@@ -518,49 +536,102 @@ mod fast_gaussian_superior {
 /// Fast gaussian approximation. This is almost gaussian blur. Significantly slower than alternatives.
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `radius` - Radius more than ~256 is not supported.
+/// * `image` - Mutable image view that will be used as a source and destination
+/// * `radius` - Radius in range 1..=256.
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided or radius is out of range
+///
 /// O(1) complexity.
 pub fn fast_gaussian_superior(
-    bytes: &mut [u8],
-    stride: u32,
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let acq_radius = std::cmp::min(radius, 256);
-    match channels {
-        FastBlurChannels::Plane => {
-            fast_gaussian_superior::fast_gaussian_impl::<u8, 1>(
-                bytes,
-                stride,
-                width,
-                height,
-                acq_radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            fast_gaussian_superior::fast_gaussian_impl::<u8, 3>(
-                bytes,
-                stride,
-                width,
-                height,
-                acq_radius,
-                threading_policy,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            fast_gaussian_superior::fast_gaussian_impl::<u8, 4>(
-                bytes,
-                stride,
-                width,
-                height,
-                acq_radius,
-                threading_policy,
-            );
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 1, 256)?;
+    filter_alpha_mode(image, alpha_mode, |image| {
+        let stride = image.stride;
+        let width = image.width;
+        let height = image.height;
+        let bytes = &mut *image.data;
+        match image.channels {
+            FastBlurChannels::Plane => {
+                fast_gaussian_superior::fast_gaussian_impl::<u8, 1>(
+                    bytes,
+                    stride,
+                    width,
+                    height,
+                    radius,
+                    threading_policy,
+                );
+            }
+            FastBlurChannels::Channels2 => {
+                fast_gaussian_superior::fast_gaussian_impl::<u8, 2>(
+                    bytes,
+                    stride,
+                    width,
+                    height,
+                    radius,
+                    threading_policy,
+                );
+            }
+            FastBlurChannels::Channels3 => {
+                fast_gaussian_superior::fast_gaussian_impl::<u8, 3>(
+                    bytes,
+                    stride,
+                    width,
+                    height,
+                    radius,
+                    threading_policy,
+                );
+            }
+            FastBlurChannels::Channels4 => {
+                fast_gaussian_superior::fast_gaussian_impl::<u8, 4>(
+                    bytes,
+                    stride,
+                    width,
+                    height,
+                    radius,
+                    threading_policy,
+                );
+            }
         }
-    }
+        Ok(())
+    })
+}
+
+/// Fast gaussian approximation. This is almost gaussian blur. Significantly slower than alternatives from `src` into `dst`.
+///
+/// Same as [fast_gaussian_superior], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius in range 1..=256.
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided or radius is out of range
+///
+/// O(1) complexity.
+pub fn fast_gaussian_superior_into(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: u32,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    fast_gaussian_superior(dst, radius, alpha_mode, threading_policy)
 }
@@ -0,0 +1,156 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    #[inline(always)]
+    pub(crate) fn new(re: f32, im: f32) -> Complex {
+        Complex { re, im }
+    }
+
+    #[inline(always)]
+    pub(crate) fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+
+    #[inline(always)]
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+
+    #[inline(always)]
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+
+    #[inline(always)]
+    fn conj(self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+}
+
+/// Iterative radix-2 complex FFT of a fixed power of two size
+pub(crate) struct Fft {
+    size: usize,
+    bit_reverse: Vec<u32>,
+    /// Twiddles of all stages stored contiguously, stage of half length `h` starts at `h - 1`
+    twiddles: Vec<Complex>,
+}
+
+impl Fft {
+    pub(crate) fn new(size: usize) -> Fft {
+        assert!(size.is_power_of_two());
+        let bits = size.trailing_zeros();
+        let bit_reverse = (0..size as u32)
+            .map(|i| {
+                if bits == 0 {
+                    0
+                } else {
+                    i.reverse_bits() >> (32 - bits)
+                }
+            })
+            .collect();
+        let mut twiddles = Vec::with_capacity(size - 1);
+        let mut half = 1usize;
+        while half < size {
+            for k in 0..half {
+                let angle = -std::f64::consts::PI * k as f64 / half as f64;
+                twiddles.push(Complex::new(angle.cos() as f32, angle.sin() as f32));
+            }
+            half *= 2;
+        }
+        Fft {
+            size,
+            bit_reverse,
+            twiddles,
+        }
+    }
+
+    pub(crate) fn forward(&self, data: &mut [Complex]) {
+        self.transform::<false>(data);
+    }
+
+    /// Inverse transform, result is not divided by the size
+    pub(crate) fn inverse(&self, data: &mut [Complex]) {
+        self.transform::<true>(data);
+    }
+
+    fn transform<const INVERSE: bool>(&self, data: &mut [Complex]) {
+        let data = &mut data[..self.size];
+        for (i, &j) in self.bit_reverse.iter().enumerate() {
+            let j = j as usize;
+            if i < j {
+                data.swap(i, j);
+            }
+        }
+        // First two stages have trivial twiddles and too short inner loops for the generic butterfly
+        if self.size >= 2 {
+            for pair in data.chunks_exact_mut(2) {
+                let (a, b) = (pair[0], pair[1]);
+                pair[0] = a.add(b);
+                pair[1] = a.sub(b);
+            }
+        }
+        if self.size >= 4 {
+            for quad in data.chunks_exact_mut(4) {
+                let (a0, a1, b0, b1) = (quad[0], quad[1], quad[2], quad[3]);
+                // Multiplication by -i for the forward transform and by i for the inverse
+                let t1 = if INVERSE {
+                    Complex::new(-b1.im, b1.re)
+                } else {
+                    Complex::new(b1.im, -b1.re)
+                };
+                quad[0] = a0.add(b0);
+                quad[2] = a0.sub(b0);
+                quad[1] = a1.add(t1);
+                quad[3] = a1.sub(t1);
+            }
+        }
+        let mut half = 4usize;
+        while half < self.size {
+            let twiddles = &self.twiddles[half - 1..half * 2 - 1];
+            for block in data.chunks_exact_mut(half * 2) {
+                let (lo, hi) = block.split_at_mut(half);
+                for ((a, b), &twiddle) in lo.iter_mut().zip(hi.iter_mut()).zip(twiddles.iter()) {
+                    let twiddle = if INVERSE { twiddle.conj() } else { twiddle };
+                    let t = b.mul(twiddle);
+                    *b = a.sub(t);
+                    *a = a.add(t);
+                }
+            }
+            half *= 2;
+        }
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use crate::edge_padding::pad_image;
+use crate::filter2d::fft::{Complex, Fft};
+use crate::filter2d::KernelShape;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{BlurImage, BlurImageMut, EdgeMode, ThreadingPolicy};
+use num_traits::AsPrimitive;
+
+/// 2D kernels with at least this many non-zero taps are convolved through FFT,
+/// around 11x11 dense kernel tiled FFT outruns direct convolution
+pub(crate) const FFT_MIN_TAPS: usize = 100;
+/// Separable kernels longer than this are convolved through FFT,
+/// two direct passes stay cheaper than 2D transform for shorter kernels
+pub(crate) const FFT_MIN_SEPARABLE_LENGTH: usize = 160;
+/// Smallest tile, keeps overlap of the neighbouring tiles small for short kernels
+const MIN_TILE_SIZE: usize = 64;
+/// Columns transformed together, 8 complex values fill a cache line
+const COLUMNS_GROUP: usize = 8;
+
+/// Tiling of the one axis for overlap-add
+struct TileAxis {
+    fft_size: usize,
+    /// Input samples consumed by one tile
+    block: usize,
+    tiles: usize,
+}
+
+impl TileAxis {
+    fn new(kernel: usize, extent: usize) -> TileAxis {
+        let tile = kernel.max(MIN_TILE_SIZE).min(extent);
+        let fft_size = (kernel - 1 + tile).next_power_of_two();
+        let block = fft_size - kernel + 1;
+        TileAxis {
+            fft_size,
+            block,
+            tiles: extent.div_ceil(block),
+        }
+    }
+}
+
+/// Spectrum of the flipped kernel stored by columns, so correlation becomes convolution.
+///
+/// Normalization of the inverse transform is folded into the spectrum.
+fn kernel_spectrum(
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    row_fft: &Fft,
+    column_fft: &Fft,
+    tile_width: usize,
+    tile_height: usize,
+) -> Vec<Complex> {
+    let mut buffer = vec![Complex::default(); tile_width * tile_height];
+    for (j, row) in kernel.chunks_exact(kernel_shape.width).enumerate() {
+        let dst_row = &mut buffer[(kernel_shape.height - 1 - j) * tile_width..];
+        for (i, &weight) in row.iter().enumerate() {
+            dst_row[kernel_shape.width - 1 - i] = Complex::new(weight, 0f32);
+        }
+    }
+    for row in buffer
+        .chunks_exact_mut(tile_width)
+        .take(kernel_shape.height)
+    {
+        row_fft.forward(row);
+    }
+    let scale = 1f32 / (tile_width * tile_height) as f32;
+    let mut spectrum = vec![Complex::default(); tile_width * tile_height];
+    for (x, column) in spectrum.chunks_exact_mut(tile_height).enumerate() {
+        for (y, v) in column.iter_mut().enumerate() {
+            *v = buffer[y * tile_width + x].mul(Complex::new(scale, 0f32));
+        }
+        column_fft.forward(column);
+    }
+    spectrum
+}
+
+/// Convolves the image with a 2D kernel by tiled overlap-add in the frequency domain.
+///
+/// Image is padded exactly as in the direct path, so results match up to float rounding.
+/// Every tile holds two real signals, one in real and one in imaginary part,
+/// kernel is real so they don't interfere.
+pub(crate) fn fft_filter_2d<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    anchor: (usize, usize),
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let channels = src.channels.get_channels();
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let (anchor_x, anchor_y) = anchor;
+    let padding = anchor_x
+        .max(anchor_y)
+        .max(kernel_shape.width - 1 - anchor_x)
+        .max(kernel_shape.height - 1 - anchor_y);
+    let padded = pad_image(src, padding, edge_mode);
+    let padded_stride = (width + padding * 2) * channels;
+    // Only the part of padded image that reaches the output is convolved
+    let origin = (padding - anchor_y) * padded_stride + (padding - anchor_x) * channels;
+    let extent_x = width + kernel_shape.width - 1;
+    let extent_y = height + kernel_shape.height - 1;
+
+    let axis_x = TileAxis::new(kernel_shape.width, extent_x);
+    let axis_y = TileAxis::new(kernel_shape.height, extent_y);
+    let tile_width = axis_x.fft_size;
+    let tile_height = axis_y.fft_size;
+    let row_fft = Fft::new(tile_width);
+    let column_fft = Fft::new(tile_height);
+    let spectrum = kernel_spectrum(
+        kernel,
+        kernel_shape,
+        &row_fft,
+        &column_fft,
+        tile_width,
+        tile_height,
+    );
+
+    // Tiles of the one tile row and channel, transformed by pairs
+    let jobs: Vec<(usize, usize)> = (0..axis_x.tiles)
+        .flat_map(|tx| (0..channels).map(move |c| (tx, c)))
+        .collect();
+
+    let row_length = width * channels;
+    // Rows of the tile result shared with the next tile row
+    let seam = kernel_shape.height - 1;
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+    let write_rows = |first_row: i64, rows: &[f32]| {
+        for (r, row) in rows.chunks_exact(row_length).enumerate() {
+            let y = first_row + r as i64;
+            if y < 0 || y >= height as i64 {
+                continue;
+            }
+            let offset = y as usize * dst_stride;
+            for (i, &v) in row.iter().enumerate() {
+                unsafe {
+                    unsafe_dst.write(offset + i, v.to_());
+                }
+            }
+        }
+    };
+
+    // Returns rows shared with the previous and the next band of tile rows
+    let filter_tile_rows = |start_ty: usize, end_ty: usize| -> (Vec<f32>, Vec<f32>) {
+        let mut buffer = vec![Complex::default(); tile_width * tile_height];
+        // Row `r` holds output row `ty * block - seam + r`
+        let mut accumulator = vec![0f32; tile_height * row_length];
+        let mut columns = vec![Complex::default(); tile_height * COLUMNS_GROUP];
+        let mut top_seam = Vec::new();
+
+        for ty in start_ty..end_ty {
+            let in_y = ty * axis_y.block;
+            let rows_in = axis_y.block.min(extent_y - in_y);
+            for pair in jobs.chunks(2) {
+                buffer.fill(Complex::default());
+                for (lane, &(tx, c)) in pair.iter().enumerate() {
+                    let in_x = tx * axis_x.block;
+                    let cols_in = axis_x.block.min(extent_x - in_x);
+                    for (i, dst_row) in buffer
+                        .chunks_exact_mut(tile_width)
+                        .take(rows_in)
+                        .enumerate()
+                    {
+                        let src_row =
+                            &padded[origin + (in_y + i) * padded_stride + in_x * channels + c..];
+                        for (v, &s) in dst_row[..cols_in]
+                            .iter_mut()
+                            .zip(src_row.iter().step_by(channels))
+                        {
+                            if lane == 0 {
+                                v.re = s.as_();
+                            } else {
+                                v.im = s.as_();
+                            }
+                        }
+                    }
+                }
+
+                for row in buffer.chunks_exact_mut(tile_width).take(rows_in) {
+                    row_fft.forward(row);
+                }
+                // Columns are gathered by groups, so every touched cache line is used fully
+                for x0 in (0..tile_width).step_by(COLUMNS_GROUP) {
+                    let group = COLUMNS_GROUP.min(tile_width - x0);
+                    for (y, row) in buffer.chunks_exact(tile_width).enumerate() {
+                        for (g, &v) in row[x0..x0 + group].iter().enumerate() {
+                            columns[g * tile_height + y] = v;
+                        }
+                    }
+                    for (column, weights) in columns
+                        .chunks_exact_mut(tile_height)
+                        .zip(spectrum[x0 * tile_height..].chunks_exact(tile_height))
+                        .take(group)
+                    {
+                        column_fft.forward(column);
+                        for (v, &w) in column.iter_mut().zip(weights.iter()) {
+                            *v = v.mul(w);
+                        }
+                        column_fft.inverse(column);
+                    }
+                    for (y, row) in buffer.chunks_exact_mut(tile_width).enumerate() {
+                        for (g, v) in row[x0..x0 + group].iter_mut().enumerate() {
+                            *v = columns[g * tile_height + y];
+                        }
+                    }
+                }
+                for row in buffer.chunks_exact_mut(tile_width) {
+                    row_fft.inverse(row);
+                }
+
+                for (lane, &(tx, c)) in pair.iter().enumerate() {
+                    // Tile column `j` lands on output column `tx * block + j - (kernel width - 1)`
+                    let shift = tx * axis_x.block;
+                    let start_j = (kernel_shape.width - 1).saturating_sub(shift);
+                    let end_j = tile_width.min(extent_x - shift);
+                    for (row, acc_row) in buffer
+                        .chunks_exact(tile_width)
+                        .zip(accumulator.chunks_exact_mut(row_length))
+                    {
+                        for (j, v) in row.iter().enumerate().take(end_j).skip(start_j) {
+                            let x = shift + j - (kernel_shape.width - 1);
+                            acc_row[x * channels + c] += if lane == 0 { v.re } else { v.im };
+                        }
+                    }
+                }
+            }
+
+            let first_row = in_y as i64 - seam as i64;
+            if ty == start_ty && start_ty > 0 {
+                top_seam = accumulator[..seam * row_length].to_vec();
+                write_rows(
+                    first_row + seam as i64,
+                    &accumulator[seam * row_length..axis_y.block * row_length],
+                );
+            } else {
+                write_rows(first_row, &accumulator[..axis_y.block * row_length]);
+            }
+            accumulator.copy_within(axis_y.block * row_length.., 0);
+            accumulator[seam * row_length..].fill(0f32);
+        }
+
+        let seam_rows = accumulator[..seam * row_length].to_vec();
+        if end_ty == axis_y.tiles {
+            write_rows((end_ty * axis_y.block) as i64 - seam as i64, &seam_rows);
+            return (top_seam, Vec::new());
+        }
+        (top_seam, seam_rows)
+    };
+
+    let thread_count = threading_policy
+        .get_threads_count(src.width, src.height)
+        .min(axis_y.tiles);
+    if thread_count == 1 {
+        filter_tile_rows(0, axis_y.tiles);
+        return;
+    }
+    let segment_size = axis_y.tiles / thread_count;
+    let mut seams = vec![(Vec::new(), Vec::new()); thread_count];
+    let pool = threading_policy.get_pool(thread_count);
+    let filter_tile_rows = &filter_tile_rows;
+    pool.scope(|scope| {
+        for (i, seams) in seams.iter_mut().enumerate() {
+            let start_ty = i * segment_size;
+            let mut end_ty = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_ty = axis_y.tiles;
+            }
+            scope.spawn(move |_| {
+                *seams = filter_tile_rows(start_ty, end_ty);
+            });
+        }
+    });
+    // Rows on the border of two bands are the sum of both bands
+    for (i, pair) in seams.windows(2).enumerate() {
+        let rows: Vec<f32> = pair[0]
+            .1
+            .iter()
+            .zip(pair[1].0.iter())
+            .map(|(a, b)| a + b)
+            .collect();
+        let first_row = ((i + 1) * segment_size * axis_y.block) as i64 - seam as i64;
+        write_rows(first_row, &rows);
+    }
+}
@@ -0,0 +1,566 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use crate::alpha_mode::filter_alpha_mode_into;
+use crate::blur_error::MismatchedSize;
+use crate::edge_padding::pad_image;
+use crate::filter2d::fft_convolution::{fft_filter_2d, FFT_MIN_TAPS};
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use crate::filter2d::filter_2d_neon::{
+    filter_2d_accumulate_f32_neon, filter_2d_accumulate_u8_neon,
+};
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+use crate::filter2d::filter_2d_sse::{filter_2d_accumulate_f32_sse, filter_2d_accumulate_u8_sse};
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, ThreadingPolicy};
+use half::f16;
+use num_traits::AsPrimitive;
+
+/// Width and height of the row-major 2D kernel
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KernelShape {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl KernelShape {
+    pub fn new(width: usize, height: usize) -> KernelShape {
+        KernelShape { width, height }
+    }
+}
+
+/// Kernel point that is placed over the filtered pixel
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KernelAnchor {
+    /// `(width / 2, height / 2)`, the centre for odd kernels
+    Center,
+    /// Column and row of the kernel
+    Point { x: usize, y: usize },
+}
+
+impl KernelAnchor {
+    pub(crate) fn position(self, shape: KernelShape) -> (usize, usize) {
+        match self {
+            KernelAnchor::Center => (shape.width / 2, shape.height / 2),
+            KernelAnchor::Point { x, y } => (x, y),
+        }
+    }
+}
+
+/// Accumulator is updated by chunks of the row so it stays in L1 cache for wide images
+const CHUNK_SIZE: usize = 1024;
+
+fn filter_2d_accumulate<T: Copy + 'static + AsPrimitive<f32>>(
+    accumulator: &mut [f32],
+    src: &[T],
+    weight: f32,
+) {
+    for (dst, &src) in accumulator.iter_mut().zip(src.iter()) {
+        *dst += src.as_() * weight;
+    }
+}
+
+fn filter_2d_impl<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    anchor: (usize, usize),
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let channels = src.channels.get_channels();
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let (anchor_x, anchor_y) = anchor;
+    let padding = anchor_x
+        .max(anchor_y)
+        .max(kernel_shape.width - 1 - anchor_x)
+        .max(kernel_shape.height - 1 - anchor_y);
+    let padded = pad_image(src, padding, edge_mode);
+    let padded_stride = (width + padding * 2) * channels;
+
+    // Offsets of the taps from the top-left padded pixel, zero weights are skipped
+    // so sparse kernels as discs or emboss cost only their support
+    let taps: Vec<(usize, f32)> = kernel
+        .chunks_exact(kernel_shape.width)
+        .enumerate()
+        .flat_map(|(j, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, &weight)| weight != 0f32)
+                .map(move |(i, &weight)| {
+                    (
+                        (j + padding - anchor_y) * padded_stride
+                            + (i + padding - anchor_x) * channels,
+                        weight,
+                    )
+                })
+        })
+        .collect();
+
+    let mut _accumulator: fn(&mut [f32], &[T], f32) = filter_2d_accumulate::<T>;
+    if std::any::type_name::<T>() == "u8" {
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        {
+            if is_x86_feature_detected!("sse4.1") {
+                _accumulator = filter_2d_accumulate_u8_sse::<T>;
+            }
+        }
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        {
+            _accumulator = filter_2d_accumulate_u8_neon::<T>;
+        }
+    } else if std::any::type_name::<T>() == "f32" {
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        {
+            if is_x86_feature_detected!("sse4.1") {
+                _accumulator = filter_2d_accumulate_f32_sse::<T>;
+            }
+        }
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        {
+            _accumulator = filter_2d_accumulate_f32_neon::<T>;
+        }
+    }
+
+    let row_length = width * channels;
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+    let filter_rows = |start_y: usize, end_y: usize| {
+        let mut accumulator = vec![0f32; CHUNK_SIZE.min(row_length)];
+        for y in start_y..end_y {
+            let row_offset = y * padded_stride;
+            for chunk_start in (0..row_length).step_by(CHUNK_SIZE) {
+                let chunk_length = CHUNK_SIZE.min(row_length - chunk_start);
+                let accumulator = &mut accumulator[..chunk_length];
+                accumulator.fill(0f32);
+                for &(offset, weight) in taps.iter() {
+                    let start = row_offset + offset + chunk_start;
+                    _accumulator(accumulator, &padded[start..start + chunk_length], weight);
+                }
+                let dst_offset = y * dst_stride + chunk_start;
+                for (i, &v) in accumulator.iter().enumerate() {
+                    unsafe {
+                        unsafe_dst.write(dst_offset + i, v.to_());
+                    }
+                }
+            }
+        }
+    };
+
+    let thread_count = threading_policy.get_threads_count(src.width, src.height);
+    if thread_count == 1 {
+        filter_rows(0, height);
+        return;
+    }
+    let pool = threading_policy.get_pool(thread_count);
+    let filter_rows = &filter_rows;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| filter_rows(start_y, end_y));
+        }
+    });
+}
+
+fn filter_2d_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    anchor: KernelAnchor,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    if kernel.len() != kernel_shape.width * kernel_shape.height {
+        return Err(BlurError::KernelShapeMismatch(MismatchedSize {
+            expected: kernel_shape.width * kernel_shape.height,
+            received: kernel.len(),
+        }));
+    }
+    let (anchor_x, anchor_y) = anchor.position(kernel_shape);
+    if anchor_x >= kernel_shape.width || anchor_y >= kernel_shape.height {
+        return Err(BlurError::InvalidAnchor);
+    }
+    if edge_mode == EdgeMode::KernelClip {
+        return Err(BlurError::EdgeModeNotSupported(edge_mode));
+    }
+    let taps = kernel.iter().filter(|&&weight| weight != 0f32).count();
+    if taps >= FFT_MIN_TAPS {
+        fft_filter_2d(
+            src,
+            dst,
+            kernel,
+            kernel_shape,
+            (anchor_x, anchor_y),
+            edge_mode,
+            threading_policy,
+        );
+        return Ok(());
+    }
+    filter_2d_impl(
+        src,
+        dst,
+        kernel,
+        kernel_shape,
+        (anchor_x, anchor_y),
+        edge_mode,
+        threading_policy,
+    );
+    Ok(())
+}
+
+/// Performs convolution with an arbitrary non-separable 2D kernel.
+///
+/// Kernel is applied as correlation, `dst(x, y) = Σ kernel[j][i] * src(x + i - anchor_x, y + j - anchor_y)`,
+/// and is not normalized, so emboss, custom PSF or disc kernels may be used as is.
+/// Intermediate sums are kept in `f32`, result is rounded and saturated into `u8`.
+/// Prefer [crate::separable_filter] when the kernel is separable.
+/// Kernels with 100 or more non-zero taps are convolved through FFT with the same edge handling.
+/// O(W*H) complexity where W and H are the kernel dimensions.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel` - Row-major kernel weights, `kernel_shape.width * kernel_shape.height` items
+/// * `kernel_shape` - Width and height of the kernel
+/// * `anchor` - Kernel point that is placed over the filtered pixel, see [KernelAnchor]
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel doesn't match its shape, anchor is outside of the kernel or edge mode is not supported
+#[allow(clippy::too_many_arguments)]
+pub fn filter_2d(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    anchor: KernelAnchor,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        filter_2d_dispatch(
+            src,
+            dst,
+            kernel,
+            kernel_shape,
+            anchor,
+            edge_mode,
+            threading_policy,
+        )
+    })
+}
+
+/// Performs convolution with an arbitrary non-separable 2D kernel on the 16-bit image.
+///
+/// Kernel is applied as correlation, `dst(x, y) = Σ kernel[j][i] * src(x + i - anchor_x, y + j - anchor_y)`,
+/// and is not normalized. Intermediate sums are kept in `f32`, result is rounded and saturated into `u16`.
+/// Kernels with 100 or more non-zero taps are convolved through FFT with the same edge handling.
+/// O(W*H) complexity where W and H are the kernel dimensions.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel` - Row-major kernel weights, `kernel_shape.width * kernel_shape.height` items
+/// * `kernel_shape` - Width and height of the kernel
+/// * `anchor` - Kernel point that is placed over the filtered pixel, see [KernelAnchor]
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel doesn't match its shape, anchor is outside of the kernel or edge mode is not supported
+#[allow(clippy::too_many_arguments)]
+pub fn filter_2d_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    anchor: KernelAnchor,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        filter_2d_dispatch(
+            src,
+            dst,
+            kernel,
+            kernel_shape,
+            anchor,
+            edge_mode,
+            threading_policy,
+        )
+    })
+}
+
+/// Performs convolution with an arbitrary non-separable 2D kernel on the image in f32.
+///
+/// Kernel is applied as correlation, `dst(x, y) = Σ kernel[j][i] * src(x + i - anchor_x, y + j - anchor_y)`,
+/// and is not normalized, signed responses are kept.
+/// Kernels with 100 or more non-zero taps are convolved through FFT with the same edge handling.
+/// O(W*H) complexity where W and H are the kernel dimensions.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel` - Row-major kernel weights, `kernel_shape.width * kernel_shape.height` items
+/// * `kernel_shape` - Width and height of the kernel
+/// * `anchor` - Kernel point that is placed over the filtered pixel, see [KernelAnchor]
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel doesn't match its shape, anchor is outside of the kernel or edge mode is not supported
+#[allow(clippy::too_many_arguments)]
+pub fn filter_2d_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    anchor: KernelAnchor,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        filter_2d_dispatch(
+            src,
+            dst,
+            kernel,
+            kernel_shape,
+            anchor,
+            edge_mode,
+            threading_policy,
+        )
+    })
+}
+
+/// Performs convolution with an arbitrary non-separable 2D kernel on the image in f16.
+///
+/// Kernel is applied as correlation, `dst(x, y) = Σ kernel[j][i] * src(x + i - anchor_x, y + j - anchor_y)`,
+/// and is not normalized. Intermediate sums are kept in `f32`.
+/// Kernels with 100 or more non-zero taps are convolved through FFT with the same edge handling.
+/// O(W*H) complexity where W and H are the kernel dimensions.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel` - Row-major kernel weights, `kernel_shape.width * kernel_shape.height` items
+/// * `kernel_shape` - Width and height of the kernel
+/// * `anchor` - Kernel point that is placed over the filtered pixel, see [KernelAnchor]
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel doesn't match its shape, anchor is outside of the kernel or edge mode is not supported
+#[allow(clippy::too_many_arguments)]
+pub fn filter_2d_f16(
+    src: &BlurImage<f16>,
+    dst: &mut BlurImageMut<f16>,
+    kernel: &[f32],
+    kernel_shape: KernelShape,
+    anchor: KernelAnchor,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        filter_2d_dispatch(
+            src,
+            dst,
+            kernel,
+            kernel_shape,
+            anchor,
+            edge_mode,
+            threading_policy,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+    use crate::FastBlurChannels;
+
+    /// Runs `filter_2d_f32` against the naive correlation for every anchor, edge mode and threading
+    fn check_f32(width: usize, height: usize, kernel: &[f32], shape: KernelShape, tolerance: f32) {
+        let src: Vec<f32> = noise(width * height * 3, 256, 3)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect();
+        let reference = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 3,
+        };
+        let anchors = [
+            KernelAnchor::Center,
+            KernelAnchor::Point { x: 0, y: 0 },
+            KernelAnchor::Point {
+                x: shape.width - 1,
+                y: shape.height / 3,
+            },
+        ];
+        for anchor in anchors {
+            for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.]) {
+                let expected =
+                    reference.filter_2d(kernel, shape.width, anchor.position(shape), edge_mode);
+                for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(4)] {
+                    let mut dst = vec![0f32; src.len()];
+                    filter_2d_f32(
+                        &BlurImage::borrow(
+                            &src,
+                            width as u32,
+                            height as u32,
+                            FastBlurChannels::Channels3,
+                        ),
+                        &mut BlurImageMut::borrow(
+                            &mut dst,
+                            width as u32,
+                            height as u32,
+                            FastBlurChannels::Channels3,
+                        ),
+                        kernel,
+                        shape,
+                        anchor,
+                        edge_mode,
+                        AlphaMode::Independent,
+                        threading_policy,
+                    )
+                    .unwrap();
+                    assert_close(
+                        &dst,
+                        &expected,
+                        tolerance,
+                        &format!("{anchor:?} {edge_mode:?} {threading_policy:?}"),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn direct_filter_2d_matches_reference() {
+        let kernel = [
+            0.1, 0., -0.2, 0.05, //
+            0.3, 0.4, 0., 0.1, //
+            0., -0.15, 0.25, 0.2,
+        ];
+        assert!(kernel.iter().filter(|&&w| w != 0.).count() < FFT_MIN_TAPS);
+        check_f32(37, 29, &kernel, KernelShape::new(4, 3), 1e-5);
+    }
+
+    #[test]
+    fn fft_filter_2d_matches_reference() {
+        let shape = KernelShape::new(11, 13);
+        let kernel: Vec<f32> = noise(shape.width * shape.height, 200, 11)
+            .iter()
+            .map(|&v| (v as f32 - 90.) / 5000.)
+            .collect();
+        assert!(kernel.iter().filter(|&&w| w != 0.).count() >= FFT_MIN_TAPS);
+        // Wider than one overlap-add tile on both axes
+        check_f32(150, 131, &kernel, shape, 1e-4);
+    }
+
+    #[test]
+    fn filter_2d_u8_matches_reference() {
+        let (width, height) = (41, 19);
+        let src: Vec<u8> = noise(width * height * 4, 256, 5)
+            .iter()
+            .map(|&v| v as u8)
+            .collect();
+        let src_f32: Vec<f32> = src.iter().map(|&v| v as f32).collect();
+        let kernel = [
+            0.0625, 0.125, 0.0625, 0.125, 0.25, 0.125, 0.0625, 0.125, 0.0625,
+        ];
+        let expected = ReferenceImage {
+            data: &src_f32,
+            width,
+            height,
+            channels: 4,
+        }
+        .filter_2d(&kernel, 3, (1, 1), EdgeMode::Reflect101);
+        let mut dst = vec![0u8; src.len()];
+        filter_2d(
+            &BlurImage::borrow(
+                &src,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Channels4,
+            ),
+            &mut BlurImageMut::borrow(
+                &mut dst,
+                width as u32,
+                height as u32,
+                FastBlurChannels::Channels4,
+            ),
+            &kernel,
+            KernelShape::new(3, 3),
+            KernelAnchor::Center,
+            EdgeMode::Reflect101,
+            AlphaMode::Independent,
+            ThreadingPolicy::Fixed(3),
+        )
+        .unwrap();
+        let dst: Vec<f32> = dst.iter().map(|&v| v as f32).collect();
+        assert_close(&dst, &expected, 0.51, "u8");
+    }
+}
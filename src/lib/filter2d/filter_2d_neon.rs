@@ -0,0 +1,128 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use crate::neon::prefer_vfmaq_f32;
+use std::arch::aarch64::*;
+
+pub(crate) fn filter_2d_accumulate_u8_neon<T>(
+    accumulator: &mut [f32],
+    undef_src: &[T],
+    weight: f32,
+) {
+    unsafe {
+        let src: &[u8] = std::mem::transmute(undef_src);
+        let length = accumulator.len().min(src.len());
+        let v_weight = vdupq_n_f32(weight);
+        let mut cx = 0usize;
+
+        while cx + 16 <= length {
+            let acc_ptr = accumulator.as_mut_ptr().add(cx);
+            let pixels = vld1q_u8(src.as_ptr().add(cx));
+            let lo = vmovl_u8(vget_low_u8(pixels));
+            let hi = vmovl_high_u8(pixels);
+            let values = [
+                vcvtq_f32_u32(vmovl_u16(vget_low_u16(lo))),
+                vcvtq_f32_u32(vmovl_high_u16(lo)),
+                vcvtq_f32_u32(vmovl_u16(vget_low_u16(hi))),
+                vcvtq_f32_u32(vmovl_high_u16(hi)),
+            ];
+            for (i, values) in values.iter().enumerate() {
+                let store = vld1q_f32(acc_ptr.add(i * 4));
+                vst1q_f32(
+                    acc_ptr.add(i * 4),
+                    prefer_vfmaq_f32(store, *values, v_weight),
+                );
+            }
+            cx += 16;
+        }
+
+        while cx + 8 <= length {
+            let acc_ptr = accumulator.as_mut_ptr().add(cx);
+            let pixels = vmovl_u8(vld1_u8(src.as_ptr().add(cx)));
+            let values = [
+                vcvtq_f32_u32(vmovl_u16(vget_low_u16(pixels))),
+                vcvtq_f32_u32(vmovl_high_u16(pixels)),
+            ];
+            for (i, values) in values.iter().enumerate() {
+                let store = vld1q_f32(acc_ptr.add(i * 4));
+                vst1q_f32(
+                    acc_ptr.add(i * 4),
+                    prefer_vfmaq_f32(store, *values, v_weight),
+                );
+            }
+            cx += 8;
+        }
+
+        for (dst, &src) in accumulator[cx..length]
+            .iter_mut()
+            .zip(src[cx..length].iter())
+        {
+            *dst += src as f32 * weight;
+        }
+    }
+}
+
+pub(crate) fn filter_2d_accumulate_f32_neon<T>(
+    accumulator: &mut [f32],
+    undef_src: &[T],
+    weight: f32,
+) {
+    unsafe {
+        let src: &[f32] = std::mem::transmute(undef_src);
+        let length = accumulator.len().min(src.len());
+        let v_weight = vdupq_n_f32(weight);
+        let mut cx = 0usize;
+
+        while cx + 16 <= length {
+            let acc_ptr = accumulator.as_mut_ptr().add(cx);
+            let store = vld1q_f32_x4(acc_ptr);
+            let values = vld1q_f32_x4(src.as_ptr().add(cx));
+            let store = float32x4x4_t(
+                prefer_vfmaq_f32(store.0, values.0, v_weight),
+                prefer_vfmaq_f32(store.1, values.1, v_weight),
+                prefer_vfmaq_f32(store.2, values.2, v_weight),
+                prefer_vfmaq_f32(store.3, values.3, v_weight),
+            );
+            vst1q_f32_x4(acc_ptr, store);
+            cx += 16;
+        }
+
+        while cx + 4 <= length {
+            let acc_ptr = accumulator.as_mut_ptr().add(cx);
+            let store = vld1q_f32(acc_ptr);
+            let values = vld1q_f32(src.as_ptr().add(cx));
+            vst1q_f32(acc_ptr, prefer_vfmaq_f32(store, values, v_weight));
+            cx += 4;
+        }
+
+        for (dst, &src) in accumulator[cx..length]
+            .iter_mut()
+            .zip(src[cx..length].iter())
+        {
+            *dst += src * weight;
+        }
+    }
+}
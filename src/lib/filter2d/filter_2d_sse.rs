@@ -0,0 +1,174 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use crate::sse::_mm_prefer_fma_ps;
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+pub(crate) fn filter_2d_accumulate_u8_sse<T>(
+    accumulator: &mut [f32],
+    undef_src: &[T],
+    weight: f32,
+) {
+    unsafe {
+        let src: &[u8] = std::mem::transmute(undef_src);
+        if std::arch::is_x86_feature_detected!("fma") {
+            filter_2d_accumulate_u8_sse_fma_def(accumulator, src, weight);
+        } else {
+            filter_2d_accumulate_u8_sse_def(accumulator, src, weight);
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn filter_2d_accumulate_u8_sse_def(accumulator: &mut [f32], src: &[u8], weight: f32) {
+    filter_2d_accumulate_u8_sse_impl::<false>(accumulator, src, weight);
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+unsafe fn filter_2d_accumulate_u8_sse_fma_def(accumulator: &mut [f32], src: &[u8], weight: f32) {
+    filter_2d_accumulate_u8_sse_impl::<true>(accumulator, src, weight);
+}
+
+#[inline(always)]
+unsafe fn filter_2d_accumulate_u8_sse_impl<const FMA: bool>(
+    accumulator: &mut [f32],
+    src: &[u8],
+    weight: f32,
+) {
+    let length = accumulator.len().min(src.len());
+    let v_weight = _mm_set1_ps(weight);
+    let zeros = _mm_setzero_si128();
+    let mut cx = 0usize;
+
+    while cx + 16 <= length {
+        let acc_ptr = accumulator.as_mut_ptr().add(cx);
+        let pixels = _mm_loadu_si128(src.as_ptr().add(cx) as *const __m128i);
+        let lo = _mm_unpacklo_epi8(pixels, zeros);
+        let hi = _mm_unpackhi_epi8(pixels, zeros);
+        let values = [
+            _mm_cvtepi32_ps(_mm_unpacklo_epi16(lo, zeros)),
+            _mm_cvtepi32_ps(_mm_unpackhi_epi16(lo, zeros)),
+            _mm_cvtepi32_ps(_mm_unpacklo_epi16(hi, zeros)),
+            _mm_cvtepi32_ps(_mm_unpackhi_epi16(hi, zeros)),
+        ];
+        for (i, values) in values.iter().enumerate() {
+            let store = _mm_loadu_ps(acc_ptr.add(i * 4));
+            _mm_storeu_ps(
+                acc_ptr.add(i * 4),
+                _mm_prefer_fma_ps::<FMA>(store, *values, v_weight),
+            );
+        }
+        cx += 16;
+    }
+
+    while cx + 4 <= length {
+        let acc_ptr = accumulator.as_mut_ptr().add(cx);
+        let pixels = _mm_cvtepu8_epi32(_mm_cvtsi32_si128(
+            (src.as_ptr().add(cx) as *const i32).read_unaligned(),
+        ));
+        let store = _mm_loadu_ps(acc_ptr);
+        _mm_storeu_ps(
+            acc_ptr,
+            _mm_prefer_fma_ps::<FMA>(store, _mm_cvtepi32_ps(pixels), v_weight),
+        );
+        cx += 4;
+    }
+
+    for (dst, &src) in accumulator[cx..length]
+        .iter_mut()
+        .zip(src[cx..length].iter())
+    {
+        *dst += src as f32 * weight;
+    }
+}
+
+pub(crate) fn filter_2d_accumulate_f32_sse<T>(
+    accumulator: &mut [f32],
+    undef_src: &[T],
+    weight: f32,
+) {
+    unsafe {
+        let src: &[f32] = std::mem::transmute(undef_src);
+        if std::arch::is_x86_feature_detected!("fma") {
+            filter_2d_accumulate_f32_sse_fma_def(accumulator, src, weight);
+        } else {
+            filter_2d_accumulate_f32_sse_def(accumulator, src, weight);
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn filter_2d_accumulate_f32_sse_def(accumulator: &mut [f32], src: &[f32], weight: f32) {
+    filter_2d_accumulate_f32_sse_impl::<false>(accumulator, src, weight);
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+unsafe fn filter_2d_accumulate_f32_sse_fma_def(accumulator: &mut [f32], src: &[f32], weight: f32) {
+    filter_2d_accumulate_f32_sse_impl::<true>(accumulator, src, weight);
+}
+
+#[inline(always)]
+unsafe fn filter_2d_accumulate_f32_sse_impl<const FMA: bool>(
+    accumulator: &mut [f32],
+    src: &[f32],
+    weight: f32,
+) {
+    let length = accumulator.len().min(src.len());
+    let v_weight = _mm_set1_ps(weight);
+    let mut cx = 0usize;
+
+    while cx + 16 <= length {
+        let acc_ptr = accumulator.as_mut_ptr().add(cx);
+        let src_ptr = src.as_ptr().add(cx);
+        for i in (0..16).step_by(4) {
+            let store = _mm_loadu_ps(acc_ptr.add(i));
+            let values = _mm_loadu_ps(src_ptr.add(i));
+            _mm_storeu_ps(
+                acc_ptr.add(i),
+                _mm_prefer_fma_ps::<FMA>(store, values, v_weight),
+            );
+        }
+        cx += 16;
+    }
+
+    while cx + 4 <= length {
+        let acc_ptr = accumulator.as_mut_ptr().add(cx);
+        let store = _mm_loadu_ps(acc_ptr);
+        let values = _mm_loadu_ps(src.as_ptr().add(cx));
+        _mm_storeu_ps(acc_ptr, _mm_prefer_fma_ps::<FMA>(store, values, v_weight));
+        cx += 4;
+    }
+
+    for (dst, &src) in accumulator[cx..length]
+        .iter_mut()
+        .zip(src[cx..length].iter())
+    {
+        *dst += src * weight;
+    }
+}
@@ -25,22 +25,78 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::gaussian::avx::utils::_mm256_prefer_fma_ps;
 use crate::gaussian::gaussian_filter::GaussianFilter;
+use crate::sse::_mm_prefer_fma_ps;
 use crate::unsafe_slice::UnsafeSlice;
-use erydanos::{_mm256_prefer_fma_ps, _mm_prefer_fma_ps};
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_filter_f32_avx<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    unsafe {
+        gaussian_blur_vertical_pass_filter_f32_avx_def::<T, CHANNEL_CONFIGURATION>(
+            undef_src,
+            src_stride,
+            undef_unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            filter,
+            start_y,
+            end_y,
+        );
+    }
+}
+
+#[target_feature(enable = "avx2,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_vertical_pass_filter_f32_avx_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_vertical_pass_filter_f32_avx_impl::<T, CHANNEL_CONFIGURATION>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_vertical_pass_filter_f32_avx_impl<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
     _: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -208,7 +264,7 @@ pub fn gaussian_blur_vertical_pass_filter_f32_avx<T, const CHANNEL_CONFIGURATION
                     let y_src_shift = py * src_stride as usize;
                     let s_ptr = src.as_ptr().add(y_src_shift + cx);
                     let lo_lo = _mm_loadu_ps(s_ptr);
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<true>(store0, lo_lo, f_weight);
 
                     j += 1;
                 }
@@ -231,7 +287,7 @@ pub fn gaussian_blur_vertical_pass_filter_f32_avx<T, const CHANNEL_CONFIGURATION
                     let y_src_shift = py * src_stride as usize;
                     let s_ptr = src.as_ptr().add(y_src_shift + cx);
                     let f_pixel = _mm_setr_ps(s_ptr.read_unaligned(), 0., 0., 0.);
-                    store0 = _mm_prefer_fma_ps(store0, f_pixel, f_weight);
+                    store0 = _mm_prefer_fma_ps::<true>(store0, f_pixel, f_weight);
 
                     j += 1;
                 }
@@ -38,21 +38,21 @@ pub const fn _mm256_shuffle(z: u32, y: u32, x: u32, w: u32) -> i32 {
 #[inline(always)]
 pub(crate) unsafe fn load_u8_u32_one(ptr: *const u8) -> __m256i {
     let u_first = u32::from_le_bytes([ptr.read_unaligned(), 0, 0, 0]);
-    return _mm256_setr_epi32(u_first as i32, 0, 0, 0, 0, 0, 0, 0);
+    _mm256_setr_epi32(u_first as i32, 0, 0, 0, 0, 0, 0, 0)
 }
 
 #[inline(always)]
 pub unsafe fn avx2_pack_u32(s_1: __m256i, s_2: __m256i) -> __m256i {
     let packed = _mm256_packus_epi32(s_1, s_2);
     const MASK: i32 = _mm256_shuffle(3, 1, 2, 0);
-    return _mm256_permute4x64_epi64::<MASK>(packed);
+    _mm256_permute4x64_epi64::<MASK>(packed)
 }
 
 #[inline(always)]
 pub unsafe fn avx2_pack_u16(s_1: __m256i, s_2: __m256i) -> __m256i {
     let packed = _mm256_packus_epi16(s_1, s_2);
     const MASK: i32 = _mm256_shuffle(3, 1, 2, 0);
-    return _mm256_permute4x64_epi64::<MASK>(packed);
+    _mm256_permute4x64_epi64::<MASK>(packed)
 }
 
 #[inline(always)]
@@ -74,5 +74,11 @@ pub(crate) unsafe fn load_u8_f32_fast<const CHANNELS_COUNT: usize>(ptr: *const u
         0,
         0,
     );
-    return _mm256_cvtepi32_ps(v_int);
+    _mm256_cvtepi32_ps(v_int)
+}
+
+#[inline(always)]
+/// Computes `b*c + a`, callers must be compiled with `fma` enabled
+pub(crate) unsafe fn _mm256_prefer_fma_ps(a: __m256, b: __m256, c: __m256) -> __m256 {
+    _mm256_fmadd_ps(b, c, a)
 }
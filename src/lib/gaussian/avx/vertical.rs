@@ -30,13 +30,14 @@ use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
-use erydanos::_mm256_prefer_fma_ps;
+use crate::gaussian::avx::utils::_mm256_prefer_fma_ps;
 
 use crate::gaussian::avx::utils::{
     avx2_pack_u16, avx2_pack_u32, load_u8_f32_fast, load_u8_u32_one,
 };
 use crate::unsafe_slice::UnsafeSlice;
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_impl_avx<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
@@ -48,6 +49,64 @@ pub fn gaussian_blur_vertical_pass_impl_avx<T, const CHANNEL_CONFIGURATION: usiz
     kernel: &[f32],
     start_y: u32,
     end_y: u32,
+) {
+    unsafe {
+        gaussian_blur_vertical_pass_impl_avx_def::<T, CHANNEL_CONFIGURATION>(
+            undef_src,
+            src_stride,
+            undef_unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            kernel_size,
+            kernel,
+            start_y,
+            end_y,
+        );
+    }
+}
+
+#[target_feature(enable = "avx2,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_vertical_pass_impl_avx_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_vertical_pass_impl_avx_impl::<T, CHANNEL_CONFIGURATION>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_vertical_pass_impl_avx_impl<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
 ) {
     let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
     let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
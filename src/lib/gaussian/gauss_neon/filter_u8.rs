@@ -52,13 +52,14 @@ macro_rules! write_u8_by_channels {
     }};
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_horizontal_pass_filter_neon<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -403,6 +404,7 @@ pub fn gaussian_blur_horizontal_pass_filter_neon<T, const CHANNEL_CONFIGURATION:
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_filter_neon<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
@@ -410,7 +412,7 @@ pub fn gaussian_blur_vertical_pass_filter_neon<T, const CHANNEL_CONFIGURATION: u
     dst_stride: u32,
     width: u32,
     _: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -32,6 +32,7 @@ use crate::gaussian::gaussian_filter::GaussianFilter;
 use crate::neon::{prefer_vfma_f32, prefer_vfmaq_f32};
 use crate::unsafe_slice::UnsafeSlice;
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_filter_f32_neon<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
@@ -39,7 +40,7 @@ pub fn gaussian_blur_vertical_pass_filter_f32_neon<T, const CHANNEL_CONFIGURATIO
     dst_stride: u32,
     width: u32,
     _: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
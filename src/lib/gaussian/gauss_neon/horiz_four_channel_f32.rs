@@ -457,13 +457,14 @@ pub fn gaussian_horiz_t_f_chan_f32<T, const CHANNEL_CONFIGURATION: usize>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_horiz_t_f_chan_filter_f32<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -276,13 +276,14 @@ pub fn gaussian_horiz_one_chan_f32<T>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_horiz_one_chan_filter_f32<T>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -31,6 +31,7 @@ mod horiz_four_channel;
 mod horiz_four_channel_f32;
 mod horiz_one_channel_f32;
 mod horiz_one_channel_u8;
+mod iir_lines;
 mod vert_four_channel;
 mod vertical_f32;
 
@@ -42,5 +43,6 @@ pub use horiz_four_channel_f32::gaussian_horiz_t_f_chan_f32;
 pub use horiz_four_channel_f32::gaussian_horiz_t_f_chan_filter_f32;
 pub use horiz_one_channel_f32::*;
 pub use horiz_one_channel_u8::*;
+pub use iir_lines::gaussian_iir_lines_neon;
 pub use vert_four_channel::*;
 pub use vertical_f32::gaussian_blur_vertical_pass_f32_neon;
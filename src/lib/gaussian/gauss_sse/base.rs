@@ -25,13 +25,13 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::sse::_mm_prefer_fma_ps;
 use crate::sse::{
     _mm_broadcast_first, _mm_broadcast_fourth, _mm_broadcast_second, _mm_broadcast_third,
 };
 use crate::sse::{load_u8_f32_fast, load_u8_u32_one};
 use crate::unsafe_slice::UnsafeSlice;
 use crate::{accumulate_2_forward_sse_u8, accumulate_4_forward_sse_u8};
-use erydanos::_mm_prefer_fma_ps;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
@@ -60,6 +60,7 @@ macro_rules! write_u8_by_channels_sse {
     }};
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
@@ -70,6 +71,104 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
     kernel: &[f32],
     start_y: u32,
     end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_blur_horizontal_pass_impl_sse_fma_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_blur_horizontal_pass_impl_sse_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_horizontal_pass_impl_sse_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_horizontal_pass_impl_sse_impl::<T, CHANNEL_CONFIGURATION, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_horizontal_pass_impl_sse_fma_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_horizontal_pass_impl_sse_impl::<T, CHANNEL_CONFIGURATION, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_horizontal_pass_impl_sse_impl<
+    T,
+    const CHANNEL_CONFIGURATION: usize,
+    const FMA: bool,
+>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
 ) {
     unsafe {
         let src: &[u8] = std::mem::transmute(undef_src);
@@ -109,10 +208,10 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_set1_ps(weights.read_unaligned());
-                        store_0 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_0, f_weight);
-                        store_1 = _mm_prefer_fma_ps(store_1, pixel_colors_f32_1, f_weight);
-                        store_2 = _mm_prefer_fma_ps(store_2, pixel_colors_f32_2, f_weight);
-                        store_3 = _mm_prefer_fma_ps(store_3, pixel_colors_f32_3, f_weight);
+                        store_0 = _mm_prefer_fma_ps::<FMA>(store_0, pixel_colors_f32_0, f_weight);
+                        store_1 = _mm_prefer_fma_ps::<FMA>(store_1, pixel_colors_f32_1, f_weight);
+                        store_2 = _mm_prefer_fma_ps::<FMA>(store_2, pixel_colors_f32_2, f_weight);
+                        store_3 = _mm_prefer_fma_ps::<FMA>(store_3, pixel_colors_f32_3, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -205,10 +304,10 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
                     );
                     let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                     let f_weight = _mm_set1_ps(weight);
-                    store_0 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_0, f_weight);
-                    store_1 = _mm_prefer_fma_ps(store_1, pixel_colors_f32_1, f_weight);
-                    store_2 = _mm_prefer_fma_ps(store_2, pixel_colors_f32_2, f_weight);
-                    store_3 = _mm_prefer_fma_ps(store_3, pixel_colors_f32_3, f_weight);
+                    store_0 = _mm_prefer_fma_ps::<FMA>(store_0, pixel_colors_f32_0, f_weight);
+                    store_1 = _mm_prefer_fma_ps::<FMA>(store_1, pixel_colors_f32_1, f_weight);
+                    store_2 = _mm_prefer_fma_ps::<FMA>(store_2, pixel_colors_f32_2, f_weight);
+                    store_3 = _mm_prefer_fma_ps::<FMA>(store_3, pixel_colors_f32_3, f_weight);
 
                     r += 1;
                 }
@@ -250,8 +349,8 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_set1_ps(weights.read_unaligned());
-                        store_0 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_0, f_weight);
-                        store_1 = _mm_prefer_fma_ps(store_1, pixel_colors_f32_1, f_weight);
+                        store_0 = _mm_prefer_fma_ps::<FMA>(store_0, pixel_colors_f32_0, f_weight);
+                        store_1 = _mm_prefer_fma_ps::<FMA>(store_1, pixel_colors_f32_1, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -322,8 +421,8 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
                         load_u8_f32_fast::<CHANNEL_CONFIGURATION>(s_ptr.add(src_stride as usize));
                     let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                     let f_weight = _mm_set1_ps(weight);
-                    store_0 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_0, f_weight);
-                    store_1 = _mm_prefer_fma_ps(store_1, pixel_colors_f32_1, f_weight);
+                    store_0 = _mm_prefer_fma_ps::<FMA>(store_0, pixel_colors_f32_0, f_weight);
+                    store_1 = _mm_prefer_fma_ps::<FMA>(store_1, pixel_colors_f32_1, f_weight);
 
                     r += 1;
                 }
@@ -358,7 +457,7 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_set1_ps(weights.read_unaligned());
-                        store = _mm_prefer_fma_ps(store, pixel_colors_f32_0, f_weight);
+                        store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32_0, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -391,7 +490,7 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
                     let pixel_colors_f32 = load_u8_f32_fast::<CHANNEL_CONFIGURATION>(s_ptr);
                     let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                     let f_weight = _mm_set1_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 1;
                 }
@@ -402,6 +501,7 @@ pub fn gaussian_blur_horizontal_pass_impl_sse<T, const CHANNEL_CONFIGURATION: us
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
@@ -413,6 +513,111 @@ pub fn gaussian_blur_vertical_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usiz
     kernel: &[f32],
     start_y: u32,
     end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_blur_vertical_pass_impl_sse_fma_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_blur_vertical_pass_impl_sse_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_vertical_pass_impl_sse_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_vertical_pass_impl_sse_impl::<T, CHANNEL_CONFIGURATION, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_vertical_pass_impl_sse_fma_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_vertical_pass_impl_sse_impl::<T, CHANNEL_CONFIGURATION, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_vertical_pass_impl_sse_impl<
+    T,
+    const CHANNEL_CONFIGURATION: usize,
+    const FMA: bool,
+>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
 ) {
     let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
     let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
@@ -452,24 +657,24 @@ pub fn gaussian_blur_vertical_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usiz
                     let hi_16 = _mm_unpackhi_epi8(pixels_u8_lo, zeros_si);
                     let lo_16 = _mm_unpacklo_epi8(pixels_u8_lo, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(lo_16, zeros_si));
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(lo_16, zeros_si));
-                    store1 = _mm_prefer_fma_ps(store1, lo_hi, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, lo_hi, f_weight);
                     let hi_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(hi_16, zeros_si));
-                    store2 = _mm_prefer_fma_ps(store2, hi_lo, f_weight);
+                    store2 = _mm_prefer_fma_ps::<FMA>(store2, hi_lo, f_weight);
                     let hi_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(hi_16, zeros_si));
-                    store3 = _mm_prefer_fma_ps(store3, hi_hi, f_weight);
+                    store3 = _mm_prefer_fma_ps::<FMA>(store3, hi_hi, f_weight);
 
                     let hi_16 = _mm_unpackhi_epi8(pixels_u8_hi, zeros_si);
                     let lo_16 = _mm_unpacklo_epi8(pixels_u8_hi, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(lo_16, zeros_si));
-                    store4 = _mm_prefer_fma_ps(store4, lo_lo, f_weight);
+                    store4 = _mm_prefer_fma_ps::<FMA>(store4, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(lo_16, zeros_si));
-                    store5 = _mm_prefer_fma_ps(store5, lo_hi, f_weight);
+                    store5 = _mm_prefer_fma_ps::<FMA>(store5, lo_hi, f_weight);
                     let hi_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(hi_16, zeros_si));
-                    store6 = _mm_prefer_fma_ps(store6, hi_lo, f_weight);
+                    store6 = _mm_prefer_fma_ps::<FMA>(store6, hi_lo, f_weight);
                     let hi_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(hi_16, zeros_si));
-                    store7 = _mm_prefer_fma_ps(store7, hi_hi, f_weight);
+                    store7 = _mm_prefer_fma_ps::<FMA>(store7, hi_hi, f_weight);
 
                     r += 1;
                 }
@@ -518,13 +723,13 @@ pub fn gaussian_blur_vertical_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usiz
                     let hi_16 = _mm_unpackhi_epi8(pixels_u8, zeros_si);
                     let lo_16 = _mm_unpacklo_epi8(pixels_u8, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(lo_16, zeros_si));
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(lo_16, zeros_si));
-                    store1 = _mm_prefer_fma_ps(store1, lo_hi, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, lo_hi, f_weight);
                     let hi_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(hi_16, zeros_si));
-                    store2 = _mm_prefer_fma_ps(store2, hi_lo, f_weight);
+                    store2 = _mm_prefer_fma_ps::<FMA>(store2, hi_lo, f_weight);
                     let hi_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(hi_16, zeros_si));
-                    store3 = _mm_prefer_fma_ps(store3, hi_hi, f_weight);
+                    store3 = _mm_prefer_fma_ps::<FMA>(store3, hi_hi, f_weight);
 
                     r += 1;
                 }
@@ -561,9 +766,9 @@ pub fn gaussian_blur_vertical_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usiz
                     let pixels_u8 = _mm_loadu_si64(s_ptr);
                     let pixels_u16 = _mm_unpacklo_epi8(pixels_u8, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(pixels_u16, zeros_si));
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(pixels_u16, zeros_si));
-                    store1 = _mm_prefer_fma_ps(store1, lo_hi, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, lo_hi, f_weight);
 
                     r += 1;
                 }
@@ -594,7 +799,7 @@ pub fn gaussian_blur_vertical_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usiz
                     let y_src_shift = py as usize * src_stride as usize;
                     let s_ptr = src.as_ptr().add(y_src_shift + cx);
                     let lo_lo = load_u8_f32_fast::<4>(s_ptr);
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
 
                     r += 1;
                 }
@@ -626,7 +831,7 @@ pub fn gaussian_blur_vertical_pass_impl_sse<T, const CHANNEL_CONFIGURATION: usiz
                     let s_ptr = src.as_ptr().add(y_src_shift + cx);
                     let pixels_u32 = load_u8_u32_one(s_ptr);
                     let lo_lo = _mm_cvtepi32_ps(pixels_u32);
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
 
                     r += 1;
                 }
@@ -26,13 +26,13 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::gaussian::gaussian_filter::GaussianFilter;
+use crate::sse::_mm_prefer_fma_ps;
 use crate::sse::{
     _mm_broadcast_first, _mm_broadcast_fourth, _mm_broadcast_second, _mm_broadcast_third,
     load_u8_f32_fast, load_u8_u32_one,
 };
 use crate::unsafe_slice::UnsafeSlice;
 use crate::write_u8_by_channels_sse;
-use erydanos::_mm_prefer_fma_ps;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
@@ -47,22 +47,22 @@ macro_rules! accumulate_4_forward_sse_u8 {
         let mut pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_u32);
 
         let first_weight = _mm_broadcast_first($weights);
-        $store = _mm_prefer_fma_ps($store, pixel_colors_f32, first_weight);
+        $store = _mm_prefer_fma_ps::<FMA>($store, pixel_colors_f32, first_weight);
 
         pixel_colors_u32 = _mm_unpackhi_epi16(pixel_colors_u16, zeros_si);
         pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_u32);
 
-        $store = _mm_prefer_fma_ps($store, pixel_colors_f32, _mm_broadcast_second($weights));
+        $store = _mm_prefer_fma_ps::<FMA>($store, pixel_colors_f32, _mm_broadcast_second($weights));
 
         pixel_colors_u16 = _mm_unpackhi_epi8($pixel_colors, zeros_si);
         pixel_colors_u32 = _mm_unpacklo_epi16(pixel_colors_u16, zeros_si);
         let mut pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_u32);
-        $store = _mm_prefer_fma_ps($store, pixel_colors_f32, _mm_broadcast_third($weights));
+        $store = _mm_prefer_fma_ps::<FMA>($store, pixel_colors_f32, _mm_broadcast_third($weights));
 
         pixel_colors_u32 = _mm_unpackhi_epi16(pixel_colors_u16, zeros_si);
         pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_u32);
 
-        $store = _mm_prefer_fma_ps($store, pixel_colors_f32, _mm_broadcast_fourth($weights));
+        $store = _mm_prefer_fma_ps::<FMA>($store, pixel_colors_f32, _mm_broadcast_fourth($weights));
     }};
 }
 
@@ -74,22 +74,117 @@ macro_rules! accumulate_2_forward_sse_u8 {
         let mut pixel_colors_u32 = _mm_unpacklo_epi16(pixel_colors_u16, zeros_si);
         let mut pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_u32);
         let first_weight = _mm_broadcast_first($weights);
-        $store = _mm_prefer_fma_ps($store, pixel_colors_f32, first_weight);
+        $store = _mm_prefer_fma_ps::<FMA>($store, pixel_colors_f32, first_weight);
 
         pixel_colors_u32 = _mm_unpackhi_epi16(pixel_colors_u16, zeros_si);
         pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_u32);
 
-        $store = _mm_prefer_fma_ps($store, pixel_colors_f32, _mm_broadcast_second($weights));
+        $store = _mm_prefer_fma_ps::<FMA>($store, pixel_colors_f32, _mm_broadcast_second($weights));
     }};
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_horizontal_pass_filter_sse<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_blur_horizontal_pass_filter_sse_fma_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                filter,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_blur_horizontal_pass_filter_sse_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                filter,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_horizontal_pass_filter_sse_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_horizontal_pass_filter_sse_impl::<T, CHANNEL_CONFIGURATION, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_horizontal_pass_filter_sse_fma_def<
+    T,
+    const CHANNEL_CONFIGURATION: usize,
+>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_horizontal_pass_filter_sse_impl::<T, CHANNEL_CONFIGURATION, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_horizontal_pass_filter_sse_impl<
+    T,
+    const CHANNEL_CONFIGURATION: usize,
+    const FMA: bool,
+>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -143,10 +238,7 @@ pub fn gaussian_blur_horizontal_pass_filter_sse<T, const CHANNEL_CONFIGURATION:
                 }
 
                 while j + 2 < current_filter.size
-                    && filter_start as i64
-                        + j as i64
-                        + (if CHANNEL_CONFIGURATION == 4 { 3 } else { 3 })
-                        < width as i64
+                    && filter_start as i64 + j as i64 + 3 < width as i64
                 {
                     let px = (filter_start + j) * CHANNEL_CONFIGURATION;
                     let s_ptr = src.as_ptr().add(y_src_shift + px);
@@ -177,8 +269,8 @@ pub fn gaussian_blur_horizontal_pass_filter_sse<T, const CHANNEL_CONFIGURATION:
                         load_u8_f32_fast::<CHANNEL_CONFIGURATION>(s_ptr.add(src_stride as usize));
                     let weight = *filter_weights.get_unchecked(j);
                     let f_weight = _mm_set1_ps(weight);
-                    store_0 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_0, f_weight);
-                    store_1 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_1, f_weight);
+                    store_0 = _mm_prefer_fma_ps::<FMA>(store_0, pixel_colors_f32_0, f_weight);
+                    store_1 = _mm_prefer_fma_ps::<FMA>(store_1, pixel_colors_f32_1, f_weight);
 
                     j += 1;
                 }
@@ -258,7 +350,7 @@ pub fn gaussian_blur_horizontal_pass_filter_sse<T, const CHANNEL_CONFIGURATION:
                     let pixel_colors_f32 = load_u8_f32_fast::<CHANNEL_CONFIGURATION>(s_ptr);
                     let weight = *filter_weights.get_unchecked(j);
                     let f_weight = _mm_set1_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     j += 1;
                 }
@@ -269,14 +361,113 @@ pub fn gaussian_blur_horizontal_pass_filter_sse<T, const CHANNEL_CONFIGURATION:
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_filter_sse<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_blur_vertical_pass_filter_sse_fma_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                filter,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_blur_vertical_pass_filter_sse_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                filter,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_vertical_pass_filter_sse_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_vertical_pass_filter_sse_impl::<T, CHANNEL_CONFIGURATION, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_blur_vertical_pass_filter_sse_fma_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_blur_vertical_pass_filter_sse_impl::<T, CHANNEL_CONFIGURATION, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_vertical_pass_filter_sse_impl<
+    T,
+    const CHANNEL_CONFIGURATION: usize,
+    const FMA: bool,
+>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
     _: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -320,24 +511,24 @@ pub fn gaussian_blur_vertical_pass_filter_sse<T, const CHANNEL_CONFIGURATION: us
                     let hi_16 = _mm_unpackhi_epi8(pixels_u8_lo, zeros_si);
                     let lo_16 = _mm_unpacklo_epi8(pixels_u8_lo, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(lo_16, zeros_si));
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(lo_16, zeros_si));
-                    store1 = _mm_prefer_fma_ps(store1, lo_hi, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, lo_hi, f_weight);
                     let hi_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(hi_16, zeros_si));
-                    store2 = _mm_prefer_fma_ps(store2, hi_lo, f_weight);
+                    store2 = _mm_prefer_fma_ps::<FMA>(store2, hi_lo, f_weight);
                     let hi_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(hi_16, zeros_si));
-                    store3 = _mm_prefer_fma_ps(store3, hi_hi, f_weight);
+                    store3 = _mm_prefer_fma_ps::<FMA>(store3, hi_hi, f_weight);
 
                     let hi_16 = _mm_unpackhi_epi8(pixels_u8_hi, zeros_si);
                     let lo_16 = _mm_unpacklo_epi8(pixels_u8_hi, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(lo_16, zeros_si));
-                    store4 = _mm_prefer_fma_ps(store4, lo_lo, f_weight);
+                    store4 = _mm_prefer_fma_ps::<FMA>(store4, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(lo_16, zeros_si));
-                    store5 = _mm_prefer_fma_ps(store5, lo_hi, f_weight);
+                    store5 = _mm_prefer_fma_ps::<FMA>(store5, lo_hi, f_weight);
                     let hi_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(hi_16, zeros_si));
-                    store6 = _mm_prefer_fma_ps(store6, hi_lo, f_weight);
+                    store6 = _mm_prefer_fma_ps::<FMA>(store6, hi_lo, f_weight);
                     let hi_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(hi_16, zeros_si));
-                    store7 = _mm_prefer_fma_ps(store7, hi_hi, f_weight);
+                    store7 = _mm_prefer_fma_ps::<FMA>(store7, hi_hi, f_weight);
 
                     j += 1;
                 }
@@ -385,13 +576,13 @@ pub fn gaussian_blur_vertical_pass_filter_sse<T, const CHANNEL_CONFIGURATION: us
                     let hi_16 = _mm_unpackhi_epi8(pixels_u8, zeros_si);
                     let lo_16 = _mm_unpacklo_epi8(pixels_u8, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(lo_16, zeros_si));
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(lo_16, zeros_si));
-                    store1 = _mm_prefer_fma_ps(store1, lo_hi, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, lo_hi, f_weight);
                     let hi_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(hi_16, zeros_si));
-                    store2 = _mm_prefer_fma_ps(store2, hi_lo, f_weight);
+                    store2 = _mm_prefer_fma_ps::<FMA>(store2, hi_lo, f_weight);
                     let hi_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(hi_16, zeros_si));
-                    store3 = _mm_prefer_fma_ps(store3, hi_hi, f_weight);
+                    store3 = _mm_prefer_fma_ps::<FMA>(store3, hi_hi, f_weight);
 
                     j += 1;
                 }
@@ -427,9 +618,9 @@ pub fn gaussian_blur_vertical_pass_filter_sse<T, const CHANNEL_CONFIGURATION: us
                     let pixels_u8 = _mm_loadu_si64(s_ptr);
                     let pixels_u16 = _mm_unpacklo_epi8(pixels_u8, zeros_si);
                     let lo_lo = _mm_cvtepi32_ps(_mm_unpacklo_epi16(pixels_u16, zeros_si));
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
                     let lo_hi = _mm_cvtepi32_ps(_mm_unpackhi_epi16(pixels_u16, zeros_si));
-                    store1 = _mm_prefer_fma_ps(store1, lo_hi, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, lo_hi, f_weight);
 
                     j += 1;
                 }
@@ -459,7 +650,7 @@ pub fn gaussian_blur_vertical_pass_filter_sse<T, const CHANNEL_CONFIGURATION: us
                     let y_src_shift = py * src_stride as usize;
                     let s_ptr = src.as_ptr().add(y_src_shift + cx);
                     let lo_lo = load_u8_f32_fast::<4>(s_ptr);
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
 
                     j += 1;
                 }
@@ -490,7 +681,7 @@ pub fn gaussian_blur_vertical_pass_filter_sse<T, const CHANNEL_CONFIGURATION: us
                     let s_ptr = src.as_ptr().add(y_src_shift + cx);
                     let pixels_u32 = load_u8_u32_one(s_ptr);
                     let lo_lo = _mm_cvtepi32_ps(pixels_u32);
-                    store0 = _mm_prefer_fma_ps(store0, lo_lo, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, lo_lo, f_weight);
 
                     j += 1;
                 }
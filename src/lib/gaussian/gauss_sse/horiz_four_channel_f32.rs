@@ -25,12 +25,12 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::sse::_mm_prefer_fma_ps;
 use crate::sse::{
     _mm_broadcast_first, _mm_broadcast_fourth, _mm_broadcast_second, _mm_broadcast_third,
     _mm_loadu_ps_x4, _mm_split_rgb_5_ps, load_f32, store_f32,
 };
 use crate::unsafe_slice::UnsafeSlice;
-use erydanos::_mm_prefer_fma_ps;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
@@ -38,23 +38,32 @@ use std::arch::x86_64::*;
 
 macro_rules! accumulate_5_items {
     ($store0:expr, $pixel_colors_0:expr, $f_weights:expr, $last_weight:expr) => {{
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.0, _mm_broadcast_first($f_weights));
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.1, _mm_broadcast_second($f_weights));
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.2, _mm_broadcast_third($f_weights));
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.3, _mm_broadcast_fourth($f_weights));
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.4, _mm_set1_ps($last_weight));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.0, _mm_broadcast_first($f_weights));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.1, _mm_broadcast_second($f_weights));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.2, _mm_broadcast_third($f_weights));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.3, _mm_broadcast_fourth($f_weights));
+        $store0 = _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.4, _mm_set1_ps($last_weight));
     }};
 }
 
 macro_rules! accumulate_4_items {
     ($store0:expr, $pixel_colors_0:expr, $f_weights:expr) => {{
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.0, _mm_broadcast_first($f_weights));
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.1, _mm_broadcast_second($f_weights));
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.2, _mm_broadcast_third($f_weights));
-        $store0 = _mm_prefer_fma_ps($store0, $pixel_colors_0.3, _mm_broadcast_fourth($f_weights));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.0, _mm_broadcast_first($f_weights));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.1, _mm_broadcast_second($f_weights));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.2, _mm_broadcast_third($f_weights));
+        $store0 =
+            _mm_prefer_fma_ps::<FMA>($store0, $pixel_colors_0.3, _mm_broadcast_fourth($f_weights));
     }};
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_horiz_sse_t_f_chan_f32<T, const CHANNEL_CONFIGURATION: usize>(
     undef_src: &[T],
     src_stride: u32,
@@ -65,6 +74,100 @@ pub fn gaussian_horiz_sse_t_f_chan_f32<T, const CHANNEL_CONFIGURATION: usize>(
     kernel: &[f32],
     start_y: u32,
     end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_horiz_sse_t_f_chan_f32_fma_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_horiz_sse_t_f_chan_f32_def::<T, CHANNEL_CONFIGURATION>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_horiz_sse_t_f_chan_f32_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_horiz_sse_t_f_chan_f32_impl::<T, CHANNEL_CONFIGURATION, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_horiz_sse_t_f_chan_f32_fma_def<T, const CHANNEL_CONFIGURATION: usize>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_horiz_sse_t_f_chan_f32_impl::<T, CHANNEL_CONFIGURATION, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_horiz_sse_t_f_chan_f32_impl<T, const CHANNEL_CONFIGURATION: usize, const FMA: bool>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
 ) {
     unsafe {
         let src: &[f32] = std::mem::transmute(undef_src);
@@ -93,8 +196,8 @@ pub fn gaussian_horiz_sse_t_f_chan_f32<T, const CHANNEL_CONFIGURATION: usize>(
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_load1_ps(weights);
-                        store0 = _mm_prefer_fma_ps(store0, pixel_colors_0, f_weight);
-                        store1 = _mm_prefer_fma_ps(store1, pixel_colors_1, f_weight);
+                        store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_0, f_weight);
+                        store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_1, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -180,8 +283,8 @@ pub fn gaussian_horiz_sse_t_f_chan_f32<T, const CHANNEL_CONFIGURATION: usize>(
                     let pixel_colors_1 = load_f32::<CHANNEL_CONFIGURATION>(s_ptr_next);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_load1_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_1, f_weight);
 
                     r += 1;
                 }
@@ -214,7 +317,7 @@ pub fn gaussian_horiz_sse_t_f_chan_f32<T, const CHANNEL_CONFIGURATION: usize>(
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_load1_ps(weights);
-                        store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                        store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -267,7 +370,7 @@ pub fn gaussian_horiz_sse_t_f_chan_f32<T, const CHANNEL_CONFIGURATION: usize>(
                     let pixel_colors_f32 = load_f32::<CHANNEL_CONFIGURATION>(s_ptr);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_load1_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 1;
                 }
@@ -26,9 +26,9 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::gaussian::gaussian_filter::GaussianFilter;
+use crate::sse::_mm_prefer_fma_ps;
 use crate::sse::{_mm_hsum_ps, _mm_loadu_ps_x4};
 use crate::unsafe_slice::UnsafeSlice;
-use erydanos::_mm_prefer_fma_ps;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
@@ -36,13 +36,14 @@ use std::arch::x86_64::*;
 
 macro_rules! accumulate_4_items {
     ($store:expr, $pixel_colors:expr, $weight:expr) => {{
-        $store = _mm_prefer_fma_ps($store, $pixel_colors.0, $weight.0);
-        $store = _mm_prefer_fma_ps($store, $pixel_colors.1, $weight.1);
-        $store = _mm_prefer_fma_ps($store, $pixel_colors.2, $weight.2);
-        $store = _mm_prefer_fma_ps($store, $pixel_colors.3, $weight.3);
+        $store = _mm_prefer_fma_ps::<FMA>($store, $pixel_colors.0, $weight.0);
+        $store = _mm_prefer_fma_ps::<FMA>($store, $pixel_colors.1, $weight.1);
+        $store = _mm_prefer_fma_ps::<FMA>($store, $pixel_colors.2, $weight.2);
+        $store = _mm_prefer_fma_ps::<FMA>($store, $pixel_colors.3, $weight.3);
     }};
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_horiz_one_chan_f32<T>(
     undef_src: &[T],
     src_stride: u32,
@@ -53,6 +54,100 @@ pub fn gaussian_horiz_one_chan_f32<T>(
     kernel: &[f32],
     start_y: u32,
     end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_horiz_one_chan_f32_fma_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_horiz_one_chan_f32_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_horiz_one_chan_f32_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_horiz_one_chan_f32_impl::<T, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_horiz_one_chan_f32_fma_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_horiz_one_chan_f32_impl::<T, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_horiz_one_chan_f32_impl<T, const FMA: bool>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
 ) {
     let src: &[f32] = unsafe { std::mem::transmute(undef_src) };
     let unsafe_dst: &UnsafeSlice<'_, f32> = unsafe { std::mem::transmute(undef_unsafe_dst) };
@@ -84,8 +179,8 @@ pub fn gaussian_horiz_one_chan_f32<T>(
                     for i in 0..diff as usize {
                         let weight = kernel.as_ptr().add(i);
                         let f_weight = _mm_load_ss(weight);
-                        store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                        store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                        store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                        store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -146,8 +241,8 @@ pub fn gaussian_horiz_one_chan_f32<T>(
                     let pixel_colors_f32_1 = _mm_loadu_ps(s_ptr_next);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_loadu_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 4;
                 }
@@ -163,8 +258,8 @@ pub fn gaussian_horiz_one_chan_f32<T>(
                     let pixel_colors_f32_1 = _mm_setr_ps(s_ptr_next.read_unaligned(), 0., 0., 0.);
                     let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                     let f_weight = _mm_set1_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 1;
                 }
@@ -201,7 +296,7 @@ pub fn gaussian_horiz_one_chan_f32<T>(
                     for i in 0..diff as usize {
                         let weight = kernel.as_ptr().add(i);
                         let f_weight = _mm_load_ss(weight);
-                        store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                        store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -248,7 +343,7 @@ pub fn gaussian_horiz_one_chan_f32<T>(
                     let pixel_colors_f32 = _mm_loadu_ps(s_ptr);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_loadu_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 4;
                 }
@@ -262,7 +357,7 @@ pub fn gaussian_horiz_one_chan_f32<T>(
                     let pixel_colors_f32 = _mm_setr_ps(s_ptr.read_unaligned(), 0., 0., 0.);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_load_ss(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 1;
                 }
@@ -276,13 +371,101 @@ pub fn gaussian_horiz_one_chan_f32<T>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_horiz_one_chan_filter_f32<T>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_horiz_one_chan_filter_f32_fma_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                filter,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_horiz_one_chan_filter_f32_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                filter,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_horiz_one_chan_filter_f32_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_horiz_one_chan_filter_f32_impl::<T, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_horiz_one_chan_filter_f32_fma_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_horiz_one_chan_filter_f32_impl::<T, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_horiz_one_chan_filter_f32_impl<T, const FMA: bool>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -374,8 +557,8 @@ pub fn gaussian_horiz_one_chan_filter_f32<T>(
                     let pixel_colors_f32_1 = _mm_loadu_ps(s_ptr_next);
                     let weight = filter_weights.as_ptr().add(r);
                     let f_weight = _mm_loadu_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 4;
                 }
@@ -392,8 +575,8 @@ pub fn gaussian_horiz_one_chan_filter_f32<T>(
                     let pixel_colors_f32_1 = _mm_setr_ps(s_ptr_next.read_unaligned(), 0., 0., 0.);
                     let weight = filter_weights.as_ptr().add(r).read_unaligned();
                     let f_weight = _mm_set1_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 1;
                 }
@@ -477,7 +660,7 @@ pub fn gaussian_horiz_one_chan_filter_f32<T>(
                     let pixel_colors_f32 = _mm_loadu_ps(s_ptr);
                     let weight = filter_weights.as_ptr().add(r);
                     let f_weight = _mm_loadu_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 4;
                 }
@@ -492,7 +675,7 @@ pub fn gaussian_horiz_one_chan_filter_f32<T>(
                     let pixel_colors_f32 = _mm_setr_ps(s_ptr.read_unaligned(), 0., 0., 0.);
                     let weight = filter_weights.as_ptr().add(r);
                     let f_weight = _mm_load_ss(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 1;
                 }
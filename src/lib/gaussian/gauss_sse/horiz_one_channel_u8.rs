@@ -26,9 +26,9 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::gaussian::gaussian_filter::GaussianFilter;
+use crate::sse::_mm_prefer_fma_ps;
 use crate::sse::{_mm_hsum_ps, _mm_loadu_ps_x2, _mm_loadu_ps_x4, _mm_loadu_si128_x2};
 use crate::unsafe_slice::UnsafeSlice;
-use erydanos::_mm_prefer_fma_ps;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
@@ -40,14 +40,14 @@ macro_rules! accumulate_4_forward {
         let pixel_colors_low_u16 = _mm_unpacklo_epi8($pixel_colors, zeros_si);
         let pixel_color0 = _mm_cvtepi32_ps(_mm_unpacklo_epi16(pixel_colors_low_u16, zeros_si));
         let pixel_color1 = _mm_cvtepi32_ps(_mm_unpackhi_epi16(pixel_colors_low_u16, zeros_si));
-        $store0 = _mm_prefer_fma_ps($store0, pixel_color0, $weights.0);
-        $store0 = _mm_prefer_fma_ps($store0, pixel_color1, $weights.1);
+        $store0 = _mm_prefer_fma_ps::<FMA>($store0, pixel_color0, $weights.0);
+        $store0 = _mm_prefer_fma_ps::<FMA>($store0, pixel_color1, $weights.1);
 
         let pixel_colors_high_u16 = _mm_unpackhi_epi8($pixel_colors, zeros_si);
         let pixel_color2 = _mm_cvtepi32_ps(_mm_unpacklo_epi16(pixel_colors_high_u16, zeros_si));
         let pixel_color3 = _mm_cvtepi32_ps(_mm_unpackhi_epi16(pixel_colors_high_u16, zeros_si));
-        $store0 = _mm_prefer_fma_ps($store0, pixel_color2, $weights.2);
-        $store0 = _mm_prefer_fma_ps($store0, pixel_color3, $weights.3);
+        $store0 = _mm_prefer_fma_ps::<FMA>($store0, pixel_color2, $weights.2);
+        $store0 = _mm_prefer_fma_ps::<FMA>($store0, pixel_color3, $weights.3);
     }};
 }
 
@@ -58,8 +58,8 @@ macro_rules! accumulate_2_forward {
 
         let pixel_color_low = _mm_cvtepi32_ps(_mm_unpacklo_epi16(pixel_colors_u16, zeros_si));
         let pixel_color_high = _mm_cvtepi32_ps(_mm_unpackhi_epi16(pixel_colors_u16, zeros_si));
-        $store0 = _mm_prefer_fma_ps($store0, pixel_color_low, $weights.0);
-        $store0 = _mm_prefer_fma_ps($store0, pixel_color_high, $weights.1);
+        $store0 = _mm_prefer_fma_ps::<FMA>($store0, pixel_color_low, $weights.0);
+        $store0 = _mm_prefer_fma_ps::<FMA>($store0, pixel_color_high, $weights.1);
     }};
 }
 
@@ -74,6 +74,7 @@ macro_rules! read_f32_by_u8_x4 {
     }};
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_sse_horiz_one_chan_u8<T>(
     undef_src: &[T],
     src_stride: u32,
@@ -84,6 +85,100 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
     kernel: &[f32],
     start_y: u32,
     end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_sse_horiz_one_chan_u8_fma_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_sse_horiz_one_chan_u8_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                kernel_size,
+                kernel,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_sse_horiz_one_chan_u8_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_sse_horiz_one_chan_u8_impl::<T, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_sse_horiz_one_chan_u8_fma_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_sse_horiz_one_chan_u8_impl::<T, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        kernel_size,
+        kernel,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_sse_horiz_one_chan_u8_impl<T, const FMA: bool>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    kernel_size: usize,
+    kernel: &[f32],
+    start_y: u32,
+    end_y: u32,
 ) {
     let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
     let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
@@ -127,10 +222,10 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_setr_ps(weights.read_unaligned(), 0f32, 0f32, 0f32);
-                        store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                        store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
-                        store2 = _mm_prefer_fma_ps(store2, pixel_colors_f32_2, f_weight);
-                        store3 = _mm_prefer_fma_ps(store3, pixel_colors_f32_3, f_weight);
+                        store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                        store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
+                        store2 = _mm_prefer_fma_ps::<FMA>(store2, pixel_colors_f32_2, f_weight);
+                        store3 = _mm_prefer_fma_ps::<FMA>(store3, pixel_colors_f32_3, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -186,7 +281,7 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     accumulate_4_forward!(store0, pixel_colors_u8_0, weights);
                     accumulate_4_forward!(store1, pixel_colors_u8_1, weights);
                     accumulate_4_forward!(store2, pixel_colors_u8_2, weights);
-                    accumulate_4_forward!(store2, pixel_colors_u8_3, weights);
+                    accumulate_4_forward!(store3, pixel_colors_u8_3, weights);
 
                     r += 16;
                 }
@@ -236,10 +331,10 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     let pixel_colors_f32_3 = _mm_cvtepi32_ps(pixel_colors_i32_3);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_loadu_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
-                    store2 = _mm_prefer_fma_ps(store2, pixel_colors_f32_2, f_weight);
-                    store3 = _mm_prefer_fma_ps(store3, pixel_colors_f32_3, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
+                    store2 = _mm_prefer_fma_ps::<FMA>(store2, pixel_colors_f32_2, f_weight);
+                    store3 = _mm_prefer_fma_ps::<FMA>(store3, pixel_colors_f32_3, f_weight);
 
                     r += 4;
                 }
@@ -269,10 +364,10 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     );
                     let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                     let f_weight = _mm_set1_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
-                    store2 = _mm_prefer_fma_ps(store2, pixel_colors_f32_2, f_weight);
-                    store3 = _mm_prefer_fma_ps(store3, pixel_colors_f32_3, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
+                    store2 = _mm_prefer_fma_ps::<FMA>(store2, pixel_colors_f32_2, f_weight);
+                    store3 = _mm_prefer_fma_ps::<FMA>(store3, pixel_colors_f32_3, f_weight);
 
                     r += 1;
                 }
@@ -280,22 +375,22 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                 let agg0 = _mm_hsum_ps(store0);
                 let offset0 = y_dst_shift + x as usize;
                 let dst_ptr0 = unsafe_dst.slice.as_ptr().add(offset0) as *mut u8;
-                dst_ptr0.write_unaligned(agg0.round().min(255f32).max(0f32) as u8);
+                dst_ptr0.write_unaligned(agg0.round().clamp(0f32, 255f32) as u8);
 
                 let agg1 = _mm_hsum_ps(store1);
                 let offset1 = offset0 + dst_stride as usize;
                 let dst_ptr1 = unsafe_dst.slice.as_ptr().add(offset1) as *mut u8;
-                dst_ptr1.write_unaligned(agg1.round().min(255f32).max(0f32) as u8);
+                dst_ptr1.write_unaligned(agg1.round().clamp(0f32, 255f32) as u8);
 
                 let agg2 = _mm_hsum_ps(store2);
                 let offset2 = offset1 + dst_stride as usize;
                 let dst_ptr2 = unsafe_dst.slice.as_ptr().add(offset2) as *mut u8;
-                dst_ptr2.write_unaligned(agg2.round().min(255f32).max(0f32) as u8);
+                dst_ptr2.write_unaligned(agg2.round().clamp(0f32, 255f32) as u8);
 
                 let agg3 = _mm_hsum_ps(store3);
                 let offset3 = offset2 + dst_stride as usize;
                 let dst_ptr3 = unsafe_dst.slice.as_ptr().add(offset3) as *mut u8;
-                dst_ptr3.write_unaligned(agg3.round().min(255f32).max(0f32) as u8);
+                dst_ptr3.write_unaligned(agg3.round().clamp(0f32, 255f32) as u8);
             }
         }
         _cy = y;
@@ -326,8 +421,8 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_setr_ps(weights.read_unaligned(), 0f32, 0f32, 0f32);
-                        store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                        store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                        store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                        store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -407,8 +502,8 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     let pixel_colors_f32_1 = _mm_cvtepi32_ps(pixel_colors_i32_1);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_loadu_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 4;
                 }
@@ -426,8 +521,8 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                         _mm_setr_ps(s_ptr_next.read_unaligned() as f32, 0f32, 0f32, 0f32);
                     let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                     let f_weight = _mm_set1_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 1;
                 }
@@ -435,12 +530,12 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                 let agg0 = _mm_hsum_ps(store0);
                 let offset0 = y_dst_shift + x as usize;
                 let dst_ptr0 = unsafe_dst.slice.as_ptr().add(offset0) as *mut u8;
-                dst_ptr0.write_unaligned(agg0.round().min(255f32).max(0f32) as u8);
+                dst_ptr0.write_unaligned(agg0.round().clamp(0f32, 255f32) as u8);
 
                 let agg1 = _mm_hsum_ps(store1);
                 let offset1 = offset0 + dst_stride as usize;
                 let dst_ptr1 = unsafe_dst.slice.as_ptr().add(offset1) as *mut u8;
-                dst_ptr1.write_unaligned(agg1.round().min(255f32).max(0f32) as u8);
+                dst_ptr1.write_unaligned(agg1.round().clamp(0f32, 255f32) as u8);
             }
         }
         _cy = y;
@@ -465,7 +560,7 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     for i in 0..diff as usize {
                         let weights = kernel.as_ptr().add(i);
                         let f_weight = _mm_setr_ps(weights.read_unaligned(), 0f32, 0f32, 0f32);
-                        store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                        store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
                     }
                     r += diff as i32;
                 }
@@ -527,7 +622,7 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     let pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_i32);
                     let weight = kernel.as_ptr().add((r + half_kernel) as usize);
                     let f_weight = _mm_loadu_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 4;
                 }
@@ -542,7 +637,7 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                     let pixel_colors_f32 = _mm_setr_ps(value, 0f32, 0f32, 0f32);
                     let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                     let f_weight = _mm_setr_ps(weight, 0f32, 0f32, 0f32);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 1;
                 }
@@ -550,19 +645,107 @@ pub fn gaussian_sse_horiz_one_chan_u8<T>(
                 let agg = _mm_hsum_ps(store);
                 let offset = y_dst_shift + x as usize;
                 let dst_ptr = unsafe_dst.slice.as_ptr().add(offset) as *mut u8;
-                dst_ptr.write_unaligned(agg.round().min(255f32).max(0f32) as u8);
+                dst_ptr.write_unaligned(agg.round().clamp(0f32, 255f32) as u8);
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
     undef_src: &[T],
     src_stride: u32,
     undef_unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    unsafe {
+        if std::arch::is_x86_feature_detected!("fma") {
+            gaussian_sse_horiz_one_chan_filter_u8_fma_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                filter,
+                start_y,
+                end_y,
+            );
+        } else {
+            gaussian_sse_horiz_one_chan_filter_u8_def::<T>(
+                undef_src,
+                src_stride,
+                undef_unsafe_dst,
+                dst_stride,
+                width,
+                filter,
+                start_y,
+                end_y,
+            );
+        }
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_sse_horiz_one_chan_filter_u8_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_sse_horiz_one_chan_filter_u8_impl::<T, false>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[target_feature(enable = "sse4.1,fma")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn gaussian_sse_horiz_one_chan_filter_u8_fma_def<T>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
+    start_y: u32,
+    end_y: u32,
+) {
+    gaussian_sse_horiz_one_chan_filter_u8_impl::<T, true>(
+        undef_src,
+        src_stride,
+        undef_unsafe_dst,
+        dst_stride,
+        width,
+        filter,
+        start_y,
+        end_y,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn gaussian_sse_horiz_one_chan_filter_u8_impl<T, const FMA: bool>(
+    undef_src: &[T],
+    src_stride: u32,
+    undef_unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) {
@@ -631,7 +814,7 @@ pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
                     let weights = _mm_loadu_ps_x4(weight);
 
                     accumulate_4_forward!(store0, pixel_colors_u8_0, weights);
-                    accumulate_4_forward!(store0, pixel_colors_u8_1, weights);
+                    accumulate_4_forward!(store1, pixel_colors_u8_1, weights);
 
                     r += 16;
                 }
@@ -685,8 +868,8 @@ pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
                     let pixel_colors_f32_1 = _mm_cvtepi32_ps(pixel_colors_i32_1);
                     let weight = filter_weights.as_ptr().add(r);
                     let f_weight = _mm_loadu_ps(weight);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 4;
                 }
@@ -705,8 +888,8 @@ pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
                         _mm_setr_ps(s_ptr_next.read_unaligned() as f32, 0f32, 0f32, 0f32);
                     let weight = filter_weights.as_ptr().add(r).read_unaligned();
                     let f_weight = _mm_setr_ps(weight, 0f32, 0f32, 0f32);
-                    store0 = _mm_prefer_fma_ps(store0, pixel_colors_f32_0, f_weight);
-                    store1 = _mm_prefer_fma_ps(store1, pixel_colors_f32_1, f_weight);
+                    store0 = _mm_prefer_fma_ps::<FMA>(store0, pixel_colors_f32_0, f_weight);
+                    store1 = _mm_prefer_fma_ps::<FMA>(store1, pixel_colors_f32_1, f_weight);
 
                     r += 1;
                 }
@@ -714,12 +897,12 @@ pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
                 let agg0 = _mm_hsum_ps(store0);
                 let offset0 = y_dst_shift + x as usize;
                 let dst_ptr0 = unsafe_dst.slice.as_ptr().add(offset0) as *mut u8;
-                dst_ptr0.write_unaligned(agg0.min(255f32).max(0f32) as u8);
+                dst_ptr0.write_unaligned(agg0.clamp(0f32, 255f32) as u8);
 
                 let agg1 = _mm_hsum_ps(store1);
                 let offset1 = offset0 + dst_stride as usize;
                 let dst_ptr1 = unsafe_dst.slice.as_ptr().add(offset1) as *mut u8;
-                dst_ptr1.write_unaligned(agg1.min(255f32).max(0f32) as u8);
+                dst_ptr1.write_unaligned(agg1.clamp(0f32, 255f32) as u8);
             }
         }
         _cy = y;
@@ -813,7 +996,7 @@ pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
                     let pixel_colors_f32 = _mm_cvtepi32_ps(pixel_colors_i32);
                     let weight = filter_weights.as_ptr().add(r);
                     let f_weight = _mm_loadu_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 4;
                 }
@@ -829,7 +1012,7 @@ pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
                     let pixel_colors_f32 = _mm_setr_ps(value, 0f32, 0f32, 0f32);
                     let weight = filter_weights.as_ptr().add(r).read_unaligned();
                     let f_weight = _mm_set1_ps(weight);
-                    store = _mm_prefer_fma_ps(store, pixel_colors_f32, f_weight);
+                    store = _mm_prefer_fma_ps::<FMA>(store, pixel_colors_f32, f_weight);
 
                     r += 1;
                 }
@@ -837,7 +1020,7 @@ pub fn gaussian_sse_horiz_one_chan_filter_u8<T>(
                 let agg = _mm_hsum_ps(store);
                 let offset = y_dst_shift + x as usize;
                 let dst_ptr = unsafe_dst.slice.as_ptr().add(offset) as *mut u8;
-                dst_ptr.write_unaligned(agg.round().min(255f32).max(0f32) as u8);
+                dst_ptr.write_unaligned(agg.round().clamp(0f32, 255f32) as u8);
             }
         }
     }
@@ -0,0 +1,89 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::gaussian::gaussian_iir::{iir_filter_lines, IirCoefficients, IirLane};
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+impl IirLane for __m128d {
+    const LANES: usize = 2;
+
+    #[inline(always)]
+    unsafe fn splat(v: f64) -> Self {
+        _mm_set1_pd(v)
+    }
+
+    #[inline(always)]
+    unsafe fn load(ptr: *const f64) -> Self {
+        _mm_loadu_pd(ptr)
+    }
+
+    #[inline(always)]
+    unsafe fn store(self, ptr: *mut f64) {
+        _mm_storeu_pd(ptr, self)
+    }
+
+    #[inline(always)]
+    unsafe fn add(self, other: Self) -> Self {
+        _mm_add_pd(self, other)
+    }
+
+    #[inline(always)]
+    unsafe fn sub(self, other: Self) -> Self {
+        _mm_sub_pd(self, other)
+    }
+
+    #[inline(always)]
+    unsafe fn mul(self, other: Self) -> Self {
+        _mm_mul_pd(self, other)
+    }
+}
+
+pub fn gaussian_iir_lines_sse(
+    buffer: &mut [f64],
+    group: usize,
+    left: &[f64],
+    right: &[f64],
+    coefficients: &IirCoefficients,
+) {
+    unsafe {
+        gaussian_iir_lines_sse_def(buffer, group, left, right, coefficients);
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn gaussian_iir_lines_sse_def(
+    buffer: &mut [f64],
+    group: usize,
+    left: &[f64],
+    right: &[f64],
+    coefficients: &IirCoefficients,
+) {
+    iir_filter_lines::<__m128d>(buffer, group, left, right, coefficients);
+}
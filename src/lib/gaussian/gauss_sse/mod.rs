@@ -31,6 +31,7 @@ mod filter_vertical_f32;
 mod horiz_four_channel_f32;
 mod horiz_one_channel_f32;
 mod horiz_one_channel_u8;
+mod iir_lines;
 mod vertical_f32;
 
 pub use base::gaussian_blur_horizontal_pass_impl_sse;
@@ -42,4 +43,5 @@ pub use horiz_four_channel_f32::gaussian_horiz_sse_t_f_chan_f32;
 pub use horiz_one_channel_f32::gaussian_horiz_one_chan_f32;
 pub use horiz_one_channel_f32::gaussian_horiz_one_chan_filter_f32;
 pub use horiz_one_channel_u8::*;
+pub use iir_lines::gaussian_iir_lines_sse;
 pub use vertical_f32::gaussian_blur_vertical_pass_impl_f32_sse;
@@ -25,45 +25,34 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::threading_policy::BlurThreadPool;
+use half::f16;
 use num_traits::cast::FromPrimitive;
 use num_traits::AsPrimitive;
-use rayon::ThreadPool;
 
+use crate::alpha_mode::filter_alpha_mode_into;
 use crate::channels_configuration::FastBlurChannels;
 use crate::edge_mode::EdgeMode;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "avx2"
-))]
+use crate::edge_padding::{copy_unpadded, pad_image};
+use crate::filter2d::{fft_filter_2d, FFT_MIN_SEPARABLE_LENGTH};
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::avx::{
     gaussian_blur_vertical_pass_impl_avx, gaussian_blur_vertical_pass_impl_f32_avx,
 };
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::gaussian::gauss_neon::*;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::gauss_sse::gaussian_horiz_one_chan_f32;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::gauss_sse::gaussian_horiz_sse_t_f_chan_f32;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::gauss_sse::gaussian_sse_horiz_one_chan_u8;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::gauss_sse::{
     gaussian_blur_horizontal_pass_impl_sse, gaussian_blur_vertical_pass_impl_f32_sse,
     gaussian_blur_vertical_pass_impl_sse,
 };
-use crate::gaussian::gaussian_filter::create_filter;
+use crate::gaussian::gaussian_filter::{create_filter, create_filter_from_kernel, GaussianFilter};
 use crate::gaussian::gaussian_horizontal::gaussian_blur_horizontal_pass_impl;
 use crate::gaussian::gaussian_kernel::get_gaussian_kernel_1d;
 use crate::gaussian::gaussian_kernel_filter_dispatch::{
@@ -74,12 +63,10 @@ use crate::gaussian::gaussian_vertical::gaussian_blur_vertical_pass_c_impl;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
 use crate::ThreadingPolicy;
+use crate::{AlphaMode, BlurError, BlurImage, BlurImageMut, KernelShape};
 
-fn gaussian_blur_horizontal_pass<
-    T: FromPrimitive + Default + Send + Sync,
-    const CHANNEL_CONFIGURATION: usize,
-    const EDGE_MODE: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_horizontal_pass<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
@@ -87,13 +74,22 @@ fn gaussian_blur_horizontal_pass<
     width: u32,
     height: u32,
     kernel_size: usize,
-    kernel: &Vec<f32>,
-    thread_pool: &ThreadPool,
+    kernel: &[f32],
+    thread_pool: &BlurThreadPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
+    #[allow(clippy::type_complexity)]
     let mut _dispatcher: fn(
         src: &[T],
         src_stride: u32,
@@ -105,20 +101,20 @@ fn gaussian_blur_horizontal_pass<
         start_y: u32,
         end_y: u32,
     ) = gaussian_blur_horizontal_pass_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
-    let edge_mode: EdgeMode = EDGE_MODE.into();
-    if CHANNEL_CONFIGURATION >= 3 {
-        if std::any::type_name::<T>() == "u8" && edge_mode == EdgeMode::Clamp {
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
-            {
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
+    if CHANNEL_CONFIGURATION >= 3
+        && std::any::type_name::<T>() == "u8"
+        && edge_mode == EdgeMode::Clamp
+    {
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        {
+            if is_x86_feature_detected!("sse4.1") {
                 _dispatcher = gaussian_blur_horizontal_pass_impl_sse::<T, CHANNEL_CONFIGURATION>;
             }
-            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
-            {
-                _dispatcher = gaussian_blur_horizontal_pass_neon::<T, CHANNEL_CONFIGURATION>;
-            }
+        }
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        {
+            _dispatcher = gaussian_blur_horizontal_pass_neon::<T, CHANNEL_CONFIGURATION>;
         }
     }
     if std::any::type_name::<T>() == "f32" {
@@ -127,24 +123,22 @@ fn gaussian_blur_horizontal_pass<
             {
                 _dispatcher = gaussian_horiz_one_chan_f32::<T>;
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                _dispatcher = gaussian_horiz_one_chan_f32::<T>;
+                if is_x86_feature_detected!("sse4.1") {
+                    _dispatcher = gaussian_horiz_one_chan_f32::<T>;
+                }
             }
         } else if edge_mode == EdgeMode::Clamp && CHANNEL_CONFIGURATION >= 3 {
             #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
             {
                 _dispatcher = gaussian_horiz_t_f_chan_f32::<T, CHANNEL_CONFIGURATION>;
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                _dispatcher = gaussian_horiz_sse_t_f_chan_f32::<T, CHANNEL_CONFIGURATION>;
+                if is_x86_feature_detected!("sse4.1") {
+                    _dispatcher = gaussian_horiz_sse_t_f_chan_f32::<T, CHANNEL_CONFIGURATION>;
+                }
             }
         }
     }
@@ -153,11 +147,8 @@ fn gaussian_blur_horizontal_pass<
         if std::any::type_name::<T>() == "u8" {
             _dispatcher = gaussian_horiz_one_chan_u8::<T>;
         }
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
-        if std::any::type_name::<T>() == "u8" {
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        if std::any::type_name::<T>() == "u8" && is_x86_feature_detected!("sse4.1") {
             _dispatcher = gaussian_sse_horiz_one_chan_u8::<T>;
         }
     }
@@ -188,11 +179,8 @@ fn gaussian_blur_horizontal_pass<
     });
 }
 
-fn gaussian_blur_vertical_pass_impl<
-    T: FromPrimitive + Default + Send + Sync,
-    const CHANNEL_CONFIGURATION: usize,
-    const EDGE_MODE: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_vertical_pass_impl<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     unsafe_dst: &UnsafeSlice<T>,
@@ -204,7 +192,15 @@ fn gaussian_blur_vertical_pass_impl<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     gaussian_blur_vertical_pass_c_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
@@ -221,11 +217,8 @@ fn gaussian_blur_vertical_pass_impl<
     );
 }
 
-fn gaussian_blur_vertical_pass<
-    T: FromPrimitive + Default + Send + Sync,
-    const CHANNEL_CONFIGURATION: usize,
-    const EDGE_MODE: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_vertical_pass<T, const CHANNEL_CONFIGURATION: usize, const EDGE_MODE: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
@@ -233,13 +226,22 @@ fn gaussian_blur_vertical_pass<
     width: u32,
     height: u32,
     kernel_size: usize,
-    kernel: &Vec<f32>,
-    thread_pool: &ThreadPool,
+    kernel: &[f32],
+    thread_pool: &BlurThreadPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
+    #[allow(clippy::type_complexity)]
     let mut _dispatcher: fn(
         src: &[T],
         src_stride: u32,
@@ -252,22 +254,20 @@ fn gaussian_blur_vertical_pass<
         start_y: u32,
         end_y: u32,
     ) = gaussian_blur_vertical_pass_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     if std::any::type_name::<T>() == "u8" && edge_mode == EdgeMode::Clamp {
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channel
-            _dispatcher = gaussian_blur_vertical_pass_impl_sse::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("sse4.1") {
+                // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channel
+                _dispatcher = gaussian_blur_vertical_pass_impl_sse::<T, CHANNEL_CONFIGURATION>;
+            }
         }
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "avx2"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            _dispatcher = gaussian_blur_vertical_pass_impl_avx::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                _dispatcher = gaussian_blur_vertical_pass_impl_avx::<T, CHANNEL_CONFIGURATION>;
+            }
         }
         #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
         {
@@ -276,21 +276,19 @@ fn gaussian_blur_vertical_pass<
         }
     }
     if std::any::type_name::<T>() == "f32" && edge_mode == EdgeMode::Clamp {
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channel
-            _dispatcher = gaussian_blur_vertical_pass_impl_f32_sse::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("sse4.1") {
+                // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channel
+                _dispatcher = gaussian_blur_vertical_pass_impl_f32_sse::<T, CHANNEL_CONFIGURATION>;
+            }
         }
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "avx2"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channel
-            _dispatcher = gaussian_blur_vertical_pass_impl_f32_avx::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channel
+                _dispatcher = gaussian_blur_vertical_pass_impl_f32_avx::<T, CHANNEL_CONFIGURATION>;
+            }
         }
         #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
         {
@@ -327,140 +325,144 @@ fn gaussian_blur_vertical_pass<
     });
 }
 
-fn gaussian_blur_impl<
-    T: FromPrimitive + Default + Send + Sync,
-    const CHANNEL_CONFIGURATION: usize,
->(
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_impl<T, const CHANNEL_CONFIGURATION: usize>(
     src: &[T],
     src_stride: u32,
     dst: &mut [T],
     dst_stride: u32,
     width: u32,
     height: u32,
-    kernel_size: u32,
-    sigma: f32,
+    weights: &SeparableWeights,
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
-    if kernel_size % 2 == 0 {
-        panic!("kernel size must be odd");
-    }
     let mut transient: Vec<T> =
         vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
 
     let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.get_pool(thread_count as usize);
 
     match edge_mode {
         EdgeMode::Reflect => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
-            gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Reflect as usize }>(
-                &src,
+            let (horizontal_kernel, vertical_kernel) = weights.kernels();
+            gaussian_blur_horizontal_pass::<
+                T,
+                CHANNEL_CONFIGURATION,
+                { EdgeMode::Reflect.as_const() },
+            >(
+                src,
                 src_stride,
                 &mut transient,
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                horizontal_kernel.len(),
+                &horizontal_kernel,
                 &pool,
                 thread_count,
             );
-            gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Reflect as usize }>(
+            gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Reflect.as_const() }>(
                 &transient,
                 dst_stride,
                 dst,
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                vertical_kernel.len(),
+                &vertical_kernel,
                 &pool,
                 thread_count,
             );
         }
         EdgeMode::Wrap => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
-            gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Wrap as usize }>(
-                &src,
+            let (horizontal_kernel, vertical_kernel) = weights.kernels();
+            gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Wrap.as_const() }>(
+                src,
                 src_stride,
                 &mut transient,
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                horizontal_kernel.len(),
+                &horizontal_kernel,
                 &pool,
                 thread_count,
             );
-            gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Wrap as usize }>(
+            gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Wrap.as_const() }>(
                 &transient,
                 dst_stride,
                 dst,
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                vertical_kernel.len(),
+                &vertical_kernel,
                 &pool,
                 thread_count,
             );
         }
-        EdgeMode::Clamp => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
-            gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Clamp as usize }>(
-                &src,
+        // Constant border is padded beforehand, so the padded image is filtered as clamped
+        EdgeMode::Clamp | EdgeMode::Constant(_) => {
+            let (horizontal_kernel, vertical_kernel) = weights.kernels();
+            gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Clamp.as_const() }>(
+                src,
                 src_stride,
                 &mut transient,
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                horizontal_kernel.len(),
+                &horizontal_kernel,
                 &pool,
                 thread_count,
             );
-            gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Clamp as usize }>(
+            gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Clamp.as_const() }>(
                 &transient,
                 dst_stride,
                 dst,
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                vertical_kernel.len(),
+                &vertical_kernel,
                 &pool,
                 thread_count,
             );
         }
         EdgeMode::Reflect101 => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
+            let (horizontal_kernel, vertical_kernel) = weights.kernels();
             gaussian_blur_horizontal_pass::<
                 T,
                 CHANNEL_CONFIGURATION,
-                { EdgeMode::Reflect101 as usize },
+                { EdgeMode::Reflect101.as_const() },
             >(
-                &src,
+                src,
                 src_stride,
                 &mut transient,
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                horizontal_kernel.len(),
+                &horizontal_kernel,
                 &pool,
                 thread_count,
             );
             gaussian_blur_vertical_pass::<
                 T,
                 CHANNEL_CONFIGURATION,
-                { EdgeMode::Reflect101 as usize },
+                { EdgeMode::Reflect101.as_const() },
             >(
                 &transient,
                 dst_stride,
@@ -468,18 +470,18 @@ fn gaussian_blur_impl<
                 dst_stride,
                 width,
                 height,
-                kernel.len(),
-                &kernel,
+                vertical_kernel.len(),
+                &vertical_kernel,
                 &pool,
                 thread_count,
             );
         }
         EdgeMode::KernelClip => {
-            let horizontal_filter = create_filter(width as usize, kernel_size, sigma);
-            let vertical_filter = create_filter(height as usize, kernel_size, sigma);
+            let (horizontal_filter, vertical_filter) =
+                weights.clip_filters(width as usize, height as usize);
             gaussian_blur_horizontal_pass_edge_clip_dispatch::<T, CHANNEL_CONFIGURATION>(
-                &src,
-                dst_stride,
+                src,
+                src_stride,
                 &mut transient,
                 dst_stride,
                 width,
@@ -503,305 +505,474 @@ fn gaussian_blur_impl<
     }
 }
 
+/// Weights of both passes of separable filter
+pub(crate) enum SeparableWeights<'a> {
+    Gaussian {
+        kernel_size: u32,
+        sigma: f32,
+    },
+    Kernels {
+        horizontal: &'a [f32],
+        vertical: &'a [f32],
+    },
+}
+
+impl SeparableWeights<'_> {
+    fn kernels(&self) -> (Vec<f32>, Vec<f32>) {
+        match *self {
+            SeparableWeights::Gaussian { kernel_size, sigma } => {
+                let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
+                (kernel.clone(), kernel)
+            }
+            SeparableWeights::Kernels {
+                horizontal,
+                vertical,
+            } => (horizontal.to_vec(), vertical.to_vec()),
+        }
+    }
+
+    fn clip_filters(
+        &self,
+        width: usize,
+        height: usize,
+    ) -> (Vec<GaussianFilter>, Vec<GaussianFilter>) {
+        match *self {
+            SeparableWeights::Gaussian { kernel_size, sigma } => (
+                create_filter(width, kernel_size, sigma),
+                create_filter(height, kernel_size, sigma),
+            ),
+            SeparableWeights::Kernels {
+                horizontal,
+                vertical,
+            } => (
+                create_filter_from_kernel(width, horizontal),
+                create_filter_from_kernel(height, vertical),
+            ),
+        }
+    }
+
+    /// Long kernels are cheaper in the frequency domain
+    fn prefers_fft(&self) -> bool {
+        let length = match *self {
+            SeparableWeights::Gaussian { kernel_size, .. } => kernel_size as usize,
+            SeparableWeights::Kernels {
+                horizontal,
+                vertical,
+            } => horizontal.len().max(vertical.len()),
+        };
+        length > FFT_MIN_SEPARABLE_LENGTH
+    }
+
+    fn padding(&self) -> u32 {
+        match *self {
+            SeparableWeights::Gaussian { kernel_size, .. } => kernel_size / 2,
+            SeparableWeights::Kernels {
+                horizontal,
+                vertical,
+            } => (horizontal.len().max(vertical.len()) / 2) as u32,
+        }
+    }
+}
+
+pub(crate) fn separable_filter_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    weights: &SeparableWeights,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    if edge_mode != EdgeMode::KernelClip && weights.prefers_fft() {
+        let (horizontal_kernel, vertical_kernel) = weights.kernels();
+        let kernel: Vec<f32> = vertical_kernel
+            .iter()
+            .flat_map(|&v| horizontal_kernel.iter().map(move |&h| v * h))
+            .collect();
+        fft_filter_2d(
+            src,
+            dst,
+            &kernel,
+            KernelShape::new(horizontal_kernel.len(), vertical_kernel.len()),
+            (horizontal_kernel.len() / 2, vertical_kernel.len() / 2),
+            edge_mode,
+            threading_policy,
+        );
+        return Ok(());
+    }
+    let _dispatcher = match src.channels {
+        FastBlurChannels::Plane => gaussian_blur_impl::<T, 1>,
+        FastBlurChannels::Channels2 => gaussian_blur_impl::<T, 2>,
+        FastBlurChannels::Channels3 => gaussian_blur_impl::<T, 3>,
+        FastBlurChannels::Channels4 => gaussian_blur_impl::<T, 4>,
+    };
+    if let EdgeMode::Constant(_) = edge_mode {
+        // Samples beyond the border are materialized once, this keeps vectorized clamping passes usable
+        let padding = weights.padding();
+        let padded_width = src.width + padding * 2;
+        let padded_height = src.height + padding * 2;
+        let padded_stride = padded_width * src.channels.get_channels() as u32;
+        let padded_src = pad_image(src, padding as usize, edge_mode);
+        let mut padded_dst = vec![T::default(); padded_src.len()];
+        _dispatcher(
+            &padded_src,
+            padded_stride,
+            &mut padded_dst,
+            padded_stride,
+            padded_width,
+            padded_height,
+            weights,
+            threading_policy,
+            edge_mode,
+        );
+        copy_unpadded(&padded_dst, padding as usize, dst);
+        return Ok(());
+    }
+    _dispatcher(
+        src.data,
+        src.stride,
+        dst.data,
+        dst.stride,
+        src.width,
+        src.height,
+        weights,
+        threading_policy,
+        edge_mode,
+    );
+    Ok(())
+}
+
+fn gaussian_blur_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    if kernel_size.is_multiple_of(2) {
+        return Err(BlurError::KernelSizeNotOdd(kernel_size));
+    }
+    let sigma = if sigma == 0f32 {
+        kernel_size as f32 / 6f32
+    } else {
+        sigma
+    };
+    if sigma <= 0f32 || !sigma.is_finite() {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    separable_filter_dispatch(
+        src,
+        dst,
+        &SeparableWeights::Gaussian { kernel_size, sigma },
+        edge_mode,
+        threading_policy,
+    )
+}
+
 /// Performs gaussian blur on the image.
 ///
 /// This performs a gaussian kernel filter on the image producing beautiful looking result.
 /// Preferred if you need to perform an advanced signal analysis after.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
 /// O(R) complexity.
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count * size_of(PixelType) if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
-/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
-/// * `channels` - Count of channels in the image
-/// * `edge_mode` - Rule to handle edge mode
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of gaussian kernel. Must be odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. `0` selects the default of `kernel_size / 6`
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
 /// * `threading_policy` - Threading policy according to *ThreadingPolicy*
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or sigma is negative or not finite
 pub fn gaussian_blur(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     kernel_size: u32,
     sigma: f32,
-    channels: FastBlurChannels,
     edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    match channels {
-        FastBlurChannels::Plane => {
-            gaussian_blur_impl::<u8, 1>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            gaussian_blur_impl::<u8, 3>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            gaussian_blur_impl::<u8, 4>(
-                src,
-                src_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        gaussian_blur_dispatch::<u8>(src, dst, kernel_size, sigma, edge_mode, threading_policy)
+    })
 }
 
 /// Performs gaussian blur on the image.
 ///
 /// This performs a gaussian kernel filter on the image producing beautiful looking result.
 /// Preferred if you need to perform an advanced signal analysis after.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
 /// O(R) complexity.
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
-/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
-/// * `channels` - Count of channels in the image
-/// * `edge_mode` - Rule to handle edge mode
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of gaussian kernel. Must be odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. `0` selects the default of `kernel_size / 6`
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
 /// * `threading_policy` - Threading policy according to *ThreadingPolicy*
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or sigma is negative or not finite
 pub fn gaussian_blur_u16(
-    src: &[u16],
-    dst: &mut [u16],
-    width: u32,
-    height: u32,
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
     kernel_size: u32,
     sigma: f32,
-    channels: FastBlurChannels,
     edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    match channels {
-        FastBlurChannels::Plane => {
-            gaussian_blur_impl::<u16, 1>(
-                src,
-                width * channels.get_channels() as u32,
-                dst,
-                width * channels.get_channels() as u32,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            gaussian_blur_impl::<u16, 3>(
-                src,
-                width * channels.get_channels() as u32,
-                dst,
-                width * channels.get_channels() as u32,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            gaussian_blur_impl::<u16, 4>(
-                src,
-                width * channels.get_channels() as u32,
-                dst,
-                width * channels.get_channels() as u32,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        gaussian_blur_dispatch::<u16>(src, dst, kernel_size, sigma, edge_mode, threading_policy)
+    })
 }
 
 /// Performs gaussian blur on the image.
 ///
 /// This performs a gaussian kernel filter on the image producing beautiful looking result.
 /// Preferred if you need to perform an advanced signal analysis after.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
 /// O(R) complexity.
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
-/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
-/// * `channels` - Count of channels in the image
-/// * `edge_mode` - Rule to handle edge mode
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of gaussian kernel. Must be odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. `0` selects the default of `kernel_size / 6`
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
 /// * `threading_policy` - Threading policy according to *ThreadingPolicy*
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or sigma is negative or not finite
 pub fn gaussian_blur_f32(
-    src: &[f32],
-    dst: &mut [f32],
-    width: u32,
-    height: u32,
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
     kernel_size: u32,
     sigma: f32,
-    channels: FastBlurChannels,
     edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    match channels {
-        FastBlurChannels::Plane => {
-            gaussian_blur_impl::<f32, 1>(
-                src,
-                width * channels.get_channels() as u32,
-                dst,
-                width * channels.get_channels() as u32,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            gaussian_blur_impl::<f32, 3>(
-                src,
-                width * channels.get_channels() as u32,
-                dst,
-                width * channels.get_channels() as u32,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-        FastBlurChannels::Channels4 => {
-            gaussian_blur_impl::<f32, 4>(
-                src,
-                width * channels.get_channels() as u32,
-                dst,
-                width * channels.get_channels() as u32,
-                width,
-                height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-    }
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        gaussian_blur_dispatch::<f32>(src, dst, kernel_size, sigma, edge_mode, threading_policy)
+    })
 }
 
 /// Performs gaussian blur on the image.
 ///
 /// This performs a gaussian kernel filter on the image producing beautiful looking result.
 /// Preferred if you need to perform an advanced signal analysis after.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
 /// O(R) complexity.
 ///
 /// # Arguments
 ///
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
-/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
-/// * `channels` - Count of channels in the image
-/// * `edge_mode` - Rule to handle edge mode
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of gaussian kernel. Must be odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. `0` selects the default of `kernel_size / 6`
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
 /// * `threading_policy` - Threading policy according to *ThreadingPolicy*
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or sigma is negative or not finite
 pub fn gaussian_blur_f16(
-    src: &[u16],
-    dst: &mut [u16],
-    width: u32,
-    height: u32,
+    src: &BlurImage<f16>,
+    dst: &mut BlurImageMut<f16>,
     kernel_size: u32,
     sigma: f32,
-    channels: FastBlurChannels,
     edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
-) {
-    match channels {
-        FastBlurChannels::Plane => {
-            gaussian_blur_impl::<half::f16, 1>(
-                unsafe { std::mem::transmute(src) },
-                width * channels.get_channels() as u32,
-                unsafe { std::mem::transmute(dst) },
-                width * channels.get_channels() as u32,
+) -> Result<(), BlurError> {
+    gaussian_blur_dispatch::<f16>(src, dst, kernel_size, sigma, edge_mode, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::noise;
+
+    fn blur_with_strides(
+        src: &[u8],
+        width: u32,
+        height: u32,
+        src_stride: u32,
+        dst_stride: u32,
+        alpha_mode: AlphaMode,
+    ) -> Vec<u8> {
+        let row_length = width as usize * 4;
+        let mut padded_src = vec![17u8; src_stride as usize * height as usize];
+        for (padded, row) in padded_src
+            .chunks_exact_mut(src_stride as usize)
+            .zip(src.chunks_exact(row_length))
+        {
+            padded[..row_length].copy_from_slice(row);
+        }
+        let mut dst = vec![0u8; dst_stride as usize * height as usize];
+        gaussian_blur(
+            &BlurImage::borrow_with_stride(
+                &padded_src,
+                src_stride,
                 width,
                 height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
-            );
-        }
-        FastBlurChannels::Channels3 => {
-            gaussian_blur_impl::<half::f16, 3>(
-                unsafe { std::mem::transmute(src) },
-                width * channels.get_channels() as u32,
-                unsafe { std::mem::transmute(dst) },
-                width * channels.get_channels() as u32,
+                FastBlurChannels::Channels4,
+            ),
+            &mut BlurImageMut::borrow_with_stride(
+                &mut dst,
+                dst_stride,
                 width,
                 height,
-                kernel_size,
-                sigma,
-                threading_policy,
-                edge_mode,
+                FastBlurChannels::Channels4,
+            ),
+            9,
+            2.,
+            EdgeMode::KernelClip,
+            alpha_mode,
+            ThreadingPolicy::Fixed(2),
+        )
+        .unwrap();
+        dst.chunks_exact(dst_stride as usize)
+            .flat_map(|row| row[..row_length].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn gaussian_blur_kernel_clip_respects_strides() {
+        let (width, height) = (21u32, 13u32);
+        let row_length = width * 4;
+        let src = noise(row_length as usize * height as usize, 256, 3)
+            .iter()
+            .map(|&v| v as u8)
+            .collect::<Vec<u8>>();
+        let packed = blur_with_strides(
+            &src,
+            width,
+            height,
+            row_length,
+            row_length,
+            AlphaMode::Independent,
+        );
+        for (src_stride, dst_stride) in
+            [(row_length + 12, row_length), (row_length, row_length + 8)]
+        {
+            let padded = blur_with_strides(
+                &src,
+                width,
+                height,
+                src_stride,
+                dst_stride,
+                AlphaMode::Independent,
+            );
+            assert_eq!(
+                padded, packed,
+                "src stride {src_stride}, dst stride {dst_stride}"
             );
         }
-        FastBlurChannels::Channels4 => {
-            gaussian_blur_impl::<half::f16, 4>(
-                unsafe { std::mem::transmute(src) },
-                width * channels.get_channels() as u32,
-                unsafe { std::mem::transmute(dst) },
-                width * channels.get_channels() as u32,
+    }
+
+    #[test]
+    fn gaussian_blur_premultiply_into_padded_destination() {
+        let (width, height) = (17u32, 11u32);
+        let row_length = width * 4;
+        let src = noise(row_length as usize * height as usize, 256, 5)
+            .iter()
+            .map(|&v| v as u8)
+            .collect::<Vec<u8>>();
+        let packed = blur_with_strides(
+            &src,
+            width,
+            height,
+            row_length,
+            row_length,
+            AlphaMode::Premultiply,
+        );
+        for (src_stride, dst_stride) in [
+            (row_length, row_length + 8),
+            (row_length + 4, row_length + 20),
+        ] {
+            let padded = blur_with_strides(
+                &src,
                 width,
                 height,
+                src_stride,
+                dst_stride,
+                AlphaMode::Premultiply,
+            );
+            assert_eq!(
+                padded, packed,
+                "src stride {src_stride}, dst stride {dst_stride}"
+            );
+        }
+    }
+
+    #[test]
+    fn gaussian_blur_zero_sigma_selects_default() {
+        let (width, height, kernel_size) = (19u32, 8u32, 15u32);
+        let src = noise(width as usize * height as usize * 3, 256, 12)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let blur = |sigma: f32| {
+            let mut dst = vec![0f32; src.len()];
+            gaussian_blur_f32(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels3),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels3),
                 kernel_size,
                 sigma,
-                threading_policy,
-                edge_mode,
-            );
+                EdgeMode::Reflect101,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .map(|_| dst)
+        };
+        assert_eq!(blur(0.).unwrap(), blur(kernel_size as f32 / 6.).unwrap());
+        for sigma in [-1., f32::NAN, f32::INFINITY] {
+            assert!(matches!(blur(sigma), Err(BlurError::NegativeOrZeroSigma)));
         }
     }
 }
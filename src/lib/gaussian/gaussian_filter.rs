@@ -72,3 +72,29 @@ pub(crate) fn create_filter(length: usize, kernel_size: u32, sigma: f32) -> Vec<
     }
     filter
 }
+
+/// Clips arbitrary kernel at the borders of the line of `length` samples.
+///
+/// Clipped weights are rescaled to keep the sum of the whole kernel, kernels summing to zero are only clipped
+pub(crate) fn create_filter_from_kernel(length: usize, kernel: &[f32]) -> Vec<GaussianFilter> {
+    let filter_radius = kernel.len() / 2;
+    let kernel_sum: f32 = kernel.iter().sum();
+    (0..length)
+        .map(|x| {
+            let start = x.saturating_sub(filter_radius);
+            let end = (x + filter_radius).min(length - 1);
+            let weights = &kernel[start + filter_radius - x..=end + filter_radius - x];
+            let clipped_sum: f32 = weights.iter().sum();
+            let scale = if clipped_sum != 0f32 && kernel_sum != 0f32 {
+                kernel_sum / clipped_sum
+            } else {
+                1f32
+            };
+            GaussianFilter::new(
+                start,
+                end - start + 1,
+                weights.iter().map(|&w| w * scale).collect(),
+            )
+        })
+        .collect()
+}
@@ -31,8 +31,9 @@ use crate::unsafe_slice::UnsafeSlice;
 use crate::{clamp_edge, reflect_101, reflect_index, EdgeMode};
 use num_traits::{AsPrimitive, FromPrimitive};
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn gaussian_blur_horizontal_pass_impl<
-    T: FromPrimitive + Default + Send + Sync,
+    T,
     const CHANNEL_CONFIGURATION: usize,
     const EDGE_MODE: usize,
 >(
@@ -46,7 +47,15 @@ pub(crate) fn gaussian_blur_horizontal_pass_impl<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     gaussian_blur_horizontal_pass_impl_c::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
@@ -94,8 +103,9 @@ macro_rules! save_4_weights {
     }};
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn gaussian_blur_horizontal_pass_impl_c<
-    T: FromPrimitive + Default + Send + Sync,
+    T,
     const CHANNEL_CONFIGURATION: usize,
     const EDGE_MODE: usize,
 >(
@@ -109,10 +119,18 @@ pub(crate) fn gaussian_blur_horizontal_pass_impl_c<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let half_kernel = (kernel_size / 2) as i32;
     let mut _cy = start_y;
 
@@ -293,20 +311,26 @@ pub(crate) fn gaussian_blur_horizontal_pass_impl_c<
     }
 }
 
-pub(crate) fn gaussian_blur_horizontal_pass_impl_clip_edge<
-    T: FromPrimitive + Default + Send + Sync,
-    const CHANNEL_CONFIGURATION: usize,
->(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gaussian_blur_horizontal_pass_impl_clip_edge<T, const CHANNEL_CONFIGURATION: usize>(
     src: &[T],
     src_stride: u32,
     unsafe_dst: &UnsafeSlice<T>,
     dst_stride: u32,
     width: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     let mut _cy = start_y;
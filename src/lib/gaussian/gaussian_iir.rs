@@ -0,0 +1,661 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::{filter_alpha_mode_into, AlphaPremultiply};
+use crate::edge_mode::{reflect_index, EdgeColor};
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use crate::gaussian::gauss_neon::gaussian_iir_lines_neon;
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+use crate::gaussian::gauss_sse::gaussian_iir_lines_sse;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    clamp_edge, reflect_101, AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode,
+    FastBlurChannels, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+
+/// Smallest sigma where Young–van Vliet approximation holds
+const MIN_IIR_SIGMA: f32 = 0.5;
+/// Largest supported sigma, recursion in f64 loses precision beyond it
+const MAX_IIR_SIGMA: f32 = 2048.;
+/// Columns filtered together in the vertical pass
+const IIR_STRIP: usize = 16;
+
+/// Young–van Vliet recursive gaussian, `y[n] = b * x[n] + a0 * y[n-1] + a1 * y[n-2] + a2 * y[n-3]`
+/// forward and the same backward.
+///
+/// `boundary` is Triggs–Sdika matrix, it maps last three forward states to the backward ones
+/// as if the signal is continued by a constant.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct IirCoefficients {
+    pub(crate) b: f64,
+    pub(crate) a: [f64; 3],
+    pub(crate) boundary: [[f64; 3]; 3],
+}
+
+impl IirCoefficients {
+    fn new(sigma: f64) -> Self {
+        let q = if sigma >= 2.5 {
+            0.98711 * sigma - 0.96330
+        } else {
+            3.97156 - 4.14554 * (1. - 0.26891 * sigma).sqrt()
+        };
+        let q2 = q * q;
+        let q3 = q2 * q;
+        let b0 = 1.57825 + 2.44413 * q + 1.4281 * q2 + 0.422205 * q3;
+        let a = [
+            (2.44413 * q + 2.85619 * q2 + 1.26661 * q3) / b0,
+            -(1.4281 * q2 + 1.26661 * q3) / b0,
+            0.422205 * q3 / b0,
+        ];
+        let b = 1. - (a[0] + a[1] + a[2]);
+        // Boundary matrix is the response to each forward state left alone over a constant tail,
+        // it decays slower than exp(-n / q), so the tail is long enough to vanish in f64
+        let tail = (40. * q).ceil() as usize + 64;
+        let mut boundary = [[0f64; 3]; 3];
+        let mut forward = vec![0f64; tail + 3];
+        let mut backward = vec![0f64; tail + 6];
+        for state in 0..3 {
+            forward.fill(0.);
+            backward.fill(0.);
+            forward[2 - state] = 1.;
+            for k in 3..tail + 3 {
+                forward[k] = a[0] * forward[k - 1] + a[1] * forward[k - 2] + a[2] * forward[k - 3];
+            }
+            for k in (3..tail + 3).rev() {
+                backward[k] = b * forward[k]
+                    + a[0] * backward[k + 1]
+                    + a[1] * backward[k + 2]
+                    + a[2] * backward[k + 3];
+            }
+            for (i, row) in boundary.iter_mut().enumerate() {
+                row[state] = backward[3 + i];
+            }
+        }
+        IirCoefficients { b, a, boundary }
+    }
+}
+
+/// Vector of f64 lanes running the recursion for several lines at once
+pub(crate) trait IirLane: Copy {
+    const LANES: usize;
+    unsafe fn splat(v: f64) -> Self;
+    unsafe fn load(ptr: *const f64) -> Self;
+    unsafe fn store(self, ptr: *mut f64);
+    unsafe fn add(self, other: Self) -> Self;
+    unsafe fn sub(self, other: Self) -> Self;
+    unsafe fn mul(self, other: Self) -> Self;
+}
+
+impl IirLane for f64 {
+    const LANES: usize = 1;
+
+    #[inline(always)]
+    unsafe fn splat(v: f64) -> Self {
+        v
+    }
+
+    #[inline(always)]
+    unsafe fn load(ptr: *const f64) -> Self {
+        ptr.read()
+    }
+
+    #[inline(always)]
+    unsafe fn store(self, ptr: *mut f64) {
+        ptr.write(self)
+    }
+
+    #[inline(always)]
+    unsafe fn add(self, other: Self) -> Self {
+        self + other
+    }
+
+    #[inline(always)]
+    unsafe fn sub(self, other: Self) -> Self {
+        self - other
+    }
+
+    #[inline(always)]
+    unsafe fn mul(self, other: Self) -> Self {
+        self * other
+    }
+}
+
+/// Filters `group` interleaved lines stored in `buffer` in place, forward and then backward.
+///
+/// `left` and `right` hold the value every line is continued with before its start and after its end.
+#[inline(always)]
+pub(crate) unsafe fn iir_filter_lines<V: IirLane>(
+    buffer: &mut [f64],
+    group: usize,
+    left: &[f64],
+    right: &[f64],
+    coefficients: &IirCoefficients,
+) {
+    let len = buffer.len() / group;
+    let b = V::splat(coefficients.b);
+    let a0 = V::splat(coefficients.a[0]);
+    let a1 = V::splat(coefficients.a[1]);
+    let a2 = V::splat(coefficients.a[2]);
+    let m = coefficients.boundary.map(|row| row.map(|v| V::splat(v)));
+    let ptr = buffer.as_mut_ptr();
+    for lane in (0..group).step_by(V::LANES) {
+        // Constant signal before the start is its own steady state
+        let mut w1 = V::load(left.as_ptr().add(lane));
+        let mut w2 = w1;
+        let mut w3 = w1;
+        for k in 0..len {
+            let item = ptr.add(k * group + lane);
+            let w = V::load(item)
+                .mul(b)
+                .add(w1.mul(a0))
+                .add(w2.mul(a1))
+                .add(w3.mul(a2));
+            w.store(item);
+            w3 = w2;
+            w2 = w1;
+            w1 = w;
+        }
+        let tail = V::load(right.as_ptr().add(lane));
+        let (u0, u1, u2) = (w1.sub(tail), w2.sub(tail), w3.sub(tail));
+        let boundary =
+            m.map(|row| tail.add(row[0].mul(u0).add(row[1].mul(u1)).add(row[2].mul(u2))));
+        let (mut y1, mut y2, mut y3) = (boundary[0], boundary[1], boundary[2]);
+        for k in (0..len).rev() {
+            let item = ptr.add(k * group + lane);
+            let y = V::load(item)
+                .mul(b)
+                .add(y1.mul(a0))
+                .add(y2.mul(a1))
+                .add(y3.mul(a2));
+            y.store(item);
+            y3 = y2;
+            y2 = y1;
+            y1 = y;
+        }
+    }
+}
+
+pub(crate) fn iir_filter_lines_scalar(
+    buffer: &mut [f64],
+    group: usize,
+    left: &[f64],
+    right: &[f64],
+    coefficients: &IirCoefficients,
+) {
+    unsafe {
+        iir_filter_lines::<f64>(buffer, group, left, right, coefficients);
+    }
+}
+
+type IirLinesFilter = fn(&mut [f64], usize, &[f64], &[f64], &IirCoefficients);
+
+/// Selects the line filter and the lanes count it expects the group to be aligned to
+fn iir_lines_filter() -> (IirLinesFilter, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    {
+        if is_x86_feature_detected!("sse4.1") {
+            return (gaussian_iir_lines_sse, 2);
+        }
+    }
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        return (gaussian_iir_lines_neon, 2);
+    }
+    #[allow(unreachable_code)]
+    (iir_filter_lines_scalar, 1)
+}
+
+/// Index of the sample `i` of the line of `n` samples continued by `edge_mode`
+#[inline(always)]
+fn edge_index(edge_mode: EdgeMode, i: i64, n: usize) -> usize {
+    clamp_edge!(edge_mode, i, 0i64, n as i64 - 1)
+}
+
+/// Samples filtered beyond each side of the line, the rest of the continuation is taken as constant.
+/// Clamp and constant continuations are constant already, so boundary matrix alone is exact for them
+fn iir_warm_up(edge_mode: EdgeMode, sigma: f32) -> usize {
+    match edge_mode {
+        EdgeMode::Clamp | EdgeMode::Constant(_) | EdgeMode::KernelClip => 0,
+        EdgeMode::Wrap | EdgeMode::Reflect | EdgeMode::Reflect101 => (sigma * 4.).ceil() as usize,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn gaussian_iir_horizontal<T, const CHANNELS: usize>(
+    src: &BlurImage<T>,
+    unsafe_dst: &UnsafeSlice<f32>,
+    start_y: usize,
+    end_y: usize,
+    edge_mode: EdgeMode,
+    warm_up: usize,
+    coefficients: &IirCoefficients,
+    filter: (IirLinesFilter, usize),
+) where
+    T: Copy + AsPrimitive<f64>,
+{
+    let (filter, lanes) = filter;
+    let width = src.width as usize;
+    let group = CHANNELS.div_ceil(lanes) * lanes;
+    let len = width + warm_up * 2;
+    let mut buffer = vec![0f64; len * group];
+    let mut left = vec![0f64; group];
+    let mut right = vec![0f64; group];
+    let dst_stride = width * CHANNELS;
+    for y in start_y..end_y {
+        let src_row = &src.data[y * src.stride as usize..];
+        for (e, dst) in buffer.chunks_exact_mut(group).enumerate() {
+            let x = edge_index(edge_mode, e as i64 - warm_up as i64, width);
+            let src = &src_row[x * CHANNELS..(x + 1) * CHANNELS];
+            for (dst, &src) in dst.iter_mut().zip(src.iter()) {
+                *dst = src.as_();
+            }
+        }
+        for c in 0..CHANNELS {
+            (left[c], right[c]) = match edge_mode {
+                EdgeMode::Constant(EdgeColor(color)) => (color[c] as f64, color[c] as f64),
+                _ => (buffer[c], buffer[(len - 1) * group + c]),
+            };
+        }
+        filter(&mut buffer, group, &left, &right, coefficients);
+        let y_dst = y * dst_stride;
+        for (x, src) in buffer
+            .chunks_exact(group)
+            .skip(warm_up)
+            .take(width)
+            .enumerate()
+        {
+            for (c, &v) in src[..CHANNELS].iter().enumerate() {
+                unsafe {
+                    unsafe_dst.write(y_dst + x * CHANNELS + c, v as f32);
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn gaussian_iir_vertical<T, const CHANNELS: usize>(
+    src: &[f32],
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: usize,
+    width: usize,
+    height: usize,
+    start_strip: usize,
+    end_strip: usize,
+    edge_mode: EdgeMode,
+    warm_up: usize,
+    coefficients: &IirCoefficients,
+    filter: (IirLinesFilter, usize),
+) where
+    T: Copy + 'static,
+    f32: ToStorage<T>,
+{
+    let (filter, lanes) = filter;
+    let row_length = width * CHANNELS;
+    let len = height + warm_up * 2;
+    let mut buffer = vec![0f64; len * IIR_STRIP];
+    let mut left = [0f64; IIR_STRIP];
+    let mut right = [0f64; IIR_STRIP];
+    for strip in start_strip..end_strip {
+        let x0 = strip * IIR_STRIP;
+        let strip_width = IIR_STRIP.min(row_length - x0);
+        let group = strip_width.div_ceil(lanes) * lanes;
+        let buffer = &mut buffer[..len * group];
+        for (e, dst) in buffer.chunks_exact_mut(group).enumerate() {
+            let y = edge_index(edge_mode, e as i64 - warm_up as i64, height);
+            let src_row = &src[y * row_length + x0..y * row_length + x0 + strip_width];
+            for (dst, &src) in dst.iter_mut().zip(src_row.iter()) {
+                *dst = src as f64;
+            }
+        }
+        for i in 0..strip_width {
+            (left[i], right[i]) = match edge_mode {
+                EdgeMode::Constant(EdgeColor(color)) => {
+                    let c = color[(x0 + i) % CHANNELS] as f64;
+                    (c, c)
+                }
+                _ => (buffer[i], buffer[(len - 1) * group + i]),
+            };
+        }
+        filter(buffer, group, &left, &right, coefficients);
+        for (y, src) in buffer
+            .chunks_exact(group)
+            .skip(warm_up)
+            .take(height)
+            .enumerate()
+        {
+            let offset = y * dst_stride + x0;
+            for (i, &v) in src[..strip_width].iter().enumerate() {
+                unsafe {
+                    unsafe_dst.write(offset + i, (v as f32).to_());
+                }
+            }
+        }
+    }
+}
+
+fn gaussian_iir_impl<T, const CHANNELS: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Send + Sync + 'static + AsPrimitive<f64>,
+    f32: ToStorage<T>,
+{
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let coefficients = IirCoefficients::new(sigma as f64);
+    let filter = iir_lines_filter();
+    let warm_up = iir_warm_up(edge_mode, sigma);
+    let mut transient = vec![0f32; width * height * CHANNELS];
+    let dst_stride = dst.stride as usize;
+    let strips = (width * CHANNELS).div_ceil(IIR_STRIP);
+
+    let thread_count = threading_policy.get_threads_count(src.width, src.height);
+    let unsafe_transient = UnsafeSlice::new(&mut transient);
+    if thread_count == 1 {
+        gaussian_iir_horizontal::<T, CHANNELS>(
+            src,
+            &unsafe_transient,
+            0,
+            height,
+            edge_mode,
+            warm_up,
+            &coefficients,
+            filter,
+        );
+    } else {
+        let pool = threading_policy.get_pool(thread_count);
+        pool.scope(|scope| {
+            let segment_size = height / thread_count;
+            for i in 0..thread_count {
+                let start_y = i * segment_size;
+                let mut end_y = (i + 1) * segment_size;
+                if i == thread_count - 1 {
+                    end_y = height;
+                }
+                let unsafe_transient = &unsafe_transient;
+                let coefficients = &coefficients;
+                scope.spawn(move |_| {
+                    gaussian_iir_horizontal::<T, CHANNELS>(
+                        src,
+                        unsafe_transient,
+                        start_y,
+                        end_y,
+                        edge_mode,
+                        warm_up,
+                        coefficients,
+                        filter,
+                    );
+                });
+            }
+        });
+    }
+
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+    if thread_count == 1 {
+        gaussian_iir_vertical::<T, CHANNELS>(
+            &transient,
+            &unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            0,
+            strips,
+            edge_mode,
+            warm_up,
+            &coefficients,
+            filter,
+        );
+    } else {
+        let pool = threading_policy.get_pool(thread_count);
+        pool.scope(|scope| {
+            let segment_size = strips / thread_count;
+            for i in 0..thread_count {
+                let start_strip = i * segment_size;
+                let mut end_strip = (i + 1) * segment_size;
+                if i == thread_count - 1 {
+                    end_strip = strips;
+                }
+                let transient = &transient;
+                let unsafe_dst = &unsafe_dst;
+                let coefficients = &coefficients;
+                scope.spawn(move |_| {
+                    gaussian_iir_vertical::<T, CHANNELS>(
+                        transient,
+                        unsafe_dst,
+                        dst_stride,
+                        width,
+                        height,
+                        start_strip,
+                        end_strip,
+                        edge_mode,
+                        warm_up,
+                        coefficients,
+                        filter,
+                    );
+                });
+            }
+        });
+    }
+}
+
+fn gaussian_iir_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f64> + AlphaPremultiply,
+    f32: ToStorage<T>,
+{
+    if sigma.is_nan() || sigma <= 0. {
+        return Err(BlurError::NegativeOrZeroSigma);
+    }
+    if !(MIN_IIR_SIGMA..=MAX_IIR_SIGMA).contains(&sigma) {
+        return Err(BlurError::SigmaOutOfRange {
+            sigma,
+            min: MIN_IIR_SIGMA,
+            max: MAX_IIR_SIGMA,
+        });
+    }
+    if edge_mode == EdgeMode::KernelClip {
+        return Err(BlurError::EdgeModeNotSupported(edge_mode));
+    }
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        src.check_layout()?;
+        dst.check_layout()?;
+        src.size_matches(dst)?;
+        threading_policy.check()?;
+        let _dispatcher = match src.channels {
+            FastBlurChannels::Plane => gaussian_iir_impl::<T, 1>,
+            FastBlurChannels::Channels2 => gaussian_iir_impl::<T, 2>,
+            FastBlurChannels::Channels3 => gaussian_iir_impl::<T, 3>,
+            FastBlurChannels::Channels4 => gaussian_iir_impl::<T, 4>,
+        };
+        _dispatcher(src, dst, sigma, edge_mode, threading_policy);
+        Ok(())
+    })
+}
+
+/// Performs recursive gaussian blur on the image.
+///
+/// Recursive Young–van Vliet filter approximates gaussian with a few multiplications per pixel
+/// regardless of sigma, use it for very large sigmas where kernel based blur is too slow.
+/// Approximation is close for sigma above ~2, for small kernels [crate::gaussian_blur] is more precise.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `sigma` - Sigma of gaussian, must be in range 0.5..=2048
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// sigma is out of range or edge mode is not supported
+pub fn gaussian_blur_iir(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    gaussian_iir_dispatch(src, dst, sigma, edge_mode, alpha_mode, threading_policy)
+}
+
+/// Performs recursive gaussian blur on the 16-bit image.
+///
+/// Recursive Young–van Vliet filter approximates gaussian with a few multiplications per pixel
+/// regardless of sigma, use it for very large sigmas where kernel based blur is too slow.
+/// Approximation is close for sigma above ~2, for small kernels [crate::gaussian_blur] is more precise.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `sigma` - Sigma of gaussian, must be in range 0.5..=2048
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// sigma is out of range or edge mode is not supported
+pub fn gaussian_blur_iir_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    gaussian_iir_dispatch(src, dst, sigma, edge_mode, alpha_mode, threading_policy)
+}
+
+/// Performs recursive gaussian blur on the image in f32.
+///
+/// Recursive Young–van Vliet filter approximates gaussian with a few multiplications per pixel
+/// regardless of sigma, use it for very large sigmas where kernel based blur is too slow.
+/// Approximation is close for sigma above ~2, for small kernels [crate::gaussian_blur] is more precise.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `sigma` - Sigma of gaussian, must be in range 0.5..=2048
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// sigma is out of range or edge mode is not supported
+pub fn gaussian_blur_iir_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    gaussian_iir_dispatch(src, dst, sigma, edge_mode, alpha_mode, threading_policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gaussian_blur_f32;
+    use crate::test_utils::{assert_close, noise};
+
+    /// Compares recursive blur with kernel based gaussian wide enough to be treated as exact
+    fn check_against_kernel(width: u32, height: u32, sigma: f32, edge_mode: EdgeMode) {
+        let channels = FastBlurChannels::Channels3;
+        let src: Vec<f32> = noise((width * height * 3) as usize, 256, sigma as u32)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect();
+        let kernel_size = 2 * (sigma * 4.).ceil() as u32 + 1;
+        let mut expected = vec![0f32; src.len()];
+        gaussian_blur_f32(
+            &BlurImage::borrow(&src, width, height, channels),
+            &mut BlurImageMut::borrow(&mut expected, width, height, channels),
+            kernel_size,
+            sigma,
+            edge_mode,
+            AlphaMode::Independent,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)] {
+            let mut dst = vec![0f32; src.len()];
+            gaussian_blur_iir_f32(
+                &BlurImage::borrow(&src, width, height, channels),
+                &mut BlurImageMut::borrow(&mut dst, width, height, channels),
+                sigma,
+                edge_mode,
+                AlphaMode::Independent,
+                threading_policy,
+            )
+            .unwrap();
+            assert_close(
+                &dst,
+                &expected,
+                0.01,
+                &format!("sigma {sigma} {edge_mode:?} {threading_policy:?}"),
+            );
+        }
+    }
+
+    #[test]
+    fn iir_matches_kernel_gaussian_for_large_sigma() {
+        for sigma in [6., 15., 32.] {
+            for edge_mode in [
+                EdgeMode::Clamp,
+                EdgeMode::Wrap,
+                EdgeMode::Reflect,
+                EdgeMode::Reflect101,
+                EdgeMode::Constant(EdgeColor([0.25, 0.5, 0.75, 1.])),
+            ] {
+                check_against_kernel(97, 83, sigma, edge_mode);
+            }
+        }
+    }
+}
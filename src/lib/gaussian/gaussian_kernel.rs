@@ -46,5 +46,5 @@ pub(crate) fn get_gaussian_kernel_1d(width: u32, sigma: f32) -> Vec<f32> {
         }
     }
 
-    return kernel;
+    kernel
 }
@@ -25,10 +25,7 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "avx2"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::avx::gaussian_blur_vertical_pass_filter_f32_avx;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::gaussian::gauss_neon::*;
@@ -36,15 +33,9 @@ use crate::gaussian::gauss_neon::*;
 use crate::gaussian::gauss_neon::{
     gaussian_blur_horizontal_pass_filter_neon, gaussian_blur_vertical_pass_filter_neon,
 };
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::gauss_sse::*;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::gaussian::gauss_sse::{
     gaussian_blur_horizontal_pass_filter_sse, gaussian_blur_vertical_pass_filter_f32_sse,
     gaussian_blur_vertical_pass_filter_sse,
@@ -52,13 +43,14 @@ use crate::gaussian::gauss_sse::{
 use crate::gaussian::gaussian_filter::GaussianFilter;
 use crate::gaussian::gaussian_horizontal::gaussian_blur_horizontal_pass_impl_clip_edge;
 use crate::gaussian::gaussian_vertical::gaussian_blur_vertical_pass_clip_edge_impl;
+use crate::threading_policy::BlurThreadPool;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
 use num_traits::{AsPrimitive, FromPrimitive};
-use rayon::ThreadPool;
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
-    T: FromPrimitive + Default + Send + Sync,
+    T,
     const CHANNEL_CONFIGURATION: usize,
 >(
     src: &[T],
@@ -67,13 +59,22 @@ pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
     dst_stride: u32,
     width: u32,
     height: u32,
-    filter: &Vec<GaussianFilter>,
-    thread_pool: &ThreadPool,
+    filter: &[GaussianFilter],
+    thread_pool: &BlurThreadPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: ToStorage<T>,
 {
+    #[allow(clippy::type_complexity)]
     let mut _dispatcher: fn(
         src: &[T],
         src_stride: u32,
@@ -81,18 +82,17 @@ pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
         dst_stride: u32,
         width: u32,
         height: u32,
-        filter: &Vec<GaussianFilter>,
+        filter: &[GaussianFilter],
         start_y: u32,
         end_y: u32,
     ) = gaussian_blur_vertical_pass_clip_edge_impl::<T, CHANNEL_CONFIGURATION>;
     if std::any::type_name::<T>() == "u8" {
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channels
-            _dispatcher = gaussian_blur_vertical_pass_filter_sse::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("sse4.1") {
+                // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channels
+                _dispatcher = gaussian_blur_vertical_pass_filter_sse::<T, CHANNEL_CONFIGURATION>;
+            }
         }
         #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
         {
@@ -106,21 +106,21 @@ pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
             // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channels
             _dispatcher = gaussian_blur_vertical_pass_filter_f32_neon::<T, CHANNEL_CONFIGURATION>;
         }
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "sse4.1"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channels
-            _dispatcher = gaussian_blur_vertical_pass_filter_f32_sse::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("sse4.1") {
+                // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channels
+                _dispatcher =
+                    gaussian_blur_vertical_pass_filter_f32_sse::<T, CHANNEL_CONFIGURATION>;
+            }
         }
-        #[cfg(all(
-            any(target_arch = "x86_64", target_arch = "x86"),
-            target_feature = "avx2"
-        ))]
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
         {
-            // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channels
-            _dispatcher = gaussian_blur_vertical_pass_filter_f32_avx::<T, CHANNEL_CONFIGURATION>;
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                // Generally vertical pass do not depends on any specific channel configuration so it is allowed to make a vectorized calls for any channels
+                _dispatcher =
+                    gaussian_blur_vertical_pass_filter_f32_avx::<T, CHANNEL_CONFIGURATION>;
+            }
         }
     }
     let unsafe_dst = UnsafeSlice::new(dst);
@@ -151,8 +151,9 @@ pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
     });
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn gaussian_blur_horizontal_pass_edge_clip_dispatch<
-    T: FromPrimitive + Default + Send + Sync,
+    T,
     const CHANNEL_CONFIGURATION: usize,
 >(
     src: &[T],
@@ -161,36 +162,42 @@ pub(crate) fn gaussian_blur_horizontal_pass_edge_clip_dispatch<
     dst_stride: u32,
     width: u32,
     height: u32,
-    filter: &Vec<GaussianFilter>,
-    thread_pool: &ThreadPool,
+    filter: &[GaussianFilter],
+    thread_pool: &BlurThreadPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static + AsPrimitive<f32>,
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
+    #[allow(clippy::type_complexity)]
     let mut _dispatcher: fn(
         src: &[T],
         src_stride: u32,
         unsafe_dst: &UnsafeSlice<T>,
         dst_stride: u32,
         width: u32,
-        filter: &Vec<GaussianFilter>,
+        filter: &[GaussianFilter],
         start_y: u32,
         end_y: u32,
     ) = gaussian_blur_horizontal_pass_impl_clip_edge::<T, CHANNEL_CONFIGURATION>;
-    if CHANNEL_CONFIGURATION >= 3 {
-        if std::any::type_name::<T>() == "u8" {
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
-            {
+    if CHANNEL_CONFIGURATION >= 3 && std::any::type_name::<T>() == "u8" {
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        {
+            if is_x86_feature_detected!("sse4.1") {
                 _dispatcher = gaussian_blur_horizontal_pass_filter_sse::<T, CHANNEL_CONFIGURATION>;
             }
-            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
-            {
-                _dispatcher = gaussian_blur_horizontal_pass_filter_neon::<T, CHANNEL_CONFIGURATION>;
-            }
+        }
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        {
+            _dispatcher = gaussian_blur_horizontal_pass_filter_neon::<T, CHANNEL_CONFIGURATION>;
         }
     }
     if std::any::type_name::<T>() == "f32" {
@@ -199,12 +206,11 @@ pub(crate) fn gaussian_blur_horizontal_pass_edge_clip_dispatch<
             {
                 _dispatcher = gaussian_horiz_one_chan_filter_f32::<T>;
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                _dispatcher = gaussian_horiz_one_chan_filter_f32::<T>;
+                if is_x86_feature_detected!("sse4.1") {
+                    _dispatcher = gaussian_horiz_one_chan_filter_f32::<T>;
+                }
             }
         } else if CHANNEL_CONFIGURATION == 3 || CHANNEL_CONFIGURATION == 4 {
             #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
@@ -213,17 +219,14 @@ pub(crate) fn gaussian_blur_horizontal_pass_edge_clip_dispatch<
             }
         }
     }
-    if std::any::type_name::<T>() == "u8" {
-        if CHANNEL_CONFIGURATION == 1 {
-            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
-            {
-                _dispatcher = gaussian_horiz_one_chan_filter_u8::<T>;
-            }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
-            {
+    if std::any::type_name::<T>() == "u8" && CHANNEL_CONFIGURATION == 1 {
+        #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+        {
+            _dispatcher = gaussian_horiz_one_chan_filter_u8::<T>;
+        }
+        #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+        {
+            if is_x86_feature_detected!("sse4.1") {
                 _dispatcher = gaussian_sse_horiz_one_chan_filter_u8::<T>;
             }
         }
@@ -25,12 +25,11 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{gaussian_blur_f32, EdgeMode, FastBlurChannels, ThreadingPolicy};
-use colorutils_rs::linear_to_planar::linear_to_plane;
-use colorutils_rs::planar_to_linear::plane_to_linear;
-use colorutils_rs::{
-    linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
+use crate::linear_transform::{from_linear_transformer, to_linear_transformer};
+use crate::{
+    gaussian_blur_f32, AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, ThreadingPolicy,
 };
+use colorutils_rs::TransferFunction;
 use std::mem::size_of;
 
 /// Performs gaussian blur on the image in linear colorspace
@@ -42,52 +41,45 @@ use std::mem::size_of;
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count * size_of(PixelType) if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
-/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
-/// * `channels` - Count of channels in the image
-/// * `edge_mode` - Rule to handle edge mode
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_size` - Length of gaussian kernel. Must be odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. `0` selects the default of `kernel_size / 6`
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in `u8` range
 /// * `threading_policy` - Threading policy according to *ThreadingPolicy*
 /// * `transfer_function` - Transfer function in linear colorspace
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or sigma is negative or not finite
 pub fn gaussian_blur_in_linear(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     kernel_size: u32,
     sigma: f32,
-    channels: FastBlurChannels,
     edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
     transfer_function: TransferFunction,
-) {
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
     let mut linear_data: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
     let mut linear_data_1: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
 
-    let forward_transformer = match channels {
-        FastBlurChannels::Plane => plane_to_linear,
-        FastBlurChannels::Channels3 => rgb_to_linear,
-        FastBlurChannels::Channels4 => rgba_to_linear,
-    };
+    let forward_transformer = to_linear_transformer(channels, src.alpha_position);
 
-    let inverse_transformer = match channels {
-        FastBlurChannels::Plane => linear_to_plane,
-        FastBlurChannels::Channels3 => linear_to_rgb,
-        FastBlurChannels::Channels4 => linear_to_rgba,
-    };
+    let inverse_transformer = from_linear_transformer(channels, src.alpha_position);
 
     forward_transformer(
-        &src,
-        src_stride,
+        src.data,
+        src.stride,
         &mut linear_data,
         width * size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
@@ -95,23 +87,22 @@ pub fn gaussian_blur_in_linear(
         transfer_function,
     );
     gaussian_blur_f32(
-        &linear_data,
-        &mut linear_data_1,
-        width,
-        height,
+        &BlurImage::borrow(&linear_data, width, height, channels),
+        &mut BlurImageMut::borrow(&mut linear_data_1, width, height, channels),
         kernel_size,
         sigma,
-        channels,
-        edge_mode,
+        edge_mode.to_linear(channels, src.alpha_position, transfer_function),
+        AlphaMode::Independent,
         threading_policy,
-    );
+    )?;
     inverse_transformer(
         &linear_data_1,
         width * size_of::<f32>() as u32 * channels.get_channels() as u32,
-        dst,
-        dst_stride,
+        dst.data,
+        dst.stride,
         width,
         height,
         transfer_function,
     );
+    Ok(())
 }
@@ -31,6 +31,7 @@ use crate::unsafe_slice::UnsafeSlice;
 use crate::{clamp_edge, reflect_101, reflect_index, EdgeMode};
 use num_traits::{AsPrimitive, FromPrimitive};
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_c_impl<
     T: FromPrimitive + Default + Send + Sync + Copy + 'static + AsPrimitive<f32>,
     const CHANNEL_CONFIGURATION: usize,
@@ -136,6 +137,7 @@ pub fn gaussian_blur_vertical_pass_c_impl<
 }
 
 #[inline]
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_vertical_row<
     T: FromPrimitive + Default + Send + Sync + Copy + 'static + AsPrimitive<f32>,
     const ROW_SIZE: usize,
@@ -154,7 +156,7 @@ pub fn gaussian_vertical_row<
 ) where
     f32: AsPrimitive<T> + ToStorage<T>,
 {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let half_kernel = (kernel_size / 2) as i32;
     let mut weights: [f32; ROW_SIZE] = [0f32; ROW_SIZE];
     for r in -half_kernel..=half_kernel {
@@ -166,7 +168,7 @@ pub fn gaussian_vertical_row<
             unsafe {
                 let v = *src.get_unchecked(y_src_shift + px);
                 let w0 = weights.get_unchecked_mut(i);
-                *w0 = *w0 + v.as_() * weight;
+                *w0 += v.as_() * weight;
             }
         }
     }
@@ -179,6 +181,7 @@ pub fn gaussian_vertical_row<
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_blur_vertical_pass_clip_edge_impl<
     T: FromPrimitive + Default + Send + Sync + Copy + 'static + AsPrimitive<f32>,
     const CHANNEL_CONFIGURATION: usize,
@@ -189,7 +192,7 @@ pub fn gaussian_blur_vertical_pass_clip_edge_impl<
     dst_stride: u32,
     width: u32,
     height: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     start_y: u32,
     end_y: u32,
 ) where
@@ -237,6 +240,7 @@ pub fn gaussian_blur_vertical_pass_clip_edge_impl<
 }
 
 #[inline]
+#[allow(clippy::too_many_arguments)]
 pub fn gaussian_vertical_row_clip_edge<
     T: FromPrimitive + Default + Send + Sync + Copy + 'static + AsPrimitive<f32>,
     const ROW_SIZE: usize,
@@ -247,7 +251,7 @@ pub fn gaussian_vertical_row_clip_edge<
     dst_stride: u32,
     _: u32,
     _: u32,
-    filter: &Vec<GaussianFilter>,
+    filter: &[GaussianFilter],
     x: u32,
     y: u32,
 ) where
@@ -266,7 +270,7 @@ pub fn gaussian_vertical_row_clip_edge<
             unsafe {
                 let v = *src.get_unchecked(y_src_shift + px);
                 let w0 = weights.get_unchecked_mut(i);
-                *w0 = *w0 + v.as_() * weight;
+                *w0 += v.as_() * weight;
             }
         }
     }
@@ -25,25 +25,27 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "avx2"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 mod avx;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 mod gauss_neon;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 mod gauss_sse;
+#[allow(clippy::module_inception)]
 mod gaussian;
 mod gaussian_filter;
 mod gaussian_horizontal;
+mod gaussian_iir;
 mod gaussian_kernel;
 mod gaussian_kernel_filter_dispatch;
 mod gaussian_linear;
 mod gaussian_vertical;
+mod separable_filter;
 
 pub use gaussian::*;
+pub use gaussian_iir::{gaussian_blur_iir, gaussian_blur_iir_f32, gaussian_blur_iir_u16};
+pub(crate) use gaussian_kernel::get_gaussian_kernel_1d;
 pub use gaussian_linear::gaussian_blur_in_linear;
+pub use separable_filter::{
+    separable_filter, separable_filter_f16, separable_filter_f32, separable_filter_u16,
+};
@@ -0,0 +1,323 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use crate::alpha_mode::filter_alpha_mode_into;
+use crate::edge_mode::EdgeMode;
+use crate::gaussian::gaussian::{separable_filter_dispatch, SeparableWeights};
+use crate::to_storage::ToStorage;
+use crate::{AlphaMode, BlurError, BlurImage, BlurImageMut, ThreadingPolicy};
+use half::f16;
+use num_traits::{AsPrimitive, FromPrimitive};
+
+fn separable_filter_impl<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    kernel_x: &[f32],
+    kernel_y: &[f32],
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: FromPrimitive
+        + Default
+        + Send
+        + Sync
+        + std::ops::AddAssign
+        + std::ops::SubAssign
+        + Copy
+        + 'static
+        + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    if kernel_x.len().is_multiple_of(2) {
+        return Err(BlurError::KernelSizeNotOdd(kernel_x.len() as u32));
+    }
+    if kernel_y.len().is_multiple_of(2) {
+        return Err(BlurError::KernelSizeNotOdd(kernel_y.len() as u32));
+    }
+    separable_filter_dispatch(
+        src,
+        dst,
+        &SeparableWeights::Kernels {
+            horizontal: kernel_x,
+            vertical: kernel_y,
+        },
+        edge_mode,
+        threading_policy,
+    )
+}
+
+/// Performs separable convolution with arbitrary 1D kernels.
+///
+/// Image is filtered with `kernel_x` along rows and then with `kernel_y` along columns,
+/// the same engine as [crate::gaussian_blur] is used, so any smoothing, derivative or windowed kernel may be applied.
+/// Kernels are applied as correlation and are not normalized, intermediate result is stored in `u8`,
+/// so negative responses such as Sobel derivatives are clamped, use [separable_filter_f32] for them.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_x` - Horizontal kernel, length must be odd
+/// * `kernel_y` - Vertical kernel, length must be odd
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::KernelClip] rescales clipped weights to the kernel sum
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided
+/// or any kernel has even length
+pub fn separable_filter(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    kernel_x: &[f32],
+    kernel_y: &[f32],
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        separable_filter_impl::<u8>(src, dst, kernel_x, kernel_y, edge_mode, threading_policy)
+    })
+}
+
+/// Performs separable convolution with arbitrary 1D kernels.
+///
+/// Image is filtered with `kernel_x` along rows and then with `kernel_y` along columns.
+/// Kernels are applied as correlation and are not normalized, intermediate result is stored in `u16`,
+/// so negative responses are clamped, use [separable_filter_f32] for them.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_x` - Horizontal kernel, length must be odd
+/// * `kernel_y` - Vertical kernel, length must be odd
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::KernelClip] rescales clipped weights to the kernel sum
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided
+/// or any kernel has even length
+pub fn separable_filter_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    kernel_x: &[f32],
+    kernel_y: &[f32],
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        separable_filter_impl::<u16>(src, dst, kernel_x, kernel_y, edge_mode, threading_policy)
+    })
+}
+
+/// Performs separable convolution with arbitrary 1D kernels.
+///
+/// Image is filtered with `kernel_x` along rows and then with `kernel_y` along columns.
+/// Kernels are applied as correlation and are not normalized, signed kernels are fully supported.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_x` - Horizontal kernel, length must be odd
+/// * `kernel_y` - Vertical kernel, length must be odd
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::KernelClip] rescales clipped weights to the kernel sum
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided
+/// or any kernel has even length
+pub fn separable_filter_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    kernel_x: &[f32],
+    kernel_y: &[f32],
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        separable_filter_impl::<f32>(src, dst, kernel_x, kernel_y, edge_mode, threading_policy)
+    })
+}
+
+/// Performs separable convolution with arbitrary 1D kernels.
+///
+/// Image is filtered with `kernel_x` along rows and then with `kernel_y` along columns.
+/// Kernels are applied as correlation and are not normalized, signed kernels are supported.
+/// Kernels longer than 160 are convolved through FFT, except with [EdgeMode::KernelClip].
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `kernel_x` - Horizontal kernel, length must be odd
+/// * `kernel_y` - Vertical kernel, length must be odd
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::KernelClip] rescales clipped weights to the kernel sum
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided
+/// or any kernel has even length
+pub fn separable_filter_f16(
+    src: &BlurImage<f16>,
+    dst: &mut BlurImageMut<f16>,
+    kernel_x: &[f32],
+    kernel_y: &[f32],
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        separable_filter_impl::<f16>(src, dst, kernel_x, kernel_y, edge_mode, threading_policy)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+    use crate::{filter_2d_f32, FastBlurChannels, KernelAnchor, KernelShape};
+
+    const WIDTH: usize = 23;
+    const HEIGHT: usize = 17;
+
+    fn outer_product(kernel_x: &[f32], kernel_y: &[f32]) -> Vec<f32> {
+        kernel_y
+            .iter()
+            .flat_map(|&y| kernel_x.iter().map(move |&x| x * y))
+            .collect()
+    }
+
+    fn check_against_reference(kernel_x: &[f32], kernel_y: &[f32], tolerance: f32) {
+        let src: Vec<f32> = noise(WIDTH * HEIGHT * 3, 256, 7)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect();
+        let reference = ReferenceImage {
+            data: &src,
+            width: WIDTH,
+            height: HEIGHT,
+            channels: 3,
+        };
+        let kernel = outer_product(kernel_x, kernel_y);
+        for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.]) {
+            let expected = reference.filter_2d(
+                &kernel,
+                kernel_x.len(),
+                (kernel_x.len() / 2, kernel_y.len() / 2),
+                edge_mode,
+            );
+            for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)] {
+                let mut dst = vec![0f32; src.len()];
+                separable_filter_f32(
+                    &BlurImage::borrow(
+                        &src,
+                        WIDTH as u32,
+                        HEIGHT as u32,
+                        FastBlurChannels::Channels3,
+                    ),
+                    &mut BlurImageMut::borrow(
+                        &mut dst,
+                        WIDTH as u32,
+                        HEIGHT as u32,
+                        FastBlurChannels::Channels3,
+                    ),
+                    kernel_x,
+                    kernel_y,
+                    edge_mode,
+                    AlphaMode::Independent,
+                    threading_policy,
+                )
+                .unwrap();
+                assert_close(
+                    &dst,
+                    &expected,
+                    tolerance,
+                    &format!("{edge_mode:?} {threading_policy:?}"),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn separable_filter_matches_outer_product_kernel() {
+        check_against_reference(&[0.1, -0.3, 0.5, 0.2, 0.05], &[0.25, 0.6, -0.1], 1e-4);
+    }
+
+    #[test]
+    fn long_separable_kernel_matches_outer_product_kernel() {
+        let kernel_x: Vec<f32> = (0..171).map(|i| ((i % 7) as f32 - 3.) / 500.).collect();
+        check_against_reference(&kernel_x, &[0.25, 0.5, 0.25], 1e-3);
+    }
+
+    #[test]
+    fn separable_filter_and_filter_2d_agree_on_borders() {
+        let src: Vec<f32> = (0..25)
+            .map(|i| (i % 5) as f32 + 10. * (i / 5) as f32)
+            .collect();
+        let image = BlurImage::borrow(&src, 5, 5, FastBlurChannels::Plane);
+        for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.]) {
+            let mut separable = vec![0f32; 25];
+            separable_filter_f32(
+                &image,
+                &mut BlurImageMut::borrow(&mut separable, 5, 5, FastBlurChannels::Plane),
+                &[1., 0., 0.],
+                &[1., 0., 0.],
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            let mut filtered = vec![0f32; 25];
+            filter_2d_f32(
+                &image,
+                &mut BlurImageMut::borrow(&mut filtered, 5, 5, FastBlurChannels::Plane),
+                &outer_product(&[1., 0., 0.], &[1., 0., 0.]),
+                KernelShape::new(3, 3),
+                KernelAnchor::Center,
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            assert_eq!(separable, filtered, "{edge_mode:?}");
+        }
+    }
+}
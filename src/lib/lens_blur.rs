@@ -0,0 +1,590 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::{filter_alpha_mode_into, AlphaPremultiply};
+use crate::blur_error::check_radius;
+use crate::edge_padding::pad_image;
+use crate::linear_transform::{from_linear_transformer, to_linear_transformer};
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
+use colorutils_rs::TransferFunction;
+use num_traits::AsPrimitive;
+
+/// Shape of the lens aperture, bright spots are spread into this shape
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Aperture {
+    /// Round aperture, produces disc shaped bokeh
+    #[default]
+    Disk,
+    /// Aperture of `blades` straight blades, produces regular polygon shaped bokeh.
+    /// `rotation` of the polygon is in degrees
+    Polygon { blades: u32, rotation: f32 },
+}
+
+/// Linear light values above this level are lifted by highlight boost
+const HIGHLIGHT_THRESHOLD: f32 = 0.8;
+
+/// Horizontal span of the kernel on the row `dy`, inclusive
+#[derive(Debug, Copy, Clone)]
+struct KernelSpan {
+    dy: i64,
+    left: i64,
+    right: i64,
+}
+
+impl Aperture {
+    /// Splits the aperture of the radius into rows of pixels whose centers are inside it
+    fn spans(&self, radius: usize) -> Result<Vec<KernelSpan>, BlurError> {
+        let r = radius as i64;
+        let extent = radius as f64 + 0.5;
+        let mut spans = Vec::with_capacity(radius * 2 + 1);
+        match *self {
+            Aperture::Disk => {
+                for dy in -r..=r {
+                    let half = (extent * extent - (dy * dy) as f64).sqrt().floor() as i64;
+                    spans.push(KernelSpan {
+                        dy,
+                        left: -half,
+                        right: half,
+                    });
+                }
+            }
+            Aperture::Polygon { blades, rotation } => {
+                if blades < 3 {
+                    return Err(BlurError::InvalidAperture);
+                }
+                let vertices = (0..blades)
+                    .map(|i| {
+                        let angle = (rotation as f64).to_radians()
+                            + std::f64::consts::TAU * i as f64 / blades as f64;
+                        let (sin, cos) = angle.sin_cos();
+                        (extent * cos, extent * sin)
+                    })
+                    .collect::<Vec<_>>();
+                for dy in -r..=r {
+                    let y = dy as f64;
+                    let (mut left, mut right) = (f64::INFINITY, f64::NEG_INFINITY);
+                    for (i, &(x0, y0)) in vertices.iter().enumerate() {
+                        let (x1, y1) = vertices[(i + 1) % vertices.len()];
+                        if (y0 - y) * (y1 - y) > 0. || y0 == y1 {
+                            continue;
+                        }
+                        let x = x0 + (x1 - x0) * (y - y0) / (y1 - y0);
+                        left = left.min(x);
+                        right = right.max(x);
+                    }
+                    let (left, right) = (left.ceil() as i64, right.floor() as i64);
+                    if left <= right {
+                        spans.push(KernelSpan {
+                            dy,
+                            left: left.max(-r),
+                            right: right.min(r),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(spans)
+    }
+}
+
+fn lens_blur_impl<T, J, const CHANNELS: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius: usize,
+    spans: &[KernelSpan],
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<J>,
+    J: Copy + Default + std::ops::AddAssign + std::ops::SubAssign + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let padded = pad_image(src, radius, edge_mode);
+    let padded_stride = (width + radius * 2) * CHANNELS;
+    let weight = 1f32 / spans.iter().map(|s| s.right - s.left + 1).sum::<i64>() as f32;
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+
+    let blur_rows = |start_y: usize, end_y: usize| {
+        let mut rows = vec![0usize; spans.len()];
+        for y in start_y..end_y {
+            for (row, span) in rows.iter_mut().zip(spans.iter()) {
+                *row = (y as i64 + radius as i64 + span.dy) as usize * padded_stride;
+            }
+            let mut sums = [J::default(); CHANNELS];
+            for (&row, span) in rows.iter().zip(spans.iter()) {
+                for i in span.left..=span.right {
+                    let offset = row + (radius as i64 + i) as usize * CHANNELS;
+                    for (c, sum) in sums.iter_mut().enumerate() {
+                        *sum += unsafe { *padded.get_unchecked(offset + c) }.as_();
+                    }
+                }
+            }
+            let y_dst = y * dst_stride;
+            for x in 0..width {
+                if x > 0 {
+                    // Every row of the kernel slides by one pixel: the right edge enters, the left one leaves
+                    for (&row, span) in rows.iter().zip(spans.iter()) {
+                        let next =
+                            row + (x as i64 + radius as i64 + span.right) as usize * CHANNELS;
+                        let previous =
+                            row + (x as i64 - 1 + radius as i64 + span.left) as usize * CHANNELS;
+                        for (c, sum) in sums.iter_mut().enumerate() {
+                            *sum += unsafe { *padded.get_unchecked(next + c) }.as_();
+                            *sum -= unsafe { *padded.get_unchecked(previous + c) }.as_();
+                        }
+                    }
+                }
+                let offset = y_dst + x * CHANNELS;
+                for (c, sum) in sums.iter().enumerate() {
+                    unsafe {
+                        unsafe_dst.write(offset + c, (sum.as_() * weight).to_());
+                    }
+                }
+            }
+        }
+    };
+
+    let thread_count = threading_policy.get_threads_count(src.width, src.height);
+    if thread_count == 1 {
+        blur_rows(0, height);
+        return;
+    }
+    let pool = threading_policy.get_pool(thread_count);
+    let blur_rows = &blur_rows;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| blur_rows(start_y, end_y));
+        }
+    });
+}
+
+fn lens_blur_dispatch<T, J>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius: u32,
+    aperture: Aperture,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<J> + AlphaPremultiply,
+    J: Copy + Default + std::ops::AddAssign + std::ops::SubAssign + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    check_radius(radius, 1, 1024)?;
+    if edge_mode == EdgeMode::KernelClip {
+        return Err(BlurError::EdgeModeNotSupported(edge_mode));
+    }
+    let spans = aperture.spans(radius as usize)?;
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        src.check_layout()?;
+        dst.check_layout()?;
+        src.size_matches(dst)?;
+        threading_policy.check()?;
+        let _dispatcher = match src.channels {
+            FastBlurChannels::Plane => lens_blur_impl::<T, J, 1>,
+            FastBlurChannels::Channels2 => lens_blur_impl::<T, J, 2>,
+            FastBlurChannels::Channels3 => lens_blur_impl::<T, J, 3>,
+            FastBlurChannels::Channels4 => lens_blur_impl::<T, J, 4>,
+        };
+        _dispatcher(
+            src,
+            dst,
+            radius as usize,
+            &spans,
+            edge_mode,
+            threading_policy,
+        );
+        Ok(())
+    })
+}
+
+/// Performs lens blur on the image.
+///
+/// Bokeh with hard edged disc or polygon, unlike soft gaussian look every pixel is spread evenly
+/// over the aperture shape. Each kernel row slides along the image, so the cost grows with the radius only.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius of the aperture, must be in range 1..=1024
+/// * `aperture` - Shape of the aperture, see [Aperture]
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range, aperture has less than 3 blades or edge mode is not supported
+pub fn lens_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: u32,
+    aperture: Aperture,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    lens_blur_dispatch::<u8, u32>(
+        src,
+        dst,
+        radius,
+        aperture,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs lens blur on the 16-bit image.
+///
+/// Bokeh with hard edged disc or polygon, unlike soft gaussian look every pixel is spread evenly
+/// over the aperture shape. Each kernel row slides along the image, so the cost grows with the radius only.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius of the aperture, must be in range 1..=1024
+/// * `aperture` - Shape of the aperture, see [Aperture]
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range, aperture has less than 3 blades or edge mode is not supported
+pub fn lens_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    radius: u32,
+    aperture: Aperture,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    lens_blur_dispatch::<u16, u64>(
+        src,
+        dst,
+        radius,
+        aperture,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs lens blur on the image in f32.
+///
+/// Bokeh with hard edged disc or polygon, unlike soft gaussian look every pixel is spread evenly
+/// over the aperture shape. Each kernel row slides along the image, so the cost grows with the radius only.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius of the aperture, must be in range 1..=1024
+/// * `aperture` - Shape of the aperture, see [Aperture]
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range, aperture has less than 3 blades or edge mode is not supported
+pub fn lens_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    radius: u32,
+    aperture: Aperture,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    lens_blur_dispatch::<f32, f64>(
+        src,
+        dst,
+        radius,
+        aperture,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs lens blur on the image in linear colorspace.
+///
+/// Bokeh with hard edged disc or polygon, unlike soft gaussian look every pixel is spread evenly
+/// over the aperture shape. Each kernel row slides along the image, so the cost grows with the radius only.
+/// O(R) complexity.
+/// Highlights are boosted in linear light before blurring, so bright spots turn into distinct discs.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius of the aperture, must be in range 1..=1024
+/// * `aperture` - Shape of the aperture, see [Aperture]
+/// * `highlight_boost` - Gain of the brightest values, linear light above `0.8` is lifted up to `1 + highlight_boost` at white, `0` disables boost
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported, [EdgeMode::Constant] color is expected in `u8` range
+/// * `threading_policy` - Threads usage policy
+/// * `transfer_function` - Transfer function in linear colorspace
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius is out of range, aperture has less than 3 blades, highlight boost is negative or not finite
+/// or edge mode is not supported
+#[allow(clippy::too_many_arguments)]
+pub fn lens_blur_in_linear(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: u32,
+    aperture: Aperture,
+    highlight_boost: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    transfer_function: TransferFunction,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    if highlight_boost < 0. || !highlight_boost.is_finite() {
+        return Err(BlurError::InvalidStrength);
+    }
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
+    let mut linear_data: Vec<f32> =
+        vec![0f32; width as usize * height as usize * channels.get_channels()];
+    let mut linear_data_1: Vec<f32> =
+        vec![0f32; width as usize * height as usize * channels.get_channels()];
+
+    let forward_transformer = to_linear_transformer(channels, src.alpha_position);
+
+    let inverse_transformer = from_linear_transformer(channels, src.alpha_position);
+
+    forward_transformer(
+        src.data,
+        src.stride,
+        &mut linear_data,
+        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width,
+        height,
+        transfer_function,
+    );
+    if highlight_boost > 0. {
+        let alpha_index = channels.alpha_index(src.alpha_position);
+        let gain = highlight_boost / (1. - HIGHLIGHT_THRESHOLD);
+        for pixel in linear_data.chunks_exact_mut(channels.get_channels()) {
+            for (c, v) in pixel.iter_mut().enumerate() {
+                if Some(c) != alpha_index && *v > HIGHLIGHT_THRESHOLD {
+                    *v += (*v - HIGHLIGHT_THRESHOLD) * gain;
+                }
+            }
+        }
+    }
+    lens_blur_f32(
+        &BlurImage::borrow(&linear_data, width, height, channels),
+        &mut BlurImageMut::borrow(&mut linear_data_1, width, height, channels),
+        radius,
+        aperture,
+        edge_mode.to_linear(channels, src.alpha_position, transfer_function),
+        AlphaMode::Independent,
+        threading_policy,
+    )?;
+    inverse_transformer(
+        &linear_data_1,
+        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        dst.data,
+        dst.stride,
+        width,
+        height,
+        transfer_function,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+
+    /// Box kernel over pixels whose centers are inside the aperture, tested point by point
+    fn reference_kernel(aperture: Aperture, radius: usize) -> Vec<f32> {
+        let extent = radius as f64 + 0.5;
+        let r = radius as i64;
+        let inside = |x: f64, y: f64| match aperture {
+            Aperture::Disk => x * x + y * y <= extent * extent,
+            Aperture::Polygon { blades, rotation } => (0..blades).all(|i| {
+                let vertex = |i: u32| {
+                    let angle = (rotation as f64).to_radians()
+                        + std::f64::consts::TAU * i as f64 / blades as f64;
+                    (extent * angle.cos(), extent * angle.sin())
+                };
+                let ((x0, y0), (x1, y1)) = (vertex(i), vertex((i + 1) % blades));
+                (x1 - x0) * (y - y0) - (y1 - y0) * (x - x0) >= -1e-9
+            }),
+        };
+        let mask = (-r..=r)
+            .flat_map(|y| (-r..=r).map(move |x| (x, y)))
+            .map(|(x, y)| if inside(x as f64, y as f64) { 1f32 } else { 0. })
+            .collect::<Vec<f32>>();
+        let area: f32 = mask.iter().sum();
+        mask.iter().map(|v| v / area).collect()
+    }
+
+    #[test]
+    fn lens_blur_f32_matches_reference() {
+        let (width, height) = (18usize, 15usize);
+        let src = noise(width * height * 2, 256, 13)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 2,
+        };
+        for aperture in [
+            Aperture::Disk,
+            Aperture::Polygon {
+                blades: 5,
+                rotation: 10.,
+            },
+            Aperture::Polygon {
+                blades: 6,
+                rotation: 0.,
+            },
+        ] {
+            for radius in [1usize, 4] {
+                let kernel = reference_kernel(aperture, radius);
+                for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.]) {
+                    let mut dst = vec![0f32; src.len()];
+                    lens_blur_f32(
+                        &BlurImage::borrow(
+                            &src,
+                            width as u32,
+                            height as u32,
+                            FastBlurChannels::Channels2,
+                        ),
+                        &mut BlurImageMut::borrow(
+                            &mut dst,
+                            width as u32,
+                            height as u32,
+                            FastBlurChannels::Channels2,
+                        ),
+                        radius as u32,
+                        aperture,
+                        edge_mode,
+                        AlphaMode::Independent,
+                        ThreadingPolicy::Fixed(2),
+                    )
+                    .unwrap();
+                    let expected =
+                        image.filter_2d(&kernel, radius * 2 + 1, (radius, radius), edge_mode);
+                    assert_close(
+                        &dst,
+                        &expected,
+                        1e-4,
+                        &format!("{aperture:?} radius {radius} {edge_mode:?}"),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lens_blur_keeps_constant_image() {
+        let (width, height) = (16u32, 19u32);
+        let pixel = [90u8, 250, 8, 255];
+        let src = pixel.repeat(width as usize * height as usize);
+        for aperture in [
+            Aperture::Disk,
+            Aperture::Polygon {
+                blades: 6,
+                rotation: 15.,
+            },
+        ] {
+            for edge_mode in edge_modes(pixel.map(|v| v as f32)) {
+                let mut dst = vec![0u8; src.len()];
+                lens_blur(
+                    &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels4),
+                    &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels4),
+                    5,
+                    aperture,
+                    edge_mode,
+                    AlphaMode::Premultiply,
+                    ThreadingPolicy::Fixed(2),
+                )
+                .unwrap();
+                assert_eq!(dst, src, "{aperture:?} {edge_mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn lens_blur_in_linear_keeps_constant_image() {
+        let (width, height) = (12u32, 9u32);
+        let pixel = [30u8, 128, 230];
+        let src = pixel.repeat(width as usize * height as usize);
+        for edge_mode in edge_modes([30., 128., 230., 255.]) {
+            let mut dst = vec![0u8; src.len()];
+            lens_blur_in_linear(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels3),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels3),
+                4,
+                Aperture::Disk,
+                0.,
+                edge_mode,
+                ThreadingPolicy::Single,
+                TransferFunction::Srgb,
+            )
+            .unwrap();
+            assert_eq!(dst, src, "{edge_mode:?}");
+        }
+    }
+}
@@ -25,52 +25,111 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+mod alpha_mode;
+mod bilateral;
+mod blur_error;
+mod blur_image;
 mod r#box;
 mod channels_configuration;
 mod edge_mode;
+mod edge_padding;
 mod fast_gaussian;
 mod fast_gaussian_next;
 mod fast_gaussian_superior;
+mod filter2d;
 mod gaussian;
+mod lens_blur;
+mod linear_transform;
 mod median_blur;
+mod median_blur_f32;
+mod median_blur_u16;
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "x86",
+    all(target_arch = "aarch64", target_feature = "neon")
+))]
+mod median_network;
+mod motion_blur;
 mod mul_table;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 mod neon;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+mod radial_blur;
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 mod sse;
 mod stack_blur;
 mod stack_blur_f16;
 mod stack_blur_f32;
 mod stack_blur_linear;
+mod stack_blur_u16;
+#[cfg(test)]
+mod test_utils;
 mod threading_policy;
+mod tilt_shift;
 mod to_storage;
 mod unsafe_slice;
+mod unsharp_mask;
+mod variable_blur;
 
-pub use channels_configuration::FastBlurChannels;
+pub use alpha_mode::AlphaMode;
+pub use bilateral::bilateral_filter;
+pub use bilateral::bilateral_filter_f32;
+pub use bilateral::bilateral_filter_u16;
+pub use blur_error::{BlurError, MismatchedSize};
+pub use blur_image::{BlurImage, BlurImageMut};
+pub use channels_configuration::{AlphaPosition, FastBlurChannels};
 pub use colorutils_rs::TransferFunction;
 pub use edge_mode::*;
 pub use fast_gaussian::fast_gaussian;
 pub use fast_gaussian::fast_gaussian_f16;
+pub use fast_gaussian::fast_gaussian_f16_into;
 pub use fast_gaussian::fast_gaussian_f32;
+pub use fast_gaussian::fast_gaussian_f32_into;
 pub use fast_gaussian::fast_gaussian_in_linear;
-pub use fast_gaussian::fast_gaussian_plane;
-pub use fast_gaussian::fast_gaussian_plane_f32;
+pub use fast_gaussian::fast_gaussian_in_linear_into;
+pub use fast_gaussian::fast_gaussian_into;
 pub use fast_gaussian::fast_gaussian_u16;
+pub use fast_gaussian::fast_gaussian_u16_into;
 pub use fast_gaussian_next::fast_gaussian_next;
 pub use fast_gaussian_next::fast_gaussian_next_f16;
+pub use fast_gaussian_next::fast_gaussian_next_f16_into;
 pub use fast_gaussian_next::fast_gaussian_next_f32;
+pub use fast_gaussian_next::fast_gaussian_next_f32_into;
 pub use fast_gaussian_next::fast_gaussian_next_in_linear;
+pub use fast_gaussian_next::fast_gaussian_next_in_linear_into;
+pub use fast_gaussian_next::fast_gaussian_next_into;
 pub use fast_gaussian_next::fast_gaussian_next_u16;
+pub use fast_gaussian_next::fast_gaussian_next_u16_into;
 pub use fast_gaussian_superior::fast_gaussian_superior;
+pub use fast_gaussian_superior::fast_gaussian_superior_into;
+pub use filter2d::filter_2d;
+pub use filter2d::filter_2d_f16;
+pub use filter2d::filter_2d_f32;
+pub use filter2d::filter_2d_u16;
+pub use filter2d::KernelAnchor;
+pub use filter2d::KernelShape;
 pub use gaussian::gaussian_blur;
 pub use gaussian::gaussian_blur_f16;
 pub use gaussian::gaussian_blur_f32;
+pub use gaussian::gaussian_blur_iir;
+pub use gaussian::gaussian_blur_iir_f32;
+pub use gaussian::gaussian_blur_iir_u16;
 pub use gaussian::gaussian_blur_in_linear;
 pub use gaussian::gaussian_blur_u16;
+pub use gaussian::separable_filter;
+pub use gaussian::separable_filter_f16;
+pub use gaussian::separable_filter_f32;
+pub use gaussian::separable_filter_u16;
+pub use lens_blur::lens_blur;
+pub use lens_blur::lens_blur_f32;
+pub use lens_blur::lens_blur_in_linear;
+pub use lens_blur::lens_blur_u16;
+pub use lens_blur::Aperture;
 pub use median_blur::median_blur;
+pub use median_blur_f32::median_blur_f32;
+pub use median_blur_u16::median_blur_u16;
+pub use motion_blur::motion_blur;
+pub use motion_blur::motion_blur_f32;
+pub use motion_blur::motion_blur_u16;
 pub use r#box::box_blur;
 pub use r#box::box_blur_f32;
 pub use r#box::box_blur_in_linear;
@@ -83,8 +142,32 @@ pub use r#box::tent_blur;
 pub use r#box::tent_blur_f32;
 pub use r#box::tent_blur_in_linear;
 pub use r#box::tent_blur_u16;
+pub use radial_blur::spin_blur;
+pub use radial_blur::spin_blur_f32;
+pub use radial_blur::spin_blur_in_linear;
+pub use radial_blur::spin_blur_u16;
+pub use radial_blur::zoom_blur;
+pub use radial_blur::zoom_blur_f32;
+pub use radial_blur::zoom_blur_in_linear;
+pub use radial_blur::zoom_blur_u16;
 pub use stack_blur::stack_blur;
+pub use stack_blur::stack_blur_into;
 pub use stack_blur_f16::stack_blur_f16;
+pub use stack_blur_f16::stack_blur_f16_into;
 pub use stack_blur_f32::stack_blur_f32;
+pub use stack_blur_f32::stack_blur_f32_into;
 pub use stack_blur_linear::stack_blur_in_linear;
+pub use stack_blur_linear::stack_blur_in_linear_into;
+pub use stack_blur_u16::stack_blur_u16;
+pub use stack_blur_u16::stack_blur_u16_into;
 pub use threading_policy::ThreadingPolicy;
+pub use tilt_shift::tilt_shift;
+pub use tilt_shift::FocusBand;
+pub use unsharp_mask::unsharp_mask;
+pub use unsharp_mask::unsharp_mask_f32;
+pub use unsharp_mask::unsharp_mask_u16;
+pub use unsharp_mask::UnsharpMaskParams;
+pub use variable_blur::variable_blur;
+pub use variable_blur::variable_blur_f32;
+pub use variable_blur::variable_blur_u16;
+pub use variable_blur::RadiusMap;
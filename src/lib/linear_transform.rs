@@ -0,0 +1,131 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{AlphaPosition, FastBlurChannels};
+use colorutils_rs::linear_to_planar::linear_to_plane;
+use colorutils_rs::planar_to_linear::plane_to_linear;
+use colorutils_rs::{
+    linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
+};
+
+pub(crate) type ToLinearTransformer = fn(&[u8], u32, &mut [f32], u32, u32, u32, TransferFunction);
+
+pub(crate) type FromLinearTransformer = fn(&[f32], u32, &mut [u8], u32, u32, u32, TransferFunction);
+
+/// Transforms image into linear colorspace, alpha at `ALPHA_INDEX` is only normalized.
+/// Strides are measured in bytes as in *colorutils-rs*
+fn alpha_channels_to_linear<const CHANNELS: usize, const ALPHA_INDEX: usize>(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [f32],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    transfer_function: TransferFunction,
+) {
+    let linearize = transfer_function.get_linearize_function();
+    let row_length = width as usize * CHANNELS;
+    for (src_row, dst_row) in src
+        .chunks(src_stride as usize)
+        .zip(dst.chunks_mut(dst_stride as usize / std::mem::size_of::<f32>()))
+        .take(height as usize)
+    {
+        for (src, dst) in src_row[..row_length]
+            .chunks_exact(CHANNELS)
+            .zip(dst_row[..row_length].chunks_exact_mut(CHANNELS))
+        {
+            for (c, (&src, dst)) in src.iter().zip(dst.iter_mut()).enumerate() {
+                let normalized = src as f32 * (1f32 / 255f32);
+                *dst = if c == ALPHA_INDEX {
+                    normalized
+                } else {
+                    linearize(normalized)
+                };
+            }
+        }
+    }
+}
+
+/// Transforms image from linear colorspace, alpha at `ALPHA_INDEX` is only denormalized.
+/// Strides are measured in bytes as in *colorutils-rs*
+fn linear_to_alpha_channels<const CHANNELS: usize, const ALPHA_INDEX: usize>(
+    src: &[f32],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    transfer_function: TransferFunction,
+) {
+    let gamma = transfer_function.get_gamma_function();
+    let row_length = width as usize * CHANNELS;
+    for (src_row, dst_row) in src
+        .chunks(src_stride as usize / std::mem::size_of::<f32>())
+        .zip(dst.chunks_mut(dst_stride as usize))
+        .take(height as usize)
+    {
+        for (src, dst) in src_row[..row_length]
+            .chunks_exact(CHANNELS)
+            .zip(dst_row[..row_length].chunks_exact_mut(CHANNELS))
+        {
+            for (c, (&src, dst)) in src.iter().zip(dst.iter_mut()).enumerate() {
+                let value = if c == ALPHA_INDEX { src } else { gamma(src) };
+                *dst = (value * 255f32).round().clamp(0f32, 255f32) as u8;
+            }
+        }
+    }
+}
+
+/// Selects forward transformer into linear colorspace for the channels layout
+pub(crate) fn to_linear_transformer(
+    channels: FastBlurChannels,
+    alpha_position: AlphaPosition,
+) -> ToLinearTransformer {
+    match (channels, alpha_position) {
+        (FastBlurChannels::Plane, _) => plane_to_linear,
+        (FastBlurChannels::Channels2, AlphaPosition::Last) => alpha_channels_to_linear::<2, 1>,
+        (FastBlurChannels::Channels2, AlphaPosition::First) => alpha_channels_to_linear::<2, 0>,
+        (FastBlurChannels::Channels3, _) => rgb_to_linear,
+        (FastBlurChannels::Channels4, AlphaPosition::Last) => rgba_to_linear,
+        (FastBlurChannels::Channels4, AlphaPosition::First) => alpha_channels_to_linear::<4, 0>,
+    }
+}
+
+/// Selects inverse transformer from linear colorspace for the channels layout
+pub(crate) fn from_linear_transformer(
+    channels: FastBlurChannels,
+    alpha_position: AlphaPosition,
+) -> FromLinearTransformer {
+    match (channels, alpha_position) {
+        (FastBlurChannels::Plane, _) => linear_to_plane,
+        (FastBlurChannels::Channels2, AlphaPosition::Last) => linear_to_alpha_channels::<2, 1>,
+        (FastBlurChannels::Channels2, AlphaPosition::First) => linear_to_alpha_channels::<2, 0>,
+        (FastBlurChannels::Channels3, _) => linear_to_rgb,
+        (FastBlurChannels::Channels4, AlphaPosition::Last) => linear_to_rgba,
+        (FastBlurChannels::Channels4, AlphaPosition::First) => linear_to_alpha_channels::<4, 0>,
+    }
+}
@@ -25,259 +25,391 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::blur_error::check_radius;
 use crate::channels_configuration::FastBlurChannels;
+use crate::edge_padding::pad_image;
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use crate::neon::{median_blur_neon, median_blur_network_neon};
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+use crate::sse::{median_blur_network_sse, median_blur_sse};
 use crate::unsafe_slice::UnsafeSlice;
-use crate::ThreadingPolicy;
-
-struct MedianHistogram {
-    r: [i32; 256],
-    g: [i32; 256],
-    b: [i32; 256],
-    a: [i32; 256],
-    n: i32,
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, ThreadingPolicy};
+use num_traits::AsPrimitive;
+
+/// Source samples of median filters.
+///
+/// Output pixel `(x, y)` is centered at `(x + offset, y + offset)` of the source,
+/// windows are clipped to the source bounds.
+pub(crate) struct MedianSource<'a, T> {
+    pub(crate) data: &'a [T],
+    pub(crate) stride: usize,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) offset: usize,
 }
 
-fn add_rgb_pixels<const CHANNEL_CONFIGURATION: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: i64,
-    x: i64,
-    width: u32,
-    height: u32,
-    size: u32,
-    histogram: &mut MedianHistogram,
-) {
-    if x < 0 || x >= width as i64 {
-        return;
+impl<T> MedianSource<'_, T> {
+    /// Returns inclusive range of source rows in the window of output row `y`
+    #[inline]
+    pub(crate) fn window_rows(&self, y: usize, radius: usize) -> (usize, usize) {
+        let center = y + self.offset;
+        (
+            center.saturating_sub(radius),
+            std::cmp::min(center + radius, self.height - 1),
+        )
     }
-    let px = x as usize * CHANNEL_CONFIGURATION;
-    let cap = std::cmp::min(y + size as i64, height as i64 - 1);
-    let start = y - size as i64;
-    for i in start..=cap {
-        if i < 0 {
-            continue;
+
+    /// Returns inclusive range of source columns in the window of output column `x`
+    #[inline]
+    pub(crate) fn window_columns(&self, x: usize, radius: usize) -> (usize, usize) {
+        let center = x + self.offset;
+        (
+            center.saturating_sub(radius),
+            std::cmp::min(center + radius, self.width - 1),
+        )
+    }
+}
+
+/// Filters rows `start_y..end_y` of the destination
+pub(crate) type MedianWorker<T> =
+    fn(&MedianSource<T>, &UnsafeSlice<T>, usize, usize, usize, usize, usize, usize);
+
+/// Limits radius to the larger image side, wider windows only add more edge samples
+/// while the extended copy of the image grows quadratically
+#[inline]
+pub(crate) fn clamp_median_radius<T>(radius: u32, src: &BlurImage<T>) -> u32 {
+    radius.min(src.width.max(src.height))
+}
+
+/// Extends the source according to `edge_mode` and runs `worker` over destination rows split between threads.
+///
+/// [EdgeMode::KernelClip] works on the source as is, so windows near the borders have fewer samples.
+/// Radius is limited by [clamp_median_radius].
+pub(crate) fn median_filter_rows<T: Copy + Default + Send + Sync + 'static>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    worker: MedianWorker<T>,
+) -> Result<(), BlurError>
+where
+    f32: AsPrimitive<T>,
+{
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let radius = clamp_median_radius(radius, src) as usize;
+    let padded;
+    let source = if edge_mode == EdgeMode::KernelClip {
+        MedianSource {
+            data: src.data,
+            stride: src.stride as usize,
+            width,
+            height,
+            offset: 0,
         }
-        let y_shift = i as usize * src_stride as usize;
-        let bytes_offset = y_shift + px;
-        let v0 = unsafe { *src.get_unchecked(bytes_offset) };
-        unsafe {
-            let k = histogram.r.get_unchecked_mut(usize::from(v0));
-            let x = *k + 1;
-            *k = x;
+    } else {
+        padded = pad_image(src, radius, edge_mode);
+        MedianSource {
+            data: &padded,
+            stride: (width + radius * 2) * src.channels.get_channels(),
+            width: width + radius * 2,
+            height: height + radius * 2,
+            offset: radius,
         }
-        if CHANNEL_CONFIGURATION > 1 {
-            let v1 = unsafe { *src.get_unchecked(bytes_offset + 1) };
-            unsafe {
-                let k = histogram.g.get_unchecked_mut(usize::from(v1));
-                let x = *k + 1;
-                *k = x;
+    };
+    let source = &source;
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+    let thread_count = threading_policy.get_threads_count(width as u32, height as u32);
+    if thread_count == 1 {
+        worker(
+            source,
+            &unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            radius,
+            0,
+            height,
+        );
+        return Ok(());
+    }
+    let pool = threading_policy.get_pool(thread_count);
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
             }
+            let unsafe_dst = &unsafe_dst;
+            scope.spawn(move |_| {
+                worker(
+                    source, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+                );
+            });
         }
-        if CHANNEL_CONFIGURATION > 2 {
-            let v2 = unsafe { *src.get_unchecked(bytes_offset + 2) };
-            unsafe {
-                let k = histogram.b.get_unchecked_mut(usize::from(v2));
-                let x = *k + 1;
-                *k = x;
-            }
+    });
+    Ok(())
+}
+
+/// Accumulates 16 bins segments of column histograms into the kernel histogram
+pub(crate) trait MedianHistogramSegment {
+    unsafe fn add(dst: &mut [u32; 16], src: &[u16; 16]);
+    unsafe fn sub(dst: &mut [u32; 16], src: &[u16; 16]);
+}
+
+pub(crate) struct MedianSegmentScalar;
+
+impl MedianHistogramSegment for MedianSegmentScalar {
+    #[inline(always)]
+    unsafe fn add(dst: &mut [u32; 16], src: &[u16; 16]) {
+        for (dst, &src) in dst.iter_mut().zip(src.iter()) {
+            *dst += src as u32;
         }
-        if CHANNEL_CONFIGURATION == 4 {
-            unsafe {
-                let v3 = *src.get_unchecked(bytes_offset + 3);
-                let k = histogram.a.get_unchecked_mut(usize::from(v3));
-                let x = *k + 1;
-                *k = x;
-            }
+    }
+
+    #[inline(always)]
+    unsafe fn sub(dst: &mut [u32; 16], src: &[u16; 16]) {
+        for (dst, &src) in dst.iter_mut().zip(src.iter()) {
+            *dst -= src as u32;
         }
-        histogram.n += 1;
     }
 }
 
-fn remove_rgb_pixels<const CHANNELS_CONFIGURATION: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: i64,
-    x: i64,
-    width: u32,
-    height: u32,
-    size: u32,
-    histogram: &mut MedianHistogram,
-) {
-    if x < 0 || x >= width as i64 {
-        return;
+/// Column histograms of Perreault–Hébert median, each column keeps 256 fine bins
+/// and 16 coarse bins per channel over the rows of the current window
+struct ColumnHistograms<const CHANNELS_CONFIGURATION: usize> {
+    coarse: Vec<u16>,
+    fine: Vec<u16>,
+}
+
+impl<const CHANNELS_CONFIGURATION: usize> ColumnHistograms<CHANNELS_CONFIGURATION> {
+    fn new(width: usize) -> Self {
+        ColumnHistograms {
+            coarse: vec![0; width * CHANNELS_CONFIGURATION * 16],
+            fine: vec![0; width * CHANNELS_CONFIGURATION * 256],
+        }
     }
-    let px = x as usize * CHANNELS_CONFIGURATION;
-    let cap = std::cmp::min(y + size as i64, height as i64 - 1);
-    let start = y - size as i64;
-    for i in start..=cap {
-        if i < 0 {
-            continue;
+
+    #[inline(always)]
+    fn add_row(&mut self, row: &[u8]) {
+        for (i, &v) in row.iter().enumerate() {
+            self.coarse[i * 16 + (v >> 4) as usize] += 1;
+            self.fine[i * 256 + v as usize] += 1;
         }
-        let y_shift = i as usize * src_stride as usize;
-        let bytes_offset = y_shift + px;
-        let v0 = unsafe { *src.get_unchecked(bytes_offset) };
-        unsafe {
-            let k = histogram.r.get_unchecked_mut(usize::from(v0));
-            let x = *k - 1;
-            *k = x;
+    }
+
+    #[inline(always)]
+    fn remove_row(&mut self, row: &[u8]) {
+        for (i, &v) in row.iter().enumerate() {
+            self.coarse[i * 16 + (v >> 4) as usize] -= 1;
+            self.fine[i * 256 + v as usize] -= 1;
         }
-        if CHANNELS_CONFIGURATION > 1 {
-            let v1 = unsafe { *src.get_unchecked(bytes_offset + 1) };
-            unsafe {
-                let k = histogram.g.get_unchecked_mut(usize::from(v1));
-                let x = *k - 1;
-                *k = x;
+    }
+
+    #[inline(always)]
+    fn coarse_segment(&self, x: usize, channel: usize) -> &[u16; 16] {
+        let i = (x * CHANNELS_CONFIGURATION + channel) * 16;
+        self.coarse[i..i + 16].try_into().unwrap()
+    }
+
+    #[inline(always)]
+    fn fine_segment(&self, x: usize, channel: usize, bucket: usize) -> &[u16; 16] {
+        let i = (x * CHANNELS_CONFIGURATION + channel) * 256 + bucket * 16;
+        self.fine[i..i + 16].try_into().unwrap()
+    }
+}
+
+/// Histogram of the whole kernel window, built from column histograms.
+///
+/// Coarse bins follow the window eagerly, while each 16 bins fine segment is brought
+/// up to date with the window only when median falls into it.
+struct KernelHistogram<const CHANNELS_CONFIGURATION: usize> {
+    coarse: [[u32; 16]; CHANNELS_CONFIGURATION],
+    fine: [[u32; 256]; CHANNELS_CONFIGURATION],
+    /// Inclusive columns range each fine segment is accumulated over
+    synced: [[Option<(usize, usize)>; 16]; CHANNELS_CONFIGURATION],
+    left: usize,
+    right: usize,
+    rows: u32,
+}
+
+impl<const CHANNELS_CONFIGURATION: usize> KernelHistogram<CHANNELS_CONFIGURATION> {
+    fn new() -> Self {
+        KernelHistogram {
+            coarse: [[0; 16]; CHANNELS_CONFIGURATION],
+            fine: [[0; 256]; CHANNELS_CONFIGURATION],
+            synced: [[None; 16]; CHANNELS_CONFIGURATION],
+            left: 0,
+            right: 0,
+            rows: 0,
+        }
+    }
+
+    /// Starts a new row with the window over columns `left..=right` of `rows` height
+    #[inline(always)]
+    unsafe fn reset<S: MedianHistogramSegment>(
+        &mut self,
+        columns: &ColumnHistograms<CHANNELS_CONFIGURATION>,
+        left: usize,
+        right: usize,
+        rows: u32,
+    ) {
+        self.coarse = [[0; 16]; CHANNELS_CONFIGURATION];
+        self.synced = [[None; 16]; CHANNELS_CONFIGURATION];
+        for x in left..=right {
+            for (c, coarse) in self.coarse.iter_mut().enumerate() {
+                S::add(coarse, columns.coarse_segment(x, c));
             }
         }
-        if CHANNELS_CONFIGURATION > 2 {
-            let v2 = unsafe { *src.get_unchecked(bytes_offset + 2) };
-            unsafe {
-                let k = histogram.b.get_unchecked_mut(usize::from(v2));
-                let x = *k - 1;
-                *k = x;
+        self.left = left;
+        self.right = right;
+        self.rows = rows;
+    }
+
+    /// Moves the window to the right over columns `left..=right`
+    #[inline(always)]
+    unsafe fn move_to<S: MedianHistogramSegment>(
+        &mut self,
+        columns: &ColumnHistograms<CHANNELS_CONFIGURATION>,
+        left: usize,
+        right: usize,
+    ) {
+        for x in self.left..left {
+            for (c, coarse) in self.coarse.iter_mut().enumerate() {
+                S::sub(coarse, columns.coarse_segment(x, c));
             }
         }
-        if CHANNELS_CONFIGURATION == 4 {
-            let v3 = unsafe { *src.get_unchecked(bytes_offset + 3) };
-            let k = unsafe { histogram.a.get_unchecked_mut(usize::from(v3)) };
-            let x = *k - 1;
-            *k = x;
+        for x in self.right + 1..=right {
+            for (c, coarse) in self.coarse.iter_mut().enumerate() {
+                S::add(coarse, columns.coarse_segment(x, c));
+            }
         }
-        histogram.n -= 1;
+        self.left = left;
+        self.right = right;
     }
-}
 
-fn init_histogram<const CHANNELS_CONFIGURATION: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: u32,
-    width: u32,
-    height: u32,
-    radius: u32,
-    histogram: &mut MedianHistogram,
-) {
-    histogram.r = [0; 256];
-    histogram.g = [0; 256];
-    histogram.b = [0; 256];
-    histogram.a = [0; 256];
-    histogram.n = 0;
-
-    for j in 0..std::cmp::min(radius, width) {
-        add_rgb_pixels::<CHANNELS_CONFIGURATION>(
-            src, src_stride, y as i64, j as i64, width, height, radius, histogram,
-        );
+    #[inline(always)]
+    unsafe fn sync_segment<S: MedianHistogramSegment>(
+        &mut self,
+        columns: &ColumnHistograms<CHANNELS_CONFIGURATION>,
+        channel: usize,
+        bucket: usize,
+    ) {
+        let segment: &mut [u32; 16] = (&mut self.fine[channel][bucket * 16..(bucket + 1) * 16])
+            .try_into()
+            .unwrap();
+        let add_from = match self.synced[channel][bucket] {
+            Some((left, right)) if right >= self.left => {
+                for x in left..self.left {
+                    S::sub(segment, columns.fine_segment(x, channel, bucket));
+                }
+                right + 1
+            }
+            _ => {
+                segment.fill(0);
+                self.left
+            }
+        };
+        for x in add_from..=self.right {
+            S::add(segment, columns.fine_segment(x, channel, bucket));
+        }
+        self.synced[channel][bucket] = Some((self.left, self.right));
     }
-}
 
-fn median_filter(x: [i32; 256], n: i32) -> i32 {
-    let mut n = n / 2;
-    let mut i = 0i64;
-    while i < 256 && i >= 0 {
-        unsafe {
-            n -= *x.get_unchecked(i as usize);
+    /// Returns value of rank `n / 2` in the channel
+    #[inline(always)]
+    unsafe fn median<S: MedianHistogramSegment>(
+        &mut self,
+        columns: &ColumnHistograms<CHANNELS_CONFIGURATION>,
+        channel: usize,
+    ) -> u8 {
+        let n = self.rows * (self.right - self.left + 1) as u32;
+        let target = n / 2;
+        let mut acc = 0u32;
+        let mut bucket = 15usize;
+        for (i, &count) in self.coarse[channel].iter().enumerate() {
+            if acc + count > target {
+                bucket = i;
+                break;
+            }
+            acc += count;
         }
-        if n > 0 {
-            i += 1;
-        } else {
-            break;
+        self.sync_segment::<S>(columns, channel, bucket);
+        for (i, &count) in self.fine[channel][bucket * 16..(bucket + 1) * 16]
+            .iter()
+            .enumerate()
+        {
+            acc += count;
+            if acc > target {
+                return (bucket * 16 + i) as u8;
+            }
         }
+        (bucket * 16 + 15) as u8
     }
-    i as i32
 }
 
-fn median_blur_impl<const CHANNELS_CONFIGURATION: usize>(
-    src: &[u8],
-    src_stride: u32,
+/// Perreault–Hébert median of 8-bit rows `start_y..end_y`, `S` provides histogram arithmetic
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn median_blur_impl<const CHANNELS_CONFIGURATION: usize, S: MedianHistogramSegment>(
+    src: &MedianSource<u8>,
     unsafe_dst: &UnsafeSlice<u8>,
-    dst_stride: u32,
-    width: u32,
-    height: u32,
-    radius: u32,
-    start_y: u32,
-    end_y: u32,
+    dst_stride: usize,
+    width: usize,
+    _: usize,
+    radius: usize,
+    start_y: usize,
+    end_y: usize,
 ) {
+    if start_y >= end_y {
+        return;
+    }
+    let row_length = src.width * CHANNELS_CONFIGURATION;
+    let source_row = |y: usize| &src.data[y * src.stride..y * src.stride + row_length];
+    let mut columns = ColumnHistograms::<CHANNELS_CONFIGURATION>::new(src.width);
+    let mut kernel = KernelHistogram::<CHANNELS_CONFIGURATION>::new();
+
+    let (mut top, mut bottom) = src.window_rows(start_y, radius);
+    for y in top..=bottom {
+        columns.add_row(source_row(y));
+    }
+
     for y in start_y..end_y {
-        let y_src_offset = y as usize * src_stride as usize;
-        let y_dst_offset = y as usize * dst_stride as usize;
-        let mut histogram = MedianHistogram {
-            r: [0; 256],
-            g: [0; 256],
-            b: [0; 256],
-            a: [0; 256],
-            n: 0,
-        };
-        for x in 0..width {
-            let px = x as usize * CHANNELS_CONFIGURATION;
-            if x == 0 {
-                init_histogram::<CHANNELS_CONFIGURATION>(
-                    src,
-                    src_stride,
-                    y,
-                    width,
-                    height,
-                    radius,
-                    &mut histogram,
-                );
-            } else {
-                remove_rgb_pixels::<CHANNELS_CONFIGURATION>(
-                    src,
-                    src_stride,
-                    y as i64,
-                    x as i64 - radius as i64,
-                    width,
-                    height,
-                    radius,
-                    &mut histogram,
-                );
-                add_rgb_pixels::<CHANNELS_CONFIGURATION>(
-                    src,
-                    src_stride,
-                    y as i64,
-                    x as i64 + radius as i64,
-                    width,
-                    height,
-                    radius,
-                    &mut histogram,
-                );
+        if y > start_y {
+            let (new_top, new_bottom) = src.window_rows(y, radius);
+            for y in top..new_top {
+                columns.remove_row(source_row(y));
+            }
+            for y in bottom + 1..=new_bottom {
+                columns.add_row(source_row(y));
             }
+            (top, bottom) = (new_top, new_bottom);
+        }
+        let rows = (bottom - top + 1) as u32;
+
+        let (left, right) = src.window_columns(0, radius);
+        unsafe {
+            kernel.reset::<S>(&columns, left, right, rows);
+        }
 
-            if histogram.n > 0 {
-                unsafe {
-                    let bytes_offset = y_dst_offset + px;
-                    unsafe_dst.write(bytes_offset, median_filter(histogram.r, histogram.n) as u8);
-                    if CHANNELS_CONFIGURATION > 1 {
-                        unsafe_dst.write(
-                            bytes_offset + 1,
-                            median_filter(histogram.g, histogram.n) as u8,
-                        );
-                    }
-                    if CHANNELS_CONFIGURATION > 2 {
-                        unsafe_dst.write(
-                            bytes_offset + 2,
-                            median_filter(histogram.b, histogram.n) as u8,
-                        );
-                    }
-                    if CHANNELS_CONFIGURATION == 4 {
-                        unsafe_dst.write(
-                            bytes_offset + 3,
-                            median_filter(histogram.a, histogram.n) as u8,
-                        );
-                    }
+        let y_dst_offset = y * dst_stride;
+        for x in 0..width {
+            let px = y_dst_offset + x * CHANNELS_CONFIGURATION;
+            unsafe {
+                if x > 0 {
+                    let (left, right) = src.window_columns(x, radius);
+                    kernel.move_to::<S>(&columns, left, right);
                 }
-            } else {
-                unsafe {
-                    let bytes_offset = y_dst_offset + px;
-                    let src_offset = y_src_offset + px;
-                    unsafe_dst.write(bytes_offset, *src.get_unchecked(src_offset));
-                    if CHANNELS_CONFIGURATION > 1 {
-                        unsafe_dst.write(bytes_offset + 1, *src.get_unchecked(src_offset + 1));
-                    }
-                    if CHANNELS_CONFIGURATION > 2 {
-                        unsafe_dst.write(bytes_offset + 2, *src.get_unchecked(src_offset + 2));
-                    }
-                    if CHANNELS_CONFIGURATION == 4 {
-                        unsafe_dst.write(bytes_offset + 3, *src.get_unchecked(src_offset + 3));
-                    }
+                for c in 0..CHANNELS_CONFIGURATION {
+                    unsafe_dst.write(px + c, kernel.median::<S>(&columns, c));
                 }
             }
         }
@@ -288,84 +420,172 @@ fn median_blur_impl<const CHANNELS_CONFIGURATION: usize>(
 ///
 /// This performs a median kernel filter on the image producing edge preserving blur result.
 /// Preferred if you need to save edges.
-/// O(R) complexity.
+/// Uses Perreault–Hébert column histograms, so the cost per pixel doesn't depend on the radius.
+/// O(1) complexity.
 ///
 /// # Arguments
 ///
-/// * `stride` - Lane length, default is width * channels_count if not aligned
-/// * `width` - Width of the image
-/// * `height` - Height of the image
-/// * `radius` - Radius of kernel
-/// * `channels` - Count of channels in the image
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius of kernel, limited into 0..=32767, radius larger than the larger image side is treated as that side
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] uses only samples inside the image
+/// * `threading_policy` - Threads usage policy
 ///
-/// # Panics
-/// Panic is stride/width/height/channel configuration do not match provided
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided or radius is out of range
 pub fn median_blur(
-    src: &[u8],
-    src_stride: u32,
-    dst: &mut [u8],
-    dst_stride: u32,
-    width: u32,
-    height: u32,
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
-) {
-    let unsafe_dst = UnsafeSlice::new(dst);
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    pool.scope(|scope| {
-        let segment_size = height / thread_count;
-        for i in 0..thread_count {
-            let start_y = i * segment_size;
-            let mut end_y = (i + 1) * segment_size;
-            if i == thread_count - 1 {
-                end_y = height;
-            }
-            scope.spawn(move |_| match channels {
-                FastBlurChannels::Plane => {
-                    median_blur_impl::<1>(
-                        src,
-                        src_stride,
-                        &unsafe_dst,
-                        dst_stride,
-                        width,
-                        height,
-                        radius,
-                        start_y,
-                        end_y,
-                    );
-                }
-                FastBlurChannels::Channels3 => {
-                    median_blur_impl::<3>(
-                        src,
-                        src_stride,
-                        &unsafe_dst,
-                        dst_stride,
-                        width,
-                        height,
-                        radius,
-                        start_y,
-                        end_y,
-                    );
-                }
-                FastBlurChannels::Channels4 => {
-                    median_blur_impl::<4>(
-                        src,
-                        src_stride,
-                        &unsafe_dst,
-                        dst_stride,
-                        width,
-                        height,
-                        radius,
-                        start_y,
-                        end_y,
-                    );
-                }
-            });
+) -> Result<(), BlurError> {
+    check_radius(radius, 0, 32767)?;
+    let radius = clamp_median_radius(radius, src);
+    let mut _dispatcher: MedianWorker<u8> = match src.channels {
+        FastBlurChannels::Plane => median_blur_impl::<1, MedianSegmentScalar>,
+        FastBlurChannels::Channels2 => median_blur_impl::<2, MedianSegmentScalar>,
+        FastBlurChannels::Channels3 => median_blur_impl::<3, MedianSegmentScalar>,
+        FastBlurChannels::Channels4 => median_blur_impl::<4, MedianSegmentScalar>,
+    };
+    // Exchange networks need a full window, so they don't apply to clipped kernels
+    let use_network = (radius == 1 || radius == 2) && edge_mode != EdgeMode::KernelClip;
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    {
+        if is_x86_feature_detected!("sse4.1") {
+            _dispatcher = match (src.channels, use_network, radius) {
+                (FastBlurChannels::Plane, true, 1) => median_blur_network_sse::<1, 1>,
+                (FastBlurChannels::Plane, true, _) => median_blur_network_sse::<1, 2>,
+                (FastBlurChannels::Plane, false, _) => median_blur_sse::<1>,
+                (FastBlurChannels::Channels2, true, 1) => median_blur_network_sse::<2, 1>,
+                (FastBlurChannels::Channels2, true, _) => median_blur_network_sse::<2, 2>,
+                (FastBlurChannels::Channels2, false, _) => median_blur_sse::<2>,
+                (FastBlurChannels::Channels3, true, 1) => median_blur_network_sse::<3, 1>,
+                (FastBlurChannels::Channels3, true, _) => median_blur_network_sse::<3, 2>,
+                (FastBlurChannels::Channels3, false, _) => median_blur_sse::<3>,
+                (FastBlurChannels::Channels4, true, 1) => median_blur_network_sse::<4, 1>,
+                (FastBlurChannels::Channels4, true, _) => median_blur_network_sse::<4, 2>,
+                (FastBlurChannels::Channels4, false, _) => median_blur_sse::<4>,
+            };
         }
-    });
+    }
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    {
+        _dispatcher = match (src.channels, use_network, radius) {
+            (FastBlurChannels::Plane, true, 1) => median_blur_network_neon::<1, 1>,
+            (FastBlurChannels::Plane, true, _) => median_blur_network_neon::<1, 2>,
+            (FastBlurChannels::Plane, false, _) => median_blur_neon::<1>,
+            (FastBlurChannels::Channels2, true, 1) => median_blur_network_neon::<2, 1>,
+            (FastBlurChannels::Channels2, true, _) => median_blur_network_neon::<2, 2>,
+            (FastBlurChannels::Channels2, false, _) => median_blur_neon::<2>,
+            (FastBlurChannels::Channels3, true, 1) => median_blur_network_neon::<3, 1>,
+            (FastBlurChannels::Channels3, true, _) => median_blur_network_neon::<3, 2>,
+            (FastBlurChannels::Channels3, false, _) => median_blur_neon::<3>,
+            (FastBlurChannels::Channels4, true, 1) => median_blur_network_neon::<4, 1>,
+            (FastBlurChannels::Channels4, true, _) => median_blur_network_neon::<4, 2>,
+            (FastBlurChannels::Channels4, false, _) => median_blur_neon::<4>,
+        };
+    }
+    median_filter_rows(src, dst, radius, edge_mode, threading_policy, _dispatcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{edge_modes, noise, ReferenceImage};
+
+    fn check_median(
+        width: usize,
+        height: usize,
+        channels: FastBlurChannels,
+        radius: u32,
+        threading_policy: ThreadingPolicy,
+    ) {
+        let channels_count = channels.get_channels();
+        let src: Vec<u8> = noise(width * height * channels_count, 256, radius + 1)
+            .iter()
+            .map(|&v| v as u8)
+            .collect();
+        let src_f32: Vec<f32> = src.iter().map(|&v| v as f32).collect();
+        let reference = ReferenceImage {
+            data: &src_f32,
+            width,
+            height,
+            channels: channels_count,
+        };
+        let image = BlurImage::borrow(&src, width as u32, height as u32, channels);
+        let reference_radius = clamp_median_radius(radius, &image) as usize;
+        for edge_mode in edge_modes([10., 128., 250., 255.])
+            .into_iter()
+            .chain([EdgeMode::KernelClip])
+        {
+            let mut dst = vec![0u8; src.len()];
+            median_blur(
+                &image,
+                &mut BlurImageMut::borrow(&mut dst, width as u32, height as u32, channels),
+                radius,
+                edge_mode,
+                threading_policy,
+            )
+            .unwrap();
+            let expected: Vec<u8> = reference
+                .median(reference_radius, edge_mode)
+                .iter()
+                .map(|&v| v as u8)
+                .collect();
+            assert_eq!(dst, expected, "radius {radius} {edge_mode:?}");
+        }
+    }
+
+    #[test]
+    fn exchange_networks_match_sorted_window() {
+        for radius in [1, 2] {
+            check_median(
+                19,
+                13,
+                FastBlurChannels::Channels3,
+                radius,
+                ThreadingPolicy::Single,
+            );
+            check_median(
+                17,
+                11,
+                FastBlurChannels::Plane,
+                radius,
+                ThreadingPolicy::Fixed(3),
+            );
+        }
+    }
+
+    #[test]
+    fn histogram_median_matches_sorted_window() {
+        for radius in [0, 3, 6] {
+            check_median(
+                19,
+                13,
+                FastBlurChannels::Channels4,
+                radius,
+                ThreadingPolicy::Single,
+            );
+            check_median(
+                21,
+                9,
+                FastBlurChannels::Channels2,
+                radius,
+                ThreadingPolicy::Fixed(2),
+            );
+        }
+    }
+
+    #[test]
+    fn median_radius_larger_than_image() {
+        check_median(1, 1, FastBlurChannels::Plane, 2, ThreadingPolicy::Single);
+        check_median(
+            4,
+            3,
+            FastBlurChannels::Channels3,
+            32767,
+            ThreadingPolicy::Single,
+        );
+    }
 }
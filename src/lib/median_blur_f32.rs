@@ -0,0 +1,163 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::blur_error::check_radius;
+use crate::channels_configuration::FastBlurChannels;
+use crate::median_blur::{median_filter_rows, MedianSource, MedianWorker};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, ThreadingPolicy};
+
+#[allow(clippy::too_many_arguments)]
+fn median_blur_f32_impl<const CHANNELS_CONFIGURATION: usize>(
+    src: &MedianSource<f32>,
+    unsafe_dst: &UnsafeSlice<f32>,
+    dst_stride: usize,
+    width: usize,
+    _: usize,
+    radius: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    let diameter = radius * 2 + 1;
+    let mut window: Vec<f32> =
+        Vec::with_capacity(diameter.min(src.width) * diameter.min(src.height));
+    for y in start_y..end_y {
+        let (top, bottom) = src.window_rows(y, radius);
+        let y_dst_offset = y * dst_stride;
+        for x in 0..width {
+            let (left, right) = src.window_columns(x, radius);
+            let px = y_dst_offset + x * CHANNELS_CONFIGURATION;
+            for c in 0..CHANNELS_CONFIGURATION {
+                window.clear();
+                for wy in top..=bottom {
+                    let row = &src.data[wy * src.stride..];
+                    window.extend(
+                        row[left * CHANNELS_CONFIGURATION + c
+                            ..(right + 1) * CHANNELS_CONFIGURATION]
+                            .iter()
+                            .step_by(CHANNELS_CONFIGURATION),
+                    );
+                }
+                let mid = window.len() / 2;
+                let (_, median, _) = window.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+                unsafe {
+                    unsafe_dst.write(px + c, *median);
+                }
+            }
+        }
+    }
+}
+
+/// Performs median blur on the f32 image.
+///
+/// This performs a median kernel filter on the image producing edge preserving blur result.
+/// Median is found by selection over the kernel window, NaN values are ordered after all numbers.
+/// O(R^2) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius of kernel, limited into 0..=32767, radius larger than the larger image side is treated as that side
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] uses only samples inside the image
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided or radius is out of range
+pub fn median_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    check_radius(radius, 0, 32767)?;
+    let _dispatcher: MedianWorker<f32> = match src.channels {
+        FastBlurChannels::Plane => median_blur_f32_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_f32_impl::<2>,
+        FastBlurChannels::Channels3 => median_blur_f32_impl::<3>,
+        FastBlurChannels::Channels4 => median_blur_f32_impl::<4>,
+    };
+    median_filter_rows(src, dst, radius, edge_mode, threading_policy, _dispatcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge_mode::EdgeColor;
+    use crate::test_utils::{noise, ReferenceImage};
+    use crate::BlurImageMut;
+
+    fn check_median_f32(width: usize, height: usize, radius: u32, reference_radius: usize) {
+        let channels = FastBlurChannels::Channels3;
+        let src: Vec<f32> = noise(width * height * 3, 1000, radius)
+            .iter()
+            .map(|&v| v as f32 / 999.)
+            .collect();
+        let reference = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 3,
+        };
+        for edge_mode in [
+            EdgeMode::Clamp,
+            EdgeMode::KernelClip,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+            EdgeMode::Constant(EdgeColor([0.25, 0.5, 1., 1.])),
+        ] {
+            let mut dst = vec![0f32; src.len()];
+            median_blur_f32(
+                &BlurImage::borrow(&src, width as u32, height as u32, channels),
+                &mut BlurImageMut::borrow(&mut dst, width as u32, height as u32, channels),
+                radius,
+                edge_mode,
+                ThreadingPolicy::Fixed(2),
+            )
+            .unwrap();
+            assert_eq!(
+                dst,
+                reference.median(reference_radius, edge_mode),
+                "radius {radius} {edge_mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn median_f32_matches_sorted_window() {
+        for radius in [0, 1, 2, 4] {
+            check_median_f32(13, 9, radius, radius as usize);
+        }
+    }
+
+    #[test]
+    fn median_f32_radius_larger_than_image() {
+        check_median_f32(4, 4, 32767, 4);
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::blur_error::check_radius;
+use crate::channels_configuration::FastBlurChannels;
+use crate::median_blur::{median_filter_rows, MedianSource, MedianWorker};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, ThreadingPolicy};
+
+/// Two level histogram of 16-bit values, coarse level counts values by their high byte
+/// so finding a median visits at most 256 coarse and 256 fine bins instead of 65536
+struct HierarchicalHistogram<const CHANNELS_CONFIGURATION: usize> {
+    coarse: Vec<u32>,
+    fine: Vec<u32>,
+    n: u32,
+}
+
+impl<const CHANNELS_CONFIGURATION: usize> HierarchicalHistogram<CHANNELS_CONFIGURATION> {
+    fn new() -> Self {
+        HierarchicalHistogram {
+            coarse: vec![0; 256 * CHANNELS_CONFIGURATION],
+            fine: vec![0; 65536 * CHANNELS_CONFIGURATION],
+            n: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn add(&mut self, pixel: &[u16]) {
+        for (c, &v) in pixel.iter().enumerate() {
+            self.coarse[c * 256 + (v >> 8) as usize] += 1;
+            self.fine[c * 65536 + v as usize] += 1;
+        }
+        self.n += 1;
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, pixel: &[u16]) {
+        for (c, &v) in pixel.iter().enumerate() {
+            self.coarse[c * 256 + (v >> 8) as usize] -= 1;
+            self.fine[c * 65536 + v as usize] -= 1;
+        }
+        self.n -= 1;
+    }
+
+    /// Returns value of rank `n / 2` in the channel
+    #[inline(always)]
+    fn median(&self, channel: usize) -> u16 {
+        let target = self.n / 2;
+        let coarse = &self.coarse[channel * 256..(channel + 1) * 256];
+        let mut acc = 0u32;
+        let mut bucket = 255usize;
+        for (i, &count) in coarse.iter().enumerate() {
+            if acc + count > target {
+                bucket = i;
+                break;
+            }
+            acc += count;
+        }
+        let fine_start = channel * 65536 + bucket * 256;
+        let fine = &self.fine[fine_start..fine_start + 256];
+        for (i, &count) in fine.iter().enumerate() {
+            acc += count;
+            if acc > target {
+                return (bucket * 256 + i) as u16;
+            }
+        }
+        (bucket * 256 + 255) as u16
+    }
+}
+
+fn update_column<const CHANNELS_CONFIGURATION: usize>(
+    src: &MedianSource<u16>,
+    x: usize,
+    top: usize,
+    bottom: usize,
+    histogram: &mut HierarchicalHistogram<CHANNELS_CONFIGURATION>,
+    add: bool,
+) {
+    let px = x * CHANNELS_CONFIGURATION;
+    for y in top..=bottom {
+        let offset = y * src.stride + px;
+        let pixel = &src.data[offset..offset + CHANNELS_CONFIGURATION];
+        if add {
+            histogram.add(pixel);
+        } else {
+            histogram.remove(pixel);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn median_blur_u16_impl<const CHANNELS_CONFIGURATION: usize>(
+    src: &MedianSource<u16>,
+    unsafe_dst: &UnsafeSlice<u16>,
+    dst_stride: usize,
+    width: usize,
+    _: usize,
+    radius: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    let mut histogram = HierarchicalHistogram::<CHANNELS_CONFIGURATION>::new();
+    for y in start_y..end_y {
+        let (top, bottom) = src.window_rows(y, radius);
+        let y_dst_offset = y * dst_stride;
+        let (mut left, mut right) = src.window_columns(0, radius);
+        for x in left..=right {
+            update_column(src, x, top, bottom, &mut histogram, true);
+        }
+        for x in 0..width {
+            if x > 0 {
+                let (new_left, new_right) = src.window_columns(x, radius);
+                for x in left..new_left {
+                    update_column(src, x, top, bottom, &mut histogram, false);
+                }
+                for x in right + 1..=new_right {
+                    update_column(src, x, top, bottom, &mut histogram, true);
+                }
+                (left, right) = (new_left, new_right);
+            }
+            let px = y_dst_offset + x * CHANNELS_CONFIGURATION;
+            for c in 0..CHANNELS_CONFIGURATION {
+                unsafe {
+                    unsafe_dst.write(px + c, histogram.median(c));
+                }
+            }
+        }
+        // Removing the last window touches only its own bins,
+        // clearing the whole fine level would cost 65536 bins per channel on every row
+        for x in left..=right {
+            update_column(src, x, top, bottom, &mut histogram, false);
+        }
+    }
+}
+
+/// Performs median blur on the 16-bit image.
+///
+/// This performs a median kernel filter on the image producing edge preserving blur result.
+/// Any bit depth up to 16 is supported, median is searched in a two level histogram
+/// so the cost doesn't depend on the bit depth.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - Radius of kernel, limited into 0..=32767, radius larger than the larger image side is treated as that side
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] uses only samples inside the image
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided or radius is out of range
+pub fn median_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    check_radius(radius, 0, 32767)?;
+    let _dispatcher: MedianWorker<u16> = match src.channels {
+        FastBlurChannels::Plane => median_blur_u16_impl::<1>,
+        FastBlurChannels::Channels2 => median_blur_u16_impl::<2>,
+        FastBlurChannels::Channels3 => median_blur_u16_impl::<3>,
+        FastBlurChannels::Channels4 => median_blur_u16_impl::<4>,
+    };
+    median_filter_rows(src, dst, radius, edge_mode, threading_policy, _dispatcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge_mode::EdgeColor;
+    use crate::test_utils::{noise, ReferenceImage};
+    use crate::BlurImageMut;
+
+    fn check_median_u16(width: usize, height: usize, radius: u32, reference_radius: usize) {
+        let channels = FastBlurChannels::Channels4;
+        for max in [1 << 10, 1 << 12, 1 << 16] {
+            let src: Vec<u16> = noise(width * height * 4, max, radius)
+                .iter()
+                .map(|&v| v as u16)
+                .collect();
+            let src_f32: Vec<f32> = src.iter().map(|&v| v as f32).collect();
+            let reference = ReferenceImage {
+                data: &src_f32,
+                width,
+                height,
+                channels: 4,
+            };
+            for edge_mode in [
+                EdgeMode::Clamp,
+                EdgeMode::KernelClip,
+                EdgeMode::Wrap,
+                EdgeMode::Reflect,
+                EdgeMode::Reflect101,
+                EdgeMode::Constant(EdgeColor([0., 300., 4000., 65535.])),
+            ] {
+                let mut dst = vec![0u16; src.len()];
+                median_blur_u16(
+                    &BlurImage::borrow(&src, width as u32, height as u32, channels),
+                    &mut BlurImageMut::borrow(&mut dst, width as u32, height as u32, channels),
+                    radius,
+                    edge_mode,
+                    ThreadingPolicy::Fixed(3),
+                )
+                .unwrap();
+                let expected: Vec<u16> = reference
+                    .median(reference_radius, edge_mode)
+                    .iter()
+                    .map(|&v| v as u16)
+                    .collect();
+                assert_eq!(dst, expected, "radius {radius} max {max} {edge_mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn median_u16_matches_sorted_window() {
+        for radius in [0, 1, 3, 5] {
+            check_median_u16(15, 11, radius, radius as usize);
+        }
+    }
+
+    #[test]
+    fn median_u16_large_radius_over_many_rows() {
+        let (width, height, radius) = (24usize, 64usize, 15usize);
+        let channels = FastBlurChannels::Plane;
+        let src: Vec<u16> = noise(width * height, 1 << 16, 9)
+            .iter()
+            .map(|&v| v as u16)
+            .collect();
+        let src_f32: Vec<f32> = src.iter().map(|&v| v as f32).collect();
+        let reference = ReferenceImage {
+            data: &src_f32,
+            width,
+            height,
+            channels: 1,
+        };
+        for edge_mode in [EdgeMode::Clamp, EdgeMode::KernelClip] {
+            let mut dst = vec![0u16; src.len()];
+            median_blur_u16(
+                &BlurImage::borrow(&src, width as u32, height as u32, channels),
+                &mut BlurImageMut::borrow(&mut dst, width as u32, height as u32, channels),
+                radius as u32,
+                edge_mode,
+                ThreadingPolicy::Fixed(2),
+            )
+            .unwrap();
+            let expected: Vec<u16> = reference
+                .median(radius, edge_mode)
+                .iter()
+                .map(|&v| v as u16)
+                .collect();
+            assert_eq!(dst, expected, "{edge_mode:?}");
+        }
+    }
+
+    #[test]
+    fn median_u16_radius_larger_than_image() {
+        check_median_u16(4, 4, 32767, 4);
+    }
+}
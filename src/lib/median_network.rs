@@ -0,0 +1,255 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+// Exchange networks are from N. Devillard, "Fast median search: an ANSI C implementation".
+
+use crate::median_blur::MedianSource;
+use crate::unsafe_slice::UnsafeSlice;
+
+/// Vector of 16 bytes that median networks sort lane by lane
+pub(crate) trait MedianNetworkLane: Copy {
+    unsafe fn load(ptr: *const u8) -> Self;
+    unsafe fn store(self, ptr: *mut u8);
+    unsafe fn min(self, other: Self) -> Self;
+    unsafe fn max(self, other: Self) -> Self;
+}
+
+#[inline(always)]
+unsafe fn sort<V: MedianNetworkLane, const N: usize>(p: &mut [V; N], a: usize, b: usize) {
+    let (lo, hi) = (p[a].min(p[b]), p[a].max(p[b]));
+    p[a] = lo;
+    p[b] = hi;
+}
+
+/// Median of 9 values with 19 exchanges, the 3x3 kernel
+#[inline(always)]
+pub(crate) unsafe fn median_of_9<V: MedianNetworkLane>(mut p: [V; 9]) -> V {
+    sort(&mut p, 1, 2);
+    sort(&mut p, 4, 5);
+    sort(&mut p, 7, 8);
+    sort(&mut p, 0, 1);
+    sort(&mut p, 3, 4);
+    sort(&mut p, 6, 7);
+    sort(&mut p, 1, 2);
+    sort(&mut p, 4, 5);
+    sort(&mut p, 7, 8);
+    sort(&mut p, 0, 3);
+    sort(&mut p, 5, 8);
+    sort(&mut p, 4, 7);
+    sort(&mut p, 3, 6);
+    sort(&mut p, 1, 4);
+    sort(&mut p, 2, 5);
+    sort(&mut p, 4, 7);
+    sort(&mut p, 4, 2);
+    sort(&mut p, 6, 4);
+    sort(&mut p, 4, 2);
+    p[4]
+}
+
+/// Median of 25 values with 99 exchanges, the 5x5 kernel
+#[inline(always)]
+pub(crate) unsafe fn median_of_25<V: MedianNetworkLane>(mut p: [V; 25]) -> V {
+    sort(&mut p, 0, 1);
+    sort(&mut p, 3, 4);
+    sort(&mut p, 2, 4);
+    sort(&mut p, 2, 3);
+    sort(&mut p, 6, 7);
+    sort(&mut p, 5, 7);
+    sort(&mut p, 5, 6);
+    sort(&mut p, 9, 10);
+    sort(&mut p, 8, 10);
+    sort(&mut p, 8, 9);
+    sort(&mut p, 12, 13);
+    sort(&mut p, 11, 13);
+    sort(&mut p, 11, 12);
+    sort(&mut p, 15, 16);
+    sort(&mut p, 14, 16);
+    sort(&mut p, 14, 15);
+    sort(&mut p, 18, 19);
+    sort(&mut p, 17, 19);
+    sort(&mut p, 17, 18);
+    sort(&mut p, 21, 22);
+    sort(&mut p, 20, 22);
+    sort(&mut p, 20, 21);
+    sort(&mut p, 23, 24);
+    sort(&mut p, 2, 5);
+    sort(&mut p, 3, 6);
+    sort(&mut p, 0, 6);
+    sort(&mut p, 0, 3);
+    sort(&mut p, 4, 7);
+    sort(&mut p, 1, 7);
+    sort(&mut p, 1, 4);
+    sort(&mut p, 11, 14);
+    sort(&mut p, 8, 14);
+    sort(&mut p, 8, 11);
+    sort(&mut p, 12, 15);
+    sort(&mut p, 9, 15);
+    sort(&mut p, 9, 12);
+    sort(&mut p, 13, 16);
+    sort(&mut p, 10, 16);
+    sort(&mut p, 10, 13);
+    sort(&mut p, 20, 23);
+    sort(&mut p, 17, 23);
+    sort(&mut p, 17, 20);
+    sort(&mut p, 21, 24);
+    sort(&mut p, 18, 24);
+    sort(&mut p, 18, 21);
+    sort(&mut p, 19, 22);
+    sort(&mut p, 8, 17);
+    sort(&mut p, 9, 18);
+    sort(&mut p, 0, 18);
+    sort(&mut p, 0, 9);
+    sort(&mut p, 10, 19);
+    sort(&mut p, 1, 19);
+    sort(&mut p, 1, 10);
+    sort(&mut p, 11, 20);
+    sort(&mut p, 2, 20);
+    sort(&mut p, 2, 11);
+    sort(&mut p, 12, 21);
+    sort(&mut p, 3, 21);
+    sort(&mut p, 3, 12);
+    sort(&mut p, 13, 22);
+    sort(&mut p, 4, 22);
+    sort(&mut p, 4, 13);
+    sort(&mut p, 14, 23);
+    sort(&mut p, 5, 23);
+    sort(&mut p, 5, 14);
+    sort(&mut p, 15, 24);
+    sort(&mut p, 6, 24);
+    sort(&mut p, 6, 15);
+    sort(&mut p, 7, 16);
+    sort(&mut p, 7, 19);
+    sort(&mut p, 13, 21);
+    sort(&mut p, 15, 23);
+    sort(&mut p, 7, 13);
+    sort(&mut p, 7, 15);
+    sort(&mut p, 1, 9);
+    sort(&mut p, 3, 11);
+    sort(&mut p, 5, 17);
+    sort(&mut p, 11, 17);
+    sort(&mut p, 9, 17);
+    sort(&mut p, 4, 10);
+    sort(&mut p, 6, 12);
+    sort(&mut p, 7, 14);
+    sort(&mut p, 4, 6);
+    sort(&mut p, 4, 7);
+    sort(&mut p, 12, 14);
+    sort(&mut p, 10, 14);
+    sort(&mut p, 6, 7);
+    sort(&mut p, 10, 12);
+    sort(&mut p, 6, 10);
+    sort(&mut p, 6, 17);
+    sort(&mut p, 12, 17);
+    sort(&mut p, 7, 17);
+    sort(&mut p, 7, 10);
+    sort(&mut p, 12, 18);
+    sort(&mut p, 7, 12);
+    sort(&mut p, 10, 18);
+    sort(&mut p, 12, 20);
+    sort(&mut p, 10, 20);
+    sort(&mut p, 10, 12);
+    p[12]
+}
+
+#[inline(always)]
+unsafe fn median_network_block<V: MedianNetworkLane, const CHANNELS: usize, const RADIUS: usize>(
+    rows: &[*const u8; 5],
+) -> V {
+    let diameter = 2 * RADIUS + 1;
+    let load = |ky: usize, kx: usize| V::load(rows[ky].add(kx * CHANNELS));
+    if RADIUS == 1 {
+        let mut p = [load(0, 0); 9];
+        for ky in 0..diameter {
+            for kx in 0..diameter {
+                p[ky * diameter + kx] = load(ky, kx);
+            }
+        }
+        median_of_9(p)
+    } else {
+        let mut p = [load(0, 0); 25];
+        for ky in 0..diameter {
+            for kx in 0..diameter {
+                p[ky * diameter + kx] = load(ky, kx);
+            }
+        }
+        median_of_25(p)
+    }
+}
+
+/// Median of 3x3 or 5x5 window for rows `start_y..end_y`, 16 bytes at a time.
+///
+/// Source must be padded by `RADIUS`, each byte sees its neighbours `CHANNELS` bytes apart.
+#[inline(always)]
+pub(crate) unsafe fn median_network_rows<
+    V: MedianNetworkLane,
+    const CHANNELS: usize,
+    const RADIUS: usize,
+>(
+    src: &MedianSource<u8>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: usize,
+    width: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    let row_length = width * CHANNELS;
+    let diameter = 2 * RADIUS + 1;
+    let src_ptr = src.data.as_ptr();
+    for y in start_y..end_y {
+        let top = y + src.offset - RADIUS;
+        let dst_ptr = (unsafe_dst.slice.as_ptr() as *mut u8).add(y * dst_stride);
+        let mut rows = [src_ptr; 5];
+        for (ky, row) in rows.iter_mut().take(diameter).enumerate() {
+            *row = src_ptr.add((top + ky) * src.stride);
+        }
+
+        let mut cx = 0usize;
+        while cx + 16 <= row_length {
+            let mut block = rows;
+            for row in block.iter_mut().take(diameter) {
+                *row = row.add(cx);
+            }
+            median_network_block::<V, CHANNELS, RADIUS>(&block).store(dst_ptr.add(cx));
+            cx += 16;
+        }
+
+        if cx < row_length {
+            // Tail is gathered into buffers to keep reads and writes inside the rows
+            let rem = row_length - cx;
+            let span = rem + 2 * RADIUS * CHANNELS;
+            let mut tail_rows = [src_ptr; 5];
+            let mut buffers = [[0u8; 32]; 5];
+            let mut tail = [0u8; 16];
+            for ky in 0..diameter {
+                std::ptr::copy_nonoverlapping(rows[ky].add(cx), buffers[ky].as_mut_ptr(), span);
+                tail_rows[ky] = buffers[ky].as_ptr();
+            }
+            median_network_block::<V, CHANNELS, RADIUS>(&tail_rows).store(tail.as_mut_ptr());
+            std::ptr::copy_nonoverlapping(tail.as_ptr(), dst_ptr.add(cx), rem);
+        }
+    }
+}
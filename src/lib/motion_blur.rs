@@ -0,0 +1,450 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::{filter_alpha_mode_into, AlphaPremultiply};
+use crate::edge_padding::pad_image;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+
+/// Taps of the motion kernel as (x, y) offsets from the output pixel.
+///
+/// Taps follow the discrete line through the pixel along the major axis,
+/// offsets are taken relative to the pixel, so shifting the image shifts the result the same way.
+fn motion_taps(angle: f32, radius: usize) -> Vec<(i64, i64)> {
+    let (sin, cos) = (angle as f64).to_radians().sin_cos();
+    let radius = radius as i64;
+    (-radius..=radius)
+        .map(|k| {
+            if sin.abs() > cos.abs() {
+                ((k as f64 * cos / sin).round() as i64, k)
+            } else {
+                (k, (k as f64 * sin / cos).round() as i64)
+            }
+        })
+        .collect()
+}
+
+fn motion_blur_impl<T, J, const CHANNELS: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    angle: f32,
+    kernel_size: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<J>,
+    J: Copy + Default + std::ops::AddAssign + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let radius = kernel_size as usize / 2;
+    let padded = pad_image(src, radius, edge_mode);
+    let padded_stride = (width + radius * 2) * CHANNELS;
+    let taps = motion_taps(angle, radius);
+    let weight = 1f32 / kernel_size as f32;
+    let row_length = width * CHANNELS;
+
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+    // Every tap adds a shifted row of the padded image, taps never leave the padding
+    let blur_rows = |start_y: usize, end_y: usize| {
+        let mut sums = vec![J::default(); row_length];
+        for y in start_y..end_y {
+            sums.fill(J::default());
+            for &(dx, dy) in taps.iter() {
+                let offset = (y as i64 + dy + radius as i64) as usize * padded_stride
+                    + (dx + radius as i64) as usize * CHANNELS;
+                for (sum, &v) in sums
+                    .iter_mut()
+                    .zip(padded[offset..offset + row_length].iter())
+                {
+                    *sum += v.as_();
+                }
+            }
+            let offset = y * dst_stride;
+            for (x, sum) in sums.iter().enumerate() {
+                unsafe {
+                    unsafe_dst.write(offset + x, (sum.as_() * weight).to_());
+                }
+            }
+        }
+    };
+
+    let thread_count = threading_policy.get_threads_count(src.width, src.height);
+    if thread_count == 1 {
+        blur_rows(0, height);
+        return;
+    }
+    let pool = threading_policy.get_pool(thread_count);
+    let blur_rows = &blur_rows;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| blur_rows(start_y, end_y));
+        }
+    });
+}
+
+fn motion_blur_dispatch<T, J>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    angle: f32,
+    kernel_size: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<J> + AlphaPremultiply,
+    J: Copy + Default + std::ops::AddAssign + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    if kernel_size.is_multiple_of(2) {
+        return Err(BlurError::KernelSizeNotOdd(kernel_size));
+    }
+    if edge_mode == EdgeMode::KernelClip {
+        return Err(BlurError::EdgeModeNotSupported(edge_mode));
+    }
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        src.check_layout()?;
+        dst.check_layout()?;
+        src.size_matches(dst)?;
+        threading_policy.check()?;
+        let _dispatcher = match src.channels {
+            FastBlurChannels::Plane => motion_blur_impl::<T, J, 1>,
+            FastBlurChannels::Channels2 => motion_blur_impl::<T, J, 2>,
+            FastBlurChannels::Channels3 => motion_blur_impl::<T, J, 3>,
+            FastBlurChannels::Channels4 => motion_blur_impl::<T, J, 4>,
+        };
+        _dispatcher(src, dst, angle, kernel_size, edge_mode, threading_policy);
+        Ok(())
+    })
+}
+
+/// Performs motion blur on the image.
+///
+/// Averages `kernel_size` pixels along a line at the given angle, centered at the pixel.
+/// Every pixel takes the same discrete line segment relative to itself.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `angle` - Direction of the motion in degrees, `0` is horizontal and `90` is vertical
+/// * `kernel_size` - Length of the motion in pixels, must be odd
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or edge mode is not supported
+pub fn motion_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    angle: f32,
+    kernel_size: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_dispatch::<u8, u32>(
+        src,
+        dst,
+        angle,
+        kernel_size,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs motion blur on the 16-bit image.
+///
+/// Averages `kernel_size` pixels along a line at the given angle, centered at the pixel.
+/// Every pixel takes the same discrete line segment relative to itself.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `angle` - Direction of the motion in degrees, `0` is horizontal and `90` is vertical
+/// * `kernel_size` - Length of the motion in pixels, must be odd
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or edge mode is not supported
+pub fn motion_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    angle: f32,
+    kernel_size: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_dispatch::<u16, u64>(
+        src,
+        dst,
+        angle,
+        kernel_size,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs motion blur on the image in f32.
+///
+/// Averages `kernel_size` pixels along a line at the given angle, centered at the pixel.
+/// Every pixel takes the same discrete line segment relative to itself.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `angle` - Direction of the motion in degrees, `0` is horizontal and `90` is vertical
+/// * `kernel_size` - Length of the motion in pixels, must be odd
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd or edge mode is not supported
+pub fn motion_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    angle: f32,
+    kernel_size: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    motion_blur_dispatch::<f32, f32>(
+        src,
+        dst,
+        angle,
+        kernel_size,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+
+    #[test]
+    fn motion_blur_keeps_constant_image() {
+        let (width, height) = (18u32, 13u32);
+        let pixel = [3u8, 180, 77, 255];
+        let src = pixel.repeat(width as usize * height as usize);
+        for angle in [0., 30., 90., 137.] {
+            for edge_mode in edge_modes(pixel.map(|v| v as f32)) {
+                let mut dst = vec![0u8; src.len()];
+                motion_blur(
+                    &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels4),
+                    &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels4),
+                    angle,
+                    11,
+                    edge_mode,
+                    AlphaMode::Premultiply,
+                    ThreadingPolicy::Fixed(2),
+                )
+                .unwrap();
+                assert_eq!(dst, src, "angle {angle} {edge_mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn motion_blur_f32_keeps_constant_image() {
+        let (width, height) = (10u32, 10u32);
+        let src = vec![0.625f32; (width * height * 2) as usize];
+        for angle in [45., 200.] {
+            let mut dst = vec![0f32; src.len()];
+            motion_blur_f32(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels2),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels2),
+                angle,
+                7,
+                EdgeMode::Reflect101,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            assert!(
+                dst.iter().all(|v| (v - 0.625).abs() < 1e-5),
+                "angle {angle}"
+            );
+        }
+    }
+
+    #[test]
+    fn motion_blur_shifts_with_image() {
+        let (width, height) = (40usize, 31usize);
+        let src = noise(width * height * 3, 256, 21)
+            .iter()
+            .map(|&v| v as u8)
+            .collect::<Vec<u8>>();
+        let blur = |src: &[u8], width: usize, height: usize, angle: f32| {
+            let mut dst = vec![0u8; src.len()];
+            motion_blur(
+                &BlurImage::borrow(
+                    src,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                &mut BlurImageMut::borrow(
+                    &mut dst,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                angle,
+                9,
+                EdgeMode::Clamp,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            dst
+        };
+        let (shift_x, shift_y) = (3usize, 5usize);
+        let (crop_width, crop_height) = (width - shift_x, height - shift_y);
+        let crop = |image: &[u8]| {
+            image
+                .chunks_exact(width * 3)
+                .skip(shift_y)
+                .flat_map(|row| row[shift_x * 3..].to_vec())
+                .collect::<Vec<u8>>()
+        };
+        let shifted = crop(&src);
+        for angle in [17., 30., 63., 137., 251.] {
+            let expected = crop(&blur(&src, width, height, angle));
+            let actual = blur(&shifted, crop_width, crop_height, angle);
+            // Away from the borders of the cropped image both see the same neighbourhood
+            for y in 4..crop_height - 4 {
+                let row = y * crop_width * 3;
+                assert_eq!(
+                    actual[row + 12..row + (crop_width - 4) * 3],
+                    expected[row + 12..row + (crop_width - 4) * 3],
+                    "angle {angle} row {y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn motion_blur_f32_matches_reference() {
+        let (width, height) = (19usize, 14usize);
+        let src = noise(width * height * 2, 256, 4)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 2,
+        };
+        let radius = 4i64;
+        for angle in [0f32, 20., 45., 90., 110., 333.] {
+            // Samples along the ray through the pixel, one per step of the dominant axis
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let offsets = (-radius..=radius)
+                .map(|k| {
+                    let t = k as f32 / sin.abs().max(cos.abs());
+                    ((t * cos).round() as i64, (t * sin).round() as i64)
+                })
+                .collect::<Vec<_>>();
+            for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.]) {
+                let mut dst = vec![0f32; src.len()];
+                motion_blur_f32(
+                    &BlurImage::borrow(
+                        &src,
+                        width as u32,
+                        height as u32,
+                        FastBlurChannels::Channels2,
+                    ),
+                    &mut BlurImageMut::borrow(
+                        &mut dst,
+                        width as u32,
+                        height as u32,
+                        FastBlurChannels::Channels2,
+                    ),
+                    angle,
+                    radius as u32 * 2 + 1,
+                    edge_mode,
+                    AlphaMode::Independent,
+                    ThreadingPolicy::Fixed(3),
+                )
+                .unwrap();
+                let mut expected = Vec::with_capacity(src.len());
+                for y in 0..height as i64 {
+                    for x in 0..width as i64 {
+                        for c in 0..2 {
+                            let sum: f32 = offsets
+                                .iter()
+                                .map(|&(dx, dy)| {
+                                    image.sample(x + dx, y + dy, c, edge_mode).unwrap()
+                                })
+                                .sum();
+                            expected.push(sum / offsets.len() as f32);
+                        }
+                    }
+                }
+                assert_close(
+                    &dst,
+                    &expected,
+                    1e-5,
+                    &format!("angle {angle} {edge_mode:?}"),
+                );
+            }
+        }
+    }
+}
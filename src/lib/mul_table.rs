@@ -132,3 +132,30 @@ pub(crate) const SHR_TABLE_STACK_BLUR: [i32; 255] = [
     24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24,
     24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24, 24,
 ];
+
+/// Fixed point reciprocal of stack blur kernel weight `(radius + 1)^2` for a storage type
+pub(crate) trait StackBlurScale {
+    /// Returns multiplier and shift so that `sum * mul >> shr` is `sum / (radius + 1)^2`
+    fn stack_blur_mul_shr(radius: u32) -> (i64, i32);
+}
+
+impl StackBlurScale for u8 {
+    #[inline]
+    fn stack_blur_mul_shr(radius: u32) -> (i64, i32) {
+        (
+            MUL_TABLE_STACK_BLUR[radius as usize] as i64,
+            SHR_TABLE_STACK_BLUR[radius as usize],
+        )
+    }
+}
+
+impl StackBlurScale for u16 {
+    /// 8-bit tables are not precise enough for 16 bit depth, so the exact reciprocal
+    /// rounded up is used, this keeps constant areas unchanged and fits i64 for any sum of u16
+    #[inline]
+    fn stack_blur_mul_shr(radius: u32) -> (i64, i32) {
+        const SHR: i32 = 32;
+        let weight = (radius as i64 + 1) * (radius as i64 + 1);
+        (((1i64 << SHR) + weight - 1) / weight, SHR)
+    }
+}
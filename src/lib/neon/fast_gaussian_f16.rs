@@ -46,7 +46,7 @@ pub fn fast_gaussian_vertical_pass_neon_f16<
     start: u32,
     end: u32,
 ) {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let bytes: &UnsafeSlice<'_, f16> = unsafe { std::mem::transmute(undef_bytes) };
     let mut buffer: [[f32; 4]; 1024] = [[0f32; 4]; 1024];
 
@@ -124,7 +124,7 @@ pub fn fast_gaussian_horizontal_pass_neon_f16<
     start: u32,
     end: u32,
 ) {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let bytes: &UnsafeSlice<'_, f16> = unsafe { std::mem::transmute(undef_bytes) };
     let mut buffer: [[f32; 4]; 1024] = [[0f32; 4]; 1024];
     let radius_64 = radius as i64;
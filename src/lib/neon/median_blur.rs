@@ -0,0 +1,118 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::median_blur::{median_blur_impl, MedianHistogramSegment, MedianSource};
+use crate::median_network::{median_network_rows, MedianNetworkLane};
+use crate::unsafe_slice::UnsafeSlice;
+use std::arch::aarch64::*;
+
+pub struct MedianSegmentNeon;
+
+impl MedianHistogramSegment for MedianSegmentNeon {
+    #[inline(always)]
+    unsafe fn add(dst: &mut [u32; 16], src: &[u16; 16]) {
+        let src = vld1q_u16_x2(src.as_ptr());
+        let acc = vld1q_u32_x4(dst.as_ptr());
+        let acc = uint32x4x4_t(
+            vaddw_u16(acc.0, vget_low_u16(src.0)),
+            vaddw_high_u16(acc.1, src.0),
+            vaddw_u16(acc.2, vget_low_u16(src.1)),
+            vaddw_high_u16(acc.3, src.1),
+        );
+        vst1q_u32_x4(dst.as_mut_ptr(), acc);
+    }
+
+    #[inline(always)]
+    unsafe fn sub(dst: &mut [u32; 16], src: &[u16; 16]) {
+        let src = vld1q_u16_x2(src.as_ptr());
+        let acc = vld1q_u32_x4(dst.as_ptr());
+        let acc = uint32x4x4_t(
+            vsubw_u16(acc.0, vget_low_u16(src.0)),
+            vsubw_high_u16(acc.1, src.0),
+            vsubw_u16(acc.2, vget_low_u16(src.1)),
+            vsubw_high_u16(acc.3, src.1),
+        );
+        vst1q_u32_x4(dst.as_mut_ptr(), acc);
+    }
+}
+
+impl MedianNetworkLane for uint8x16_t {
+    #[inline(always)]
+    unsafe fn load(ptr: *const u8) -> Self {
+        vld1q_u8(ptr)
+    }
+
+    #[inline(always)]
+    unsafe fn store(self, ptr: *mut u8) {
+        vst1q_u8(ptr, self)
+    }
+
+    #[inline(always)]
+    unsafe fn min(self, other: Self) -> Self {
+        vminq_u8(self, other)
+    }
+
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        vmaxq_u8(self, other)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn median_blur_neon<const CHANNELS_CONFIGURATION: usize>(
+    src: &MedianSource<u8>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: usize,
+    width: usize,
+    height: usize,
+    radius: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    median_blur_impl::<CHANNELS_CONFIGURATION, MedianSegmentNeon>(
+        src, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+    );
+}
+
+/// 3x3 and 5x5 median through exchange networks, source must be padded by `RADIUS`
+#[allow(clippy::too_many_arguments)]
+pub fn median_blur_network_neon<const CHANNELS_CONFIGURATION: usize, const RADIUS: usize>(
+    src: &MedianSource<u8>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: usize,
+    width: usize,
+    _: usize,
+    _: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    unsafe {
+        median_network_rows::<uint8x16_t, CHANNELS_CONFIGURATION, RADIUS>(
+            src, unsafe_dst, dst_stride, width, start_y, end_y,
+        );
+    }
+}
@@ -32,6 +32,7 @@ mod fast_gaussian_f32;
 mod fast_gaussian_next;
 mod fast_gaussian_next_f16;
 mod fast_gaussian_next_f32;
+mod median_blur;
 mod stack_blur_f16;
 mod stack_blur_f32;
 mod stack_blur_i32;
@@ -50,6 +51,7 @@ pub use fast_gaussian_next_f16::{
 };
 pub use fast_gaussian_next_f32::fast_gaussian_next_horizontal_pass_neon_f32;
 pub use fast_gaussian_next_f32::fast_gaussian_next_vertical_pass_neon_f32;
+pub use median_blur::{median_blur_neon, median_blur_network_neon};
 pub use stack_blur_f16::stack_blur_pass_neon_f16;
 pub use stack_blur_f32::stack_blur_pass_neon_f32;
 pub use stack_blur_i32::*;
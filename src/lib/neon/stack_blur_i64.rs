@@ -25,17 +25,48 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mul_table::{MUL_TABLE_STACK_BLUR, SHR_TABLE_STACK_BLUR};
+use crate::mul_table::StackBlurScale;
 use crate::neon::{
-    load_u8_s64x2_fast, store_u8_s32, vaddq_s64x2, vdupq_n_s64x2, vmulq_n_s64x2, vsubq_s64x2,
+    load_u16_s64x2_fast, load_u8_s64x2_fast, store_u16_s32, store_u8_s32, vaddq_s64x2,
+    vdupq_n_s64x2, vmulq_n_s64x2, vsubq_s64x2,
 };
 use crate::stack_blur::StackBlurPass;
 use crate::unsafe_slice::UnsafeSlice;
 use erydanos::vmulq_s64;
 use std::arch::aarch64::*;
 
-pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
-    pixels: &UnsafeSlice<u8>,
+/// Storage types that i64 stack blur loads into and stores from 64-bit lanes
+pub trait NeonStackBlurI64Storage: Copy + StackBlurScale {
+    unsafe fn load_s64x2<const COMPONENTS: usize>(ptr: *const Self) -> int64x2x2_t;
+    unsafe fn store_s32<const COMPONENTS: usize>(ptr: *mut Self, regi: int32x4_t);
+}
+
+impl NeonStackBlurI64Storage for u8 {
+    #[inline(always)]
+    unsafe fn load_s64x2<const COMPONENTS: usize>(ptr: *const Self) -> int64x2x2_t {
+        load_u8_s64x2_fast::<COMPONENTS>(ptr)
+    }
+
+    #[inline(always)]
+    unsafe fn store_s32<const COMPONENTS: usize>(ptr: *mut Self, regi: int32x4_t) {
+        store_u8_s32::<COMPONENTS>(ptr, regi)
+    }
+}
+
+impl NeonStackBlurI64Storage for u16 {
+    #[inline(always)]
+    unsafe fn load_s64x2<const COMPONENTS: usize>(ptr: *const Self) -> int64x2x2_t {
+        load_u16_s64x2_fast::<COMPONENTS>(ptr)
+    }
+
+    #[inline(always)]
+    unsafe fn store_s32<const COMPONENTS: usize>(ptr: *mut Self, regi: int32x4_t) {
+        store_u16_s32::<COMPONENTS>(ptr, regi)
+    }
+}
+
+pub fn stack_blur_pass_neon_i64<T: NeonStackBlurI64Storage, const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -58,13 +89,14 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
         let wm = width - 1;
         let hm = height - 1;
         let div = (radius * 2) + 1;
-        let mul_sum = vdupq_n_s64(MUL_TABLE_STACK_BLUR[radius as usize] as i64);
-        let shr_sum = vdupq_n_s64(-SHR_TABLE_STACK_BLUR[radius as usize] as i64);
+        let (mul_sum, shr_sum) = T::stack_blur_mul_shr(radius);
+        let mul_sum = vdupq_n_s64(mul_sum);
+        let shr_sum = vdupq_n_s64(-shr_sum as i64);
 
         let mut src_ptr;
         let mut dst_ptr;
 
-        if pass == StackBlurPass::HORIZONTAL {
+        if pass == StackBlurPass::Horizontal {
             let min_y = thread * height as usize / total_threads;
             let max_y = (thread + 1) * height as usize / total_threads;
 
@@ -75,8 +107,8 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
 
                 src_ptr = stride as usize * y; // start of line (0,y)
 
-                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
-                let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
 
                 for i in 0..=radius {
                     let stack_value = stacks.as_mut_ptr().add(i as usize * 4);
@@ -90,8 +122,8 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
                         src_ptr += COMPONENTS;
                     }
                     let stack_ptr = stacks.as_mut_ptr().add((i + radius) as usize * 4);
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     vst1q_s64_x2(stack_ptr, src_pixel);
                     sums =
                         vaddq_s64x2(sums, vmulq_n_s64x2(src_pixel, radius as i64 + 1 - i as i64));
@@ -108,11 +140,11 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
                 src_ptr = COMPONENTS * xp as usize + y * stride as usize;
                 dst_ptr = y * stride as usize;
                 for _ in 0..width {
-                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut u8;
+                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut T;
                     let blurred_lo = vshlq_s64(vmulq_s64(sums.0, mul_sum), shr_sum);
                     let blurred_hi = vshlq_s64(vmulq_s64(sums.1, mul_sum), shr_sum);
                     let blurred = vcombine_s32(vmovn_s64(blurred_lo), vmovn_s64(blurred_hi));
-                    store_u8_s32::<COMPONENTS>(store_ld, blurred);
+                    T::store_s32::<COMPONENTS>(store_ld, blurred);
                     dst_ptr += COMPONENTS;
 
                     sums = vsubq_s64x2(sums, sum_out);
@@ -132,8 +164,8 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
                         xp += 1;
                     }
 
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr);
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     vst1q_s64_x2(stack, src_pixel);
 
                     sum_in = vaddq_s64x2(sum_in, src_pixel);
@@ -150,7 +182,7 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
                     sum_in = vsubq_s64x2(sum_in, stack_val);
                 }
             }
-        } else if pass == StackBlurPass::VERTICAL {
+        } else if pass == StackBlurPass::Vertical {
             let min_x = thread * width as usize / total_threads;
             let max_x = (thread + 1) * width as usize / total_threads;
 
@@ -161,9 +193,9 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
 
                 src_ptr = COMPONENTS * x; // x,0
 
-                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
+                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
 
-                let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
 
                 for i in 0..=radius {
                     let stack_ptr = stacks.as_mut_ptr().add(i as usize * 4);
@@ -178,8 +210,8 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
                     }
 
                     let stack_ptr = stacks.as_mut_ptr().add((i + radius) as usize * 4);
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     vst1q_s64_x2(stack_ptr, src_pixel);
                     sums =
                         vaddq_s64x2(sums, vmulq_n_s64x2(src_pixel, radius as i64 + 1 - i as i64));
@@ -195,11 +227,11 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
                 src_ptr = COMPONENTS * x + yp as usize * stride as usize;
                 dst_ptr = COMPONENTS * x;
                 for _ in 0..height {
-                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut u8;
+                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut T;
                     let blurred_lo = vshlq_s64(vmulq_s64(sums.0, mul_sum), shr_sum);
                     let blurred_hi = vshlq_s64(vmulq_s64(sums.1, mul_sum), shr_sum);
                     let blurred = vcombine_s32(vmovn_s64(blurred_lo), vmovn_s64(blurred_hi));
-                    store_u8_s32::<COMPONENTS>(store_ld, blurred);
+                    T::store_s32::<COMPONENTS>(store_ld, blurred);
 
                     dst_ptr += stride as usize;
 
@@ -219,8 +251,8 @@ pub fn stack_blur_pass_neon_i64<const COMPONENTS: usize>(
                         yp += 1;
                     }
 
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr);
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     vst1q_s64_x2(stack_ptr, src_pixel);
 
                     sum_in = vaddq_s64x2(sum_in, src_pixel);
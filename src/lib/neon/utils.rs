@@ -83,6 +83,26 @@ pub(crate) unsafe fn store_u8_s32<const CHANNELS_COUNT: usize>(dst_ptr: *mut u8,
     }
 }
 
+#[inline(always)]
+pub(crate) unsafe fn store_u16_s32<const CHANNELS_COUNT: usize>(
+    dst_ptr: *mut u16,
+    regi: int32x4_t,
+) {
+    let v16 = vqmovun_s32(regi);
+    if CHANNELS_COUNT == 4 {
+        vst1_u16(dst_ptr, v16);
+    } else if CHANNELS_COUNT == 3 {
+        let pixel_u64 = vget_lane_u64::<0>(vreinterpret_u64_u16(v16));
+        (dst_ptr as *mut u32).write_unaligned(pixel_u64 as u32);
+        dst_ptr.add(2).write_unaligned((pixel_u64 >> 32) as u16);
+    } else if CHANNELS_COUNT == 2 {
+        let pixel_u64 = vget_lane_u64::<0>(vreinterpret_u64_u16(v16));
+        (dst_ptr as *mut u32).write_unaligned(pixel_u64 as u32);
+    } else {
+        dst_ptr.write_unaligned(vget_lane_u16::<0>(v16));
+    }
+}
+
 #[inline(always)]
 pub(crate) unsafe fn store_u8_u32<const CHANNELS_COUNT: usize>(dst_ptr: *mut u8, regi: uint32x4_t) {
     let s16 = vqmovn_u32(regi);
@@ -167,8 +187,14 @@ pub(crate) unsafe fn load_u8_u32_fast<const CHANNELS_COUNT: usize>(ptr: *const u
 #[inline(always)]
 pub(crate) unsafe fn load_u8_u64_fast<const CHANNELS_COUNT: usize>(ptr: *const u8) -> uint64x2x2_t {
     let u_first = u64::from_le_bytes([ptr.read_unaligned(), 0, 0, 0, 0, 0, 0, 0]);
-    let u_second = u64::from_le_bytes([ptr.add(1).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]);
-    let u_third = u64::from_le_bytes([ptr.add(2).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]);
+    let u_second = match CHANNELS_COUNT {
+        2..=4 => u64::from_le_bytes([ptr.add(1).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]),
+        _ => 0,
+    };
+    let u_third = match CHANNELS_COUNT {
+        3 | 4 => u64::from_le_bytes([ptr.add(2).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]),
+        _ => 0,
+    };
     let u_fourth = match CHANNELS_COUNT {
         4 => u64::from_le_bytes([ptr.add(3).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]),
         _ => 0,
@@ -227,11 +253,38 @@ pub(crate) unsafe fn load_u8_s64x2_fast<const CHANNELS_COUNT: usize>(
     int64x2x2_t(sx_0, sx_1)
 }
 
+#[inline(always)]
+pub(crate) unsafe fn load_u16_s64x2_fast<const CHANNELS_COUNT: usize>(
+    ptr: *const u16,
+) -> int64x2x2_t {
+    let u_first = ptr.read_unaligned() as i64;
+    let u_second = match CHANNELS_COUNT {
+        2..=4 => ptr.add(1).read_unaligned() as i64,
+        _ => 0,
+    };
+    let u_third = match CHANNELS_COUNT {
+        3 | 4 => ptr.add(2).read_unaligned() as i64,
+        _ => 0,
+    };
+    let u_fourth = match CHANNELS_COUNT {
+        4 => ptr.add(3).read_unaligned() as i64,
+        _ => 0,
+    };
+    let store: [i64; 4] = [u_first, u_second, u_third, u_fourth];
+    vld1q_s64_x2(store.as_ptr())
+}
+
 #[inline(always)]
 pub(crate) unsafe fn load_u8_u16<const CHANNELS_COUNT: usize>(ptr: *const u8) -> uint16x4_t {
     let u_first = u16::from_le_bytes([ptr.read(), 0]);
-    let u_second = u16::from_le_bytes([ptr.add(1).read_unaligned(), 0]);
-    let u_third = u16::from_le_bytes([ptr.add(2).read_unaligned(), 0]);
+    let u_second = match CHANNELS_COUNT {
+        2..=4 => u16::from_le_bytes([ptr.add(1).read_unaligned(), 0]),
+        _ => 0,
+    };
+    let u_third = match CHANNELS_COUNT {
+        3 | 4 => u16::from_le_bytes([ptr.add(2).read_unaligned(), 0]),
+        _ => 0,
+    };
     let u_fourth = match CHANNELS_COUNT {
         4 => u16::from_le_bytes([ptr.add(3).read_unaligned(), 0]),
         _ => 0,
@@ -0,0 +1,924 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::{filter_alpha_mode_into, AlphaPremultiply};
+use crate::edge_mode::{reflect_index, EdgeColor};
+use crate::linear_transform::{from_linear_transformer, to_linear_transformer};
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    clamp_edge, reflect_101, AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode,
+    FastBlurChannels, ThreadingPolicy,
+};
+use colorutils_rs::TransferFunction;
+use num_traits::AsPrimitive;
+
+/// Samples are taken about one pixel apart, but never more than this count per pixel
+const MAX_SAMPLES: usize = 64;
+const CLIP_TOLERANCE: f32 = 1e-3;
+
+#[derive(Debug, Copy, Clone)]
+enum RadialPath {
+    /// Ray through the center, scale of the offset from the center spans `1 ± strength / 2`
+    Zoom(f32),
+    /// Arc around the center, rotation spans `± angle / 2` in radians
+    Spin(f32),
+}
+
+struct RadialSampler {
+    path: RadialPath,
+    /// Rotation to the first sample of the arc
+    start: (f32, f32),
+    /// Rotation between neighbouring samples for each samples count
+    steps: Vec<(f32, f32)>,
+}
+
+impl RadialSampler {
+    fn new(path: RadialPath) -> RadialSampler {
+        let (start, steps) = match path {
+            RadialPath::Zoom(_) => ((1., 0.), vec![]),
+            RadialPath::Spin(angle) => {
+                let (sin, cos) = (-angle * 0.5).sin_cos();
+                let steps = (0..=MAX_SAMPLES)
+                    .map(|count| {
+                        let step = angle / std::cmp::max(count, 2).saturating_sub(1) as f32;
+                        let (sin, cos) = step.sin_cos();
+                        (cos, sin)
+                    })
+                    .collect();
+                ((cos, sin), steps)
+            }
+        };
+        RadialSampler { path, start, steps }
+    }
+
+    /// Fills sample positions relative to the center for the pixel at `(dx, dy)` from the center,
+    /// returns samples count
+    #[inline(always)]
+    fn fill(&self, dx: f32, dy: f32, positions: &mut [(f32, f32); MAX_SAMPLES]) -> usize {
+        let distance = dx.hypot(dy);
+        match self.path {
+            RadialPath::Zoom(strength) => {
+                let count = samples_count(distance * strength.abs());
+                if count == 1 {
+                    positions[0] = (dx, dy);
+                    return 1;
+                }
+                let step = 1. / (count - 1) as f32;
+                for (i, position) in positions.iter_mut().take(count).enumerate() {
+                    let scale = 1. + strength * (i as f32 * step - 0.5);
+                    *position = (dx * scale, dy * scale);
+                }
+                count
+            }
+            RadialPath::Spin(angle) => {
+                let count = samples_count(distance * angle.abs());
+                if count == 1 {
+                    positions[0] = (dx, dy);
+                    return 1;
+                }
+                let (cos, sin) = self.start;
+                let (step_cos, step_sin) = self.steps[count];
+                let (mut x, mut y) = (dx * cos - dy * sin, dx * sin + dy * cos);
+                for position in positions.iter_mut().take(count) {
+                    *position = (x, y);
+                    (x, y) = (x * step_cos - y * step_sin, x * step_sin + y * step_cos);
+                }
+                count
+            }
+        }
+    }
+}
+
+/// Samples count for the path of the given length in pixels, always in `1..=MAX_SAMPLES`
+/// even for a far center or an infinite length
+#[inline(always)]
+fn samples_count(path_length: f32) -> usize {
+    (path_length.ceil().min(MAX_SAMPLES as f32) as usize + 1).min(MAX_SAMPLES)
+}
+
+#[inline(always)]
+fn read<T: Copy + AsPrimitive<f32>, const CHANNELS: usize>(
+    data: &[T],
+    offset: usize,
+) -> [f32; CHANNELS] {
+    let mut pixel = [0f32; CHANNELS];
+    for (c, dst) in pixel.iter_mut().enumerate() {
+        *dst = unsafe { *data.get_unchecked(offset + c) }.as_();
+    }
+    pixel
+}
+
+/// Reads pixel at the given coordinates, extending the image beyond its borders by `edge_mode`
+#[inline(always)]
+fn fetch<T: Copy + AsPrimitive<f32>, const CHANNELS: usize>(
+    src: &BlurImage<T>,
+    x: i64,
+    y: i64,
+    edge_mode: EdgeMode,
+) -> [f32; CHANNELS] {
+    let width = src.width as i64;
+    let height = src.height as i64;
+    if let EdgeMode::Constant(EdgeColor(color)) = edge_mode {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            let mut pixel = [0f32; CHANNELS];
+            pixel.copy_from_slice(&color[..CHANNELS]);
+            return pixel;
+        }
+    }
+    let px = clamp_edge!(edge_mode, x, 0i64, width - 1);
+    let py = clamp_edge!(edge_mode, y, 0i64, height - 1);
+    read::<T, CHANNELS>(src.data, py * src.stride as usize + px * CHANNELS)
+}
+
+fn radial_blur_impl<T, const CHANNELS: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    center_x: f32,
+    center_y: f32,
+    path: RadialPath,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32>,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let width = src.width as usize;
+    let height = src.height as usize;
+    let sampler = RadialSampler::new(path);
+    let (max_x, max_y) = ((width - 1) as f32, (height - 1) as f32);
+    let src_stride = src.stride as usize;
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+
+    let blur_rows = |start_y: usize, end_y: usize| {
+        let mut positions = [(0f32, 0f32); MAX_SAMPLES];
+        for y in start_y..end_y {
+            for x in 0..width {
+                let count = sampler.fill(x as f32 - center_x, y as f32 - center_y, &mut positions);
+                let mut sums = [0f32; CHANNELS];
+                let mut taken = 0usize;
+                for &(sx, sy) in positions.iter().take(count) {
+                    let (sx, sy) = (sx + center_x, sy + center_y);
+                    // Tolerance keeps samples that drift off the border only by rounding
+                    if edge_mode == EdgeMode::KernelClip
+                        && (sx < -CLIP_TOLERANCE
+                            || sy < -CLIP_TOLERANCE
+                            || sx > max_x + CLIP_TOLERANCE
+                            || sy > max_y + CLIP_TOLERANCE)
+                    {
+                        continue;
+                    }
+                    let (fx, fy) = (sx.floor(), sy.floor());
+                    let (wx, wy) = (sx - fx, sy - fy);
+                    let (ix, iy) = (fx as i64, fy as i64);
+                    let (p00, p10, p01, p11) =
+                        if ix >= 0 && iy >= 0 && ix < width as i64 - 1 && iy < height as i64 - 1 {
+                            let offset = iy as usize * src_stride + ix as usize * CHANNELS;
+                            (
+                                read::<T, CHANNELS>(src.data, offset),
+                                read::<T, CHANNELS>(src.data, offset + CHANNELS),
+                                read::<T, CHANNELS>(src.data, offset + src_stride),
+                                read::<T, CHANNELS>(src.data, offset + src_stride + CHANNELS),
+                            )
+                        } else {
+                            (
+                                fetch::<T, CHANNELS>(src, ix, iy, edge_mode),
+                                fetch::<T, CHANNELS>(src, ix.saturating_add(1), iy, edge_mode),
+                                fetch::<T, CHANNELS>(src, ix, iy.saturating_add(1), edge_mode),
+                                fetch::<T, CHANNELS>(
+                                    src,
+                                    ix.saturating_add(1),
+                                    iy.saturating_add(1),
+                                    edge_mode,
+                                ),
+                            )
+                        };
+                    for c in 0..CHANNELS {
+                        let top = p00[c] + (p10[c] - p00[c]) * wx;
+                        let bottom = p01[c] + (p11[c] - p01[c]) * wx;
+                        sums[c] += top + (bottom - top) * wy;
+                    }
+                    taken += 1;
+                }
+                // Even samples count skips the pixel itself, so near corners every sample may be clipped
+                if taken == 0 {
+                    sums = read::<T, CHANNELS>(src.data, y * src_stride + x * CHANNELS);
+                    taken = 1;
+                }
+                let norm = 1. / taken as f32;
+                let offset = y * dst_stride + x * CHANNELS;
+                for (c, sum) in sums.iter().enumerate() {
+                    unsafe {
+                        unsafe_dst.write(offset + c, (sum * norm).to_());
+                    }
+                }
+            }
+        }
+    };
+
+    let thread_count = threading_policy.get_threads_count(src.width, src.height);
+    if thread_count == 1 {
+        blur_rows(0, height);
+        return;
+    }
+    let pool = threading_policy.get_pool(thread_count);
+    let blur_rows = &blur_rows;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| blur_rows(start_y, end_y));
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn radial_blur_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    center_x: f32,
+    center_y: f32,
+    path: RadialPath,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<f32> + AlphaPremultiply,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let strength = match path {
+        RadialPath::Zoom(strength) => strength,
+        RadialPath::Spin(angle) => angle,
+    };
+    if !strength.is_finite() || !center_x.is_finite() || !center_y.is_finite() {
+        return Err(BlurError::InvalidStrength);
+    }
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        src.check_layout()?;
+        dst.check_layout()?;
+        src.size_matches(dst)?;
+        threading_policy.check()?;
+        let _dispatcher = match src.channels {
+            FastBlurChannels::Plane => radial_blur_impl::<T, 1>,
+            FastBlurChannels::Channels2 => radial_blur_impl::<T, 2>,
+            FastBlurChannels::Channels3 => radial_blur_impl::<T, 3>,
+            FastBlurChannels::Channels4 => radial_blur_impl::<T, 4>,
+        };
+        _dispatcher(
+            src,
+            dst,
+            center_x,
+            center_y,
+            path,
+            edge_mode,
+            threading_policy,
+        );
+        Ok(())
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn radial_blur_in_linear(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    center_x: f32,
+    center_y: f32,
+    path: RadialPath,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    transfer_function: TransferFunction,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
+    let mut linear_data: Vec<f32> =
+        vec![0f32; width as usize * height as usize * channels.get_channels()];
+    let mut linear_data_1: Vec<f32> =
+        vec![0f32; width as usize * height as usize * channels.get_channels()];
+
+    let forward_transformer = to_linear_transformer(channels, src.alpha_position);
+
+    let inverse_transformer = from_linear_transformer(channels, src.alpha_position);
+
+    forward_transformer(
+        src.data,
+        src.stride,
+        &mut linear_data,
+        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width,
+        height,
+        transfer_function,
+    );
+    radial_blur_dispatch(
+        &BlurImage::borrow(&linear_data, width, height, channels),
+        &mut BlurImageMut::borrow(&mut linear_data_1, width, height, channels),
+        center_x,
+        center_y,
+        path,
+        edge_mode.to_linear(channels, src.alpha_position, transfer_function),
+        AlphaMode::Independent,
+        threading_policy,
+    )?;
+    inverse_transformer(
+        &linear_data_1,
+        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        dst.data,
+        dst.stride,
+        width,
+        height,
+        transfer_function,
+    );
+    Ok(())
+}
+
+/// Performs zoom blur on the image.
+///
+/// Averages samples along the ray from the center through the pixel, objects look like rushing towards the viewer.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `strength` - Part of the distance to the center covered by the blur, `0.2` is a moderate zoom
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] skips samples outside of the image
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and strength are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn zoom_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    center_x: f32,
+    center_y: f32,
+    strength: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    radial_blur_dispatch(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Zoom(strength),
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs zoom blur on the 16-bit image.
+///
+/// Averages samples along the ray from the center through the pixel, objects look like rushing towards the viewer.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `strength` - Part of the distance to the center covered by the blur, `0.2` is a moderate zoom
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] skips samples outside of the image
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and strength are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn zoom_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    center_x: f32,
+    center_y: f32,
+    strength: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    radial_blur_dispatch(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Zoom(strength),
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs zoom blur on the image in f32.
+///
+/// Averages samples along the ray from the center through the pixel, objects look like rushing towards the viewer.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `strength` - Part of the distance to the center covered by the blur, `0.2` is a moderate zoom
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] skips samples outside of the image
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and strength are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn zoom_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    center_x: f32,
+    center_y: f32,
+    strength: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    radial_blur_dispatch(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Zoom(strength),
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs zoom blur on the image in linear colorspace.
+///
+/// Averages samples along the ray from the center through the pixel, objects look like rushing towards the viewer.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `strength` - Part of the distance to the center covered by the blur, `0.2` is a moderate zoom
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::Constant] color is expected in `u8` range
+/// * `threading_policy` - Threads usage policy
+/// * `transfer_function` - Transfer function in linear colorspace
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and strength are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn zoom_blur_in_linear(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    center_x: f32,
+    center_y: f32,
+    strength: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    transfer_function: TransferFunction,
+) -> Result<(), BlurError> {
+    radial_blur_in_linear(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Zoom(strength),
+        edge_mode,
+        threading_policy,
+        transfer_function,
+    )
+}
+
+/// Performs spin blur on the image.
+///
+/// Averages samples along the arc around the center through the pixel, objects look like rotating around it.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `angle` - Angle of the arc in degrees
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] skips samples outside of the image
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and angle are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn spin_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    center_x: f32,
+    center_y: f32,
+    angle: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    radial_blur_dispatch(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Spin(angle.to_radians()),
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs spin blur on the 16-bit image.
+///
+/// Averages samples along the arc around the center through the pixel, objects look like rotating around it.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `angle` - Angle of the arc in degrees
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] skips samples outside of the image
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and angle are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn spin_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    center_x: f32,
+    center_y: f32,
+    angle: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    radial_blur_dispatch(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Spin(angle.to_radians()),
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs spin blur on the image in f32.
+///
+/// Averages samples along the arc around the center through the pixel, objects look like rotating around it.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `angle` - Angle of the arc in degrees
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] skips samples outside of the image
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and angle are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn spin_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    center_x: f32,
+    center_y: f32,
+    angle: f32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    radial_blur_dispatch(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Spin(angle.to_radians()),
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs spin blur on the image in linear colorspace.
+///
+/// Averages samples along the arc around the center through the pixel, objects look like rotating around it.
+/// Samples are taken with bilinear interpolation about one pixel apart, at most 64 per pixel.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `center_x` - Horizontal position of the center in pixels, may be outside of the image
+/// * `center_y` - Vertical position of the center in pixels, may be outside of the image
+/// * `angle` - Angle of the arc in degrees
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::Constant] color is expected in `u8` range
+/// * `threading_policy` - Threads usage policy
+/// * `transfer_function` - Transfer function in linear colorspace
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// or center and angle are not finite
+#[allow(clippy::too_many_arguments)]
+pub fn spin_blur_in_linear(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    center_x: f32,
+    center_y: f32,
+    angle: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    transfer_function: TransferFunction,
+) -> Result<(), BlurError> {
+    radial_blur_in_linear(
+        src,
+        dst,
+        center_x,
+        center_y,
+        RadialPath::Spin(angle.to_radians()),
+        edge_mode,
+        threading_policy,
+        transfer_function,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+
+    /// Bilinear sample of the reference with `edge_mode` applied to every corner
+    fn bilinear(image: &ReferenceImage, x: f64, y: f64, c: usize, edge_mode: EdgeMode) -> f64 {
+        let (fx, fy) = (x.floor(), y.floor());
+        let (wx, wy) = (x - fx, y - fy);
+        let (ix, iy) = (fx as i64, fy as i64);
+        let at = |x: i64, y: i64| image.sample(x, y, c, edge_mode).unwrap() as f64;
+        let top = at(ix, iy) * (1. - wx) + at(ix + 1, iy) * wx;
+        let bottom = at(ix, iy + 1) * (1. - wx) + at(ix + 1, iy + 1) * wx;
+        top * (1. - wy) + bottom * wy
+    }
+
+    /// Averages bilinear samples along the path of every pixel, computed in `f64` without stepping
+    fn reference_radial(
+        image: &ReferenceImage,
+        (center_x, center_y): (f64, f64),
+        path: RadialPath,
+        edge_mode: EdgeMode,
+    ) -> Vec<f64> {
+        let sample_edge = match edge_mode {
+            EdgeMode::KernelClip => EdgeMode::Clamp,
+            edge_mode => edge_mode,
+        };
+        let (max_x, max_y) = ((image.width - 1) as f64, (image.height - 1) as f64);
+        let mut dst = Vec::with_capacity(image.data.len());
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let (dx, dy) = (x as f64 - center_x, y as f64 - center_y);
+                let distance = dx.hypot(dy);
+                let (length, strength) = match path {
+                    RadialPath::Zoom(strength) => (distance * strength.abs() as f64, strength),
+                    RadialPath::Spin(angle) => (distance * angle.abs() as f64, angle),
+                };
+                let count = samples_count(length as f32);
+                let positions = (0..count)
+                    .map(|i| {
+                        let t = if count == 1 {
+                            0.
+                        } else {
+                            i as f64 / (count - 1) as f64 - 0.5
+                        };
+                        match path {
+                            RadialPath::Zoom(_) => {
+                                let scale = 1. + strength as f64 * t;
+                                (center_x + dx * scale, center_y + dy * scale)
+                            }
+                            RadialPath::Spin(_) => {
+                                let (sin, cos) = (strength as f64 * t).sin_cos();
+                                (
+                                    center_x + dx * cos - dy * sin,
+                                    center_y + dx * sin + dy * cos,
+                                )
+                            }
+                        }
+                    })
+                    .filter(|&(sx, sy)| {
+                        edge_mode != EdgeMode::KernelClip
+                            || (sx >= -1e-3
+                                && sy >= -1e-3
+                                && sx <= max_x + 1e-3
+                                && sy <= max_y + 1e-3)
+                    })
+                    .collect::<Vec<_>>();
+                for c in 0..image.channels {
+                    if positions.is_empty() {
+                        dst.push(image.data[(y * image.width + x) * image.channels + c] as f64);
+                        continue;
+                    }
+                    let sum: f64 = positions
+                        .iter()
+                        .map(|&(sx, sy)| bilinear(image, sx, sy, c, sample_edge))
+                        .sum();
+                    dst.push(sum / positions.len() as f64);
+                }
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn samples_count_is_bounded() {
+        assert_eq!(samples_count(0.), 1);
+        assert_eq!(samples_count(2.5), 4);
+        assert_eq!(samples_count(1e30), MAX_SAMPLES);
+        assert_eq!(samples_count(f32::INFINITY), MAX_SAMPLES);
+    }
+
+    #[test]
+    fn radial_blur_keeps_constant_image() {
+        let (width, height) = (23u32, 17u32);
+        let pixel = [77u8, 120, 200, 255];
+        let src = pixel.repeat(width as usize * height as usize);
+        for center in [(11., 8.), (-40., 90.), (1e30, -1e30)] {
+            for edge_mode in edge_modes(pixel.map(|v| v as f32))
+                .into_iter()
+                .chain([EdgeMode::KernelClip])
+            {
+                for (path, threading_policy) in [
+                    (RadialPath::Zoom(0.5), ThreadingPolicy::Single),
+                    (RadialPath::Spin(0.3), ThreadingPolicy::Fixed(3)),
+                ] {
+                    let mut dst = vec![0u8; src.len()];
+                    radial_blur_dispatch(
+                        &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels4),
+                        &mut BlurImageMut::borrow(
+                            &mut dst,
+                            width,
+                            height,
+                            FastBlurChannels::Channels4,
+                        ),
+                        center.0,
+                        center.1,
+                        path,
+                        edge_mode,
+                        AlphaMode::Premultiply,
+                        threading_policy,
+                    )
+                    .unwrap();
+                    assert_eq!(dst, src, "{path:?} center {center:?} {edge_mode:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zoom_blur_f32_far_center() {
+        let (width, height) = (4u32, 4u32);
+        let src: Vec<f32> = (0..16).map(|v| v as f32 / 15.).collect();
+        for edge_mode in edge_modes([0.5; 4])
+            .into_iter()
+            .chain([EdgeMode::KernelClip])
+        {
+            let mut dst = vec![0f32; src.len()];
+            zoom_blur_f32(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Plane),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Plane),
+                1e30,
+                -1e30,
+                0.5,
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            assert!(dst.iter().all(|v| v.is_finite()), "{edge_mode:?}");
+        }
+    }
+
+    #[test]
+    fn radial_blur_f32_matches_reference() {
+        let (width, height) = (21usize, 16usize);
+        let src = noise(width * height * 3, 256, 9)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 3,
+        };
+        for center in [(8.5f32, 6.), (-5., 20.)] {
+            for path in [RadialPath::Zoom(0.4), RadialPath::Spin(0.35)] {
+                for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.])
+                    .into_iter()
+                    .chain([EdgeMode::KernelClip])
+                {
+                    let mut dst = vec![0f32; src.len()];
+                    radial_blur_dispatch(
+                        &BlurImage::borrow(
+                            &src,
+                            width as u32,
+                            height as u32,
+                            FastBlurChannels::Channels3,
+                        ),
+                        &mut BlurImageMut::borrow(
+                            &mut dst,
+                            width as u32,
+                            height as u32,
+                            FastBlurChannels::Channels3,
+                        ),
+                        center.0,
+                        center.1,
+                        path,
+                        edge_mode,
+                        AlphaMode::Independent,
+                        ThreadingPolicy::Fixed(2),
+                    )
+                    .unwrap();
+                    let expected = reference_radial(
+                        &image,
+                        (center.0 as f64, center.1 as f64),
+                        path,
+                        edge_mode,
+                    )
+                    .iter()
+                    .map(|&v| v as f32)
+                    .collect::<Vec<f32>>();
+                    assert_close(
+                        &dst,
+                        &expected,
+                        1e-3,
+                        &format!("{path:?} center {center:?} {edge_mode:?}"),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -51,7 +51,59 @@ pub fn fast_gaussian_next_vertical_pass_sse_f16<
     start: u32,
     end: u32,
 ) {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    unsafe {
+        fast_gaussian_next_vertical_pass_sse_f16_def::<T, CHANNELS_COUNT, EDGE_MODE>(
+            undefined_slice,
+            stride,
+            width,
+            height,
+            radius,
+            start,
+            end,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1,f16c")]
+unsafe fn fast_gaussian_next_vertical_pass_sse_f16_def<
+    T,
+    const CHANNELS_COUNT: usize,
+    const EDGE_MODE: usize,
+>(
+    undefined_slice: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    start: u32,
+    end: u32,
+) {
+    fast_gaussian_next_vertical_pass_sse_f16_impl::<T, CHANNELS_COUNT, EDGE_MODE>(
+        undefined_slice,
+        stride,
+        width,
+        height,
+        radius,
+        start,
+        end,
+    );
+}
+
+#[inline(always)]
+fn fast_gaussian_next_vertical_pass_sse_f16_impl<
+    T,
+    const CHANNELS_COUNT: usize,
+    const EDGE_MODE: usize,
+>(
+    undefined_slice: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    start: u32,
+    end: u32,
+) {
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let bytes: &UnsafeSlice<'_, f16> = unsafe { std::mem::transmute(undefined_slice) };
     let mut buffer: [[f32; 4]; 1024] = [[0.; 4]; 1024];
 
@@ -152,7 +204,59 @@ pub fn fast_gaussian_next_horizontal_pass_sse_f16<
     start: u32,
     end: u32,
 ) {
-    let edge_mode: EdgeMode = EDGE_MODE.into();
+    unsafe {
+        fast_gaussian_next_horizontal_pass_sse_f16_def::<T, CHANNELS_COUNT, EDGE_MODE>(
+            undefined_slice,
+            stride,
+            width,
+            height,
+            radius,
+            start,
+            end,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1,f16c")]
+unsafe fn fast_gaussian_next_horizontal_pass_sse_f16_def<
+    T,
+    const CHANNELS_COUNT: usize,
+    const EDGE_MODE: usize,
+>(
+    undefined_slice: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    start: u32,
+    end: u32,
+) {
+    fast_gaussian_next_horizontal_pass_sse_f16_impl::<T, CHANNELS_COUNT, EDGE_MODE>(
+        undefined_slice,
+        stride,
+        width,
+        height,
+        radius,
+        start,
+        end,
+    );
+}
+
+#[inline(always)]
+fn fast_gaussian_next_horizontal_pass_sse_f16_impl<
+    T,
+    const CHANNELS_COUNT: usize,
+    const EDGE_MODE: usize,
+>(
+    undefined_slice: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    start: u32,
+    end: u32,
+) {
+    let edge_mode = EdgeMode::from_const(EDGE_MODE);
     let bytes: &UnsafeSlice<'_, f16> = unsafe { std::mem::transmute(undefined_slice) };
     let mut buffer: [[f32; 4]; 1024] = [[0.; 4]; 1024];
 
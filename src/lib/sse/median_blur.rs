@@ -0,0 +1,160 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::median_blur::{median_blur_impl, MedianHistogramSegment, MedianSource};
+use crate::median_network::{median_network_rows, MedianNetworkLane};
+use crate::unsafe_slice::UnsafeSlice;
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+pub struct MedianSegmentSse;
+
+impl MedianHistogramSegment for MedianSegmentSse {
+    #[inline(always)]
+    unsafe fn add(dst: &mut [u32; 16], src: &[u16; 16]) {
+        let (lo, hi) = load_u16_widen(src);
+        let dst_ptr = dst.as_mut_ptr() as *mut __m128i;
+        for (i, v) in lo.into_iter().chain(hi).enumerate() {
+            let acc = _mm_loadu_si128(dst_ptr.add(i));
+            _mm_storeu_si128(dst_ptr.add(i), _mm_add_epi32(acc, v));
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn sub(dst: &mut [u32; 16], src: &[u16; 16]) {
+        let (lo, hi) = load_u16_widen(src);
+        let dst_ptr = dst.as_mut_ptr() as *mut __m128i;
+        for (i, v) in lo.into_iter().chain(hi).enumerate() {
+            let acc = _mm_loadu_si128(dst_ptr.add(i));
+            _mm_storeu_si128(dst_ptr.add(i), _mm_sub_epi32(acc, v));
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn load_u16_widen(src: &[u16; 16]) -> ([__m128i; 2], [__m128i; 2]) {
+    let zeros = _mm_setzero_si128();
+    let src_ptr = src.as_ptr() as *const __m128i;
+    let lo = _mm_loadu_si128(src_ptr);
+    let hi = _mm_loadu_si128(src_ptr.add(1));
+    (
+        [_mm_cvtepu16_epi32(lo), _mm_unpackhi_epi16(lo, zeros)],
+        [_mm_cvtepu16_epi32(hi), _mm_unpackhi_epi16(hi, zeros)],
+    )
+}
+
+impl MedianNetworkLane for __m128i {
+    #[inline(always)]
+    unsafe fn load(ptr: *const u8) -> Self {
+        _mm_loadu_si128(ptr as *const __m128i)
+    }
+
+    #[inline(always)]
+    unsafe fn store(self, ptr: *mut u8) {
+        _mm_storeu_si128(ptr as *mut __m128i, self)
+    }
+
+    #[inline(always)]
+    unsafe fn min(self, other: Self) -> Self {
+        _mm_min_epu8(self, other)
+    }
+
+    #[inline(always)]
+    unsafe fn max(self, other: Self) -> Self {
+        _mm_max_epu8(self, other)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn median_blur_sse<const CHANNELS_CONFIGURATION: usize>(
+    src: &MedianSource<u8>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: usize,
+    width: usize,
+    height: usize,
+    radius: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    unsafe {
+        median_blur_sse_def::<CHANNELS_CONFIGURATION>(
+            src, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn median_blur_sse_def<const CHANNELS_CONFIGURATION: usize>(
+    src: &MedianSource<u8>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: usize,
+    width: usize,
+    height: usize,
+    radius: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    median_blur_impl::<CHANNELS_CONFIGURATION, MedianSegmentSse>(
+        src, unsafe_dst, dst_stride, width, height, radius, start_y, end_y,
+    );
+}
+
+/// 3x3 and 5x5 median through exchange networks, source must be padded by `RADIUS`
+#[allow(clippy::too_many_arguments)]
+pub fn median_blur_network_sse<const CHANNELS_CONFIGURATION: usize, const RADIUS: usize>(
+    src: &MedianSource<u8>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: usize,
+    width: usize,
+    _: usize,
+    _: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    unsafe {
+        median_blur_network_sse_def::<CHANNELS_CONFIGURATION, RADIUS>(
+            src, unsafe_dst, dst_stride, width, start_y, end_y,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+unsafe fn median_blur_network_sse_def<const CHANNELS_CONFIGURATION: usize, const RADIUS: usize>(
+    src: &MedianSource<u8>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: usize,
+    width: usize,
+    start_y: usize,
+    end_y: usize,
+) {
+    median_network_rows::<__m128i, CHANNELS_CONFIGURATION, RADIUS>(
+        src, unsafe_dst, dst_stride, width, start_y, end_y,
+    );
+}
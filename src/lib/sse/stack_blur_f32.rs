@@ -33,6 +33,7 @@ use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn stack_blur_pass_sse_f<const COMPONENTS: usize>(
     pixels: &UnsafeSlice<f32>,
     stride: u32,
@@ -42,6 +43,56 @@ pub fn stack_blur_pass_sse_f<const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+) {
+    unsafe {
+        stack_blur_pass_sse_f_def::<COMPONENTS>(
+            pixels,
+            stride,
+            width,
+            height,
+            radius,
+            pass,
+            thread,
+            total_threads,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn stack_blur_pass_sse_f_def<const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<f32>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    pass: StackBlurPass,
+    thread: usize,
+    total_threads: usize,
+) {
+    stack_blur_pass_sse_f_impl::<COMPONENTS>(
+        pixels,
+        stride,
+        width,
+        height,
+        radius,
+        pass,
+        thread,
+        total_threads,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn stack_blur_pass_sse_f_impl<const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<f32>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    pass: StackBlurPass,
+    thread: usize,
+    total_threads: usize,
 ) {
     unsafe {
         let div = ((radius * 2) + 1) as usize;
@@ -63,7 +114,7 @@ pub fn stack_blur_pass_sse_f<const COMPONENTS: usize>(
         let mut src_ptr;
         let mut dst_ptr;
 
-        if pass == StackBlurPass::HORIZONTAL {
+        if pass == StackBlurPass::Horizontal {
             let min_y = thread * height as usize / total_threads;
             let max_y = (thread + 1) * height as usize / total_threads;
 
@@ -149,7 +200,7 @@ pub fn stack_blur_pass_sse_f<const COMPONENTS: usize>(
                     sum_in = _mm_sub_ps(sum_in, stack_val);
                 }
             }
-        } else if pass == StackBlurPass::VERTICAL {
+        } else if pass == StackBlurPass::Vertical {
             let min_x = thread * width as usize / total_threads;
             let max_x = (thread + 1) * width as usize / total_threads;
 
@@ -26,15 +26,16 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::mul_table::{MUL_TABLE_STACK_BLUR, SHR_TABLE_STACK_BLUR};
+use crate::sse::_mm_mul_epi64;
 use crate::sse::utils::{_mm_packus_epi64, load_u8_s32_fast, store_u8_s32};
 use crate::stack_blur::StackBlurPass;
 use crate::unsafe_slice::UnsafeSlice;
-use erydanos::_mm_mul_epi64;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn stack_blur_pass_sse<const COMPONENTS: usize>(
     pixels: &UnsafeSlice<u8>,
     stride: u32,
@@ -44,6 +45,56 @@ pub fn stack_blur_pass_sse<const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+) {
+    unsafe {
+        stack_blur_pass_sse_def::<COMPONENTS>(
+            pixels,
+            stride,
+            width,
+            height,
+            radius,
+            pass,
+            thread,
+            total_threads,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn stack_blur_pass_sse_def<const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<u8>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    pass: StackBlurPass,
+    thread: usize,
+    total_threads: usize,
+) {
+    stack_blur_pass_sse_impl::<COMPONENTS>(
+        pixels,
+        stride,
+        width,
+        height,
+        radius,
+        pass,
+        thread,
+        total_threads,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn stack_blur_pass_sse_impl<const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<u8>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    pass: StackBlurPass,
+    thread: usize,
+    total_threads: usize,
 ) {
     unsafe {
         let div = ((radius * 2) + 1) as usize;
@@ -65,7 +116,7 @@ pub fn stack_blur_pass_sse<const COMPONENTS: usize>(
         let mut src_ptr;
         let mut dst_ptr;
 
-        if pass == StackBlurPass::HORIZONTAL {
+        if pass == StackBlurPass::Horizontal {
             let min_y = thread * height as usize / total_threads;
             let max_y = (thread + 1) * height as usize / total_threads;
 
@@ -158,7 +209,7 @@ pub fn stack_blur_pass_sse<const COMPONENTS: usize>(
                     sum_in = _mm_sub_epi32(sum_in, stack_val);
                 }
             }
-        } else if pass == StackBlurPass::VERTICAL {
+        } else if pass == StackBlurPass::Vertical {
             let min_x = thread * width as usize / total_threads;
             let max_x = (thread + 1) * width as usize / total_threads;
 
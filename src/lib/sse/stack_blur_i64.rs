@@ -25,22 +25,103 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mul_table::{MUL_TABLE_STACK_BLUR, SHR_TABLE_STACK_BLUR};
-use crate::sse::utils::{_mm_packus_epi64, store_u8_s32};
+use crate::mul_table::StackBlurScale;
+use crate::sse::_mm_mul_epi64;
+use crate::sse::utils::{_mm_packus_epi64, store_u16_s32, store_u8_s32};
 use crate::sse::{
     __mm128ix2, _mm_add_epi64x2, _mm_load_epi64x2, _mm_mul_n_epi64x2, _mm_set1_epi64x2,
-    _mm_store_epi64x2, _mm_sub_epi64x2, load_u8_s64x2_fast,
+    _mm_store_epi64x2, _mm_sub_epi64x2, load_u16_s64x2_fast, load_u8_s64x2_fast,
 };
 use crate::stack_blur::StackBlurPass;
 use crate::unsafe_slice::UnsafeSlice;
-use erydanos::_mm_mul_epi64;
 #[cfg(target_arch = "x86")]
 use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
-pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
-    pixels: &UnsafeSlice<u8>,
+/// Storage types that i64 stack blur loads into and stores from 64-bit lanes
+pub trait SseStackBlurI64Storage: Copy + StackBlurScale {
+    unsafe fn load_s64x2<const COMPONENTS: usize>(ptr: *const Self) -> __mm128ix2;
+    unsafe fn store_s32<const COMPONENTS: usize>(ptr: *mut Self, regi: __m128i);
+}
+
+impl SseStackBlurI64Storage for u8 {
+    #[inline(always)]
+    unsafe fn load_s64x2<const COMPONENTS: usize>(ptr: *const Self) -> __mm128ix2 {
+        load_u8_s64x2_fast::<COMPONENTS>(ptr)
+    }
+
+    #[inline(always)]
+    unsafe fn store_s32<const COMPONENTS: usize>(ptr: *mut Self, regi: __m128i) {
+        store_u8_s32::<COMPONENTS>(ptr, regi)
+    }
+}
+
+impl SseStackBlurI64Storage for u16 {
+    #[inline(always)]
+    unsafe fn load_s64x2<const COMPONENTS: usize>(ptr: *const Self) -> __mm128ix2 {
+        load_u16_s64x2_fast::<COMPONENTS>(ptr)
+    }
+
+    #[inline(always)]
+    unsafe fn store_s32<const COMPONENTS: usize>(ptr: *mut Self, regi: __m128i) {
+        store_u16_s32::<COMPONENTS>(ptr, regi)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn stack_blur_pass_sse_i64<T: SseStackBlurI64Storage, const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    pass: StackBlurPass,
+    thread: usize,
+    total_threads: usize,
+) {
+    unsafe {
+        stack_blur_pass_sse_i64_def::<T, COMPONENTS>(
+            pixels,
+            stride,
+            width,
+            height,
+            radius,
+            pass,
+            thread,
+            total_threads,
+        );
+    }
+}
+
+#[target_feature(enable = "sse4.1")]
+#[allow(clippy::too_many_arguments)]
+unsafe fn stack_blur_pass_sse_i64_def<T: SseStackBlurI64Storage, const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    pass: StackBlurPass,
+    thread: usize,
+    total_threads: usize,
+) {
+    stack_blur_pass_sse_i64_impl::<T, COMPONENTS>(
+        pixels,
+        stride,
+        width,
+        height,
+        radius,
+        pass,
+        thread,
+        total_threads,
+    );
+}
+
+#[inline(always)]
+#[allow(clippy::too_many_arguments)]
+fn stack_blur_pass_sse_i64_impl<T: SseStackBlurI64Storage, const COMPONENTS: usize>(
+    pixels: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -63,13 +144,14 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
         let wm = width - 1;
         let hm = height - 1;
         let div = (radius * 2) + 1;
-        let mul_sum = _mm_set1_epi64x(MUL_TABLE_STACK_BLUR[radius as usize] as i64);
-        let shr_sum = _mm_setr_epi32(SHR_TABLE_STACK_BLUR[radius as usize], 0i32, 0i32, 0i32);
+        let (mul_sum, shr_sum) = T::stack_blur_mul_shr(radius);
+        let mul_sum = _mm_set1_epi64x(mul_sum);
+        let shr_sum = _mm_setr_epi32(shr_sum, 0i32, 0i32, 0i32);
 
         let mut src_ptr;
         let mut dst_ptr;
 
-        if pass == StackBlurPass::HORIZONTAL {
+        if pass == StackBlurPass::Horizontal {
             let min_y = thread * height as usize / total_threads;
             let max_y = (thread + 1) * height as usize / total_threads;
 
@@ -80,8 +162,8 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
 
                 src_ptr = stride as usize * y; // start of line (0,y)
 
-                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
-                let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
 
                 for i in 0..=radius {
                     let stack_value = stacks.as_mut_ptr().add(i as usize * 4);
@@ -95,8 +177,8 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
                         src_ptr += COMPONENTS;
                     }
                     let stack_ptr = stacks.as_mut_ptr().add((i + radius) as usize * 4);
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     _mm_store_epi64x2(stack_ptr, src_pixel);
                     sums = _mm_add_epi64x2(
                         sums,
@@ -115,11 +197,11 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
                 src_ptr = COMPONENTS * xp as usize + y * stride as usize;
                 dst_ptr = y * stride as usize;
                 for _ in 0..width {
-                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut u8;
+                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut T;
                     let blurred_hi = _mm_srl_epi64(_mm_mul_epi64(sums.1, mul_sum), shr_sum);
                     let blurred_lo = _mm_srl_epi64(_mm_mul_epi64(sums.0, mul_sum), shr_sum);
                     let blurred = _mm_packus_epi64(blurred_lo, blurred_hi);
-                    store_u8_s32::<COMPONENTS>(store_ld, blurred);
+                    T::store_s32::<COMPONENTS>(store_ld, blurred);
                     dst_ptr += COMPONENTS;
 
                     sums = _mm_sub_epi64x2(sums, sum_out);
@@ -139,8 +221,8 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
                         xp += 1;
                     }
 
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr);
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     _mm_store_epi64x2(stack, src_pixel);
 
                     sum_in = _mm_add_epi64x2(sum_in, src_pixel);
@@ -157,7 +239,7 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
                     sum_in = _mm_sub_epi64x2(sum_in, stack_val);
                 }
             }
-        } else if pass == StackBlurPass::VERTICAL {
+        } else if pass == StackBlurPass::Vertical {
             let min_x = thread * width as usize / total_threads;
             let max_x = (thread + 1) * width as usize / total_threads;
 
@@ -168,9 +250,9 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
 
                 src_ptr = COMPONENTS * x; // x,0
 
-                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
+                let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
 
-                let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
 
                 for i in 0..=radius {
                     let stack_ptr = stacks.as_mut_ptr().add(i as usize * 4);
@@ -185,8 +267,8 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
                     }
 
                     let stack_ptr = stacks.as_mut_ptr().add((i + radius) as usize * 4);
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const i32;
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     _mm_store_epi64x2(stack_ptr, src_pixel);
                     sums = _mm_add_epi64x2(
                         sums,
@@ -204,11 +286,11 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
                 src_ptr = COMPONENTS * x + yp as usize * stride as usize;
                 dst_ptr = COMPONENTS * x;
                 for _ in 0..height {
-                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut u8;
+                    let store_ld = pixels.slice.as_ptr().add(dst_ptr) as *mut T;
                     let blurred_hi = _mm_srl_epi64(_mm_mul_epi64(sums.1, mul_sum), shr_sum);
                     let blurred_lo = _mm_srl_epi64(_mm_mul_epi64(sums.0, mul_sum), shr_sum);
                     let blurred = _mm_packus_epi64(blurred_lo, blurred_hi);
-                    store_u8_s32::<COMPONENTS>(store_ld, blurred);
+                    T::store_s32::<COMPONENTS>(store_ld, blurred);
 
                     dst_ptr += stride as usize;
 
@@ -228,8 +310,8 @@ pub fn stack_blur_pass_sse_i64<const COMPONENTS: usize>(
                         yp += 1;
                     }
 
-                    let src_ld = pixels.slice.as_ptr().add(src_ptr);
-                    let src_pixel = load_u8_s64x2_fast::<COMPONENTS>(src_ld as *const u8);
+                    let src_ld = pixels.slice.as_ptr().add(src_ptr) as *const T;
+                    let src_pixel = T::load_s64x2::<COMPONENTS>(src_ld);
                     _mm_store_epi64x2(stack_ptr, src_pixel);
 
                     sum_in = _mm_add_epi64x2(sum_in, src_pixel);
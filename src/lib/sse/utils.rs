@@ -30,8 +30,6 @@ use std::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
-use erydanos::{_mm_extract_epi64x, _mm_mul_epi64};
-#[cfg(target_feature = "f16c")]
 use half::f16;
 
 #[allow(non_camel_case_types)]
@@ -77,8 +75,14 @@ pub(crate) unsafe fn _mm_load_epi64x2(ptr: *const i64) -> __mm128ix2 {
 #[inline(always)]
 pub(crate) unsafe fn load_u8_s64x2_fast<const CHANNELS_COUNT: usize>(ptr: *const u8) -> __mm128ix2 {
     let u_first = i64::from_le_bytes([ptr.read_unaligned(), 0, 0, 0, 0, 0, 0, 0]);
-    let u_second = i64::from_le_bytes([ptr.add(1).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]);
-    let u_third = i64::from_le_bytes([ptr.add(2).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]);
+    let u_second = match CHANNELS_COUNT {
+        2..=4 => i64::from_le_bytes([ptr.add(1).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]),
+        _ => 0,
+    };
+    let u_third = match CHANNELS_COUNT {
+        3 | 4 => i64::from_le_bytes([ptr.add(2).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]),
+        _ => 0,
+    };
     let u_fourth = match CHANNELS_COUNT {
         4 => i64::from_le_bytes([ptr.add(3).read_unaligned(), 0, 0, 0, 0, 0, 0, 0]),
         _ => 0,
@@ -89,7 +93,30 @@ pub(crate) unsafe fn load_u8_s64x2_fast<const CHANNELS_COUNT: usize>(ptr: *const
     )
 }
 
-#[inline]
+#[inline(always)]
+pub(crate) unsafe fn load_u16_s64x2_fast<const CHANNELS_COUNT: usize>(
+    ptr: *const u16,
+) -> __mm128ix2 {
+    let u_first = ptr.read_unaligned() as i64;
+    let u_second = match CHANNELS_COUNT {
+        2..=4 => ptr.add(1).read_unaligned() as i64,
+        _ => 0,
+    };
+    let u_third = match CHANNELS_COUNT {
+        3 | 4 => ptr.add(2).read_unaligned() as i64,
+        _ => 0,
+    };
+    let u_fourth = match CHANNELS_COUNT {
+        4 => ptr.add(3).read_unaligned() as i64,
+        _ => 0,
+    };
+    __mm128ix2(
+        _mm_set_epi64x(u_second, u_first),
+        _mm_set_epi64x(u_fourth, u_third),
+    )
+}
+
+#[inline(always)]
 pub(crate) unsafe fn load_f32<const CHANNELS_COUNT: usize>(ptr: *const f32) -> __m128 {
     if CHANNELS_COUNT == 4 {
         return _mm_loadu_ps(ptr);
@@ -108,20 +135,26 @@ pub(crate) unsafe fn load_f32<const CHANNELS_COUNT: usize>(ptr: *const f32) -> _
             0f32,
         );
     }
-    return _mm_setr_ps(ptr.read_unaligned(), 0f32, 0f32, 0f32);
+    _mm_setr_ps(ptr.read_unaligned(), 0f32, 0f32, 0f32)
 }
 
-#[inline]
+#[inline(always)]
 pub(crate) unsafe fn load_u8_s32_fast<const CHANNELS_COUNT: usize>(ptr: *const u8) -> __m128i {
     let u_first = u32::from_le_bytes([ptr.read_unaligned(), 0, 0, 0]);
-    let u_second = u32::from_le_bytes([ptr.add(1).read_unaligned(), 0, 0, 0]);
-    let u_third = u32::from_le_bytes([ptr.add(2).read_unaligned(), 0, 0, 0]);
+    let u_second = match CHANNELS_COUNT {
+        2..=4 => u32::from_le_bytes([ptr.add(1).read_unaligned(), 0, 0, 0]),
+        _ => 0,
+    };
+    let u_third = match CHANNELS_COUNT {
+        3 | 4 => u32::from_le_bytes([ptr.add(2).read_unaligned(), 0, 0, 0]),
+        _ => 0,
+    };
     let u_fourth = match CHANNELS_COUNT {
         4 => u32::from_le_bytes([ptr.add(3).read_unaligned(), 0, 0, 0]),
         _ => 0,
     };
     let store: [u32; 4] = [u_first, u_second, u_third, u_fourth];
-    return _mm_loadu_si128(store.as_ptr() as *const __m128i);
+    _mm_loadu_si128(store.as_ptr() as *const __m128i)
 }
 
 #[inline(always)]
@@ -146,25 +179,24 @@ pub const fn shuffle(z: u32, y: u32, x: u32, w: u32) -> i32 {
     ((z << 6) | (y << 4) | (x << 2) | w) as i32
 }
 
-#[inline]
+#[inline(always)]
 pub(crate) unsafe fn _mm_packus_epi64(a: __m128i, b: __m128i) -> __m128i {
     const SHUFFLE_MASK: i32 = shuffle(3, 1, 2, 0);
     let a = _mm_shuffle_epi32::<SHUFFLE_MASK>(a);
     let b1 = _mm_shuffle_epi32::<SHUFFLE_MASK>(b);
-    let moved = _mm_castps_si128(_mm_movelh_ps(_mm_castsi128_ps(a), _mm_castsi128_ps(b1)));
-    moved
+    _mm_castps_si128(_mm_movelh_ps(_mm_castsi128_ps(a), _mm_castsi128_ps(b1)))
 }
 
 #[inline(always)]
 pub(crate) unsafe fn load_u8_f32_fast<const CHANNELS_COUNT: usize>(ptr: *const u8) -> __m128 {
     let vl = load_u8_s32_fast::<CHANNELS_COUNT>(ptr);
-    return _mm_cvtepi32_ps(vl);
+    _mm_cvtepi32_ps(vl)
 }
 
 #[inline(always)]
 pub(crate) unsafe fn load_u8_u32_one(ptr: *const u8) -> __m128i {
     let u_first = u32::from_le_bytes([ptr.read_unaligned(), 0, 0, 0]);
-    return _mm_set1_epi32(u_first as i32);
+    _mm_set1_epi32(u_first as i32)
 }
 
 #[inline(always)]
@@ -185,6 +217,23 @@ pub(crate) unsafe fn store_f32<const CHANNELS_COUNT: usize>(dst_ptr: *mut f32, r
     }
 }
 
+/// Stores s32 up to x4 as u16 up to x4 based on channels count
+#[inline(always)]
+pub(crate) unsafe fn store_u16_s32<const CHANNELS_COUNT: usize>(dst_ptr: *mut u16, regi: __m128i) {
+    let v16 = _mm_packus_epi32(regi, regi);
+    let pixel_s64 = _mm_extract_epi64x::<0>(v16);
+    if CHANNELS_COUNT == 4 {
+        (dst_ptr as *mut i64).write_unaligned(pixel_s64);
+    } else if CHANNELS_COUNT == 3 {
+        (dst_ptr as *mut i32).write_unaligned(pixel_s64 as i32);
+        dst_ptr.add(2).write_unaligned((pixel_s64 >> 32) as u16);
+    } else if CHANNELS_COUNT == 2 {
+        (dst_ptr as *mut i32).write_unaligned(pixel_s64 as i32);
+    } else {
+        dst_ptr.write_unaligned(pixel_s64 as u16);
+    }
+}
+
 /// Stores u32 up to x4 as u8 up to x4 based on channels count
 #[inline(always)]
 pub(crate) unsafe fn store_u8_s32<const CHANNELS_COUNT: usize>(dst_ptr: *mut u8, regi: __m128i) {
@@ -318,8 +367,7 @@ pub unsafe fn _mm_broadcast_fourth(item: __m128) -> __m128 {
     _mm_shuffle_ps::<FLAG>(item, item)
 }
 
-#[cfg(target_feature = "f16c")]
-#[inline]
+#[inline(always)]
 pub(crate) unsafe fn load_f32_f16<const CHANNELS_COUNT: usize>(ptr: *const f16) -> __m128 {
     if CHANNELS_COUNT == 4 {
         let in_regi = _mm_loadu_si64(ptr as *const u8);
@@ -353,10 +401,9 @@ pub(crate) unsafe fn load_f32_f16<const CHANNELS_COUNT: usize>(ptr: *const f16)
     }
     let casted_ptr = ptr as *const i16;
     let in_regi = _mm_setr_epi16(casted_ptr.read_unaligned(), 0, 0, 0, 0, 0, 0, 0);
-    return _mm_cvtph_ps(in_regi);
+    _mm_cvtph_ps(in_regi)
 }
 
-#[cfg(target_feature = "f16c")]
 #[inline(always)]
 pub(crate) unsafe fn store_f32_f16<const CHANNELS_COUNT: usize>(
     dst_ptr: *mut f16,
@@ -385,3 +432,50 @@ pub(crate) unsafe fn store_f32_f16<const CHANNELS_COUNT: usize>(
         casted_ptr.write_unaligned(item0);
     }
 }
+
+#[inline(always)]
+/// Multiplies lanes as signed 64-bit integers, keeping low 64 bits of the product
+pub(crate) unsafe fn _mm_mul_epi64(ab: __m128i, cd: __m128i) -> __m128i {
+    let ac = _mm_mul_epu32(ab, cd);
+    let b = _mm_srli_epi64::<32>(ab);
+    let bc = _mm_mul_epu32(b, cd);
+    let d = _mm_srli_epi64::<32>(cd);
+    let ad = _mm_mul_epu32(ab, d);
+    let high = _mm_slli_epi64::<32>(_mm_add_epi64(bc, ad));
+    _mm_add_epi64(high, ac)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn _mm_extract_epi64x<const IMM: i32>(d: __m128i) -> i64 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if IMM == 0 {
+            _mm_cvtsi128_si64(d)
+        } else {
+            _mm_extract_epi64::<IMM>(d)
+        }
+    }
+    #[cfg(target_arch = "x86")]
+    {
+        let (low, high);
+        if IMM == 0 {
+            low = _mm_cvtsi128_si32(d);
+            high = _mm_cvtsi128_si32(_mm_srli_si128::<4>(d));
+        } else {
+            low = _mm_cvtsi128_si32(_mm_srli_si128::<8>(d));
+            high = _mm_cvtsi128_si32(_mm_srli_si128::<12>(d));
+        }
+        ((high as i64) << 32) | (low as u32 as i64)
+    }
+}
+
+#[inline(always)]
+/// Computes `b*c + a`, fused when `FMA` is set.
+/// Callers selecting `FMA = true` must be compiled with `fma` enabled
+pub(crate) unsafe fn _mm_prefer_fma_ps<const FMA: bool>(a: __m128, b: __m128, c: __m128) -> __m128 {
+    if FMA {
+        _mm_fmadd_ps(b, c, a)
+    } else {
+        _mm_add_ps(_mm_mul_ps(b, c), a)
+    }
+}
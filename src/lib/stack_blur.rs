@@ -25,16 +25,18 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mul_table::{MUL_TABLE_STACK_BLUR, SHR_TABLE_STACK_BLUR};
+use crate::alpha_mode::filter_alpha_mode;
+use crate::blur_error::check_radius;
+use crate::edge_padding::filter_edge_padded;
+use crate::mul_table::StackBlurScale;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::neon::{stack_blur_pass_neon_i32, stack_blur_pass_neon_i64};
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::sse::{stack_blur_pass_sse, stack_blur_pass_sse_i64};
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{FastBlurChannels, ThreadingPolicy};
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
 use num_traits::{AsPrimitive, FromPrimitive};
 use std::ops::AddAssign;
 
@@ -64,8 +66,8 @@ where
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub(crate) enum StackBlurPass {
-    HORIZONTAL,
-    VERTICAL,
+    Horizontal,
+    Vertical,
 }
 
 ///
@@ -74,7 +76,8 @@ pub(crate) enum StackBlurPass {
 /// `T` - buffer type u8, u16 etc, this method expected only integral types
 /// `J` - accumulator type, i32, i64
 /// `I` - intermediate multiplication type, when sum will be adopting into higher it may overflow, use this parameter to control overflowing
-fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
     pixels: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
@@ -93,7 +96,7 @@ fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
         + std::ops::SubAssign
         + AsPrimitive<T>
         + AsPrimitive<I>,
-    T: Copy + AsPrimitive<J> + FromPrimitive,
+    T: Copy + AsPrimitive<J> + FromPrimitive + StackBlurScale,
     I: Copy
         + AsPrimitive<T>
         + FromPrimitive
@@ -127,13 +130,14 @@ fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
     let wm = width - 1;
     let hm = height - 1;
     let div = (radius * 2) + 1;
-    let mul_sum = I::from_i32(MUL_TABLE_STACK_BLUR[radius as usize]).unwrap();
-    let shr_sum = I::from_i32(SHR_TABLE_STACK_BLUR[radius as usize]).unwrap();
+    let (mul_sum, shr_sum) = T::stack_blur_mul_shr(radius);
+    let mul_sum = I::from_i64(mul_sum).unwrap();
+    let shr_sum = I::from_i32(shr_sum).unwrap();
 
     let mut src_ptr;
     let mut dst_ptr;
 
-    if pass == StackBlurPass::HORIZONTAL {
+    if pass == StackBlurPass::Horizontal {
         let min_y = thread * height as usize / total_threads;
         let max_y = (thread + 1) * height as usize / total_threads;
 
@@ -403,7 +407,7 @@ fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
                 }
             }
         }
-    } else if pass == StackBlurPass::VERTICAL {
+    } else if pass == StackBlurPass::Vertical {
         let min_x = thread * width as usize / total_threads;
         let max_x = (thread + 1) * width as usize / total_threads;
 
@@ -675,6 +679,7 @@ fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn stack_blur_worker_horizontal(
     slice: &UnsafeSlice<u8>,
     stride: u32,
@@ -702,12 +707,30 @@ fn stack_blur_worker_horizontal(
                 stack_blur_pass::<u8, i32, i64, 1>
             };
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
+                thread,
+                thread_count,
+            );
+        }
+        FastBlurChannels::Channels2 => {
+            let _dispatcher: fn(&UnsafeSlice<u8>, u32, u32, u32, u32, StackBlurPass, usize, usize) =
+                if radius < BASE_RADIUS_I64_CUTOFF {
+                    stack_blur_pass::<u8, i64, i64, 2>
+                } else {
+                    stack_blur_pass::<u8, i32, i64, 2>
+                };
+            _dispatcher(
+                slice,
+                stride,
+                width,
+                height,
+                radius,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -732,33 +755,31 @@ fn stack_blur_worker_horizontal(
                 {
                     _dispatcher = stack_blur_pass_neon_i32::<3>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse::<3>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse::<3>;
+                    }
                 }
             } else {
                 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
                 {
-                    _dispatcher = stack_blur_pass_neon_i64::<3>;
+                    _dispatcher = stack_blur_pass_neon_i64::<u8, 3>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse_i64::<3>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse_i64::<u8, 3>;
+                    }
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -783,33 +804,31 @@ fn stack_blur_worker_horizontal(
                 {
                     _dispatcher = stack_blur_pass_neon_i32::<4>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse::<4>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse::<4>;
+                    }
                 }
             } else {
                 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
                 {
-                    _dispatcher = stack_blur_pass_neon_i64::<4>;
+                    _dispatcher = stack_blur_pass_neon_i64::<u8, 4>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse_i64::<4>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse_i64::<u8, 4>;
+                    }
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -817,6 +836,7 @@ fn stack_blur_worker_horizontal(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn stack_blur_worker_vertical(
     slice: &UnsafeSlice<u8>,
     stride: u32,
@@ -844,12 +864,30 @@ fn stack_blur_worker_vertical(
                 stack_blur_pass::<u8, i32, i64, 1>
             };
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
+                thread,
+                thread_count,
+            );
+        }
+        FastBlurChannels::Channels2 => {
+            let _dispatcher: fn(&UnsafeSlice<u8>, u32, u32, u32, u32, StackBlurPass, usize, usize) =
+                if radius < BASE_RADIUS_I64_CUTOFF {
+                    stack_blur_pass::<u8, i64, i64, 2>
+                } else {
+                    stack_blur_pass::<u8, i32, i64, 2>
+                };
+            _dispatcher(
+                slice,
+                stride,
+                width,
+                height,
+                radius,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -874,33 +912,31 @@ fn stack_blur_worker_vertical(
                 {
                     _dispatcher = stack_blur_pass_neon_i32::<3>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse::<3>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse::<3>;
+                    }
                 }
             } else {
                 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
                 {
-                    _dispatcher = stack_blur_pass_neon_i64::<3>;
+                    _dispatcher = stack_blur_pass_neon_i64::<u8, 3>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse_i64::<3>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse_i64::<u8, 3>;
+                    }
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -925,33 +961,31 @@ fn stack_blur_worker_vertical(
                 {
                     _dispatcher = stack_blur_pass_neon_i32::<4>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse::<4>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse::<4>;
+                    }
                 }
             } else {
                 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
                 {
-                    _dispatcher = stack_blur_pass_neon_i64::<4>;
+                    _dispatcher = stack_blur_pass_neon_i64::<u8, 4>;
                 }
-                #[cfg(all(
-                    any(target_arch = "x86_64", target_arch = "x86"),
-                    target_feature = "sse4.1"
-                ))]
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
                 {
-                    _dispatcher = stack_blur_pass_sse_i64::<4>;
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse_i64::<u8, 4>;
+                    }
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -966,26 +1000,83 @@ fn stack_blur_worker_vertical(
 /// after it to avoid overflowing fallback to i64 accumulator will be used with some computational slowdown with factor ~1.5-2
 ///
 /// # Arguments
-/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
-/// * `stride` - Bytes per lane, default is width * channels_count if not aligned
-/// * `width` - image width
-/// * `height` - image height
-/// * `radius` - radius is limited into 2..254
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `image` - mutable image view that will be used as a source and destination
+/// * `radius` - radius is limited into 2..=254
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
 /// * `threading_policy` - Threads usage policy
 ///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
 /// # Complexity
 /// O(1) complexity.
 pub fn stack_blur(
-    in_place: &mut [u8],
-    stride: u32,
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
     threading_policy: ThreadingPolicy,
-) {
-    let radius = std::cmp::max(std::cmp::min(254, radius), 2);
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 2, 254)?;
+    match edge_mode {
+        EdgeMode::Clamp => filter_alpha_mode(image, alpha_mode, |image| {
+            stack_blur_impl(image, radius, threading_policy);
+            Ok(())
+        }),
+        EdgeMode::KernelClip => Err(BlurError::EdgeModeNotSupported(EdgeMode::KernelClip)),
+        EdgeMode::Wrap | EdgeMode::Reflect | EdgeMode::Reflect101 | EdgeMode::Constant(_) => {
+            filter_alpha_mode(image, alpha_mode, |image| {
+                filter_edge_padded(image, radius, edge_mode, |padded| {
+                    stack_blur_impl(padded, radius, threading_policy);
+                    Ok(())
+                })
+            })
+        }
+    }
+}
+
+/// Fastest available blur option from `src` into `dst`.
+///
+/// Same as [stack_blur], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - radius is limited into 2..=254
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_into(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    stack_blur(dst, radius, edge_mode, alpha_mode, threading_policy)
+}
+
+fn stack_blur_impl(image: &mut BlurImageMut<u8>, radius: u32, threading_policy: ThreadingPolicy) {
+    let stride = image.stride;
+    let width = image.width;
+    let height = image.height;
+    let channels = image.channels;
+    let in_place = &mut *image.data;
     let thread_count = threading_policy.get_threads_count(width, height) as u32;
     if thread_count == 1 {
         let slice = UnsafeSlice::new(in_place);
@@ -993,10 +1084,7 @@ pub fn stack_blur(
         stack_blur_worker_vertical(&slice, stride, width, height, radius, channels, 0, 1);
         return;
     }
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.get_pool(thread_count as usize);
     pool.scope(|scope| {
         let slice = UnsafeSlice::new(in_place);
         for i in 0..thread_count {
@@ -1030,5 +1118,5 @@ pub fn stack_blur(
                 );
             });
         }
-    })
+    });
 }
@@ -27,18 +27,18 @@
 
 use half::f16;
 
+use crate::blur_error::check_radius;
+use crate::edge_padding::filter_edge_padded;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::neon::stack_blur_pass_neon_f16;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    all(target_feature = "sse4.1", target_feature = "f16c")
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::sse::stack_blur_pass_sse_f16;
 use crate::stack_blur::StackBlurPass;
 use crate::stack_blur_f32::stack_blur_pass_f;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{FastBlurChannels, ThreadingPolicy};
+use crate::{BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy};
 
+#[allow(clippy::too_many_arguments)]
 fn stack_blur_worker_horizontal(
     slice: &UnsafeSlice<f16>,
     stride: u32,
@@ -62,12 +62,34 @@ fn stack_blur_worker_horizontal(
                 usize,
             ) = stack_blur_pass_f::<f16, f32, 1>;
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
+                thread,
+                thread_count,
+            );
+        }
+        FastBlurChannels::Channels2 => {
+            let _dispatcher: fn(
+                &UnsafeSlice<f16>,
+                u32,
+                u32,
+                u32,
+                u32,
+                StackBlurPass,
+                usize,
+                usize,
+            ) = stack_blur_pass_f::<f16, f32, 2>;
+            _dispatcher(
+                slice,
+                stride,
+                width,
+                height,
+                radius,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -87,20 +109,19 @@ fn stack_blur_worker_horizontal(
             {
                 _dispatcher = stack_blur_pass_neon_f16::<3>;
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                all(target_feature = "sse4.1", target_feature = "f16c")
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                _dispatcher = stack_blur_pass_sse_f16::<3>;
+                if is_x86_feature_detected!("sse4.1") && is_x86_feature_detected!("f16c") {
+                    _dispatcher = stack_blur_pass_sse_f16::<3>;
+                }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -120,20 +141,19 @@ fn stack_blur_worker_horizontal(
             {
                 _dispatcher = stack_blur_pass_neon_f16::<4>;
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                all(target_feature = "sse4.1", target_feature = "f16c")
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                _dispatcher = stack_blur_pass_sse_f16::<4>;
+                if is_x86_feature_detected!("sse4.1") && is_x86_feature_detected!("f16c") {
+                    _dispatcher = stack_blur_pass_sse_f16::<4>;
+                }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -141,6 +161,7 @@ fn stack_blur_worker_horizontal(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn stack_blur_worker_vertical(
     slice: &UnsafeSlice<f16>,
     stride: u32,
@@ -164,12 +185,34 @@ fn stack_blur_worker_vertical(
                 usize,
             ) = stack_blur_pass_f::<f16, f32, 1>;
             _dispatcher(
-                &slice,
+                slice,
+                stride,
+                width,
+                height,
+                radius,
+                StackBlurPass::Vertical,
+                thread,
+                thread_count,
+            );
+        }
+        FastBlurChannels::Channels2 => {
+            let _dispatcher: fn(
+                &UnsafeSlice<f16>,
+                u32,
+                u32,
+                u32,
+                u32,
+                StackBlurPass,
+                usize,
+                usize,
+            ) = stack_blur_pass_f::<f16, f32, 2>;
+            _dispatcher(
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -189,20 +232,19 @@ fn stack_blur_worker_vertical(
             {
                 _dispatcher = stack_blur_pass_neon_f16::<3>;
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                all(target_feature = "sse4.1", target_feature = "f16c")
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                _dispatcher = stack_blur_pass_sse_f16::<3>;
+                if is_x86_feature_detected!("sse4.1") && is_x86_feature_detected!("f16c") {
+                    _dispatcher = stack_blur_pass_sse_f16::<3>;
+                }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -222,20 +264,19 @@ fn stack_blur_worker_vertical(
             {
                 _dispatcher = stack_blur_pass_neon_f16::<4>;
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                all(target_feature = "sse4.1", target_feature = "f16c")
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                _dispatcher = stack_blur_pass_sse_f16::<4>;
+                if is_x86_feature_detected!("sse4.1") && is_x86_feature_detected!("f16c") {
+                    _dispatcher = stack_blur_pass_sse_f16::<4>;
+                }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -249,25 +290,81 @@ fn stack_blur_worker_vertical(
 /// This is a very fast approximation using f32 accumulator size
 ///
 /// # Arguments
-/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
-/// * `width` - image width
-/// * `height` - image height
-/// * `radius` - radius almost is not limited for f16 implementation
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `image` - mutable image view that will be used as a source and destination
+/// * `radius` - radius almost is not limited for f16 implementation, must be at least 2
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
 /// * `threading_policy` - Threads usage policy
 ///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
 /// # Complexity
 /// O(1) complexity.
 pub fn stack_blur_f16(
-    in_place: &mut [f16],
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<f16>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 2, u32::MAX / 2)?;
+    match edge_mode {
+        EdgeMode::Clamp => {
+            stack_blur_f16_impl(image, radius, threading_policy);
+            Ok(())
+        }
+        EdgeMode::KernelClip => Err(BlurError::EdgeModeNotSupported(EdgeMode::KernelClip)),
+        EdgeMode::Wrap | EdgeMode::Reflect | EdgeMode::Reflect101 | EdgeMode::Constant(_) => {
+            filter_edge_padded(image, radius, edge_mode, |padded| {
+                stack_blur_f16_impl(padded, radius, threading_policy);
+                Ok(())
+            })
+        }
+    }
+}
+
+/// Fastest available blur option in f16, values may be denormalized, or normalized from `src` into `dst`.
+///
+/// Same as [stack_blur_f16], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - radius almost is not limited for f16 implementation, must be at least 2
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_f16_into(
+    src: &BlurImage<f16>,
+    dst: &mut BlurImageMut<f16>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    stack_blur_f16(dst, radius, edge_mode, threading_policy)
+}
+
+fn stack_blur_f16_impl(
+    image: &mut BlurImageMut<f16>,
     radius: u32,
-    channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
 ) {
-    let stride = width * channels.get_channels() as u32;
-    let radius = std::cmp::max(radius, 2);
+    let stride = image.stride;
+    let width = image.width;
+    let height = image.height;
+    let channels = image.channels;
+    let in_place = &mut *image.data;
     let thread_count = threading_policy.get_threads_count(width, height) as u32;
     if thread_count == 1 {
         let slice = UnsafeSlice::new(in_place);
@@ -275,10 +372,7 @@ pub fn stack_blur_f16(
         stack_blur_worker_vertical(&slice, stride, width, height, radius, channels, 0, 1);
         return;
     }
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.get_pool(thread_count as usize);
     pool.scope(|scope| {
         let slice = UnsafeSlice::new(in_place);
         for i in 0..thread_count {
@@ -312,5 +406,5 @@ pub fn stack_blur_f16(
                 );
             });
         }
-    })
+    });
 }
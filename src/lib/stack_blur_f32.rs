@@ -27,19 +27,22 @@
 
 const BASE_RADIUS_F64_CUTOFF: u32 = 327;
 
+use crate::alpha_mode::filter_alpha_mode;
+use crate::blur_error::check_radius;
+use crate::edge_padding::filter_edge_padded;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::neon::stack_blur_pass_neon_f32;
-#[cfg(all(
-    any(target_arch = "x86_64", target_arch = "x86"),
-    target_feature = "sse4.1"
-))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
 use crate::sse::stack_blur_pass_sse_f;
 use crate::stack_blur::{BlurStack, StackBlurPass};
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{FastBlurChannels, ThreadingPolicy};
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
 use num_traits::{AsPrimitive, FromPrimitive};
 use std::ops::AddAssign;
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn stack_blur_pass_f<T, J, const COMPONENTS: usize>(
     pixels: &UnsafeSlice<T>,
     stride: u32,
@@ -92,7 +95,7 @@ pub(crate) fn stack_blur_pass_f<T, J, const COMPONENTS: usize>(
     let mut src_ptr;
     let mut dst_ptr;
 
-    if pass == StackBlurPass::HORIZONTAL {
+    if pass == StackBlurPass::Horizontal {
         let min_y = thread * height as usize / total_threads;
         let max_y = (thread + 1) * height as usize / total_threads;
 
@@ -357,7 +360,7 @@ pub(crate) fn stack_blur_pass_f<T, J, const COMPONENTS: usize>(
                 }
             }
         }
-    } else if pass == StackBlurPass::VERTICAL {
+    } else if pass == StackBlurPass::Vertical {
         let min_x = thread * width as usize / total_threads;
         let max_x = (thread + 1) * width as usize / total_threads;
 
@@ -625,6 +628,7 @@ pub(crate) fn stack_blur_pass_f<T, J, const COMPONENTS: usize>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn stack_blur_worker_horizontal(
     slice: &UnsafeSlice<f32>,
     stride: u32,
@@ -652,12 +656,38 @@ fn stack_blur_worker_horizontal(
                 stack_blur_pass_f::<f32, f64, 1>
             };
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
+                thread,
+                thread_count,
+            );
+        }
+        FastBlurChannels::Channels2 => {
+            let _dispatcher: fn(
+                &UnsafeSlice<f32>,
+                u32,
+                u32,
+                u32,
+                u32,
+                StackBlurPass,
+                usize,
+                usize,
+            ) = if radius < BASE_RADIUS_F64_CUTOFF {
+                stack_blur_pass_f::<f32, f32, 2>
+            } else {
+                stack_blur_pass_f::<f32, f64, 2>
+            };
+            _dispatcher(
+                slice,
+                stride,
+                width,
+                height,
+                radius,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -683,22 +713,19 @@ fn stack_blur_worker_horizontal(
                     _dispatcher = stack_blur_pass_neon_f32::<3>;
                 }
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                if radius < BASE_RADIUS_F64_CUTOFF {
+                if is_x86_feature_detected!("sse4.1") && radius < BASE_RADIUS_F64_CUTOFF {
                     _dispatcher = stack_blur_pass_sse_f::<3>;
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -724,22 +751,19 @@ fn stack_blur_worker_horizontal(
                     _dispatcher = stack_blur_pass_neon_f32::<4>;
                 }
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                if radius < BASE_RADIUS_F64_CUTOFF {
+                if is_x86_feature_detected!("sse4.1") && radius < BASE_RADIUS_F64_CUTOFF {
                     _dispatcher = stack_blur_pass_sse_f::<4>;
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::HORIZONTAL,
+                StackBlurPass::Horizontal,
                 thread,
                 thread_count,
             );
@@ -747,6 +771,7 @@ fn stack_blur_worker_horizontal(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn stack_blur_worker_vertical(
     slice: &UnsafeSlice<f32>,
     stride: u32,
@@ -774,12 +799,38 @@ fn stack_blur_worker_vertical(
                 stack_blur_pass_f::<f32, f64, 1>
             };
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
+                thread,
+                thread_count,
+            );
+        }
+        FastBlurChannels::Channels2 => {
+            let _dispatcher: fn(
+                &UnsafeSlice<f32>,
+                u32,
+                u32,
+                u32,
+                u32,
+                StackBlurPass,
+                usize,
+                usize,
+            ) = if radius < BASE_RADIUS_F64_CUTOFF {
+                stack_blur_pass_f::<f32, f32, 2>
+            } else {
+                stack_blur_pass_f::<f32, f64, 2>
+            };
+            _dispatcher(
+                slice,
+                stride,
+                width,
+                height,
+                radius,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -805,22 +856,19 @@ fn stack_blur_worker_vertical(
                     _dispatcher = stack_blur_pass_neon_f32::<3>;
                 }
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                if radius < BASE_RADIUS_F64_CUTOFF {
+                if is_x86_feature_detected!("sse4.1") && radius < BASE_RADIUS_F64_CUTOFF {
                     _dispatcher = stack_blur_pass_sse_f::<3>;
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -846,22 +894,19 @@ fn stack_blur_worker_vertical(
                     _dispatcher = stack_blur_pass_neon_f32::<4>;
                 }
             }
-            #[cfg(all(
-                any(target_arch = "x86_64", target_arch = "x86"),
-                target_feature = "sse4.1"
-            ))]
+            #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
             {
-                if radius < BASE_RADIUS_F64_CUTOFF {
+                if is_x86_feature_detected!("sse4.1") && radius < BASE_RADIUS_F64_CUTOFF {
                     _dispatcher = stack_blur_pass_sse_f::<4>;
                 }
             }
             _dispatcher(
-                &slice,
+                slice,
                 stride,
                 width,
                 height,
                 radius,
-                StackBlurPass::VERTICAL,
+                StackBlurPass::Vertical,
                 thread,
                 thread_count,
             );
@@ -876,25 +921,87 @@ fn stack_blur_worker_vertical(
 /// after it to avoid overflowing fallback to f64 accumulator will be used with some computational slowdown with factor ~1.5-2
 ///
 /// # Arguments
-/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
-/// * `width` - image width
-/// * `height` - image height
-/// * `radius` - radius almost is not limited for f32 implementation
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `image` - mutable image view that will be used as a source and destination
+/// * `radius` - radius almost is not limited for f32 implementation, must be at least 2
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
 /// * `threading_policy` - Threads usage policy
 ///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
 /// # Complexity
 /// O(1) complexity.
 pub fn stack_blur_f32(
-    in_place: &mut [f32],
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<f32>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 2, u32::MAX / 2)?;
+    match edge_mode {
+        EdgeMode::Clamp => filter_alpha_mode(image, alpha_mode, |image| {
+            stack_blur_f32_impl(image, radius, threading_policy);
+            Ok(())
+        }),
+        EdgeMode::KernelClip => Err(BlurError::EdgeModeNotSupported(EdgeMode::KernelClip)),
+        EdgeMode::Wrap | EdgeMode::Reflect | EdgeMode::Reflect101 | EdgeMode::Constant(_) => {
+            filter_alpha_mode(image, alpha_mode, |image| {
+                filter_edge_padded(image, radius, edge_mode, |padded| {
+                    stack_blur_f32_impl(padded, radius, threading_policy);
+                    Ok(())
+                })
+            })
+        }
+    }
+}
+
+/// Fastest available blur option in f32, values may be denormalized, or normalized from `src` into `dst`.
+///
+/// Same as [stack_blur_f32], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - radius almost is not limited for f32 implementation, must be at least 2
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_f32_into(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    stack_blur_f32(dst, radius, edge_mode, alpha_mode, threading_policy)
+}
+
+fn stack_blur_f32_impl(
+    image: &mut BlurImageMut<f32>,
     radius: u32,
-    channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
 ) {
-    let stride = width * channels.get_channels() as u32;
-    let radius = std::cmp::max(radius, 2);
+    let stride = image.stride;
+    let width = image.width;
+    let height = image.height;
+    let channels = image.channels;
+    let in_place = &mut *image.data;
     let thread_count = threading_policy.get_threads_count(width, height) as u32;
     if thread_count == 1 {
         let slice = UnsafeSlice::new(in_place);
@@ -902,10 +1009,7 @@ pub fn stack_blur_f32(
         stack_blur_worker_vertical(&slice, stride, width, height, radius, channels, 0, 1);
         return;
     }
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.get_pool(thread_count as usize);
     pool.scope(|scope| {
         let slice = UnsafeSlice::new(in_place);
         for i in 0..thread_count {
@@ -939,5 +1043,5 @@ pub fn stack_blur_f32(
                 );
             });
         }
-    })
+    });
 }
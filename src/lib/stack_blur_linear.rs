@@ -25,12 +25,10 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{FastBlurChannels, ThreadingPolicy};
-use colorutils_rs::linear_to_planar::linear_to_plane;
-use colorutils_rs::planar_to_linear::plane_to_linear;
-use colorutils_rs::{
-    linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
-};
+use crate::blur_error::check_radius;
+use crate::linear_transform::{from_linear_transformer, to_linear_transformer};
+use crate::{AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, ThreadingPolicy};
+use colorutils_rs::TransferFunction;
 use std::mem::size_of;
 
 /// Stack blur that will be performed in linear color space
@@ -41,44 +39,40 @@ use std::mem::size_of;
 /// after it to avoid overflowing fallback to f64 accumulator will be used with some computational slowdown with factor ~1.5-2
 ///
 /// # Arguments
-/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
-/// * `stride` - Bytes per lane, default is width * channels_count if not aligned
-/// * `width` - image width
-/// * `height` - image height
-/// * `radius` - since f32 accumulator is used under the hood radius almost is not limited
-/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `image` - mutable image view that will be used as a source and destination
+/// * `radius` - since f32 accumulator is used under the hood radius almost is not limited, must be at least 2
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
 /// * `threading_policy` - Threads usage policy
 /// * `transfer_function` - Transfer function in linear colorspace
 ///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
 /// # Complexity
 /// O(1) complexity.
 pub fn stack_blur_in_linear(
-    in_place: &mut [u8],
-    stride: u32,
-    width: u32,
-    height: u32,
+    image: &mut BlurImageMut<u8>,
     radius: u32,
-    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
     transfer_function: TransferFunction,
-) {
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 2, u32::MAX / 2)?;
+    let stride = image.stride;
+    let width = image.width;
+    let height = image.height;
+    let channels = image.channels;
     let mut linear_data: Vec<f32> =
         vec![0f32; width as usize * height as usize * channels.get_channels()];
 
-    let forward_transformer = match channels {
-        FastBlurChannels::Plane => plane_to_linear,
-        FastBlurChannels::Channels3 => rgb_to_linear,
-        FastBlurChannels::Channels4 => rgba_to_linear,
-    };
+    let forward_transformer = to_linear_transformer(channels, image.alpha_position);
 
-    let inverse_transformer = match channels {
-        FastBlurChannels::Plane => linear_to_plane,
-        FastBlurChannels::Channels3 => linear_to_rgb,
-        FastBlurChannels::Channels4 => linear_to_rgba,
-    };
+    let inverse_transformer = from_linear_transformer(channels, image.alpha_position);
 
     forward_transformer(
-        &in_place,
+        image.data,
         stride,
         &mut linear_data,
         width * size_of::<f32>() as u32 * channels.get_channels() as u32,
@@ -87,22 +81,56 @@ pub fn stack_blur_in_linear(
         transfer_function,
     );
 
+    let mut linear_image = BlurImageMut::borrow(&mut linear_data, width, height, channels);
     crate::stack_blur_f32(
-        &mut linear_data,
-        width,
-        height,
+        &mut linear_image,
         radius,
-        channels,
+        edge_mode.to_linear(channels, image.alpha_position, transfer_function),
+        AlphaMode::Independent,
         threading_policy,
-    );
+    )?;
 
     inverse_transformer(
         &linear_data,
         width * size_of::<f32>() as u32 * channels.get_channels() as u32,
-        in_place,
+        image.data,
         stride,
         width,
         height,
         transfer_function,
     );
+    Ok(())
+}
+
+/// Stack blur that will be performed in linear color space from `src` into `dst`.
+///
+/// Same as [stack_blur_in_linear], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - since f32 accumulator is used under the hood radius almost is not limited, must be at least 2
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `threading_policy` - Threads usage policy
+/// * `transfer_function` - Transfer function in linear colorspace
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_in_linear_into(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    transfer_function: TransferFunction,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    stack_blur_in_linear(dst, radius, edge_mode, threading_policy, transfer_function)
 }
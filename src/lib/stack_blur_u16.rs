@@ -0,0 +1,232 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::filter_alpha_mode;
+use crate::blur_error::check_radius;
+use crate::edge_padding::filter_edge_padded;
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use crate::neon::stack_blur_pass_neon_i64;
+#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+use crate::sse::stack_blur_pass_sse_i64;
+use crate::stack_blur::{stack_blur_pass, StackBlurPass};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
+
+#[allow(clippy::too_many_arguments)]
+fn stack_blur_worker_u16(
+    slice: &UnsafeSlice<u16>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    pass: StackBlurPass,
+    thread: usize,
+    thread_count: usize,
+) {
+    let _dispatcher: fn(&UnsafeSlice<u16>, u32, u32, u32, u32, StackBlurPass, usize, usize) =
+        match channels {
+            FastBlurChannels::Plane => stack_blur_pass::<u16, i64, i64, 1>,
+            FastBlurChannels::Channels2 => stack_blur_pass::<u16, i64, i64, 2>,
+            FastBlurChannels::Channels3 => {
+                let mut _dispatcher: fn(
+                    &UnsafeSlice<u16>,
+                    u32,
+                    u32,
+                    u32,
+                    u32,
+                    StackBlurPass,
+                    usize,
+                    usize,
+                ) = stack_blur_pass::<u16, i64, i64, 3>;
+                #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+                {
+                    _dispatcher = stack_blur_pass_neon_i64::<u16, 3>;
+                }
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+                {
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse_i64::<u16, 3>;
+                    }
+                }
+                _dispatcher
+            }
+            FastBlurChannels::Channels4 => {
+                let mut _dispatcher: fn(
+                    &UnsafeSlice<u16>,
+                    u32,
+                    u32,
+                    u32,
+                    u32,
+                    StackBlurPass,
+                    usize,
+                    usize,
+                ) = stack_blur_pass::<u16, i64, i64, 4>;
+                #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+                {
+                    _dispatcher = stack_blur_pass_neon_i64::<u16, 4>;
+                }
+                #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+                {
+                    if is_x86_feature_detected!("sse4.1") {
+                        _dispatcher = stack_blur_pass_sse_i64::<u16, 4>;
+                    }
+                }
+                _dispatcher
+            }
+        };
+    _dispatcher(
+        slice,
+        stride,
+        width,
+        height,
+        radius,
+        pass,
+        thread,
+        thread_count,
+    );
+}
+
+/// Fastest available blur option for u16 images
+///
+/// Fast gaussian approximation using stack blur
+/// This is a very fast approximation using i64 accumulator size to keep full 16 bit depth
+///
+/// # Arguments
+/// * `image` - mutable image view that will be used as a source and destination
+/// * `radius` - radius is limited into 2..=254
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_u16(
+    image: &mut BlurImageMut<u16>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    image.check_layout()?;
+    threading_policy.check()?;
+    check_radius(radius, 2, 254)?;
+    match edge_mode {
+        EdgeMode::Clamp => filter_alpha_mode(image, alpha_mode, |image| {
+            stack_blur_u16_impl(image, radius, threading_policy);
+            Ok(())
+        }),
+        EdgeMode::KernelClip => Err(BlurError::EdgeModeNotSupported(EdgeMode::KernelClip)),
+        EdgeMode::Wrap | EdgeMode::Reflect | EdgeMode::Reflect101 | EdgeMode::Constant(_) => {
+            filter_alpha_mode(image, alpha_mode, |image| {
+                filter_edge_padded(image, radius, edge_mode, |padded| {
+                    stack_blur_u16_impl(padded, radius, threading_policy);
+                    Ok(())
+                })
+            })
+        }
+    }
+}
+
+/// Fastest available blur option for u16 images from `src` into `dst`.
+///
+/// Same as [stack_blur_u16], but source is left untouched and may have a different stride than destination.
+///
+/// # Arguments
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius` - radius is limited into 2..=254
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::KernelClip] is not supported
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided, radius is out of range or edge mode is not supported
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_u16_into(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    radius: u32,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    dst.copy_from(src);
+    stack_blur_u16(dst, radius, edge_mode, alpha_mode, threading_policy)
+}
+
+fn stack_blur_u16_impl(
+    image: &mut BlurImageMut<u16>,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+) {
+    let stride = image.stride;
+    let width = image.width;
+    let height = image.height;
+    let channels = image.channels;
+    let in_place = &mut *image.data;
+    let thread_count = threading_policy.get_threads_count(width, height) as u32;
+    if thread_count == 1 {
+        let slice = UnsafeSlice::new(in_place);
+        for pass in [StackBlurPass::Horizontal, StackBlurPass::Vertical] {
+            stack_blur_worker_u16(&slice, stride, width, height, radius, channels, pass, 0, 1);
+        }
+        return;
+    }
+    let pool = threading_policy.get_pool(thread_count as usize);
+    for pass in [StackBlurPass::Horizontal, StackBlurPass::Vertical] {
+        pool.scope(|scope| {
+            let slice = UnsafeSlice::new(in_place);
+            for i in 0..thread_count {
+                scope.spawn(move |_| {
+                    stack_blur_worker_u16(
+                        &slice,
+                        stride,
+                        width,
+                        height,
+                        radius,
+                        channels,
+                        pass,
+                        i as usize,
+                        thread_count as usize,
+                    );
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Naive reference implementations shared by tests
+
+use crate::edge_mode::EdgeColor;
+use crate::EdgeMode;
+
+/// Straightforward border rules of [EdgeMode],
+/// `None` means the sample is taken from [EdgeMode::Constant] color or skipped by [EdgeMode::KernelClip]
+pub(crate) fn reference_edge_index(edge_mode: EdgeMode, i: i64, n: i64) -> Option<usize> {
+    if (0..n).contains(&i) {
+        return Some(i as usize);
+    }
+    let i = match edge_mode {
+        EdgeMode::Clamp => i.clamp(0, n - 1),
+        EdgeMode::KernelClip | EdgeMode::Constant(_) => return None,
+        EdgeMode::Wrap => i.rem_euclid(n),
+        EdgeMode::Reflect => {
+            let i = i.rem_euclid(2 * n);
+            if i < n {
+                i
+            } else {
+                2 * n - 1 - i
+            }
+        }
+        EdgeMode::Reflect101 => {
+            if n == 1 {
+                return Some(0);
+            }
+            let i = i.rem_euclid(2 * n - 2);
+            if i < n {
+                i
+            } else {
+                2 * n - 2 - i
+            }
+        }
+    };
+    Some(i as usize)
+}
+
+/// Every [EdgeMode] that reads samples outside of the image, [EdgeMode::Constant] is filled with `color`
+pub(crate) fn edge_modes(color: [f32; 4]) -> [EdgeMode; 5] {
+    [
+        EdgeMode::Clamp,
+        EdgeMode::Wrap,
+        EdgeMode::Reflect,
+        EdgeMode::Reflect101,
+        EdgeMode::Constant(EdgeColor(color)),
+    ]
+}
+
+/// Deterministic pseudo random samples in `0..max`
+pub(crate) fn noise(length: usize, max: u32, seed: u32) -> Vec<u32> {
+    let mut state = seed.wrapping_mul(747796405).wrapping_add(2891336453);
+    (0..length)
+        .map(|_| {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            (state >> 8) % max
+        })
+        .collect()
+}
+
+/// Tightly packed `f32` image for reference computations
+#[derive(Copy, Clone)]
+pub(crate) struct ReferenceImage<'a> {
+    pub(crate) data: &'a [f32],
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) channels: usize,
+}
+
+impl ReferenceImage<'_> {
+    /// Sample `(x, y, c)` with `edge_mode` applied, `None` for [EdgeMode::KernelClip] outside of the image
+    pub(crate) fn sample(&self, x: i64, y: i64, c: usize, edge_mode: EdgeMode) -> Option<f32> {
+        match (
+            reference_edge_index(edge_mode, x, self.width as i64),
+            reference_edge_index(edge_mode, y, self.height as i64),
+        ) {
+            (Some(x), Some(y)) => Some(self.data[(y * self.width + x) * self.channels + c]),
+            _ => match edge_mode {
+                EdgeMode::Constant(EdgeColor(color)) => Some(color[c]),
+                _ => None,
+            },
+        }
+    }
+
+    /// Copy extended by `padding` pixels on every side with `edge_mode`, skipped samples are zero
+    pub(crate) fn padded(&self, padding: usize, edge_mode: EdgeMode) -> Vec<f32> {
+        let padding = padding as i64;
+        let mut dst = Vec::new();
+        for y in -padding..self.height as i64 + padding {
+            for x in -padding..self.width as i64 + padding {
+                for c in 0..self.channels {
+                    dst.push(self.sample(x, y, c, edge_mode).unwrap_or(0.));
+                }
+            }
+        }
+        dst
+    }
+
+    /// Central area of an image made by [ReferenceImage::padded]
+    pub(crate) fn unpadded(&self, padding: usize) -> Vec<f32> {
+        let row = self.width * self.channels;
+        self.data
+            .chunks_exact(row)
+            .skip(padding)
+            .take(self.height - padding * 2)
+            .flat_map(|r| &r[padding * self.channels..row - padding * self.channels])
+            .copied()
+            .collect()
+    }
+
+    /// Correlation with row-major `kernel` of `kernel_width` columns placed at `anchor`, computed in `f64`
+    pub(crate) fn filter_2d(
+        &self,
+        kernel: &[f32],
+        kernel_width: usize,
+        (anchor_x, anchor_y): (usize, usize),
+        edge_mode: EdgeMode,
+    ) -> Vec<f32> {
+        let kernel_height = kernel.len() / kernel_width;
+        let mut dst = vec![0f32; self.data.len()];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for c in 0..self.channels {
+                    let mut sum = 0f64;
+                    for j in 0..kernel_height {
+                        for i in 0..kernel_width {
+                            let sample = self
+                                .sample(
+                                    x as i64 + i as i64 - anchor_x as i64,
+                                    y as i64 + j as i64 - anchor_y as i64,
+                                    c,
+                                    edge_mode,
+                                )
+                                .unwrap_or(0.);
+                            sum += sample as f64 * kernel[j * kernel_width + i] as f64;
+                        }
+                    }
+                    dst[(y * self.width + x) * self.channels + c] = sum as f32;
+                }
+            }
+        }
+        dst
+    }
+
+    /// Mean of the square window of `radius` around the pixel, samples skipped by the edge mode are left out
+    pub(crate) fn box_mean(
+        &self,
+        (x, y, c): (i64, i64, usize),
+        radius: i64,
+        edge_mode: EdgeMode,
+    ) -> f64 {
+        let samples = (-radius..=radius)
+            .flat_map(|j| (-radius..=radius).map(move |i| (i, j)))
+            .filter_map(|(i, j)| self.sample(x + i, y + j, c, edge_mode))
+            .collect::<Vec<f32>>();
+        samples.iter().map(|&v| v as f64).sum::<f64>() / samples.len() as f64
+    }
+
+    /// Median of rank `n / 2` over the sorted square window of `radius`
+    pub(crate) fn median(&self, radius: usize, edge_mode: EdgeMode) -> Vec<f32> {
+        let radius = radius as i64;
+        let mut dst = vec![0f32; self.data.len()];
+        let mut window = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                for c in 0..self.channels {
+                    window.clear();
+                    for j in -radius..=radius {
+                        for i in -radius..=radius {
+                            if let Some(sample) =
+                                self.sample(x as i64 + i, y as i64 + j, c, edge_mode)
+                            {
+                                window.push(sample);
+                            }
+                        }
+                    }
+                    window.sort_by(f32::total_cmp);
+                    dst[(y * self.width + x) * self.channels + c] = window[window.len() / 2];
+                }
+            }
+        }
+        dst
+    }
+}
+
+/// Asserts that every sample differs from reference by no more than `tolerance`
+#[track_caller]
+pub(crate) fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32, what: &str) {
+    assert_eq!(actual.len(), expected.len(), "{what}");
+    for (i, (&a, &e)) in actual.iter().zip(expected.iter()).enumerate() {
+        assert!(
+            (a - e).abs() <= tolerance,
+            "{what}: sample {i} is {a}, expected {e}"
+        );
+    }
+}
@@ -25,28 +25,138 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::BlurError;
+use rayon::{Scope, ThreadPool, ThreadPoolBuilder};
+use std::sync::{Arc, Mutex, OnceLock};
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 /// Declares thread policy usage
+///
+/// Pools for [ThreadingPolicy::Adaptive] and [ThreadingPolicy::Fixed] are created once per thread count
+/// and reused across calls. At most 16 most recently used pools are kept alive, older ones are
+/// shut down once filters running on them finish.
 pub enum ThreadingPolicy {
     /// Will use only one thread, current is preferred
     Single,
     /// Computes adaptive thread count between 1...12 for given image bounds
     Adaptive,
-    /// Spawn provided threads count
+    /// Spawn provided threads count, must not be zero
     Fixed(usize),
+    /// Runs on the rayon pool of the calling thread: the global pool,
+    /// or the one entered with [ThreadPool::install].
+    /// Thread count is computed as for [ThreadingPolicy::Adaptive] and limited by the pool size
+    Global,
 }
 
 impl ThreadingPolicy {
+    /// Rejects [ThreadingPolicy::Fixed] with zero threads
+    pub(crate) fn check(&self) -> Result<(), BlurError> {
+        if let ThreadingPolicy::Fixed(0) = self {
+            return Err(BlurError::ZeroThreadsCount);
+        }
+        Ok(())
+    }
+
     pub fn get_threads_count(&self, width: u32, height: u32) -> usize {
         match self {
             ThreadingPolicy::Single => 1,
             ThreadingPolicy::Adaptive => {
-                let thread_count =
-                    std::cmp::max(std::cmp::min(width * height / (256 * 256), 12), 1);
+                let thread_count = (width * height / (256 * 256)).clamp(1, 12);
                 thread_count as usize
             }
             ThreadingPolicy::Fixed(fixed) => *fixed,
+            ThreadingPolicy::Global => ThreadingPolicy::Adaptive
+                .get_threads_count(width, height)
+                .min(rayon::current_num_threads())
+                .max(1),
+        }
+    }
+
+    /// Returns pool to run `thread_count` workers according to the policy
+    pub(crate) fn get_pool(&self, thread_count: usize) -> BlurThreadPool {
+        match self {
+            ThreadingPolicy::Global => BlurThreadPool::Current,
+            _ => BlurThreadPool::Shared(shared_pool(thread_count)),
+        }
+    }
+}
+
+/// Upper bound of pools kept alive between calls
+const MAX_SHARED_POOLS: usize = 16;
+
+/// Pools with their threads count
+type SharedPools = Vec<(usize, Arc<ThreadPool>)>;
+
+fn shared_pool(thread_count: usize) -> Arc<ThreadPool> {
+    static POOLS: OnceLock<Mutex<SharedPools>> = OnceLock::new();
+    let mut pools = POOLS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    // Pools are kept in least recently used first order
+    let pool = match pools.iter().position(|(count, _)| *count == thread_count) {
+        Some(index) => pools.remove(index).1,
+        None => Arc::new(
+            ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+                .unwrap(),
+        ),
+    };
+    if pools.len() == MAX_SHARED_POOLS {
+        pools.remove(0);
+    }
+    pools.push((thread_count, pool.clone()));
+    pool
+}
+
+/// Thread pool that runs filter workers
+pub(crate) enum BlurThreadPool {
+    /// Pool of the calling thread
+    Current,
+    /// Pool owned by the library and shared between calls
+    Shared(Arc<ThreadPool>),
+}
+
+impl BlurThreadPool {
+    pub(crate) fn scope<'scope, OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce(&Scope<'scope>) -> R + Send,
+        R: Send,
+    {
+        match self {
+            BlurThreadPool::Current => rayon::scope(op),
+            BlurThreadPool::Shared(pool) => pool.scope(op),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stack_blur, AlphaMode, BlurImageMut, EdgeMode, FastBlurChannels};
+
+    #[test]
+    fn fixed_zero_threads_is_rejected() {
+        let mut data = vec![0u8; 8 * 8 * 3];
+        let mut image = BlurImageMut::borrow(&mut data, 8, 8, FastBlurChannels::Channels3);
+        let result = stack_blur(
+            &mut image,
+            3,
+            EdgeMode::Clamp,
+            AlphaMode::Independent,
+            ThreadingPolicy::Fixed(0),
+        );
+        assert_eq!(result, Err(BlurError::ZeroThreadsCount));
+    }
+
+    #[test]
+    fn shared_pools_are_bounded() {
+        let first = shared_pool(20);
+        for thread_count in 21..=20 + MAX_SHARED_POOLS {
+            shared_pool(thread_count);
         }
+        assert_eq!(Arc::strong_count(&first), 1);
     }
 }
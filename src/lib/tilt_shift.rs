@@ -0,0 +1,329 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::blur_error::check_radius;
+use crate::linear_transform::{from_linear_transformer, to_linear_transformer};
+use crate::variable_blur::{variable_blur_f32, RadiusMap};
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
+use colorutils_rs::TransferFunction;
+
+/// Band of the image that stays in focus for [tilt_shift]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FocusBand {
+    /// Horizontal position of the point on the centre line, in pixels
+    pub center_x: f32,
+    /// Vertical position of the point on the centre line, in pixels
+    pub center_y: f32,
+    /// Direction of the centre line in degrees, `0` is horizontal
+    pub angle: f32,
+    /// Full width of the sharp band around the centre line, in pixels
+    pub width: f32,
+    /// Distance from the band edge where blur reaches the max radius, in pixels
+    pub falloff: f32,
+}
+
+impl FocusBand {
+    /// Creates horizontal focus band through the given row
+    pub fn horizontal(center_y: f32, width: f32, falloff: f32) -> Self {
+        FocusBand {
+            center_x: 0.,
+            center_y,
+            angle: 0.,
+            width,
+            falloff,
+        }
+    }
+
+    fn validate(&self) -> Result<(), BlurError> {
+        if !self.center_x.is_finite()
+            || !self.center_y.is_finite()
+            || !self.angle.is_finite()
+            || !self.width.is_finite()
+            || !self.falloff.is_finite()
+            || self.width < 0.
+            || self.falloff < 0.
+        {
+            return Err(BlurError::InvalidFocusBand);
+        }
+        Ok(())
+    }
+
+    /// Radius of every pixel, zero inside the band and smoothly growing to `max_radius` over the falloff
+    fn radius_profile(&self, width: u32, height: u32, max_radius: f32) -> Vec<f32> {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let half_width = self.width * 0.5;
+        let mut radii = vec![0f32; width as usize * height as usize];
+        for (y, row) in radii.chunks_exact_mut(width as usize).enumerate() {
+            let dy = y as f32 - self.center_y;
+            for (x, radius) in row.iter_mut().enumerate() {
+                let dx = x as f32 - self.center_x;
+                let outside = ((dy * cos - dx * sin).abs() - half_width).max(0.);
+                let t = if self.falloff > 0. {
+                    (outside / self.falloff).min(1.)
+                } else if outside > 0. {
+                    1.
+                } else {
+                    0.
+                };
+                *radius = t * t * (3. - 2. * t) * max_radius;
+            }
+        }
+        radii
+    }
+}
+
+/// Performs tilt-shift blur in linear colorspace.
+///
+/// Pixels inside the focus band stay sharp, outside it the blur radius smoothly grows
+/// up to `max_radius` over the band falloff, miniature look of the tilted lens.
+/// Blurring is done by [crate::variable_blur_f32] over the generated radius profile.
+/// O(1) complexity.
+///
+/// Memory cost is the one of [crate::variable_blur_f32] for `R = max_radius`
+/// plus two linear `f32` copies of the image and the radius profile.
+///
+/// # Arguments
+///
+/// * `src` - Source image, RGB or RGBA
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `focus_band` - Band that stays in focus, see [FocusBand]
+/// * `max_radius` - Radius of the blur far from the band, must be in range 1..=1024
+/// * `edge_mode` - Rule to extend the image beyond its borders, [EdgeMode::Constant] color is expected in `u8` range
+/// * `threading_policy` - Threads usage policy
+/// * `transfer_function` - Transfer function in linear colorspace
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// image is not RGB or RGBA, focus band is not finite or has negative width or falloff,
+/// or radius is out of range
+pub fn tilt_shift(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    focus_band: FocusBand,
+    max_radius: u32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    transfer_function: TransferFunction,
+) -> Result<(), BlurError> {
+    src.check_layout()?;
+    dst.check_layout()?;
+    src.size_matches(dst)?;
+    threading_policy.check()?;
+    if src.channels != FastBlurChannels::Channels3 && src.channels != FastBlurChannels::Channels4 {
+        return Err(BlurError::UnsupportedChannels(src.channels.get_channels()));
+    }
+    check_radius(max_radius, 1, 1024)?;
+    focus_band.validate()?;
+    let width = src.width;
+    let height = src.height;
+    let channels = src.channels;
+    let radii = focus_band.radius_profile(width, height, max_radius as f32);
+
+    let mut linear_data: Vec<f32> =
+        vec![0f32; width as usize * height as usize * channels.get_channels()];
+    let mut linear_data_1: Vec<f32> =
+        vec![0f32; width as usize * height as usize * channels.get_channels()];
+
+    let forward_transformer = to_linear_transformer(channels, src.alpha_position);
+
+    let inverse_transformer = from_linear_transformer(channels, src.alpha_position);
+
+    forward_transformer(
+        src.data,
+        src.stride,
+        &mut linear_data,
+        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width,
+        height,
+        transfer_function,
+    );
+    variable_blur_f32(
+        &BlurImage::borrow(&linear_data, width, height, channels),
+        &mut BlurImageMut::borrow(&mut linear_data_1, width, height, channels),
+        &RadiusMap::F32(BlurImage::borrow(
+            &radii,
+            width,
+            height,
+            FastBlurChannels::Plane,
+        )),
+        edge_mode.to_linear(channels, src.alpha_position, transfer_function),
+        AlphaMode::Independent,
+        threading_policy,
+    )?;
+    inverse_transformer(
+        &linear_data_1,
+        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        dst.data,
+        dst.stride,
+        width,
+        height,
+        transfer_function,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{edge_modes, noise, ReferenceImage};
+    use crate::EdgeColor;
+
+    fn srgb_to_linear(v: f64) -> f64 {
+        if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    fn linear_to_srgb(v: f64) -> f64 {
+        if v <= 0.0031308 {
+            v * 12.92
+        } else {
+            1.055 * v.powf(1. / 2.4) - 0.055
+        }
+    }
+
+    #[test]
+    fn tilt_shift_keeps_constant_image() {
+        let (width, height) = (17u32, 25u32);
+        let pixel = [40u8, 90, 210];
+        let src = pixel.repeat(width as usize * height as usize);
+        for focus_band in [
+            FocusBand::horizontal(12., 4., 6.),
+            FocusBand {
+                center_x: 3.,
+                center_y: 20.,
+                angle: 35.,
+                width: 0.,
+                falloff: 0.,
+            },
+        ] {
+            for edge_mode in edge_modes([40., 90., 210., 255.])
+                .into_iter()
+                .chain([EdgeMode::KernelClip])
+            {
+                let mut dst = vec![0u8; src.len()];
+                tilt_shift(
+                    &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels3),
+                    &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels3),
+                    focus_band,
+                    9,
+                    edge_mode,
+                    ThreadingPolicy::Fixed(2),
+                    TransferFunction::Srgb,
+                )
+                .unwrap();
+                assert_eq!(dst, src, "{focus_band:?} {edge_mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn tilt_shift_matches_reference() {
+        let (width, height) = (15usize, 22usize);
+        let src = noise(width * height * 3, 256, 23)
+            .iter()
+            .map(|&v| v as u8)
+            .collect::<Vec<u8>>();
+        let linear = src
+            .iter()
+            .map(|&v| srgb_to_linear(v as f64 / 255.) as f32)
+            .collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &linear,
+            width,
+            height,
+            channels: 3,
+        };
+        let focus_band = FocusBand {
+            center_x: 6.,
+            center_y: 10.,
+            angle: 20.,
+            width: 3.,
+            falloff: 7.,
+        };
+        let max_radius = 5.;
+        let color = [30f32, 200., 120., 255.];
+        for edge_mode in edge_modes(color).into_iter().chain([EdgeMode::KernelClip]) {
+            let mut dst = vec![0u8; src.len()];
+            tilt_shift(
+                &BlurImage::borrow(
+                    &src,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                &mut BlurImageMut::borrow(
+                    &mut dst,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                focus_band,
+                max_radius as u32,
+                edge_mode,
+                ThreadingPolicy::Fixed(2),
+                TransferFunction::Srgb,
+            )
+            .unwrap();
+            let reference_edge = match edge_mode {
+                EdgeMode::Constant(_) => EdgeMode::Constant(EdgeColor(
+                    color.map(|v| srgb_to_linear(v as f64 / 255.) as f32),
+                )),
+                edge_mode => edge_mode,
+            };
+            let (sin, cos) = (focus_band.angle as f64).to_radians().sin_cos();
+            for y in 0..height {
+                for x in 0..width {
+                    // Distance to the centre line, then smoothstep from the band edge over the falloff
+                    let distance = ((x as f64 - focus_band.center_x as f64) * sin
+                        - (y as f64 - focus_band.center_y as f64) * cos)
+                        .abs();
+                    let t = ((distance - focus_band.width as f64 / 2.).max(0.)
+                        / focus_band.falloff as f64)
+                        .min(1.);
+                    let radius = t * t * (3. - 2. * t) * max_radius;
+                    let (lower, fraction) = (radius.floor(), radius.fract());
+                    for c in 0..3 {
+                        let pixel = (x as i64, y as i64, c);
+                        let low = image.box_mean(pixel, lower as i64, reference_edge);
+                        let high = image.box_mean(pixel, lower as i64 + 1, reference_edge);
+                        let expected = linear_to_srgb(low + (high - low) * fraction) * 255.;
+                        let actual = dst[(y * width + x) * 3 + c] as f64;
+                        assert!(
+                            (actual - expected).abs() <= 1.,
+                            "{edge_mode:?} pixel ({x}, {y}, {c}) is {actual}, expected {expected}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
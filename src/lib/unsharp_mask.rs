@@ -0,0 +1,415 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::{filter_alpha_mode_into, AlphaPremultiply};
+use crate::to_storage::ToStorage;
+use crate::{
+    gaussian_blur, gaussian_blur_f32, gaussian_blur_u16, AlphaMode, BlurError, BlurImage,
+    BlurImageMut, EdgeMode, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+
+type GaussianBlurFn<T> = fn(
+    &BlurImage<T>,
+    &mut BlurImageMut<T>,
+    u32,
+    f32,
+    EdgeMode,
+    AlphaMode,
+    ThreadingPolicy,
+) -> Result<(), BlurError>;
+
+/// Parameters of the unsharp mask
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UnsharpMaskParams {
+    /// Length of gaussian kernel. Must be odd, even kernels with unbalanced center is not accepted.
+    pub kernel_size: u32,
+    /// Sigma for a gaussian kernel, corresponds to kernel flattening level. `0` selects the default of `kernel_size / 6`
+    pub sigma: f32,
+    /// Strength of sharpening, `1` doubles local contrast, negative values blur
+    pub amount: f32,
+    /// Minimum difference with the blurred image to be sharpened, in range of the image storage
+    pub threshold: f32,
+}
+
+impl UnsharpMaskParams {
+    /// Creates parameters with zero threshold, so every difference is sharpened
+    pub fn new(kernel_size: u32, sigma: f32, amount: f32) -> Self {
+        UnsharpMaskParams {
+            kernel_size,
+            sigma,
+            amount,
+            threshold: 0.,
+        }
+    }
+}
+
+/// Sharpens the row in place, `dst` holds the blurred row on entry
+fn sharpen_row<T>(src: &[T], dst: &mut [T], amount: f32, threshold: f32)
+where
+    T: Copy + AsPrimitive<f32>,
+    f32: ToStorage<T>,
+{
+    for (src, dst) in src.iter().zip(dst.iter_mut()) {
+        let v: f32 = src.as_();
+        let diff = v - dst.as_();
+        *dst = if diff.abs() >= threshold {
+            (v + diff * amount).to_()
+        } else {
+            *src
+        };
+    }
+}
+
+fn unsharp_mask_dispatch<T>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+    blur: GaussianBlurFn<T>,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + AsPrimitive<f32> + AlphaPremultiply,
+    f32: ToStorage<T>,
+{
+    let UnsharpMaskParams {
+        kernel_size,
+        sigma,
+        amount,
+        threshold,
+    } = params;
+    if !amount.is_finite() || !threshold.is_finite() || threshold < 0. {
+        return Err(BlurError::InvalidStrength);
+    }
+    threading_policy.check()?;
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        // Blurred image is kept in the destination, so no full size temporary is needed
+        blur(
+            src,
+            dst,
+            kernel_size,
+            sigma,
+            edge_mode,
+            AlphaMode::Independent,
+            threading_policy,
+        )?;
+        let width = src.width as usize;
+        let height = src.height as usize;
+        let row_length = width * src.channels.get_channels();
+        let src_stride = src.stride as usize;
+        let dst_stride = dst.stride as usize;
+        // Only the rows of the image are touched, buffers may be longer than that
+        let src_data = &src.data[..src_stride * (height - 1) + row_length];
+        let dst_data = &mut dst.data[..dst_stride * (height - 1) + row_length];
+        let thread_count = threading_policy.get_threads_count(src.width, src.height);
+        let segment_rows = height.div_ceil(thread_count);
+        let sharpen_rows = |src: &[T], dst: &mut [T]| {
+            for (src, dst) in src.chunks(src_stride).zip(dst.chunks_mut(dst_stride)) {
+                sharpen_row(
+                    &src[..row_length],
+                    &mut dst[..row_length],
+                    amount,
+                    threshold,
+                );
+            }
+        };
+        if thread_count == 1 {
+            sharpen_rows(src_data, dst_data);
+            return Ok(());
+        }
+        let pool = threading_policy.get_pool(thread_count);
+        let sharpen_rows = &sharpen_rows;
+        pool.scope(|scope| {
+            for (src, dst) in src_data
+                .chunks(src_stride * segment_rows)
+                .zip(dst_data.chunks_mut(dst_stride * segment_rows))
+            {
+                scope.spawn(move |_| sharpen_rows(src, dst));
+            }
+        });
+        Ok(())
+    })
+}
+
+/// Sharpens the image with unsharp mask.
+///
+/// Gaussian blurred image is subtracted from the source, the difference is scaled by [UnsharpMaskParams::amount] and added back.
+/// Differences below [UnsharpMaskParams::threshold] are left untouched, so noise in flat areas is not amplified.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `params` - Kernel and strength of sharpening, see [UnsharpMaskParams]
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd, sigma is negative or not finite, amount is not finite or threshold is negative
+pub fn unsharp_mask(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    unsharp_mask_dispatch(
+        src,
+        dst,
+        params,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+        gaussian_blur,
+    )
+}
+
+/// Sharpens the 16-bit image with unsharp mask.
+///
+/// Gaussian blurred image is subtracted from the source, the difference is scaled by [UnsharpMaskParams::amount] and added back.
+/// Differences below [UnsharpMaskParams::threshold] are left untouched, so noise in flat areas is not amplified.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `params` - Kernel and strength of sharpening, see [UnsharpMaskParams]
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd, sigma is negative or not finite, amount is not finite or threshold is negative
+pub fn unsharp_mask_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    unsharp_mask_dispatch(
+        src,
+        dst,
+        params,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+        gaussian_blur_u16,
+    )
+}
+
+/// Sharpens the image in f32 with unsharp mask.
+///
+/// Gaussian blurred image is subtracted from the source, the difference is scaled by [UnsharpMaskParams::amount] and added back.
+/// Differences below [UnsharpMaskParams::threshold] are left untouched, so noise in flat areas is not amplified.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `params` - Kernel and strength of sharpening, see [UnsharpMaskParams]
+/// * `edge_mode` - Rule to handle edge mode, [EdgeMode::Constant] color is expected in the range of the image storage
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// kernel size is not odd, sigma is negative or not finite, amount is not finite or threshold is negative
+pub fn unsharp_mask_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    params: UnsharpMaskParams,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    unsharp_mask_dispatch(
+        src,
+        dst,
+        params,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+        gaussian_blur_f32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+    use crate::FastBlurChannels;
+
+    #[test]
+    fn unsharp_mask_f32_matches_reference() {
+        let (width, height) = (16usize, 12usize);
+        let src = noise(width * height * 3, 256, 31)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 3,
+        };
+        let (kernel_size, sigma) = (7usize, 1.3f32);
+        let gaussian = (0..kernel_size)
+            .map(|i| {
+                let x = i as f32 - (kernel_size / 2) as f32;
+                (-x * x / (2. * sigma * sigma)).exp()
+            })
+            .collect::<Vec<f32>>();
+        let norm: f32 = gaussian.iter().sum();
+        let kernel = gaussian
+            .iter()
+            .flat_map(|&y| gaussian.iter().map(move |&x| x * y / (norm * norm)))
+            .collect::<Vec<f32>>();
+        for threshold in [0f32, 0.05] {
+            let params = UnsharpMaskParams {
+                threshold,
+                ..UnsharpMaskParams::new(kernel_size as u32, sigma, 1.5)
+            };
+            for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.]) {
+                let mut dst = vec![0f32; src.len()];
+                unsharp_mask_f32(
+                    &BlurImage::borrow(
+                        &src,
+                        width as u32,
+                        height as u32,
+                        FastBlurChannels::Channels3,
+                    ),
+                    &mut BlurImageMut::borrow(
+                        &mut dst,
+                        width as u32,
+                        height as u32,
+                        FastBlurChannels::Channels3,
+                    ),
+                    params,
+                    edge_mode,
+                    AlphaMode::Independent,
+                    ThreadingPolicy::Fixed(2),
+                )
+                .unwrap();
+                let blurred = image.filter_2d(
+                    &kernel,
+                    kernel_size,
+                    (kernel_size / 2, kernel_size / 2),
+                    edge_mode,
+                );
+                let expected = src
+                    .iter()
+                    .zip(blurred.iter())
+                    .map(|(&v, &blurred)| {
+                        if (v - blurred).abs() >= threshold {
+                            v + (v - blurred) * params.amount
+                        } else {
+                            v
+                        }
+                    })
+                    .collect::<Vec<f32>>();
+                assert_close(
+                    &dst,
+                    &expected,
+                    1e-4,
+                    &format!("threshold {threshold} {edge_mode:?}"),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unsharp_mask_keeps_constant_image() {
+        let (width, height) = (19u32, 12u32);
+        let pixel = [25u8, 130, 240, 255];
+        let src = pixel.repeat(width as usize * height as usize);
+        for edge_mode in edge_modes(pixel.map(|v| v as f32)) {
+            let mut dst = vec![0u8; src.len()];
+            unsharp_mask(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels4),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels4),
+                UnsharpMaskParams::new(7, 1.2, 2.),
+                edge_mode,
+                AlphaMode::Premultiply,
+                ThreadingPolicy::Fixed(2),
+            )
+            .unwrap();
+            assert_eq!(dst, src, "{edge_mode:?}");
+        }
+    }
+
+    #[test]
+    fn unsharp_mask_u16_keeps_constant_image() {
+        let (width, height) = (11u32, 16u32);
+        let pixel = [1000u16, 40000, 65535];
+        let src = pixel.repeat(width as usize * height as usize);
+        for edge_mode in edge_modes([1000., 40000., 65535., 0.]) {
+            let mut dst = vec![0u16; src.len()];
+            unsharp_mask_u16(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels3),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels3),
+                UnsharpMaskParams::new(5, 1., 1.5),
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            assert_eq!(dst, src, "{edge_mode:?}");
+        }
+    }
+
+    #[test]
+    fn unsharp_mask_f32_threshold_keeps_small_differences() {
+        let (width, height) = (9u32, 9u32);
+        let mut src = vec![0.5f32; (width * height) as usize];
+        src[40] = 0.52;
+        let mut dst = vec![0f32; src.len()];
+        unsharp_mask_f32(
+            &BlurImage::borrow(&src, width, height, FastBlurChannels::Plane),
+            &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Plane),
+            UnsharpMaskParams {
+                threshold: 0.05,
+                ..UnsharpMaskParams::new(3, 0.8, 1.)
+            },
+            EdgeMode::Clamp,
+            AlphaMode::Independent,
+            ThreadingPolicy::Single,
+        )
+        .unwrap();
+        assert_eq!(dst, src);
+    }
+}
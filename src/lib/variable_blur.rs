@@ -0,0 +1,597 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::alpha_mode::{filter_alpha_mode_into, AlphaPremultiply};
+use crate::edge_padding::pad_image;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{
+    AlphaMode, BlurError, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels, ThreadingPolicy,
+};
+use num_traits::AsPrimitive;
+
+/// Largest radius accepted from the radius map
+const MAX_VARIABLE_RADIUS: u32 = 1024;
+
+/// Per-pixel blur radius, single plane with the same size as the blurred image.
+///
+/// Values are radii in pixels, `0` keeps the pixel as is.
+/// Fractional radii from `F32` map are blended between two neighbouring integer radii.
+#[derive(Debug, Copy, Clone)]
+pub enum RadiusMap<'a> {
+    U8(BlurImage<'a, u8>),
+    F32(BlurImage<'a, f32>),
+}
+
+impl RadiusMap<'_> {
+    /// Validates the map against the image and returns the largest radius in it
+    fn max_radius(&self, width: u32, height: u32) -> Result<u32, BlurError> {
+        let (map_width, map_height, channels) = match self {
+            RadiusMap::U8(map) => {
+                map.check_layout()?;
+                (map.width, map.height, map.channels)
+            }
+            RadiusMap::F32(map) => {
+                map.check_layout()?;
+                (map.width, map.height, map.channels)
+            }
+        };
+        if channels != FastBlurChannels::Plane {
+            return Err(BlurError::UnsupportedChannels(channels.get_channels()));
+        }
+        if map_width != width || map_height != height {
+            return Err(BlurError::ImagesMustMatch);
+        }
+        match self {
+            RadiusMap::U8(map) => Ok(map
+                .data
+                .chunks(map.stride as usize)
+                .take(height as usize)
+                .flat_map(|row| row[..width as usize].iter())
+                .fold(0u32, |acc, &v| acc.max(v as u32))),
+            RadiusMap::F32(map) => {
+                let mut max_radius = 0f32;
+                for row in map.data.chunks(map.stride as usize).take(height as usize) {
+                    for &v in row[..width as usize].iter() {
+                        if v < 0. || !v.is_finite() {
+                            return Err(BlurError::InvalidRadiusMap);
+                        }
+                        max_radius = max_radius.max(v);
+                    }
+                }
+                let max_radius = max_radius.ceil();
+                if max_radius > MAX_VARIABLE_RADIUS as f32 {
+                    return Err(BlurError::RadiusOutOfRange {
+                        radius: max_radius as u32,
+                        min: 0,
+                        max: MAX_VARIABLE_RADIUS,
+                    });
+                }
+                Ok(max_radius as u32)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn radius(&self, x: usize, y: usize) -> f32 {
+        match self {
+            RadiusMap::U8(map) => unsafe {
+                *map.data.get_unchecked(y * map.stride as usize + x) as f32
+            },
+            RadiusMap::F32(map) => unsafe { *map.data.get_unchecked(y * map.stride as usize + x) },
+        }
+    }
+}
+
+/// Accumulator of the summed area table.
+///
+/// Integral accumulators wrap around, difference of four corners is still exact
+/// while the sum over a single window fits into the type.
+trait AreaSum: Copy + Default + Send + Sync + 'static {
+    fn add(self, other: Self) -> Self;
+    fn sub(self, other: Self) -> Self;
+    fn to_f32(self) -> f32;
+}
+
+impl AreaSum for u32 {
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self.wrapping_add(other)
+    }
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        self.wrapping_sub(other)
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+impl AreaSum for u64 {
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self.wrapping_add(other)
+    }
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        self.wrapping_sub(other)
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+impl AreaSum for f64 {
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        self - other
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+/// Summed area table of the image, row and column zero are zeros
+struct SummedAreaTable<J, const CHANNELS: usize> {
+    table: Vec<J>,
+    stride: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<J: AreaSum, const CHANNELS: usize> SummedAreaTable<J, CHANNELS> {
+    fn new<T: Copy + AsPrimitive<J>>(data: &[T], width: usize, height: usize) -> Self {
+        let stride = (width + 1) * CHANNELS;
+        let mut table = vec![J::default(); stride * (height + 1)];
+        for y in 0..height {
+            let (previous, current) = table.split_at_mut((y + 1) * stride);
+            let previous = &previous[y * stride..];
+            let current = &mut current[..stride];
+            let src_row = &data[y * width * CHANNELS..(y + 1) * width * CHANNELS];
+            let mut row_sum = [J::default(); CHANNELS];
+            for (x, src) in src_row.chunks_exact(CHANNELS).enumerate() {
+                let offset = (x + 1) * CHANNELS;
+                for c in 0..CHANNELS {
+                    row_sum[c] = row_sum[c].add(src[c].as_());
+                    current[offset + c] = previous[offset + c].add(row_sum[c]);
+                }
+            }
+        }
+        SummedAreaTable {
+            table,
+            stride,
+            width,
+            height,
+        }
+    }
+
+    /// Mean of the window `radius` around table pixel, window is clipped to the table
+    #[inline(always)]
+    fn mean(&self, x: usize, y: usize, radius: usize) -> [f32; CHANNELS] {
+        let x0 = x.saturating_sub(radius);
+        let y0 = y.saturating_sub(radius);
+        let x1 = (x + radius + 1).min(self.width);
+        let y1 = (y + radius + 1).min(self.height);
+        let scale = 1f32 / ((x1 - x0) * (y1 - y0)) as f32;
+        let top = y0 * self.stride;
+        let bottom = y1 * self.stride;
+        let mut mean = [0f32; CHANNELS];
+        for (c, v) in mean.iter_mut().enumerate() {
+            let (a, b, d, e) = unsafe {
+                (
+                    *self.table.get_unchecked(top + x0 * CHANNELS + c),
+                    *self.table.get_unchecked(top + x1 * CHANNELS + c),
+                    *self.table.get_unchecked(bottom + x0 * CHANNELS + c),
+                    *self.table.get_unchecked(bottom + x1 * CHANNELS + c),
+                )
+            };
+            *v = e.sub(b).sub(d).add(a).to_f32() * scale;
+        }
+        mean
+    }
+}
+
+fn variable_blur_impl<T, J, const CHANNELS: usize>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius_map: &RadiusMap,
+    max_radius: usize,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<J>,
+    J: AreaSum,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let width = src.width as usize;
+    let height = src.height as usize;
+    // Clipped kernel is served by clipping the window on the table itself
+    let padding = if edge_mode == EdgeMode::KernelClip {
+        0
+    } else {
+        max_radius
+    };
+    let padded = pad_image(src, padding, edge_mode);
+    let table =
+        SummedAreaTable::<J, CHANNELS>::new(&padded, width + padding * 2, height + padding * 2);
+    let dst_stride = dst.stride as usize;
+    let unsafe_dst = UnsafeSlice::new(dst.data);
+
+    let blur_rows = |start_y: usize, end_y: usize| {
+        for y in start_y..end_y {
+            let y_dst = y * dst_stride;
+            for x in 0..width {
+                let radius = radius_map.radius(x, y);
+                let lower = radius as usize;
+                let fraction = radius - lower as f32;
+                let mut mean = table.mean(x + padding, y + padding, lower);
+                if fraction > 0. {
+                    let upper = table.mean(x + padding, y + padding, lower + 1);
+                    for (v, u) in mean.iter_mut().zip(upper.iter()) {
+                        *v += (*u - *v) * fraction;
+                    }
+                }
+                let offset = y_dst + x * CHANNELS;
+                for (c, v) in mean.iter().enumerate() {
+                    unsafe {
+                        unsafe_dst.write(offset + c, v.to_());
+                    }
+                }
+            }
+        }
+    };
+
+    let thread_count = threading_policy.get_threads_count(src.width, src.height);
+    if thread_count == 1 {
+        blur_rows(0, height);
+        return;
+    }
+    let pool = threading_policy.get_pool(thread_count);
+    let blur_rows = &blur_rows;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| blur_rows(start_y, end_y));
+        }
+    });
+}
+
+fn variable_blur_dispatch<T, J>(
+    src: &BlurImage<T>,
+    dst: &mut BlurImageMut<T>,
+    radius_map: &RadiusMap,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError>
+where
+    T: Copy + Default + Send + Sync + 'static + AsPrimitive<J> + AlphaPremultiply,
+    J: AreaSum,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let max_radius = radius_map.max_radius(src.width, src.height)?;
+    filter_alpha_mode_into(src, dst, alpha_mode, |src, dst| {
+        src.check_layout()?;
+        dst.check_layout()?;
+        src.size_matches(dst)?;
+        threading_policy.check()?;
+        let _dispatcher = match src.channels {
+            FastBlurChannels::Plane => variable_blur_impl::<T, J, 1>,
+            FastBlurChannels::Channels2 => variable_blur_impl::<T, J, 2>,
+            FastBlurChannels::Channels3 => variable_blur_impl::<T, J, 3>,
+            FastBlurChannels::Channels4 => variable_blur_impl::<T, J, 4>,
+        };
+        _dispatcher(
+            src,
+            dst,
+            radius_map,
+            max_radius as usize,
+            edge_mode,
+            threading_policy,
+        );
+        Ok(())
+    })
+}
+
+/// Performs spatially varying box blur on the image.
+///
+/// Every pixel is averaged over the box of its own radius taken from `radius_map`,
+/// useful for depth of field with a depth derived radius.
+/// Box sums are read from the summed area table, so any radius has the same cost.
+/// O(1) complexity.
+///
+/// The whole image padded by the largest radius `R` of the map is held in memory along with
+/// its summed area table of `u32` sums, about `(width + 2R) * (height + 2R) * channels * 5` bytes.
+/// [EdgeMode::KernelClip] needs no padding, so `R` is zero there.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius_map` - Radius of every pixel, single plane with the same size as the image, radii must be in range 0..=1024, see [RadiusMap]
+/// * `edge_mode` - Rule to extend the image beyond its borders
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius map doesn't match the image or contains negative, not finite or too large radius
+pub fn variable_blur(
+    src: &BlurImage<u8>,
+    dst: &mut BlurImageMut<u8>,
+    radius_map: &RadiusMap,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_dispatch::<u8, u32>(
+        src,
+        dst,
+        radius_map,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs spatially varying box blur on the 16-bit image.
+///
+/// Every pixel is averaged over the box of its own radius taken from `radius_map`,
+/// useful for depth of field with a depth derived radius.
+/// Box sums are read from the summed area table, so any radius has the same cost.
+/// O(1) complexity.
+///
+/// The whole image padded by the largest radius `R` of the map is held in memory along with
+/// its summed area table of `u64` sums, about `(width + 2R) * (height + 2R) * channels * 10` bytes.
+/// [EdgeMode::KernelClip] needs no padding, so `R` is zero there.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius_map` - Radius of every pixel, single plane with the same size as the image, radii must be in range 0..=1024, see [RadiusMap]
+/// * `edge_mode` - Rule to extend the image beyond its borders
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius map doesn't match the image or contains negative, not finite or too large radius
+pub fn variable_blur_u16(
+    src: &BlurImage<u16>,
+    dst: &mut BlurImageMut<u16>,
+    radius_map: &RadiusMap,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_dispatch::<u16, u64>(
+        src,
+        dst,
+        radius_map,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+/// Performs spatially varying box blur on the image in f32.
+///
+/// Every pixel is averaged over the box of its own radius taken from `radius_map`,
+/// useful for depth of field with a depth derived radius.
+/// Box sums are read from the summed area table, so any radius has the same cost.
+/// O(1) complexity.
+///
+/// The whole image padded by the largest radius `R` of the map is held in memory along with
+/// its summed area table of `f64` sums, about `(width + 2R) * (height + 2R) * channels * 12` bytes.
+/// [EdgeMode::KernelClip] needs no padding, so `R` is zero there.
+///
+/// # Arguments
+///
+/// * `src` - Source image
+/// * `dst` - Destination image, must have the same size and channels as source
+/// * `radius_map` - Radius of every pixel, single plane with the same size as the image, radii must be in range 0..=1024, see [RadiusMap]
+/// * `edge_mode` - Rule to extend the image beyond its borders
+/// * `alpha_mode` - Alpha handling for images with alpha channel, see [AlphaMode]
+/// * `threading_policy` - Threads usage policy
+///
+/// # Errors
+/// Returns [BlurError] if stride/width/height/channel configuration do not match provided,
+/// radius map doesn't match the image or contains negative, not finite or too large radius
+pub fn variable_blur_f32(
+    src: &BlurImage<f32>,
+    dst: &mut BlurImageMut<f32>,
+    radius_map: &RadiusMap,
+    edge_mode: EdgeMode,
+    alpha_mode: AlphaMode,
+    threading_policy: ThreadingPolicy,
+) -> Result<(), BlurError> {
+    variable_blur_dispatch::<f32, f64>(
+        src,
+        dst,
+        radius_map,
+        edge_mode,
+        alpha_mode,
+        threading_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{assert_close, edge_modes, noise, ReferenceImage};
+
+    #[test]
+    fn variable_blur_keeps_constant_image() {
+        let (width, height) = (21u32, 14u32);
+        let pixel = [12u8, 140, 230, 255];
+        let src = pixel.repeat(width as usize * height as usize);
+        let radii_u8: Vec<u8> = (0..width * height).map(|i| (i % 9) as u8).collect();
+        let radii_f32: Vec<f32> = (0..width * height)
+            .map(|i| (i % 23) as f32 * 0.37)
+            .collect();
+        for radius_map in [
+            RadiusMap::U8(BlurImage::borrow(
+                &radii_u8,
+                width,
+                height,
+                FastBlurChannels::Plane,
+            )),
+            RadiusMap::F32(BlurImage::borrow(
+                &radii_f32,
+                width,
+                height,
+                FastBlurChannels::Plane,
+            )),
+        ] {
+            for edge_mode in edge_modes(pixel.map(|v| v as f32))
+                .into_iter()
+                .chain([EdgeMode::KernelClip])
+            {
+                let mut dst = vec![0u8; src.len()];
+                variable_blur(
+                    &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels4),
+                    &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels4),
+                    &radius_map,
+                    edge_mode,
+                    AlphaMode::Premultiply,
+                    ThreadingPolicy::Fixed(2),
+                )
+                .unwrap();
+                assert_eq!(dst, src, "{edge_mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn variable_blur_f32_keeps_constant_image() {
+        let (width, height) = (9u32, 30u32);
+        let src = vec![0.3f32; width as usize * height as usize * 3];
+        let radii: Vec<f32> = (0..width * height).map(|i| (i % 31) as f32 * 0.5).collect();
+        let radius_map = RadiusMap::F32(BlurImage::borrow(
+            &radii,
+            width,
+            height,
+            FastBlurChannels::Plane,
+        ));
+        for edge_mode in [EdgeMode::Clamp, EdgeMode::KernelClip, EdgeMode::Reflect101] {
+            let mut dst = vec![0f32; src.len()];
+            variable_blur_f32(
+                &BlurImage::borrow(&src, width, height, FastBlurChannels::Channels3),
+                &mut BlurImageMut::borrow(&mut dst, width, height, FastBlurChannels::Channels3),
+                &radius_map,
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Single,
+            )
+            .unwrap();
+            assert!(dst.iter().all(|v| (v - 0.3).abs() < 1e-5), "{edge_mode:?}");
+        }
+    }
+
+    #[test]
+    fn variable_blur_f32_matches_reference() {
+        let (width, height) = (16usize, 13usize);
+        let src = noise(width * height * 3, 256, 17)
+            .iter()
+            .map(|&v| v as f32 / 255.)
+            .collect::<Vec<f32>>();
+        let image = ReferenceImage {
+            data: &src,
+            width,
+            height,
+            channels: 3,
+        };
+        let radii = noise(width * height, 600, 5)
+            .iter()
+            .map(|&v| v as f32 / 100.)
+            .collect::<Vec<f32>>();
+        let radius_map = RadiusMap::F32(BlurImage::borrow(
+            &radii,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        ));
+        for edge_mode in edge_modes([0.2, 0.4, 0.6, 1.])
+            .into_iter()
+            .chain([EdgeMode::KernelClip])
+        {
+            let mut dst = vec![0f32; src.len()];
+            variable_blur_f32(
+                &BlurImage::borrow(
+                    &src,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                &mut BlurImageMut::borrow(
+                    &mut dst,
+                    width as u32,
+                    height as u32,
+                    FastBlurChannels::Channels3,
+                ),
+                &radius_map,
+                edge_mode,
+                AlphaMode::Independent,
+                ThreadingPolicy::Fixed(2),
+            )
+            .unwrap();
+            let mut expected = Vec::with_capacity(src.len());
+            for y in 0..height {
+                for x in 0..width {
+                    let radius = radii[y * width + x] as f64;
+                    let (lower, fraction) = (radius.floor(), radius.fract());
+                    for c in 0..3 {
+                        let pixel = (x as i64, y as i64, c);
+                        let low = image.box_mean(pixel, lower as i64, edge_mode);
+                        let high = image.box_mean(pixel, lower as i64 + 1, edge_mode);
+                        expected.push((low + (high - low) * fraction) as f32);
+                    }
+                }
+            }
+            assert_close(&dst, &expected, 1e-4, &format!("{edge_mode:?}"));
+        }
+    }
+}
@@ -10,7 +10,8 @@ use image::{EncodableLayout, GenericImageView};
 use libblur::{
     fast_gaussian, fast_gaussian_f16, fast_gaussian_f32, fast_gaussian_next,
     fast_gaussian_next_f16, fast_gaussian_next_f32, stack_blur, stack_blur_f16, stack_blur_f32,
-    stack_blur_in_linear, EdgeMode, FastBlurChannels, ThreadingPolicy,
+    stack_blur_in_linear, AlphaMode, BlurImage, BlurImageMut, EdgeMode, FastBlurChannels,
+    ThreadingPolicy,
 };
 use std::time::Instant;
 
@@ -43,37 +44,46 @@ fn perform_planar_pass_3(img: &[u8], width: usize, height: usize) -> Vec<u8> {
     let mut dst_plane_3 = Vec::from(plane_3);
 
     libblur::fast_gaussian_next(
-        &mut dst_plane_1,
-        width as u32,
-        width as u32,
-        height as u32,
+        &mut BlurImageMut::borrow(
+            &mut dst_plane_1,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        ),
         35,
-        FastBlurChannels::Plane,
-        ThreadingPolicy::Single,
         EdgeMode::Clamp,
-    );
+        AlphaMode::Independent,
+        ThreadingPolicy::Single,
+    )
+    .unwrap();
 
     libblur::fast_gaussian_next(
-        &mut dst_plane_2,
-        width as u32,
-        width as u32,
-        height as u32,
+        &mut BlurImageMut::borrow(
+            &mut dst_plane_2,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        ),
         35,
-        FastBlurChannels::Plane,
-        ThreadingPolicy::Single,
         EdgeMode::Clamp,
-    );
+        AlphaMode::Independent,
+        ThreadingPolicy::Single,
+    )
+    .unwrap();
 
     libblur::fast_gaussian_next(
-        &mut dst_plane_3,
-        width as u32,
-        width as u32,
-        height as u32,
+        &mut BlurImageMut::borrow(
+            &mut dst_plane_3,
+            width as u32,
+            height as u32,
+            FastBlurChannels::Plane,
+        ),
         35,
-        FastBlurChannels::Plane,
-        ThreadingPolicy::Single,
         EdgeMode::Clamp,
-    );
+        AlphaMode::Independent,
+        ThreadingPolicy::Single,
+    )
+    .unwrap();
 
     // libblur::gaussian_blur(
     //     &plane_1,
@@ -226,19 +236,25 @@ fn main() {
     //     .collect();
 
     libblur::gaussian_blur_in_linear(
-        &bytes,
-        stride as u32,
-        &mut dst_bytes,
-        stride as u32,
-        dimensions.0,
-        dimensions.1,
+        &BlurImage::borrow(
+            &bytes,
+            dimensions.0,
+            dimensions.1,
+            FastBlurChannels::Channels3,
+        ),
+        &mut BlurImageMut::borrow(
+            &mut dst_bytes,
+            dimensions.0,
+            dimensions.1,
+            FastBlurChannels::Channels3,
+        ),
         67 * 2 + 1,
         67. * 2f32 / 6f32,
-        FastBlurChannels::Channels3,
         EdgeMode::Clamp,
         ThreadingPolicy::Single,
         TransferFunction::Srgb,
-    );
+    )
+    .unwrap();
 
     // stack_blur(
     //     &mut dst_bytes,